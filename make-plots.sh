@@ -32,9 +32,11 @@ ts3=$(latest_timestamp "append-doltdb")
 if [ ! -z "$ts1" ]; then
   python3 scripts/scatter-plot-xy.py \
     "$dir/$ts1-append-slate-file.csv=Slate (file)" \
+    "$dir/$ts1-append-slate-file-preallocated.csv=Slate (file, preallocated)" \
     "$dir/$ts1-append-slate-rocksdb.csv=Slate (rocksdb)" \
     "$dir/$ts1-append-slate-memkvs.csv=Slate (memkvs)" \
     "$dir/$ts1-append-seqfile-file.csv=Unindexed Sequence File" \
+    "$dir/$ts1-append-seqfile-file-preallocated.csv=Unindexed Sequence File (preallocated)" \
     "$dir/$ts2-append-iavl-leveldb.csv=IAVL+ (leveldb)" \
     "$dir/$ts3-append-doltdb-file.csv=DoltDB (file)" \
     -o "$dir/$([[ "$ts1" > "$ts2" ]] && echo "$ts1" || echo "$ts2")-append.png" \
@@ -55,6 +57,7 @@ if [ ! -z "$ts1" ]; then
     "$dir/$ts1-get-slate-rocksdb.csv=Slate (rocksdb)" \
     "$dir/$ts1-get-slate-memkvs.csv=Slate (memkvs)" \
     "$dir/$ts1-get-hashtree-file.csv=Binary Tree (file)" \
+    "$dir/$ts1-get-seqfile-indexed-file.csv=Indexed Sequence File" \
     "$dir/$ts2-get-iavl-leveldb.csv=IAVL+ (leveldb)" \
     "$dir/$ts3-get-doltdb-file.csv=DoltDB (file)" \
     -o "$dir/$([[ "$ts1" > "$ts2" ]] && echo "$ts1" || echo "$ts2")-get.png" \
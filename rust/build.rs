@@ -0,0 +1,32 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// slate のバージョンとコミットをワークスペースの Cargo.lock (git 依存の場合は source の #commit、なければ Cargo.toml の
+// rev) から取得し、SLATE_VERSION と SLATE_COMMIT としてセッションのメタデータに記録できるようにする
+fn main() {
+  let dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+  let (lock, manifest) = (dir.join("../Cargo.lock"), dir.join("Cargo.toml"));
+  println!("cargo:rerun-if-changed={}", lock.display());
+  println!("cargo:rerun-if-changed={}", manifest.display());
+
+  let lock = fs::read_to_string(lock).unwrap_or_default();
+  let package = lock.split("[[package]]").find(|package| package.lines().any(|line| line.trim() == "name = \"slate\""));
+  let field = |name: &str| {
+    let prefix = format!("{name} = \"");
+    package?.lines().find_map(|line| line.trim().strip_prefix(prefix.as_str())?.strip_suffix('"').map(String::from))
+  };
+  let version = field("version").unwrap_or_else(|| String::from("unknown"));
+  let commit = field("source").and_then(|source| Some(String::from(source.strip_prefix("git+")?.rsplit_once('#')?.1)));
+  let commit = commit.or_else(|| {
+    let manifest = fs::read_to_string(&manifest).ok()?;
+    let line = manifest.lines().find(|line| line.starts_with("slate "))?;
+    let rev = line.split_once("rev = \"")?.1;
+    Some(String::from(rev.split_once('"')?.0))
+  });
+
+  println!("cargo:rustc-env=SLATE_VERSION={version}");
+  if let Some(commit) = commit {
+    println!("cargo:rustc-env=SLATE_COMMIT={commit}");
+  }
+}
@@ -0,0 +1,54 @@
+use core_affinity::CoreId;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::writer;
+
+/// Pin the current thread, which runs the measurements, to the core.
+pub fn pin_current(core: usize) -> Result<()> {
+  let core = core_id(core)?;
+  if !core_affinity::set_for_current(core) {
+    return Err(BenchError::Config(format!("fail to pin the measurement thread to core {}", core.id)));
+  }
+  Ok(())
+}
+
+/// Build the global rayon pool used to prepare the databases in the background with a thread pinned to each core,
+/// so that the preparation doesn't migrate to the core of the measurement thread.
+pub fn pin_prepare_threads(cores: &[usize]) -> Result<()> {
+  let cores = cores.iter().map(|core| core_id(*core)).collect::<Result<Vec<_>>>()?;
+  rayon::ThreadPoolBuilder::new()
+    .num_threads(cores.len())
+    .start_handler(move |i| {
+      if !core_affinity::set_for_current(cores[i]) {
+        eprintln!("WARN: fail to pin the prepare thread #{i} to core {}", cores[i].id);
+      }
+    })
+    .build_global()
+    .map_err(|err| BenchError::Config(format!("fail to build the prepare thread pool: {err}")))
+}
+
+/// Pin the report writer thread to a core other than the measurement thread's `measurement`, the first of the
+/// `prepare` cores if any, so that the reporting doesn't preempt the measurement.
+pub fn pin_writer_thread(measurement: usize, prepare: &[usize]) -> Result<()> {
+  let available = core_affinity::get_core_ids().unwrap_or_default();
+  let core = prepare
+    .iter()
+    .copied()
+    .chain(available.iter().map(|id| id.id))
+    .find(|core| *core != measurement)
+    .ok_or_else(|| BenchError::Config(String::from("no core is available for the report writer thread")))?;
+  let core = core_id(core)?;
+  writer::start(move || {
+    if !core_affinity::set_for_current(core) {
+      eprintln!("WARN: fail to pin the report writer thread to core {}", core.id);
+    }
+  });
+  Ok(())
+}
+
+fn core_id(core: usize) -> Result<CoreId> {
+  let available = core_affinity::get_core_ids().unwrap_or_default();
+  available
+    .into_iter()
+    .find(|id| id.id == core)
+    .ok_or_else(|| BenchError::Config(format!("core {core} is not available for this process")))
+}
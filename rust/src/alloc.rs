@@ -0,0 +1,91 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::Experiment;
+use crate::error::Result;
+use crate::report::append_csv;
+
+/// Global allocator that counts the allocations and tracks the peak heap size, installed by the `slate-bench` binary
+/// with the `alloc-stats` feature. It's not enabled by default since the atomic counters add to the latency of every
+/// allocation.
+pub struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+impl CountingAllocator {
+  fn allocated(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED.fetch_add(size as u64, Ordering::Relaxed);
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+  }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let ptr = unsafe { System.alloc(layout) };
+    if !ptr.is_null() {
+      Self::allocated(layout.size());
+    }
+    ptr
+  }
+
+  unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+    let ptr = unsafe { System.alloc_zeroed(layout) };
+    if !ptr.is_null() {
+      Self::allocated(layout.size());
+    }
+    ptr
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    unsafe { System.dealloc(ptr, layout) };
+    CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+    if !new_ptr.is_null() {
+      CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+      Self::allocated(new_size);
+    }
+    new_ptr
+  }
+}
+
+/// Allocations during a test unit, reported per CUT to `{session}-alloc.csv`.
+pub struct AllocStats {
+  allocations: u64,
+  allocated: u64,
+}
+
+impl AllocStats {
+  pub fn start() -> Self {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+    AllocStats { allocations: ALLOCATIONS.load(Ordering::Relaxed), allocated: ALLOCATED.load(Ordering::Relaxed) }
+  }
+
+  /// Append the allocations since `start` to the report. Nothing is reported without the `alloc-stats` feature.
+  pub fn finish(self, experiment: &Experiment, cut: &str, unit: &str) -> Result<()> {
+    if !cfg!(feature = "alloc-stats") {
+      return Ok(());
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - self.allocations;
+    let allocated = ALLOCATED.load(Ordering::Relaxed) - self.allocated;
+    let peak = PEAK.load(Ordering::Relaxed);
+    println!("allocations: {allocations} ({allocated} bytes), peak heap: {peak} bytes");
+
+    let path = experiment.report_path("alloc.csv");
+    let header = "CUT,UNIT,ALLOCATIONS,ALLOCATED BYTES,PEAK BYTES";
+    append_csv(&path, header, &format!("{cut},{unit},{allocations},{allocated},{peak}"))
+  }
+}
+
+/// Heap size in use by this process, if counted by the `alloc-stats` feature. The resident set size isn't used in its
+/// place since it also includes the pages freed but kept by the allocator.
+pub fn memory_in_use() -> Option<u64> {
+  cfg!(feature = "alloc-stats").then(|| CURRENT.load(Ordering::Relaxed) as u64)
+}
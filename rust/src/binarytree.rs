@@ -1,68 +1,421 @@
-use std::fs::remove_file;
+use std::cell::OnceCell;
+use std::fs::{remove_dir_all, remove_file};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
 
-use slate::Index;
-use slate::Result;
-use slate_benchmark::hashtree::{HashTree, binary::BinaryHashTree};
-use slate_benchmark::unique_file;
+use slate::file::FileDevice;
+#[cfg(feature = "rocksdb")]
+use slate::rocksdb::RocksDBStorage;
+use slate::{BlockStorage, Index, Storage};
+use slate_benchmark::clock::Stopwatch;
+use slate_benchmark::ensure;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::hashtree::binary::{BinaryHashTree, Node};
+use slate_benchmark::hashtree::hash::HashValue;
+use slate_benchmark::hashtree::hash::{Blake3, HashAlgorithm};
+use slate_benchmark::hashtree::implicit::{Alignment, ImplicitHashTree};
+use slate_benchmark::hashtree::leveled::LeveledHashTree;
+use slate_benchmark::hashtree::{AuthPath, HashTree};
+use slate_benchmark::knobs::Knobs;
+use slate_benchmark::{CUT, ConcurrentGetCUT, GetCUT, HandleMode, Measurement, RangeCUT, RootHashCUT, unique_file};
 
-use crate::{CUT, GetCUT};
+use crate::dataset::{DatasetCache, Fingerprint};
 
-#[derive(Default)]
-pub struct FileBinaryTreeCUT {
+/// A hash tree stored in a single file or directory, which can be benchmarked by [`FileHashTreeCUT`].
+pub trait FileHashTree: HashTree<Error = slate::error::Error> + Sized {
+  fn name() -> String;
+  fn create<V: Fn(u64) -> slate::Result<Vec<u8>>>(
+    path: &Path,
+    h: u8,
+    cache_limit: usize,
+    knobs: &Knobs,
+    values: V,
+  ) -> slate::Result<Self>;
+  fn open(path: &Path, cache_limit: usize, knobs: &Knobs) -> slate::Result<Self>;
+
+  /// Read the values of the leaves `first..=last`. The default implementation repeats point-gets.
+  fn scan(&mut self, first: Index, last: Index) -> slate::Result<Vec<Vec<u8>>> {
+    (first..=last)
+      .map(|k| -> slate::Result<Vec<u8>> {
+        self.get(k)?.ok_or_else(|| {
+          let msg = format!("the leaf {k} of {first}..={last} doesn't exist");
+          std::io::Error::new(std::io::ErrorKind::InvalidData, msg).into()
+        })
+      })
+      .collect()
+  }
+}
+
+fn name_with_hash<H: HashAlgorithm>(name: &str) -> String {
+  if H::name() == Blake3::name() { String::from(name) } else { format!("{name}-{}", H::name()) }
+}
+
+impl<H: HashAlgorithm> FileHashTree for BinaryHashTree<BlockStorage<FileDevice>, H> {
+  fn name() -> String {
+    name_with_hash::<H>("hashtree-file")
+  }
+  fn create<V: Fn(u64) -> slate::Result<Vec<u8>>>(
+    path: &Path,
+    h: u8,
+    cache_limit: usize,
+    _: &Knobs,
+    values: V,
+  ) -> slate::Result<Self> {
+    Self::create_on_file(path, h, cache_limit, values)
+  }
+  fn open(path: &Path, cache_limit: usize, _: &Knobs) -> slate::Result<Self> {
+    Self::from_file(path, cache_limit)
+  }
+  fn scan(&mut self, first: Index, last: Index) -> slate::Result<Vec<Vec<u8>>> {
+    self.leaves(first..=last)?.map(|leaf| leaf.map(|(_, value)| value)).collect()
+  }
+}
+
+impl<H: HashAlgorithm> FileHashTree for ImplicitHashTree<H> {
+  fn name() -> String {
+    name_with_hash::<H>("hashtree-implicit")
+  }
+  fn create<V: Fn(u64) -> slate::Result<Vec<u8>>>(
+    path: &Path,
+    h: u8,
+    cache_limit: usize,
+    _: &Knobs,
+    values: V,
+  ) -> slate::Result<Self> {
+    Self::create_on_file(path, h, 8, cache_limit, values)
+  }
+  fn open(path: &Path, cache_limit: usize, _: &Knobs) -> slate::Result<Self> {
+    Self::from_file(path, cache_limit)
+  }
+}
+
+/// [`ImplicitHashTree`] whose nodes are aligned to the block boundaries, as a separate CUT from the packed one.
+pub struct AlignedImplicitHashTree<const BLOCKED: bool, H: HashAlgorithm>(ImplicitHashTree<H>);
+
+impl<const BLOCKED: bool, H: HashAlgorithm> HashTree for AlignedImplicitHashTree<BLOCKED, H> {
+  type Error = slate::error::Error;
+  type Proof = AuthPath;
+
+  fn size(&self) -> u64 {
+    self.0.size()
+  }
+  fn get(&mut self, index: u64) -> slate::Result<Option<Vec<u8>>> {
+    self.0.get(index)
+  }
+  fn root_hash(&mut self) -> slate::Result<Option<HashValue>> {
+    self.0.root_hash()
+  }
+  fn prove(&mut self, index: u64) -> slate::Result<Option<AuthPath>> {
+    self.0.prove(index)
+  }
+  fn append(&mut self, data: &[u8]) -> slate::Result<u64> {
+    self.0.append(data)
+  }
+}
+
+impl<const BLOCKED: bool, H: HashAlgorithm> FileHashTree for AlignedImplicitHashTree<BLOCKED, H> {
+  fn name() -> String {
+    name_with_hash::<H>(if BLOCKED { "hashtree-implicit-blocked" } else { "hashtree-implicit-padded" })
+  }
+  fn create<V: Fn(u64) -> slate::Result<Vec<u8>>>(
+    path: &Path,
+    h: u8,
+    cache_limit: usize,
+    _: &Knobs,
+    values: V,
+  ) -> slate::Result<Self> {
+    let alignment = if BLOCKED { Alignment::Blocked } else { Alignment::Padded };
+    ImplicitHashTree::create_on_file_with_alignment(path, h, 8, alignment, cache_limit, values).map(Self)
+  }
+  fn open(path: &Path, cache_limit: usize, _: &Knobs) -> slate::Result<Self> {
+    ImplicitHashTree::from_file(path, cache_limit).map(Self)
+  }
+}
+
+impl<H: HashAlgorithm> FileHashTree for LeveledHashTree<H> {
+  fn name() -> String {
+    name_with_hash::<H>("hashtree-leveled")
+  }
+  fn create<V: Fn(u64) -> slate::Result<Vec<u8>>>(
+    path: &Path,
+    h: u8,
+    cache_limit: usize,
+    _: &Knobs,
+    values: V,
+  ) -> slate::Result<Self> {
+    Self::create_on_file(path, h, 8, cache_limit, values)
+  }
+  fn open(path: &Path, cache_limit: usize, _: &Knobs) -> slate::Result<Self> {
+    Self::from_file(path, cache_limit)
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl<H: HashAlgorithm> FileHashTree for BinaryHashTree<RocksDBStorage, H> {
+  fn name() -> String {
+    name_with_hash::<H>("hashtree-rocksdb")
+  }
+  fn create<V: Fn(u64) -> slate::Result<Vec<u8>>>(
+    path: &Path,
+    h: u8,
+    cache_limit: usize,
+    knobs: &Knobs,
+    values: V,
+  ) -> slate::Result<Self> {
+    Self::create_on_rocksdb(path, h, cache_limit, knobs, values)
+  }
+  fn open(path: &Path, cache_limit: usize, knobs: &Knobs) -> slate::Result<Self> {
+    Self::from_rocksdb(path, cache_limit, knobs)
+  }
+  fn scan(&mut self, first: Index, last: Index) -> slate::Result<Vec<Vec<u8>>> {
+    self.leaves(first..=last)?.map(|leaf| leaf.map(|(_, value)| value)).collect()
+  }
+}
+
+pub type FileBinaryTreeCUT<H = Blake3> = FileHashTreeCUT<BinaryHashTree<BlockStorage<FileDevice>, H>>;
+#[cfg(feature = "rocksdb")]
+pub type RocksDBBinaryTreeCUT<H = Blake3> = FileHashTreeCUT<BinaryHashTree<RocksDBStorage, H>>;
+pub type FileImplicitTreeCUT<H = Blake3> = FileHashTreeCUT<ImplicitHashTree<H>>;
+pub type FilePaddedImplicitTreeCUT<H = Blake3> = FileHashTreeCUT<AlignedImplicitHashTree<false, H>>;
+pub type FileBlockedImplicitTreeCUT<H = Blake3> = FileHashTreeCUT<AlignedImplicitHashTree<true, H>>;
+pub type FileLeveledTreeCUT<H = Blake3> = FileHashTreeCUT<LeveledHashTree<H>>;
+
+pub struct FileHashTreeCUT<T: FileHashTree> {
   path: PathBuf,
   cache_level: usize,
+  prepared: Option<Index>,
+  dataset_cache: Option<DatasetCache>,
+  handle_mode: HandleMode,
+  knobs: Knobs,
+  /// Tree kept open between the gets in [`HandleMode::Persistent`]
+  tree: Option<T>,
 }
 
-impl FileBinaryTreeCUT {
+impl<T: FileHashTree> FileHashTreeCUT<T> {
   pub fn new(dir: &Path, n: u64) -> Result<Self> {
-    assert_eq!((n & (n - 1)), 0, "must be binary");
-    let path = unique_file(dir, "hashtree-file", ".db");
+    if n & (n - 1) != 0 {
+      return Err(BenchError::Config(format!("the data size {n} must be a power of 2 for {}", T::name())));
+    }
+    let path = unique_file(dir, &T::name(), ".db");
     let cache_level = 0;
-    Ok(Self { path, cache_level })
+    let handle_mode = HandleMode::Persistent;
+    let knobs = Knobs::default();
+    Ok(Self { path, cache_level, prepared: None, dataset_cache: None, handle_mode, knobs, tree: None })
+  }
+
+  /// Reuse the databases prepared in the previous sessions, see [`DatasetCache`].
+  pub fn with_dataset_cache(mut self, dataset_cache: Option<DatasetCache>) -> Self {
+    self.dataset_cache = dataset_cache;
+    self
+  }
+
+  /// Apply the storage knobs to the trees stored in RocksDB; the other trees ignore them.
+  pub fn with_knobs(mut self, knobs: &Knobs) -> Self {
+    self.knobs = knobs.clone();
+    self
+  }
+}
+
+fn fingerprint<T: FileHashTree>(path: &Path, knobs: &Knobs) -> Result<Fingerprint> {
+  let mut tree = T::open(path, 0, knobs)?;
+  let n = tree.size();
+  let root = tree.root_hash()?.unwrap_or_default();
+  Ok(Fingerprint { n, root })
+}
+
+/// Remove the file or the RocksDB directory of the tree, if exists.
+fn remove(path: &Path) -> std::io::Result<()> {
+  if path.is_dir() {
+    remove_dir_all(path)
+  } else if path.exists() {
+    remove_file(path)
+  } else {
+    Ok(())
   }
 }
 
-impl Drop for FileBinaryTreeCUT {
+impl<T: FileHashTree> Drop for FileHashTreeCUT<T> {
   fn drop(&mut self) {
-    if self.path.exists() {
-      if let Err(e) = remove_file(&self.path) {
-        eprintln!("WARN: fail to remove file {:?}: {}", self.path, e);
-      }
+    drop(self.tree.take());
+    if let Err(e) = remove(&self.path) {
+      eprintln!("WARN: fail to remove file {:?}: {}", self.path, e);
     }
   }
 }
 
-impl CUT for FileBinaryTreeCUT {
+impl<T: FileHashTree> CUT for FileHashTreeCUT<T> {
   fn implementation(&self) -> String {
-    String::from("hashtree-file")
+    T::name()
+  }
+
+  fn prepare<V: Fn(u64) -> u64, P: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
+    ensure!(n & (n - 1) == 0, "the data size {n} must be a power of 2");
+    self.tree = None;
+    // 固定長の木は追記できないため、同じサイズで構築済みの場合のみ再利用する
+    if self.prepared == Some(n) && self.path.exists() {
+      (progress)(n)?;
+      return Ok(());
+    }
+    self.prepared = None;
+    remove(&self.path)?;
+
+    let key = DatasetCache::key(&T::name(), n);
+    if let Some(dataset_cache) = &self.dataset_cache
+      && dataset_cache.restore(&key, &self.path, |path| fingerprint::<T>(path, &self.knobs))?
+    {
+      (progress)(n)?;
+      self.prepared = Some(n);
+      return Ok(());
+    }
+
+    // 進捗のエラーで構築を中断し、木の構築のエラーではなく進捗のエラーを返す
+    let interrupted = OnceCell::new();
+    let created = T::create(&self.path, u64::ilog2(n) as u8 + 1, 1 << self.cache_level, &self.knobs, |i| {
+      if let Err(err) = (progress)(1) {
+        let _ = interrupted.set(err);
+        return Err(std::io::Error::from(std::io::ErrorKind::Interrupted).into());
+      }
+      Ok(values(i).to_le_bytes().to_vec())
+    });
+    if let Some(err) = interrupted.into_inner() {
+      return Err(err);
+    }
+    let mut tree = created?;
+    if let Some(dataset_cache) = &self.dataset_cache {
+      let root = tree.root_hash()?.unwrap_or_default();
+      drop(tree);
+      dataset_cache.store(&key, &self.path, Fingerprint { n, root })?;
+    }
+    self.prepared = Some(n);
+    Ok(())
+  }
+
+  fn cleanup(&mut self) -> Result<()> {
+    self.tree = None;
+    self.prepared = None;
+    remove(&self.path)?;
+    Ok(())
   }
 }
 
-impl GetCUT for FileBinaryTreeCUT {
+impl<T: FileHashTree> GetCUT for FileHashTreeCUT<T> {
+  #[inline(never)]
+  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Measurement> {
+    if self.handle_mode == HandleMode::Reopen || self.tree.is_none() {
+      // hashtree-rocksdb は DB のロックを保持するため、開き直す前に閉じる
+      self.tree = None;
+      self.tree = Some(T::open(&self.path, 1 << self.cache_level, &self.knobs)?);
+    }
+    let tree = self.tree.as_mut().unwrap();
+    let start = Stopwatch::start();
+    let value = tree.get(i)?;
+    let elapsed = start.elapsed();
+    let value = value.map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+    ensure!(value == Some(values(i)), "unexpected value {value:?} at {i}");
+    Ok(Measurement::new(elapsed))
+  }
+
   #[inline(never)]
-  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Duration> {
-    let mut bht = BinaryHashTree::from_file(&self.path, 1 << self.cache_level)?;
-    let start = Instant::now();
-    let value = bht.get(i)?;
+  fn get_batch<V: Fn(u64) -> u64>(&mut self, is: &[Index], values: V) -> Result<Measurement> {
+    if self.tree.is_none() {
+      self.tree = Some(T::open(&self.path, 1 << self.cache_level, &self.knobs)?);
+    }
+    let tree = self.tree.as_mut().unwrap();
+    let mut read = Vec::with_capacity(is.len());
+    let start = Stopwatch::start();
+    for i in is.iter() {
+      read.push(tree.get(*i)?);
+    }
     let elapsed = start.elapsed();
-    assert_eq!(Some(values(i)), value.map(|b| u64::from_le_bytes(b.try_into().unwrap())), " at {i}");
-    Ok(elapsed)
+    for (i, value) in is.iter().zip(read) {
+      let value = value.map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+      ensure!(value == Some(values(*i)), "unexpected value {value:?} at {i}");
+    }
+    Ok(Measurement::new(elapsed).with_count(is.len() as u64))
   }
 
   fn set_cache_level(&mut self, cache_size: usize) -> Result<()> {
     self.cache_level = cache_size;
+    self.tree = None;
     Ok(())
   }
 
-  fn prepare<V: Fn(u64) -> u64, P: Fn(Index)>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
-    assert_eq!((n & (n - 1)), 0, "must be binary");
-    BinaryHashTree::create_on_file(&self.path, u64::ilog2(n) as u8 + 1, 1 << self.cache_level, |i| {
-      let bytes = values(i).to_le_bytes().to_vec();
-      (progress)(1);
-      bytes
-    })?;
+  fn set_handle_mode(&mut self, mode: HandleMode) -> Result<()> {
+    self.handle_mode = mode;
+    self.tree = None;
     Ok(())
   }
 }
+
+impl<T: FileHashTree> RangeCUT for FileHashTreeCUT<T> {
+  #[inline(never)]
+  fn scan<V: Fn(u64) -> u64>(&mut self, i: Index, m: Index, values: V) -> Result<Measurement> {
+    self.tree = None;
+    let mut tree = T::open(&self.path, 1 << self.cache_level, &self.knobs)?;
+    let start = Stopwatch::start();
+    let scanned = tree.scan(i, i + m - 1)?;
+    let elapsed = start.elapsed();
+    ensure!(scanned.len() == m as usize, "{} values scanned from {i}, expected {m}", scanned.len());
+    for (k, value) in (i..).zip(scanned) {
+      let value = u64::from_le_bytes(value.try_into().unwrap());
+      ensure!(value == values(k), "unexpected value {value} at {k}");
+    }
+    Ok(Measurement::new(elapsed).with_count(m))
+  }
+}
+
+impl<T: FileHashTree> RootHashCUT for FileHashTreeCUT<T> {
+  #[inline(never)]
+  fn root_hash(&mut self) -> Result<Measurement> {
+    self.tree = None;
+    let mut tree = T::open(&self.path, 1 << self.cache_level, &self.knobs)?;
+    let start = Stopwatch::start();
+    let root = tree.root_hash()?;
+    let elapsed = start.elapsed();
+    ensure!(root.is_some(), "no root hash of {} entries", tree.size());
+    Ok(Measurement::new(elapsed))
+  }
+}
+
+impl<S, H> ConcurrentGetCUT for FileHashTreeCUT<BinaryHashTree<S, H>>
+where
+  S: Storage<Node>,
+  H: HashAlgorithm,
+  BinaryHashTree<S, H>: FileHashTree + Sync,
+{
+  #[inline(never)]
+  fn concurrent_get<V: Fn(u64) -> u64 + Sync>(
+    &mut self,
+    threads: usize,
+    is: &[Index],
+    values: V,
+  ) -> Result<Measurement> {
+    self.tree = None;
+    let tree = <BinaryHashTree<S, H> as FileHashTree>::open(&self.path, 1 << self.cache_level, &self.knobs)?;
+    let (tree, values) = (&tree, &values);
+    let start = Stopwatch::start();
+    let results = std::thread::scope(|scope| {
+      let handles = is
+        .chunks(is.len().div_ceil(threads))
+        .map(|is| {
+          scope.spawn(move || -> Result<()> {
+            let mut reader = tree.reader()?;
+            for i in is.iter() {
+              let value = reader.get(*i)?.map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+              ensure!(value == Some(values(*i)), "unexpected value {value:?} at {i}");
+            }
+            Ok(())
+          })
+        })
+        .collect::<Vec<_>>();
+      handles
+        .into_iter()
+        .map(|handle| {
+          handle.join().unwrap_or_else(|_| Err(BenchError::Interrupted(String::from("reader thread panicked"))))
+        })
+        .collect::<Result<Vec<()>>>()
+    });
+    let elapsed = start.elapsed();
+    results?;
+    Ok(Measurement::new(elapsed).with_count(is.len() as u64))
+  }
+}
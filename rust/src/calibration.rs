@@ -0,0 +1,141 @@
+//! Short fio-like calibration of the storage of the working directory, recorded in the session metadata so that the
+//! results on different storage hardware can be normalized. The cost of the hash functions is recorded likewise as the
+//! constant factor of the predictions based on the tree height.
+use std::fs::{File, OpenOptions};
+use std::hint::black_box;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+use rand::Rng;
+
+use crate::error::Result;
+use crate::hashtree::hash::HashAlgorithm;
+use crate::platform;
+use crate::{RemoveOnDrop, splitmix64, unique_file};
+
+const BLOCK_SIZE: usize = 1024 * 1024;
+const FILE_SIZE: usize = 64 * BLOCK_SIZE;
+const PAGE_SIZE: usize = 4 * 1024;
+const RANDOM_READS: usize = 2048;
+const FSYNCS: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Calibration {
+  /// Sequential write throughput in MB/s, including the final fsync
+  pub sequential_write: f64,
+  /// Random 4KiB read in IOPS
+  pub random_read: f64,
+  /// Median latency of a 4KiB write followed by fsync in milliseconds
+  pub fsync_latency: f64,
+}
+
+impl Calibration {
+  /// Run the calibration on a temporary file in `dir`. It takes a few seconds.
+  pub fn run(dir: &Path) -> Result<Self> {
+    let file = RemoveOnDrop::new(unique_file(dir, "slate_benchmark-calibration", ".dat"));
+    let path = file.path();
+
+    // sequential write
+    let block = (0..BLOCK_SIZE).map(|i| i as u8).collect::<Vec<_>>();
+    let start = Instant::now();
+    let mut f = File::create(path)?;
+    for _ in 0..FILE_SIZE / BLOCK_SIZE {
+      f.write_all(&block)?;
+    }
+    f.sync_all()?;
+    let sequential_write = FILE_SIZE as f64 / 1000.0 / 1000.0 / start.elapsed().as_secs_f64();
+    drop(f);
+
+    // random 4KiB read
+    let f = File::open(path)?;
+    platform::drop_file_cache(&f);
+    let mut rng = rand::rng();
+    let mut page = vec![0u8; PAGE_SIZE];
+    let start = Instant::now();
+    for _ in 0..RANDOM_READS {
+      let offset = rng.random_range(0..FILE_SIZE / PAGE_SIZE) * PAGE_SIZE;
+      platform::read_exact_at(&f, &mut page, offset as u64)?;
+    }
+    let random_read = RANDOM_READS as f64 / start.elapsed().as_secs_f64();
+    drop(f);
+
+    // fsync latency
+    let mut f = OpenOptions::new().write(true).truncate(true).open(path)?;
+    let mut latencies = Vec::with_capacity(FSYNCS);
+    for _ in 0..FSYNCS {
+      let start = Instant::now();
+      f.write_all(&block[..PAGE_SIZE])?;
+      f.sync_data()?;
+      latencies.push(start.elapsed().as_secs_f64() * 1000.0);
+    }
+    latencies.sort_unstable_by(f64::total_cmp);
+    let fsync_latency = latencies[latencies.len() / 2];
+
+    Ok(Calibration { sequential_write, random_read, fsync_latency })
+  }
+
+  /// Entries of the session metadata, `calibration.{device}.*` if the device label is given.
+  pub fn metadata(&self, device: Option<&str>) -> Vec<(String, String)> {
+    let prefix = device.map(|device| format!("calibration.{device}.")).unwrap_or(String::from("calibration."));
+    vec![
+      (format!("{prefix}sequential_write_mbps"), format!("{:.1}", self.sequential_write)),
+      (format!("{prefix}random_read_4k_iops"), format!("{:.0}", self.random_read)),
+      (format!("{prefix}fsync_latency_ms"), format!("{:.3}", self.fsync_latency)),
+    ]
+  }
+}
+
+const HASH_INPUT_SIZE: usize = 64 * 1024;
+const HASH_BATCHES: usize = 16;
+const HASHES_PER_BATCH: usize = 64;
+const COMBINES_PER_BATCH: usize = 4096;
+
+/// Cost of a hash function on this machine, taken as the median of the batches.
+#[derive(Debug, Clone, Copy)]
+pub struct HashCost {
+  /// Throughput of hashing a 64KiB input in MB/s
+  pub throughput: f64,
+  /// Time to combine two hash values into their parent in nanoseconds
+  pub combine: f64,
+}
+
+impl HashCost {
+  /// Measure the cost of `H`. It takes less than a second.
+  pub fn run<H: HashAlgorithm>() -> Self {
+    let data = (0..HASH_INPUT_SIZE as u64).map(|i| splitmix64(i) as u8).collect::<Vec<_>>();
+    let throughput = median((0..HASH_BATCHES).map(|_| {
+      let start = Instant::now();
+      for _ in 0..HASHES_PER_BATCH {
+        black_box(H::hash(black_box(&data)));
+      }
+      (HASH_INPUT_SIZE * HASHES_PER_BATCH) as f64 / 1000.0 / 1000.0 / start.elapsed().as_secs_f64()
+    }));
+
+    let (left, right) = (H::hash(&data[..8]), H::hash(&data[8..16]));
+    let combine = median((0..HASH_BATCHES).map(|_| {
+      let start = Instant::now();
+      for _ in 0..COMBINES_PER_BATCH {
+        black_box(H::combine(black_box(&left), black_box(&right)));
+      }
+      start.elapsed().as_nanos() as f64 / COMBINES_PER_BATCH as f64
+    }));
+
+    HashCost { throughput, combine }
+  }
+
+  /// Entries of the session metadata, `calibration.hash.{name}.*`.
+  pub fn metadata(&self, name: &str) -> Vec<(String, String)> {
+    let prefix = format!("calibration.hash.{name}.");
+    vec![
+      (format!("{prefix}throughput_mbps"), format!("{:.1}", self.throughput)),
+      (format!("{prefix}combine_ns"), format!("{:.1}", self.combine)),
+    ]
+  }
+}
+
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+  let mut values = values.collect::<Vec<_>>();
+  values.sort_unstable_by(f64::total_cmp);
+  values[values.len() / 2]
+}
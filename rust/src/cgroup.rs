@@ -0,0 +1,90 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use slate_benchmark::error::{BenchError, Result};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// cgroup v2 の memory.max で子プロセスのメモリ使用量 (ページキャッシュを含む) を制限する。
+///
+/// このプロセスが属する cgroup は委譲されて書き込み可能である必要がある。子 cgroup を作成できるように、このプロセス
+/// 自身は `supervisor` という葉の cgroup に移動する。
+///
+/// ```text
+/// {cgroup}/supervisor   このプロセス
+/// {cgroup}/cut-{name}   memory.max を設定した CUT の子プロセス
+/// ```
+pub struct MemoryLimit {
+  base: PathBuf,
+  memory_max: String,
+}
+
+impl MemoryLimit {
+  /// `memory_max` is written as is to `memory.max`, e.g. `512M`.
+  pub fn new(memory_max: &str) -> Result<Self> {
+    let cgroup = fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    let Some(path) = cgroup.lines().find_map(|line| line.strip_prefix("0::")) else {
+      return Err(BenchError::Config(String::from("cgroup v2 is not available on this system")));
+    };
+    let base = Path::new(CGROUP_ROOT).join(path.trim_start_matches('/'));
+
+    let subtree_control = base.join("cgroup.subtree_control");
+    if !fs::read_to_string(&subtree_control)?.split_whitespace().any(|c| c == "memory") {
+      // プロセスを持つ cgroup では子 cgroup のコントローラを有効にできない (no internal process constraint)
+      let supervisor = base.join("supervisor");
+      create_dir(&supervisor)?;
+      write(&supervisor.join("cgroup.procs"), "0")?;
+      write(&subtree_control, "+memory")?;
+    }
+    Ok(Self { base, memory_max: String::from(memory_max) })
+  }
+
+  /// Create the cgroup for the CUT with the memory limit.
+  pub fn scope(&self, name: &str) -> Result<Scope> {
+    let path = self.base.join(format!("cut-{name}"));
+    create_dir(&path)?;
+    let scope = Scope { path };
+    write(&scope.path.join("memory.max"), &self.memory_max)?;
+    // スワップに逃がすと制限の意味がなくなるため、スワップが有効な環境では禁止する
+    let swap_max = scope.path.join("memory.swap.max");
+    if swap_max.exists() {
+      write(&swap_max, "0")?;
+    }
+    Ok(scope)
+  }
+}
+
+/// cgroup of a CUT, removed when dropped after its processes exit.
+pub struct Scope {
+  path: PathBuf,
+}
+
+impl Scope {
+  pub fn add(&self, pid: u32) -> Result<()> {
+    write(&self.path.join("cgroup.procs"), &pid.to_string())
+  }
+}
+
+impl Drop for Scope {
+  fn drop(&mut self) {
+    if let Err(e) = fs::remove_dir(&self.path) {
+      eprintln!("WARN: fail to remove cgroup {:?}: {}", self.path, e);
+    }
+  }
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+  match fs::create_dir(path) {
+    Err(err) if err.kind() != ErrorKind::AlreadyExists => Err(cgroup_error(path, err)),
+    _ => Ok(()),
+  }
+}
+
+fn write(path: &Path, value: &str) -> Result<()> {
+  fs::write(path, value).map_err(|err| cgroup_error(path, err))
+}
+
+fn cgroup_error(path: &Path, err: std::io::Error) -> BenchError {
+  BenchError::Config(format!("fail to configure cgroup {path:?}, it may not be delegated to this user: {err}"))
+}
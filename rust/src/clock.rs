@@ -0,0 +1,213 @@
+//! Time source of the timed sections, selected by `--clock`. For the sub-microsecond operations such as the gets on
+//! MemKVS, the overhead and the granularity of the clock are a significant fraction of the measurement.
+use std::hint::black_box;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::error::{BenchError, Result};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ClockSource {
+  /// std::time::Instant (Linux では CLOCK_MONOTONIC)
+  #[default]
+  Instant,
+  /// NTP による周波数の調整を受けない CLOCK_MONOTONIC_RAW (Linux と macOS のみ)
+  MonotonicRaw,
+  /// quanta による TSC の読み取り (TSC が不変でない CPU では quanta が OS のクロックにフォールバックする)
+  Tsc,
+}
+
+impl ClockSource {
+  pub fn name(self) -> String {
+    self.to_possible_value().unwrap().get_name().to_string()
+  }
+}
+
+static SOURCE: AtomicU8 = AtomicU8::new(ClockSource::Instant as u8);
+
+static TSC: OnceLock<quanta::Clock> = OnceLock::new();
+
+/// Number of the recorders armed by [`armed`] on any thread, checked first so that the stopwatches cost nothing more
+/// otherwise.
+static ARMED: AtomicUsize = AtomicUsize::new(0);
+
+/// Run `f` with the recorder of the current thread armed, so that the [`Stopwatch`]es within it enable the recorder
+/// only in the timed sections.
+pub(crate) fn armed<T, F: FnOnce() -> T>(f: F) -> T {
+  struct Disarm;
+  impl Drop for Disarm {
+    fn drop(&mut self) {
+      ARMED.fetch_sub(1, Ordering::Relaxed);
+    }
+  }
+
+  ARMED.fetch_add(1, Ordering::Relaxed);
+  let _disarm = Disarm;
+  f()
+}
+
+/// Select the time source of the [`Stopwatch`]es started afterwards.
+pub fn set_clock(source: ClockSource) -> Result<()> {
+  match source {
+    ClockSource::MonotonicRaw if !cfg!(any(target_os = "linux", target_os = "macos")) => {
+      return Err(BenchError::Config(String::from("--clock monotonic-raw is supported only on Linux and macOS")));
+    }
+    // キャリブレーションに時間がかかるため、計測の前に済ませておく
+    ClockSource::Tsc => _ = TSC.get_or_init(quanta::Clock::new),
+    _ => {}
+  }
+  SOURCE.store(source as u8, Ordering::Relaxed);
+  Ok(())
+}
+
+pub fn clock() -> ClockSource {
+  match SOURCE.load(Ordering::Relaxed) {
+    s if s == ClockSource::MonotonicRaw as u8 => ClockSource::MonotonicRaw,
+    s if s == ClockSource::Tsc as u8 => ClockSource::Tsc,
+    _ => ClockSource::Instant,
+  }
+}
+
+/// Start of a timed section on the selected clock. The recorder armed on the thread, the hardware counters of `--perf`
+/// or the syscalls of `--syscalls`, is enabled from the start until the first [`elapsed`](Self::elapsed).
+#[derive(Debug, Clone, Copy)]
+pub enum Stopwatch {
+  Instant(Instant),
+  MonotonicRaw(u64),
+  Tsc(u64),
+}
+
+impl Stopwatch {
+  #[inline(always)]
+  pub fn start() -> Self {
+    Self::start_with(clock())
+  }
+
+  #[inline(always)]
+  pub fn start_with(source: ClockSource) -> Self {
+    if ARMED.load(Ordering::Relaxed) > 0 {
+      enter();
+    }
+    match source {
+      ClockSource::Instant => Stopwatch::Instant(Instant::now()),
+      ClockSource::MonotonicRaw => Stopwatch::MonotonicRaw(monotonic_raw()),
+      ClockSource::Tsc => Stopwatch::Tsc(tsc().raw()),
+    }
+  }
+
+  #[inline(always)]
+  pub fn elapsed(&self) -> Duration {
+    let elapsed = match *self {
+      Stopwatch::Instant(start) => start.elapsed(),
+      Stopwatch::MonotonicRaw(start) => Duration::from_nanos(monotonic_raw().saturating_sub(start)),
+      Stopwatch::Tsc(start) => {
+        let clock = tsc();
+        clock.delta(start, clock.raw())
+      }
+    };
+    if ARMED.load(Ordering::Relaxed) > 0 {
+      leave();
+    }
+    elapsed
+  }
+}
+
+#[cold]
+#[inline(never)]
+fn enter() {
+  crate::syscall::enter();
+  crate::perf::enter();
+}
+
+#[cold]
+#[inline(never)]
+fn leave() {
+  crate::perf::leave();
+  crate::syscall::leave();
+}
+
+fn tsc() -> &'static quanta::Clock {
+  TSC.get_or_init(quanta::Clock::new)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[inline(always)]
+fn monotonic_raw() -> u64 {
+  let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+  unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut ts) };
+  ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn monotonic_raw() -> u64 {
+  unreachable!("CLOCK_MONOTONIC_RAW is rejected by set_clock")
+}
+
+static SUBTRAHEND: AtomicU64 = AtomicU64::new(0);
+
+/// Subtract the overhead from the elapsed time of every [`Measurement`](crate::Measurement) created afterwards.
+pub fn set_overhead_subtraction(overhead: Duration) {
+  SUBTRAHEND.store(overhead.as_nanos().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+}
+
+/// Overhead subtracted from the measurements, zero unless `--subtract-overhead` is given.
+pub fn overhead_subtraction() -> Duration {
+  Duration::from_nanos(SUBTRAHEND.load(Ordering::Relaxed))
+}
+
+/// Overhead of an empty timed section and the smallest nonzero step of the clock.
+#[derive(Debug, Clone, Copy)]
+pub struct ClockCost {
+  pub overhead: Duration,
+  /// Median of the elapsed time reported for a timed section of a closure call and a check of its result that do
+  /// nothing, i.e. the harness overhead included in every measurement
+  pub empty_section: Duration,
+  pub resolution: Duration,
+}
+
+impl ClockCost {
+  /// Measure the cost of the selected clock with the given number of empty timed sections.
+  pub fn measure(samples: u32) -> Self {
+    let source = clock();
+    let start = Instant::now();
+    let mut resolution = Duration::MAX;
+    for _ in 0..samples {
+      let elapsed = black_box(Stopwatch::start_with(source)).elapsed();
+      if !elapsed.is_zero() {
+        resolution = resolution.min(elapsed);
+      }
+    }
+    let overhead = start.elapsed() / samples.max(1);
+    let resolution = if resolution == Duration::MAX { Duration::ZERO } else { resolution };
+
+    // CUT の計測区間と同様に、クロージャの呼び出しと結果の確認を挟む
+    let operation = black_box(|| -> Result<u64> { Ok(black_box(0)) });
+    let mut elapsed = (0..samples.max(1))
+      .map(|_| {
+        let start = Stopwatch::start_with(source);
+        let value = operation();
+        let elapsed = start.elapsed();
+        assert!(matches!(value, Ok(0)));
+        elapsed
+      })
+      .collect::<Vec<_>>();
+    elapsed.sort_unstable();
+    ClockCost { overhead, empty_section: elapsed[elapsed.len() / 2], resolution }
+  }
+
+  pub fn metadata(&self) -> Vec<(String, String)> {
+    vec![
+      (String::from("clock"), clock().name()),
+      (String::from("clock.overhead_ns"), format!("{:.1}", self.overhead.as_secs_f64() * 1e9)),
+      (String::from("clock.resolution_ns"), self.resolution.as_nanos().to_string()),
+      (String::from("clock.empty_section_ns"), self.empty_section.as_nanos().to_string()),
+      (String::from("clock.subtracted_ns"), overhead_subtraction().as_nanos().to_string()),
+    ]
+  }
+}
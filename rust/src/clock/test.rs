@@ -0,0 +1,29 @@
+use std::thread;
+use std::time::Duration;
+
+use super::*;
+
+#[test]
+fn measure_elapsed_time_on_each_clock() {
+  let mut sources = vec![ClockSource::Instant, ClockSource::Tsc];
+  if cfg!(any(target_os = "linux", target_os = "macos")) {
+    sources.push(ClockSource::MonotonicRaw);
+  }
+  for source in sources {
+    let start = Stopwatch::start_with(source);
+    thread::sleep(Duration::from_millis(10));
+    let elapsed = start.elapsed();
+    // TSC の周波数は推定値のため、多少の誤差を許容する
+    assert!(elapsed >= Duration::from_millis(9), "{source:?}: {elapsed:?}");
+    assert!(elapsed < Duration::from_secs(5), "{source:?}: {elapsed:?}");
+  }
+}
+
+#[test]
+fn measure_the_cost_of_the_clock() {
+  let cost = ClockCost::measure(1000);
+  assert!(cost.overhead < Duration::from_millis(1), "{cost:?}");
+  assert!(cost.empty_section < Duration::from_millis(1), "{cost:?}");
+  let metadata = cost.metadata();
+  assert!(metadata.iter().any(|(key, _)| key == "clock.empty_section_ns"), "{metadata:?}");
+}
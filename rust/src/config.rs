@@ -0,0 +1,139 @@
+//! Named experiments in the TOML config file, run by `slate-bench run NAME`.
+//!
+//! ```toml
+//! [experiments.nightly]
+//! description = "all the CUTs on NVMe"
+//! data-size = 8388608
+//! data-size-large = 2147483648
+//! dir = ["nvme=/mnt/slate/bench"]
+//! unit = ["append", "uniformed-get"]
+//! discard-warmup = true
+//! knob = { rocksdb.block-cache-size = 1073741824, rocksdb.write-buffer-size = 67108864 }
+//! ```
+//!
+//! The keys other than `description`, `data-size` and `data-size-large` are the long options of the command line:
+//! `true` for a flag, an array for a repeatable option, a table for a repeatable `KEY=VALUE` option with the dotted
+//! keys.
+//!
+//! The scenarios run by `--scenario NAME` are defined in the `[scenarios]` tables of the same file, see
+//! [`slate_benchmark::scenario`].
+use std::ffi::OsString;
+use std::path::Path;
+
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::scenario::Scenario;
+use toml::{Table, Value};
+
+/// Default config file in the current directory.
+pub const DEFAULT_CONFIG: &str = "slate-bench.toml";
+
+const POSITIONALS: [&str; 2] = ["data-size", "data-size-large"];
+
+pub struct Config {
+  experiments: Table,
+  scenarios: Table,
+}
+
+impl Config {
+  pub fn load(path: &Path) -> Result<Self> {
+    let text = std::fs::read_to_string(path)
+      .map_err(|err| BenchError::Config(format!("fail to read the config {}: {err}", path.to_string_lossy())))?;
+    let mut table = text
+      .parse::<Table>()
+      .map_err(|err| BenchError::Config(format!("invalid config {}: {err}", path.to_string_lossy())))?;
+    let experiments = match table.remove("experiments") {
+      Some(Value::Table(experiments)) => experiments,
+      Some(_) => return Err(BenchError::Config(String::from("[experiments] must be a table of the experiments"))),
+      None => Table::new(),
+    };
+    let scenarios = match table.remove("scenarios") {
+      Some(Value::Table(scenarios)) => scenarios,
+      Some(_) => return Err(BenchError::Config(String::from("[scenarios] must be a table of the scenarios"))),
+      None => Table::new(),
+    };
+    Ok(Self { experiments, scenarios })
+  }
+
+  /// Names and descriptions of the experiments.
+  pub fn experiments(&self) -> Vec<(&str, Option<&str>)> {
+    let description = |value: &Value| value.get("description").and_then(Value::as_str);
+    self.experiments.iter().map(|(name, value)| (name.as_str(), description(value))).collect()
+  }
+
+  pub fn scenario(&self, name: &str) -> Result<Scenario> {
+    match self.scenarios.get(name) {
+      Some(scenario) => Scenario::parse(name, scenario),
+      None => Err(BenchError::Config(format!("no scenario {name} in the config"))),
+    }
+  }
+
+  /// Command-line arguments of the experiment, without the program name.
+  pub fn args(&self, name: &str) -> Result<Vec<OsString>> {
+    let experiment = match self.experiments.get(name) {
+      Some(Value::Table(experiment)) => experiment,
+      Some(_) => return Err(BenchError::Config(format!("[experiments.{name}] must be a table"))),
+      None => return Err(BenchError::Config(format!("no experiment {name} in the config"))),
+    };
+
+    // 位置引数はオプションより前に置く
+    let mut args = Vec::<OsString>::new();
+    if experiment.contains_key(POSITIONALS[1]) && !experiment.contains_key(POSITIONALS[0]) {
+      return Err(BenchError::Config(format!("data-size-large requires data-size in the experiment {name}")));
+    }
+    for key in POSITIONALS {
+      if let Some(value) = experiment.get(key) {
+        args.push(scalar(name, key, value)?.into());
+      }
+    }
+    for (key, value) in
+      experiment.iter().filter(|(key, _)| *key != "description" && !POSITIONALS.contains(&key.as_str()))
+    {
+      let option = format!("--{key}");
+      match value {
+        Value::Boolean(true) => args.push(option.into()),
+        Value::Boolean(false) => (),
+        Value::Array(values) => {
+          for value in values {
+            args.push(option.clone().into());
+            args.push(scalar(name, key, value)?.into());
+          }
+        }
+        Value::Table(values) => {
+          for (subkey, value) in flatten("", values) {
+            args.push(option.clone().into());
+            args.push(format!("{subkey}={}", scalar(name, key, value)?).into());
+          }
+        }
+        value => {
+          args.push(option.into());
+          args.push(scalar(name, key, value)?.into());
+        }
+      }
+    }
+    Ok(args)
+  }
+}
+
+/// Pairs of the dotted keys and the values of the nested tables, e.g. `rocksdb.block-cache-size` for
+/// `{ rocksdb = { block-cache-size = 1 } }`.
+fn flatten<'a>(prefix: &str, table: &'a Table) -> Vec<(String, &'a Value)> {
+  let mut pairs = Vec::new();
+  for (key, value) in table {
+    let key = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+    match value {
+      Value::Table(table) => pairs.extend(flatten(&key, table)),
+      value => pairs.push((key, value)),
+    }
+  }
+  pairs
+}
+
+fn scalar(name: &str, key: &str, value: &Value) -> Result<String> {
+  match value {
+    Value::String(value) => Ok(value.clone()),
+    Value::Integer(value) => Ok(value.to_string()),
+    Value::Float(value) => Ok(value.to_string()),
+    Value::Boolean(value) => Ok(value.to_string()),
+    _ => Err(BenchError::Config(format!("unsupported value of {key} in the experiment {name}: {value}"))),
+  }
+}
@@ -0,0 +1,228 @@
+//! Instrumentation of any [`Storage`] with the number of calls, the bytes transferred and the latency histogram of each
+//! kind of call, e.g. to derive the read and write amplification of the file and RocksDB backends of slate.
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use slate::{Position, Reader, Result, Serializable, Storage};
+
+use crate::clock::Stopwatch;
+
+#[cfg(test)]
+mod test;
+
+/// Number of the buckets of the latency histograms; bucket k counts the calls taking [2^k, 2^(k+1)) nanoseconds.
+pub const BUCKETS: usize = 64;
+
+/// Calls of one kind on a [`CountingStorage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallStats {
+  pub count: u64,
+  /// Serialized size of the entries written or read
+  pub bytes: u64,
+  pub elapsed: Duration,
+  /// Latency histogram by powers of 2 in nanoseconds, see [`BUCKETS`]
+  pub histogram: [u64; BUCKETS],
+}
+
+impl Default for CallStats {
+  fn default() -> Self {
+    CallStats { count: 0, bytes: 0, elapsed: Duration::ZERO, histogram: [0; BUCKETS] }
+  }
+}
+
+impl CallStats {
+  /// Upper bound of the latency below which the fraction `p` of the calls fall, or zero without calls.
+  pub fn percentile(&self, p: f64) -> Duration {
+    let target = (self.count as f64 * p).ceil().max(1.0) as u64;
+    let mut count = 0;
+    for (k, n) in self.histogram.iter().enumerate() {
+      count += n;
+      if count >= target {
+        return Duration::from_nanos(1u64.checked_shl(k as u32 + 1).unwrap_or(u64::MAX));
+      }
+    }
+    Duration::ZERO
+  }
+}
+
+/// Snapshot of the calls on a [`CountingStorage`] and the readers it created.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageStats {
+  pub first: CallStats,
+  pub last: CallStats,
+  pub put: CallStats,
+  pub read: CallStats,
+}
+
+impl StorageStats {
+  /// Bytes written to the storage per byte of the application data.
+  pub fn write_amplification(&self, logical_bytes: u64) -> f64 {
+    self.put.bytes as f64 / logical_bytes as f64
+  }
+
+  /// Bytes read from the storage per byte of the application data.
+  pub fn read_amplification(&self, logical_bytes: u64) -> f64 {
+    (self.first.bytes + self.last.bytes + self.read.bytes) as f64 / logical_bytes as f64
+  }
+}
+
+struct CallCounter {
+  count: AtomicU64,
+  bytes: AtomicU64,
+  nanos: AtomicU64,
+  histogram: [AtomicU64; BUCKETS],
+}
+
+impl Default for CallCounter {
+  fn default() -> Self {
+    CallCounter {
+      count: AtomicU64::new(0),
+      bytes: AtomicU64::new(0),
+      nanos: AtomicU64::new(0),
+      histogram: std::array::from_fn(|_| AtomicU64::new(0)),
+    }
+  }
+}
+
+impl CallCounter {
+  fn record(&self, elapsed: Duration, bytes: u64) {
+    let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+    self.count.fetch_add(1, Ordering::Relaxed);
+    self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    self.nanos.fetch_add(nanos, Ordering::Relaxed);
+    self.histogram[nanos.max(1).ilog2() as usize].fetch_add(1, Ordering::Relaxed);
+  }
+
+  fn snapshot(&self) -> CallStats {
+    CallStats {
+      count: self.count.load(Ordering::Relaxed),
+      bytes: self.bytes.load(Ordering::Relaxed),
+      elapsed: Duration::from_nanos(self.nanos.load(Ordering::Relaxed)),
+      histogram: std::array::from_fn(|k| self.histogram[k].load(Ordering::Relaxed)),
+    }
+  }
+
+  fn reset(&self) {
+    self.count.store(0, Ordering::Relaxed);
+    self.bytes.store(0, Ordering::Relaxed);
+    self.nanos.store(0, Ordering::Relaxed);
+    self.histogram.iter().for_each(|n| n.store(0, Ordering::Relaxed));
+  }
+}
+
+/// Counters shared by a [`CountingStorage`], its readers and the clones of this handle, so that the calls can be
+/// retrieved after a test unit while slate owns the storage.
+#[derive(Clone, Default)]
+pub struct StorageCounter(Arc<[CallCounter; 4]>);
+
+impl StorageCounter {
+  const FIRST: usize = 0;
+  const LAST: usize = 1;
+  const PUT: usize = 2;
+  const READ: usize = 3;
+
+  pub fn snapshot(&self) -> StorageStats {
+    StorageStats {
+      first: self.0[Self::FIRST].snapshot(),
+      last: self.0[Self::LAST].snapshot(),
+      put: self.0[Self::PUT].snapshot(),
+      read: self.0[Self::READ].snapshot(),
+    }
+  }
+
+  pub fn reset(&self) {
+    self.0.iter().for_each(CallCounter::reset);
+  }
+}
+
+/// [`Storage`] that counts the calls on the wrapped storage. The latency covers only the call on the wrapped storage;
+/// the bytes are the serialized size of the entries, measured out of the latency.
+pub struct CountingStorage<S> {
+  inner: S,
+  counter: StorageCounter,
+}
+
+impl<S> CountingStorage<S> {
+  pub fn new(inner: S) -> Self {
+    Self::with_counter(inner, StorageCounter::default())
+  }
+
+  /// Wrap the storage counting into the given counters, e.g. to accumulate the calls over the storages that a CUT
+  /// opens again.
+  pub fn with_counter(inner: S, counter: StorageCounter) -> Self {
+    CountingStorage { inner, counter }
+  }
+
+  pub fn counter(&self) -> StorageCounter {
+    self.counter.clone()
+  }
+
+  pub fn into_inner(self) -> S {
+    self.inner
+  }
+}
+
+impl<T: Serializable + 'static, S: Storage<T>> Storage<T> for CountingStorage<S> {
+  fn first(&mut self) -> Result<(Option<T>, Position)> {
+    let start = Stopwatch::start();
+    let (first, position) = self.inner.first()?;
+    let elapsed = start.elapsed();
+    self.counter.0[StorageCounter::FIRST].record(elapsed, first.as_ref().map_or(Ok(0), serialized_size)?);
+    Ok((first, position))
+  }
+
+  fn last(&mut self) -> Result<(Option<T>, Position)> {
+    let start = Stopwatch::start();
+    let (last, position) = self.inner.last()?;
+    let elapsed = start.elapsed();
+    self.counter.0[StorageCounter::LAST].record(elapsed, last.as_ref().map_or(Ok(0), serialized_size)?);
+    Ok((last, position))
+  }
+
+  fn put(&mut self, position: Position, data: &T) -> Result<Position> {
+    let start = Stopwatch::start();
+    let next = self.inner.put(position, data)?;
+    let elapsed = start.elapsed();
+    self.counter.0[StorageCounter::PUT].record(elapsed, serialized_size(data)?);
+    Ok(next)
+  }
+
+  fn reader(&self) -> Result<Box<dyn Reader<T>>> {
+    let inner = self.inner.reader()?;
+    Ok(Box::new(CountingReader { inner, counter: self.counter.clone() }))
+  }
+}
+
+struct CountingReader<T> {
+  inner: Box<dyn Reader<T>>,
+  counter: StorageCounter,
+}
+
+impl<T: Serializable> Reader<T> for CountingReader<T> {
+  fn read(&mut self, position: Position) -> Result<T> {
+    let start = Stopwatch::start();
+    let value = self.inner.read(position)?;
+    let elapsed = start.elapsed();
+    self.counter.0[StorageCounter::READ].record(elapsed, serialized_size(&value)?);
+    Ok(value)
+  }
+}
+
+/// Number of bytes that the entry is serialized into; the size returned by [`Serializable::write`] isn't always exact.
+fn serialized_size<T: Serializable>(value: &T) -> Result<u64> {
+  struct Counter(u64);
+  impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0 += buf.len() as u64;
+      Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+  let mut counter = Counter(0);
+  value.write(&mut counter)?;
+  Ok(counter.0)
+}
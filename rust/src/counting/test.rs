@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use slate::{Serializable, Storage};
+
+use super::{CallStats, CountingStorage};
+use crate::MemKVS;
+use crate::hashtree::binary::Node;
+use crate::hashtree::hash::Blake3;
+
+#[test]
+fn count_calls_and_bytes() {
+  let mut storage = CountingStorage::new(MemKVS::<Node>::new());
+  let counter = storage.counter();
+  let nodes = (1..=3).map(|i| Node::new_leaf::<Blake3>(i, i, vec![0u8; 8 * i as usize])).collect::<Vec<_>>();
+  let mut position = storage.first().unwrap().1;
+  for node in nodes.iter() {
+    position = storage.put(position, node).unwrap();
+  }
+  let mut reader = storage.reader().unwrap();
+  reader.read(2).unwrap();
+  reader.read(2).unwrap();
+
+  let size = |node: &Node| {
+    let mut buffer = Vec::new();
+    node.write(&mut buffer).unwrap();
+    buffer.len() as u64
+  };
+  let stats = counter.snapshot();
+  assert_eq!(1, stats.first.count);
+  assert_eq!(0, stats.first.bytes);
+  assert_eq!(3, stats.put.count);
+  assert_eq!(nodes.iter().map(size).sum::<u64>(), stats.put.bytes);
+  assert_eq!(2, stats.read.count);
+  assert_eq!(2 * size(&nodes[1]), stats.read.bytes);
+  assert_eq!(2, stats.read.histogram.iter().sum::<u64>());
+  assert_eq!(0.5, stats.write_amplification(2 * stats.put.bytes));
+
+  counter.reset();
+  assert_eq!(0, counter.snapshot().put.count);
+}
+
+#[test]
+fn percentile_of_the_histogram() {
+  let mut stats = CallStats::default();
+  assert_eq!(Duration::ZERO, stats.percentile(0.5));
+  stats.histogram[3] = 90;
+  stats.histogram[10] = 10;
+  stats.count = 100;
+  assert_eq!(Duration::from_nanos(16), stats.percentile(0.5));
+  assert_eq!(Duration::from_nanos(16), stats.percentile(0.9));
+  assert_eq!(Duration::from_nanos(2048), stats.percentile(0.99));
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::report::write_atomically;
+
+const CPU_DIR: &str = "/sys/devices/system/cpu";
+const THERMAL_DIR: &str = "/sys/class/thermal";
+
+/// 最初のサンプルからこの割合を下回る周波数をスロットリングとみなす
+const FREQUENCY_DROP_RATIO: f64 = 0.9;
+/// この温度 (℃) 以上をスロットリングとみなす
+const THERMAL_LIMIT: f64 = 85.0;
+const SAMPLING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Warn if the CPU frequency is not fixed, i.e. the governor is not `performance` or the turbo boost is enabled.
+pub fn check_frequency_scaling() {
+  let mut governors = cpus().filter_map(|cpu| read_line(&cpu.join("cpufreq/scaling_governor"))).collect::<Vec<_>>();
+  governors.sort();
+  governors.dedup();
+  if governors.iter().any(|governor| governor != "performance") {
+    println!("*** WARNING: CPU frequency governor is {governors:?}, not \"performance\" ***");
+  }
+  let turbo = match read_line(&Path::new(CPU_DIR).join("intel_pstate/no_turbo")) {
+    Some(no_turbo) => no_turbo == "0",
+    None => read_line(&Path::new(CPU_DIR).join("cpufreq/boost")).is_some_and(|boost| boost == "1"),
+  };
+  if turbo {
+    println!("*** WARNING: CPU turbo boost is enabled, the results may vary with the temperature ***");
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+  at: Instant,
+  /// Highest current frequency among the CPUs in MHz
+  frequency: Option<f64>,
+  /// Highest temperature among the thermal zones in ℃
+  temperature: Option<f64>,
+}
+
+impl Sample {
+  fn take() -> Self {
+    let frequency = cpus()
+      .filter_map(|cpu| read_line(&cpu.join("cpufreq/scaling_cur_freq"))?.parse::<f64>().ok())
+      .map(|khz| khz / 1000.0)
+      .reduce(f64::max);
+    let temperature = fs::read_dir(THERMAL_DIR)
+      .into_iter()
+      .flatten()
+      .flatten()
+      .filter(|e| e.file_name().to_string_lossy().starts_with("thermal_zone"))
+      .filter_map(|e| read_line(&e.path().join("temp"))?.parse::<f64>().ok())
+      .map(|millis| millis / 1000.0)
+      .reduce(f64::max);
+    Sample { at: Instant::now(), frequency, temperature }
+  }
+}
+
+static SAMPLES: OnceLock<Mutex<Vec<Sample>>> = OnceLock::new();
+
+/// Start sampling the CPU frequency and temperature in the background until the process exits.
+pub fn start_monitor() {
+  if SAMPLES.set(Mutex::new(vec![Sample::take()])).is_ok() {
+    std::thread::spawn(|| {
+      loop {
+        std::thread::sleep(SAMPLING_INTERVAL);
+        SAMPLES.get().unwrap().lock().unwrap().push(Sample::take());
+      }
+    });
+  }
+}
+
+/// Describe the throttling observed since `since`, if the monitor is running and detected it.
+pub fn throttling_since(since: Instant) -> Option<String> {
+  let samples = SAMPLES.get()?.lock().unwrap();
+  let baseline = samples.first()?.frequency;
+  let mut reasons = Vec::new();
+  let recent = samples.iter().filter(|s| s.at >= since);
+  if let (Some(baseline), Some(min)) = (baseline, recent.clone().filter_map(|s| s.frequency).reduce(f64::min))
+    && min < baseline * FREQUENCY_DROP_RATIO
+  {
+    reasons.push(format!("{min:.0}MHz < {baseline:.0}MHz"));
+  }
+  if let Some(max) = recent.filter_map(|s| s.temperature).reduce(f64::max)
+    && max >= THERMAL_LIMIT
+  {
+    reasons.push(format!("{max:.0}℃"));
+  }
+  if reasons.is_empty() { None } else { Some(reasons.join(", ")) }
+}
+
+/// Entries of the session metadata with the throttling observed over the run, `cpu.throttling.{cut}` if the CUT
+/// runs in its own process. Empty unless the monitor is running.
+pub fn metadata(cut: Option<&str>) -> Vec<(String, String)> {
+  let Some(start) = SAMPLES.get().and_then(|samples| samples.lock().unwrap().first().map(|s| s.at)) else {
+    return Vec::new();
+  };
+  let key = cut.map(|cut| format!("cpu.throttling.{cut}")).unwrap_or(String::from("cpu.throttling"));
+  vec![(key, throttling_since(start).unwrap_or(String::from("none")))]
+}
+
+/// Save the samples so far as CSV, if the monitor is running.
+pub fn save_samples(path: &PathBuf) -> Result<()> {
+  let Some(samples) = SAMPLES.get() else {
+    return Ok(());
+  };
+  let samples = samples.lock().unwrap();
+  let Some(first) = samples.first() else {
+    return Ok(());
+  };
+  write_atomically(path, |writer| {
+    writeln!(writer, "SECONDS,MHZ,CELSIUS")?;
+    for s in samples.iter() {
+      let frequency = s.frequency.map(|f| format!("{f:.0}")).unwrap_or_default();
+      let temperature = s.temperature.map(|t| format!("{t:.1}")).unwrap_or_default();
+      writeln!(writer, "{:.1},{frequency},{temperature}", (s.at - first.at).as_secs_f64())?;
+    }
+    Ok(())
+  })
+}
+
+fn cpus() -> impl Iterator<Item = PathBuf> {
+  fs::read_dir(CPU_DIR).into_iter().flatten().flatten().map(|e| e.path()).filter(|path| {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.strip_prefix("cpu").is_some_and(|i| !i.is_empty() && i.chars().all(|c| c.is_ascii_digit()))
+  })
+}
+
+fn read_line(path: &Path) -> Option<String> {
+  fs::read_to_string(path).ok().map(|line| String::from(line.trim()))
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use serde_json::json;
+
+use crate::error::Result;
+use crate::stat::Stat;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Progress and the latest results of the running session shown by the dashboard.
+struct State {
+  started: Instant,
+  /// Report that was updated last, i.e. the test unit running now
+  current: Option<String>,
+  trials: usize,
+  max_trials: usize,
+  eta: String,
+  /// Mean and standard deviation for each x-value of each report, in the order the x-values are measured
+  series: BTreeMap<String, Vec<(String, f64, f64)>>,
+}
+
+static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+
+/// Start the HTTP server of the dashboard on `addr` in the background until the process exits. Once started, the
+/// reports and the timers of the test units feed their progress to it.
+pub fn start(addr: &str) -> Result<()> {
+  let listener = TcpListener::bind(addr)?;
+  let state = State {
+    started: Instant::now(),
+    current: None,
+    trials: 0,
+    max_trials: 0,
+    eta: String::new(),
+    series: BTreeMap::new(),
+  };
+  if STATE.set(Mutex::new(state)).is_ok() {
+    crate::monitor::enable();
+    println!("Dashboard: http://{}/", listener.local_addr()?);
+    std::thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        // ダッシュボードの接続エラーはベンチマークを中断しない
+        let _ = respond(stream);
+      }
+    });
+  }
+  Ok(())
+}
+
+/// Update the curve of the report with the statistics of `x`, if the dashboard is running.
+pub(crate) fn update(report: &Path, x: String, stat: &Stat) {
+  let Some(state) = STATE.get() else {
+    return;
+  };
+  let name = report.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+  let mut state = state.lock().unwrap();
+  let series = state.series.entry(name.clone()).or_default();
+  match series.iter_mut().find(|(x0, _, _)| *x0 == x) {
+    Some(point) => *point = (x, stat.mean, stat.std_dev),
+    None => series.push((x, stat.mean, stat.std_dev)),
+  }
+  state.current = Some(name);
+}
+
+/// Update the number of the trials carried out and the ETA of the running test unit, if the dashboard is running.
+pub(crate) fn progress(trials: usize, max_trials: usize, eta: String) {
+  if let Some(state) = STATE.get() {
+    let mut state = state.lock().unwrap();
+    (state.trials, state.max_trials, state.eta) = (trials, max_trials, eta);
+  }
+}
+
+fn respond(mut stream: TcpStream) -> Result<()> {
+  let mut request = String::new();
+  BufReader::new(&stream).read_line(&mut request)?;
+  let (status, content_type, body) = match request.split_whitespace().nth(1) {
+    Some("/") => ("200 OK", "text/html; charset=utf-8", String::from(PAGE)),
+    Some("/state") => ("200 OK", "application/json", state_json()),
+    _ => ("404 Not Found", "text/plain", String::from("not found")),
+  };
+  write!(
+    stream,
+    "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+     Connection: close\r\n\r\n{body}",
+    body.len()
+  )?;
+  Ok(())
+}
+
+fn state_json() -> String {
+  let state = STATE.get().unwrap().lock().unwrap();
+  let series = state
+    .series
+    .iter()
+    .map(|(name, points)| {
+      let points = points.iter().map(|(x, mean, std_dev)| json!({"x": x, "mean": mean, "std_dev": std_dev}));
+      json!({"name": name, "points": points.collect::<Vec<_>>()})
+    })
+    .collect::<Vec<_>>();
+  json!({
+    "elapsed": state.started.elapsed().as_secs(),
+    "current": state.current,
+    "trials": state.trials,
+    "max_trials": state.max_trials,
+    "eta": state.eta,
+    "series": series,
+  })
+  .to_string()
+}
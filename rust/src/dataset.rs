@@ -0,0 +1,246 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use slate::Index;
+use slate_benchmark::CleanPolicy;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::hashtree::hash::{HASH_SIZE, HashValue};
+use slate_benchmark::platform;
+
+/// Name and seed of the value generator used by all test units, see [`splitmix64`](slate_benchmark::splitmix64).
+pub const VALUE_GENERATOR: &str = "splitmix64";
+pub const VALUE_SEED: u64 = 0;
+
+const FINGERPRINT: &str = "FINGERPRINT";
+const DATA: &str = "data";
+const MANIFEST: &str = "MANIFEST";
+
+/// Content of the prepared database that must match to reuse it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+  pub n: Index,
+  pub root: HashValue,
+}
+
+impl Fingerprint {
+  fn to_line(self) -> String {
+    let root = self.root.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    format!("{} {root}\n", self.n)
+  }
+
+  fn from_line(line: &str) -> Option<Self> {
+    let (n, root) = line.trim().split_once(' ')?;
+    let n = n.parse().ok()?;
+    if root.len() != HASH_SIZE * 2 {
+      return None;
+    }
+    let mut hash = [0u8; HASH_SIZE];
+    for (i, b) in hash.iter_mut().enumerate() {
+      *b = u8::from_str_radix(&root[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(Fingerprint { n, root: hash })
+  }
+}
+
+/// 構築済みデータベースのセッションをまたいだキャッシュ。
+///
+/// データベースはバックエンド名、エントリ数、値の生成方法から決まるキーのディレクトリに、その内容のフィンガープリント
+/// (エントリ数とルートハッシュ) とともに保存される。キャッシュのデータベースは直接開かず、CUT の作業ディレクトリに
+/// 複製してから使用するため、計測はキャッシュのデバイスではなく対象のデバイスで行われ、キャッシュが書き換えられることも
+/// ない。対象はハッシュ木の CUT のみで、slate の CUT は毎回構築する。
+///
+/// ```text
+/// {dir}/{key}/data         データベースのファイルまたはディレクトリ
+/// {dir}/{key}/FINGERPRINT  "{n} {root hash in hex}"
+/// ```
+pub struct DatasetCache {
+  dir: PathBuf,
+}
+
+impl DatasetCache {
+  pub fn new(dir: &Path) -> Result<Self> {
+    fs::create_dir_all(dir)?;
+    Ok(Self { dir: dir.to_path_buf() })
+  }
+
+  /// Content-addressed key of the database with n entries on the backend.
+  pub fn key(backend: &str, n: Index) -> String {
+    let id = format!("{backend}\n{n}\n{VALUE_GENERATOR}\n{VALUE_SEED}");
+    let hash = blake3::hash(id.as_bytes());
+    format!("{backend}-{n}-{}", &hash.to_hex()[..16])
+  }
+
+  /// Copy the cached database to `path`, where it's benchmarked, if it exists and `fingerprint` computed from the copy
+  /// matches the stored one, and mark the entry as used now for [`clean`](Self::clean). The cached database itself is
+  /// never opened. The copy is written back before returning, so that its write-back doesn't run into the measurement
+  /// on a disk but not on tmpfs. A database that doesn't match is removed from the cache, while an error computing the
+  /// fingerprint is returned without removing it.
+  ///
+  /// ## Returns
+  /// - whether the database has been restored to `path`
+  pub fn restore<F>(&self, key: &str, path: &Path, fingerprint: F) -> Result<bool>
+  where
+    F: FnOnce(&Path) -> Result<Fingerprint>,
+  {
+    let dir = self.dir.join(key);
+    let stored = match fs::read_to_string(dir.join(FINGERPRINT)) {
+      Ok(line) => Fingerprint::from_line(&line),
+      Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+      Err(err) => return Err(err.into()),
+    };
+    let data = dir.join(DATA);
+    let valid = match stored {
+      Some(stored) if data.exists() => {
+        copy(&data, path)?;
+        platform::sync();
+        fingerprint(path)? == stored
+      }
+      _ => false,
+    };
+    if valid {
+      // --clean --older-than が使用中のデータベースを削除しないよう、ヒットした時刻を最終更新時刻とする
+      fs::File::open(&dir)?.set_modified(SystemTime::now())?;
+      Ok(true)
+    } else {
+      eprintln!("WARN: the cached dataset {key} doesn't match its fingerprint, discarded");
+      remove(path)?;
+      fs::remove_dir_all(&dir)?;
+      Ok(false)
+    }
+  }
+
+  /// Copy the database built at `path` into the cache with its fingerprint.
+  pub fn store(&self, key: &str, path: &Path, fingerprint: Fingerprint) -> Result<PathBuf> {
+    let dir = self.dir.join(key);
+    if dir.exists() {
+      fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    let data = dir.join(DATA);
+    copy(path, &data)?;
+
+    // フィンガープリントは最後に書き込み、コピーが中断したデータベースを再利用しないようにする
+    fs::write(dir.join(FINGERPRINT), fingerprint.to_line())?;
+    Ok(data)
+  }
+
+  /// Keys of all the databases in the cache.
+  pub fn keys(&self) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    for entry in fs::read_dir(&self.dir)? {
+      let entry = entry?;
+      if entry.path().join(FINGERPRINT).is_file() {
+        keys.push(entry.file_name().to_string_lossy().into_owned());
+      }
+    }
+    keys.sort();
+    Ok(keys)
+  }
+
+  /// Remove the databases selected by the policy.
+  pub fn clean(&self, policy: &CleanPolicy) -> Result<()> {
+    policy.apply(&self.keys()?.iter().map(|key| self.dir.join(key)).collect::<Vec<_>>())
+  }
+
+  /// Export the databases to a tar archive with a manifest, so that the same datasets can be benchmarked on another
+  /// host by [`import`](Self::import)ing it. All databases are exported if `keys` is empty. As the cache, it covers
+  /// only the hash trees.
+  ///
+  /// ```text
+  /// MANIFEST  "{generator} {seed}" followed by "{key} {n} {root hash in hex}" for each database
+  /// {key}/... the entries of the cache
+  /// ```
+  pub fn export(&self, keys: &[String], archive: &Path) -> Result<()> {
+    let keys = if keys.is_empty() { self.keys()? } else { keys.to_vec() };
+    let mut manifest = format!("{VALUE_GENERATOR} {VALUE_SEED}\n");
+    for key in keys.iter() {
+      let line = fs::read_to_string(self.dir.join(key).join(FINGERPRINT))?;
+      manifest.push_str(&format!("{key} {}", line.trim_end()));
+      manifest.push('\n');
+    }
+    let staging = tempfile::tempdir()?;
+    fs::write(staging.path().join(MANIFEST), manifest)?;
+
+    let mut tar = Command::new("tar");
+    tar.arg("-cf").arg(archive).arg("-C").arg(staging.path()).arg(MANIFEST).arg("-C").arg(&self.dir).args(&keys);
+    run(tar)
+  }
+
+  /// Import the databases from the archive created by [`export`](Self::export), replacing the databases with the same
+  /// keys. The fingerprints are verified when the databases are looked up.
+  ///
+  /// ## Returns
+  /// - keys of the imported databases
+  pub fn import(&self, archive: &Path) -> Result<Vec<String>> {
+    let staging = tempfile::tempdir_in(&self.dir)?;
+    let mut tar = Command::new("tar");
+    tar.arg("-xf").arg(archive).arg("-C").arg(staging.path());
+    run(tar)?;
+
+    let manifest = fs::read_to_string(staging.path().join(MANIFEST))?;
+    let mut lines = manifest.lines();
+    let expected = format!("{VALUE_GENERATOR} {VALUE_SEED}");
+    match lines.next() {
+      Some(generator) if generator == expected => (),
+      generator => {
+        let msg = format!("the datasets are generated by {generator:?}, but {expected} is used on this host");
+        return Err(BenchError::Validation(msg));
+      }
+    }
+
+    let mut keys = Vec::new();
+    for line in lines {
+      let (key, fingerprint) = line.split_once(' ').unwrap_or((line, ""));
+      // キーはキャッシュ直下のディレクトリ名でなければならない (../ などでキャッシュの外に書き込ませない)
+      if !matches!(Path::new(key).components().collect::<Vec<_>>()[..], [Component::Normal(_)]) {
+        return Err(BenchError::Validation(format!("invalid key {key:?} in the manifest")));
+      }
+      let imported = staging.path().join(key);
+      let stored = fs::read_to_string(imported.join(FINGERPRINT)).unwrap_or_default();
+      if Fingerprint::from_line(fingerprint).is_none() || stored.trim_end() != fingerprint {
+        return Err(BenchError::Validation(format!("{key} doesn't match the manifest")));
+      }
+      let dir = self.dir.join(key);
+      if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+      }
+      fs::rename(&imported, &dir)?;
+      keys.push(String::from(key));
+    }
+    Ok(keys)
+  }
+}
+
+fn run(mut command: Command) -> Result<()> {
+  let status = command.status()?;
+  if status.success() { Ok(()) } else { Err(Error::other(format!("{command:?} failed: {status}")).into()) }
+}
+
+/// Copy the file or the directory. `fs::copy` uses `copy_file_range(2)` on Linux, which shares the extents on the file
+/// systems supporting reflinks.
+fn copy(from: &Path, to: &Path) -> Result<()> {
+  if from.is_dir() {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+      let entry = entry?;
+      copy(&entry.path(), &to.join(entry.file_name()))?;
+    }
+  } else if from.is_file() {
+    fs::copy(from, to)?;
+  } else {
+    return Err(Error::new(ErrorKind::NotFound, format!("{from:?} doesn't exist")).into());
+  }
+  Ok(())
+}
+
+fn remove(path: &Path) -> Result<()> {
+  if path.is_dir() {
+    fs::remove_dir_all(path)?;
+  } else if path.exists() {
+    fs::remove_file(path)?;
+  }
+  Ok(())
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+use crate::Experiment;
+use crate::error::{BenchError, Result};
+use crate::report::append_csv;
+
+const POWERCAP_DIR: &str = "/sys/class/powercap";
+
+/// Whether a test unit is measuring the energy, so that the operations are counted only then
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// Number of the timed operations counted so far
+static OPERATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Count the timed operations on the CUT for the joules per operation. Nothing is shared between the threads unless a
+/// test unit is measuring the energy.
+pub(crate) fn count(operations: u64) {
+  if COUNTING.load(Ordering::Relaxed) {
+    OPERATIONS.fetch_add(operations, Ordering::Relaxed);
+  }
+}
+
+/// Energy counter of a CPU package from powercap/RAPL (`intel-rapl:N`). Its subdomains such as `intel-rapl:N:M` (core,
+/// uncore, dram) are included in the package, except dram on some CPUs, and aren't counted separately.
+struct Domain {
+  dir: PathBuf,
+  /// The counter wraps around to 0 at this value
+  max_energy_range: u64,
+}
+
+impl Domain {
+  fn packages() -> Vec<Domain> {
+    let mut packages = fs::read_dir(POWERCAP_DIR)
+      .into_iter()
+      .flatten()
+      .flatten()
+      .filter(|e| e.file_name().to_string_lossy().strip_prefix("intel-rapl:").is_some_and(|i| !i.contains(':')))
+      .filter_map(|e| {
+        let max_energy_range = read_u64(&e.path().join("max_energy_range_uj")).ok()?;
+        Some(Domain { dir: e.path(), max_energy_range })
+      })
+      .collect::<Vec<_>>();
+    packages.sort_by(|a, b| a.dir.cmp(&b.dir));
+    packages
+  }
+
+  /// Energy in microjoules.
+  fn energy(&self) -> Result<u64> {
+    read_u64(&self.dir.join("energy_uj"))
+  }
+}
+
+fn read_u64(path: &Path) -> Result<u64> {
+  let value = fs::read_to_string(path)?;
+  value.trim().parse().map_err(|_| BenchError::Validation(format!("invalid value in {}: {value}", path.display())))
+}
+
+/// Check if the energy counters are readable. `energy_uj` is readable only by root on recent kernels.
+pub fn check() -> Result<()> {
+  let packages = Domain::packages();
+  if packages.is_empty() || packages.iter().any(|domain| domain.energy().is_err()) {
+    let msg = format!("--energy requires readable {POWERCAP_DIR}/intel-rapl:*/energy_uj (Linux/Intel RAPL, as root)");
+    return Err(BenchError::Config(msg));
+  }
+  Ok(())
+}
+
+/// Energy consumed by the CPU packages during a test unit, reported per CUT to `{session}-energy.csv` if enabled by
+/// `--energy`. It's the energy of the whole system, including the database preparation and other processes, so the
+/// joules per timed operation is an upper bound to be compared between the CUTs in the same session.
+pub struct UnitEnergy {
+  start: Instant,
+  operations: u64,
+  /// Packages with their counters at the start, empty if disabled
+  before: Vec<(Domain, u64)>,
+}
+
+impl UnitEnergy {
+  pub fn start(experiment: &Experiment) -> Result<Self> {
+    let packages = if experiment.energy { Domain::packages() } else { Vec::new() };
+    let before = packages
+      .into_iter()
+      .map(|domain| {
+        let energy = domain.energy()?;
+        Ok((domain, energy))
+      })
+      .collect::<Result<Vec<_>>>()?;
+    COUNTING.store(!before.is_empty(), Ordering::Relaxed);
+    Ok(UnitEnergy { start: Instant::now(), operations: OPERATIONS.load(Ordering::Relaxed), before })
+  }
+
+  pub fn finish(self, experiment: &Experiment, cut: &str, unit: &str) -> Result<()> {
+    if self.before.is_empty() {
+      return Ok(());
+    }
+    let seconds = self.start.elapsed().as_secs_f64();
+    let operations = OPERATIONS.load(Ordering::Relaxed) - self.operations;
+    let mut microjoules = 0;
+    for (domain, before) in self.before.iter() {
+      // カウンタが一周した場合は最大値を加える (テストユニットの間に二周することはないものとする)
+      let after = domain.energy()?;
+      microjoules += if after >= *before { after - before } else { domain.max_energy_range - before + after };
+    }
+    let joules = microjoules as f64 / 1000.0 / 1000.0;
+    let per_operation = if operations == 0 { f64::NAN } else { joules / operations as f64 };
+    println!("energy: {joules:.3} J in {seconds:.1}[sec], {per_operation:.6} J/op for {operations} operations");
+
+    let path = experiment.report_path("energy.csv");
+    let header = "CUT,UNIT,SECONDS,JOULES,OPERATIONS,JOULES PER OPERATION";
+    append_csv(&path, header, &format!("{cut},{unit},{seconds:.3},{joules:.6},{operations},{per_operation:.9}"))
+  }
+}
+
+impl Drop for UnitEnergy {
+  fn drop(&mut self) {
+    COUNTING.store(false, Ordering::Relaxed);
+  }
+}
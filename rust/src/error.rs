@@ -0,0 +1,84 @@
+use std::io::ErrorKind;
+
+use thiserror::Error;
+
+/// Errors that abort a test unit. The session continues with the next CUT, so that the reports of the completed test
+/// units are still written.
+#[derive(Debug, Error)]
+pub enum BenchError {
+  #[error("I/O error: {0}")]
+  Io(#[from] std::io::Error),
+
+  /// Error from the storage or the hash tree under test
+  #[error("storage error: {0}")]
+  Storage(#[from] slate::error::Error),
+
+  /// Invalid command line arguments or environment
+  #[error("configuration error: {0}")]
+  Config(String),
+
+  /// The CUT returned a value or a result different from the expected one
+  #[error("validation error: {0}")]
+  Validation(String),
+
+  /// The operation didn't run to the end, e.g. a worker thread panicked
+  #[error("interrupted: {0}")]
+  Interrupted(String),
+
+  /// The working directories of the session exceeded `--workdir-quota`
+  #[error("quota exceeded: {0}")]
+  Quota(String),
+}
+
+impl BenchError {
+  /// Whether the error may not occur on a retry, such as a lock held by another process or a temporary shortage of the
+  /// disk space. The storage errors are judged by the kind of the I/O error they wrap, or else by the whole words of
+  /// their messages since the backends such as RocksDB report the errors only as messages.
+  pub fn is_transient(&self) -> bool {
+    const TRANSIENT_MESSAGES: [&str; 6] =
+      ["lock", "temporarily unavailable", "resource busy", "no space left", "timed out", "interrupted system call"];
+    match self {
+      Self::Io(err) => is_transient_kind(err.kind()),
+      Self::Storage(err) => {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+        while let Some(err) = source {
+          if let Some(err) = err.downcast_ref::<std::io::Error>() {
+            return is_transient_kind(err.kind());
+          }
+          source = err.source();
+        }
+        let msg = err.to_string().to_lowercase();
+        let words = msg.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect::<Vec<_>>();
+        TRANSIENT_MESSAGES.iter().any(|m| {
+          let phrase = m.split(' ').collect::<Vec<_>>();
+          words.windows(phrase.len()).any(|w| w == phrase)
+        })
+      }
+      Self::Config(_) | Self::Validation(_) | Self::Interrupted(_) | Self::Quota(_) => false,
+    }
+  }
+}
+
+fn is_transient_kind(kind: ErrorKind) -> bool {
+  matches!(
+    kind,
+    ErrorKind::Interrupted
+      | ErrorKind::WouldBlock
+      | ErrorKind::TimedOut
+      | ErrorKind::ResourceBusy
+      | ErrorKind::StorageFull
+      | ErrorKind::QuotaExceeded
+  )
+}
+
+pub type Result<T> = std::result::Result<T, BenchError>;
+
+/// Return [`BenchError::Validation`] with the formatted message unless the condition holds.
+#[macro_export]
+macro_rules! ensure {
+  ($cond:expr, $($arg:tt)+) => {
+    if !$cond {
+      return Err($crate::error::BenchError::Validation(format!($($arg)+)));
+    }
+  };
+}
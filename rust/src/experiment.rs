@@ -0,0 +1,3009 @@
+//! Measurement of the test units on a CUT (Component under Test).
+//!
+//! A storage is benchmarked by implementing [`CUT`] and the traits of the operations it supports, and running the test
+//! units of an [`Experiment`] on it. The reports are written as CSV into the report directory.
+//!
+//! ```ignore
+//! let experiment = Experiment::new("my-session", &work_dir, &report_dir)?.profile(Profile::Simple);
+//! experiment.run_testunit_uniformed_get(&mut MyStorageCUT::new(&work_dir)?, &DataSize::Small(10_000))?;
+//! ```
+use ::slate::formula::{entry_access_distance, entry_access_distance_limits};
+use ::slate::{Index, Serializable};
+use chrono::Local;
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rayon::iter::Either;
+use rayon::prelude::*;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::alloc;
+use crate::clock::{self, Stopwatch};
+use crate::counting::StorageCounter;
+use crate::energy;
+use crate::error::{BenchError, Result};
+use crate::hashtree::binary::MAX_DATA_SIZE;
+use crate::hashtree::hash::HashAlgorithm;
+use crate::interleave;
+use crate::payload;
+use crate::perf::PerfRecorder;
+use crate::quiesce::Quiesce;
+use crate::quota::WorkdirQuota;
+use crate::report::{self, SessionMetadata, append_csv, write_atomically};
+use crate::samples::{RawOutput, RawSamples};
+use crate::scenario::{Distribution, Scenario, Step};
+use crate::stat::{self, ExpirationTimer, Unit, XYReport};
+use crate::sweep::{Sweep, SweepPoint};
+use crate::syscall::SyscallRecorder;
+use crate::trace::{self, Operation};
+use crate::writer;
+use crate::{ZipfSampler, file_size, splitmix64};
+
+#[cfg(test)]
+mod test;
+
+macro_rules! property_decl {
+  ($name:ident, $type:ident) => {
+    pub fn $name(mut self, $name: $type) -> Self {
+      self.$name = $name;
+      self
+    }
+  };
+  // Simple プロファイルでは固定値を維持する
+  ($name:ident, $type:ident, fixed_in_simple) => {
+    pub fn $name(mut self, $name: $type) -> Self {
+      if self.profile != Profile::Simple {
+        self.$name = $name;
+      }
+      self
+    }
+  };
+}
+
+/// 計測の試行方法。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+  /// テストユニットごとの目盛りで、変動係数が十分に小さくなるまで試行を繰り返す
+  Standard,
+  /// すべてのテストユニットで等間隔の目盛りを決まった回数だけ試行する
+  Simple,
+}
+
+/// 同じセッション ID のレポートが既に存在する場合の扱い。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportPolicy {
+  /// セッション ID に番号を付けて別のセッションとする
+  Suffix,
+  /// エラーとして終了する
+  Error,
+  /// 既存のレポートを上書きする
+  Overwrite,
+}
+
+/// 計測する取得操作ごとのバックエンドのハンドルの扱い。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HandleMode {
+  /// 開いたハンドルを取得操作の間で使い回す
+  Persistent,
+  /// 取得操作ごとにバックエンドを閉じて開き直す (開き直す時間は計測に含まない)
+  Reopen,
+}
+
+/// Length of the soak test unit and the interval of its samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Soak {
+  pub duration: Duration,
+  pub interval: Duration,
+}
+
+impl Default for Soak {
+  fn default() -> Self {
+    Soak { duration: Duration::from_secs(24 * 60 * 60), interval: Duration::from_secs(10 * 60) }
+  }
+}
+
+/// レポートディレクトリ内のファイルの配置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+  /// `{session}-{name}` のファイル名でレポートディレクトリの直下に出力する
+  Flat,
+  /// `{session}/` 以下の csv/, charts/, raw/ に分けて出力し、その一覧を manifest.json とする
+  Session,
+}
+
+impl Layout {
+  /// Path of the report `name` of the session, e.g. `append-slate-file.csv`.
+  pub fn path(&self, dir_report: &Path, session: &str, name: &str) -> PathBuf {
+    match self {
+      Layout::Flat => dir_report.join(format!("{session}-{name}")),
+      Layout::Session => {
+        let extension = Path::new(name).extension().unwrap_or_default().to_string_lossy();
+        dir_report.join(session).join(Self::subdir(&extension)).join(name)
+      }
+    }
+  }
+
+  /// Subdirectory of the session for the files with the extension in the per-session layout.
+  fn subdir(extension: &str) -> &'static str {
+    match extension {
+      "csv" => "csv",
+      "parquet" => "raw",
+      "png" | "svg" | "pdf" => "charts",
+      _ => "",
+    }
+  }
+
+  /// Path of the `{session}.session` marker file with the metadata of the session.
+  pub fn marker(&self, dir_report: &Path, session: &str) -> PathBuf {
+    match self {
+      Layout::Flat => dir_report.join(format!("{session}.session")),
+      Layout::Session => dir_report.join(session).join(format!("{session}.session")),
+    }
+  }
+}
+
+/// Selection of the entries removed by `--clean`. All the entries are removed if no limit is specified.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanPolicy {
+  /// Remove the entries last modified before this age
+  pub older_than: Option<Duration>,
+  /// Remove the oldest entries until the rest fit in this total size in bytes
+  pub max_total_size: Option<u64>,
+}
+
+impl CleanPolicy {
+  /// Select the entries to remove from `(path, last modified, size)`.
+  pub fn select<'a>(
+    &self,
+    entries: &'a [(PathBuf, SystemTime, u64)],
+    now: SystemTime,
+  ) -> Vec<&'a (PathBuf, SystemTime, u64)> {
+    if self.older_than.is_none() && self.max_total_size.is_none() {
+      return entries.iter().collect();
+    }
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(_, modified, _)| *modified);
+    let mut total = entries.iter().map(|(_, _, size)| *size).sum::<u64>();
+    let mut selected = Vec::new();
+    for entry in entries {
+      let (_, modified, size) = entry;
+      let expired = self.older_than.is_some_and(|age| now.duration_since(*modified).unwrap_or_default() > age);
+      let oversized = self.max_total_size.is_some_and(|max| total > max);
+      if expired || oversized {
+        selected.push(entry);
+        total -= size;
+      }
+    }
+    selected
+  }
+
+  /// Remove the files or directories selected from `paths`.
+  pub fn apply(&self, paths: &[PathBuf]) -> Result<()> {
+    let entries = paths
+      .iter()
+      .map(|path| Ok((path.clone(), fs::metadata(path)?.modified()?, file_size(path))))
+      .collect::<Result<Vec<_>>>()?;
+    let (mut total, mut count) = (0u64, 0);
+    for (path, _, size) in self.select(&entries, SystemTime::now()) {
+      println!("Removing: {} ({} bytes)", path.display(), size);
+      if path.is_dir() {
+        fs::remove_dir_all(path)?;
+      } else if path.is_file() {
+        fs::remove_file(path)?;
+      }
+      total += size;
+      count += 1;
+    }
+    eprintln!("{count} files are removed, total {total} bytes, {} are kept", entries.len() - count);
+    Ok(())
+  }
+}
+
+/// 計測する位置やサイズの目盛りの取り方。
+pub enum Scale {
+  Linear,
+  Log,
+  BestCase,
+  WorstCase,
+}
+
+/// A benchmark session that runs the test units on the CUTs and writes their reports. The measurement parameters are
+/// set with the builder methods and shared by all test units.
+pub struct Experiment {
+  pub(crate) session: String,
+  dir: PathBuf,
+  pub(crate) dir_report: PathBuf,
+  layout: Layout,
+  profile: Profile,
+  perf: bool,
+  syscalls: bool,
+  raw_samples: Option<RawOutput>,
+  max_samples: Option<usize>,
+  workdir_quota: Option<u64>,
+  handle_mode: HandleMode,
+  soak: Soak,
+  batch_min_time: Option<Duration>,
+  pub(crate) energy: bool,
+  discard_warmup: bool,
+  variance_components: bool,
+  abort_unreachable: bool,
+  quiesce: Quiesce,
+  replay_trace: Option<PathBuf>,
+  scenarios: Vec<Scenario>,
+
+  stability_threshold: f64, // 例: 0.10 (=10%)
+  min_trials: usize,        // 例: 5
+  max_trials: usize,        // 例: 100
+  max_duration: Duration,   // 例: Duration::from_secs(30),
+}
+
+pub struct Case {
+  pub session: String,
+  pub dir: PathBuf,
+  pub dir_report: PathBuf,
+  layout: Layout,
+  profile: Profile,
+  perf: bool,
+  syscalls: bool,
+  raw_samples: Option<RawOutput>,
+  max_samples: Option<usize>,
+  quota: Option<WorkdirQuota>,
+  handle_mode: HandleMode,
+  soak: Soak,
+  batch_min_time: Option<Duration>,
+  discard_warmup: bool,
+  variance_components: bool,
+  abort_unreachable: bool,
+  quiesce: Quiesce,
+  scale: Scale,
+  division: usize,
+  cv_threshold: f64,      // 例: 0.10 (=10%)
+  min_trials: usize,      // 例: 5
+  max_trials: usize,      // 例: 100
+  max_duration: Duration, // 例: Duration::from_secs(30),
+}
+
+impl Experiment {
+  /// Create an experiment with the default parameters. The databases are built under `dir` and the reports are
+  /// written into `dir_report`, both named after `session`.
+  pub fn new(session: &str, dir: &Path, dir_report: &Path) -> Result<Self> {
+    let session = String::from(session);
+    let dir = dir.to_path_buf();
+    let dir_report = dir_report.to_path_buf();
+
+    if !dir.exists() {
+      fs::create_dir_all(&dir)?;
+    }
+    if !dir_report.exists() {
+      fs::create_dir_all(&dir_report)?;
+    }
+
+    Ok(Self {
+      session,
+      dir,
+      dir_report,
+      layout: Layout::Flat,
+      profile: Profile::Standard,
+      perf: false,
+      syscalls: false,
+      raw_samples: None,
+      max_samples: None,
+      workdir_quota: None,
+      handle_mode: HandleMode::Persistent,
+      soak: Soak::default(),
+      batch_min_time: None,
+      energy: false,
+      discard_warmup: false,
+      variance_components: false,
+      abort_unreachable: false,
+      quiesce: Quiesce::default(),
+      replay_trace: None,
+      scenarios: Vec::new(),
+      stability_threshold: 0.05,
+      min_trials: 5,
+      max_trials: 1000,
+      max_duration: Duration::from_secs(600),
+    })
+  }
+
+  property_decl!(layout, Layout);
+  property_decl!(profile, Profile);
+  property_decl!(handle_mode, HandleMode);
+  property_decl!(soak, Soak);
+  property_decl!(perf, bool);
+  property_decl!(syscalls, bool);
+  property_decl!(energy, bool);
+  property_decl!(discard_warmup, bool);
+  property_decl!(variance_components, bool);
+  property_decl!(abort_unreachable, bool);
+  property_decl!(quiesce, Quiesce);
+  property_decl!(stability_threshold, f64);
+  property_decl!(min_trials, usize);
+  property_decl!(max_trials, usize);
+  property_decl!(max_duration, Duration);
+
+  /// Format of the raw samples exported next to the reports, if specified.
+  pub fn raw_samples(mut self, raw_samples: Option<RawOutput>) -> Self {
+    self.raw_samples = raw_samples;
+    self
+  }
+
+  /// Maximum number of the samples kept at each x-value of the reports, sampled uniformly beyond it.
+  pub fn max_samples(mut self, max_samples: Option<usize>) -> Self {
+    self.max_samples = max_samples;
+    self
+  }
+
+  /// Maximum total size in bytes of the working directories of the session, checked while the databases are prepared
+  /// and after each trial.
+  pub fn workdir_quota(mut self, workdir_quota: Option<u64>) -> Self {
+    self.workdir_quota = workdir_quota;
+    self
+  }
+
+  /// Minimum duration of a sample of the get time relative to the position, timing as many consecutive gets as needed
+  /// in a sample and dividing the time by them, if specified. It can't be combined with the reopen handle mode, perf or
+  /// syscalls, see [`check`](Self::check).
+  pub fn batch_min_time(mut self, batch_min_time: Option<Duration>) -> Self {
+    self.batch_min_time = batch_min_time;
+    self
+  }
+
+  /// Check the combination of the settings, which is also checked before each test unit runs.
+  pub fn check(&self) -> Result<()> {
+    // まとめて計測した取得の時間には、開き直しやカウンタの記録を含められない
+    if self.batch_min_time.is_some() && (self.handle_mode != HandleMode::Persistent || self.perf || self.syscalls) {
+      let msg = "--batch-min-time can't be used with --get-handle reopen, --perf or --syscalls";
+      return Err(BenchError::Config(String::from(msg)));
+    }
+    Ok(())
+  }
+
+  /// Trace whose get operations are replayed by [`run_testunit_replay`](Self::run_testunit_replay).
+  pub fn replay_trace(mut self, replay_trace: Option<PathBuf>) -> Self {
+    self.replay_trace = replay_trace;
+    self
+  }
+
+  /// Scenarios of the config run by [`run_testunit_scenario`](Self::run_testunit_scenario).
+  pub fn scenarios(mut self, scenarios: Vec<Scenario>) -> Self {
+    self.scenarios = scenarios;
+    self
+  }
+
+  /// Reserve the session ID in the report directory with a `{session}.session` marker file created exclusively, so that
+  /// the sessions with the same ID, e.g. launched within the same second, don't overwrite the reports of each other.
+  /// If the reports of the session already exist, the ID is numbered or an error is returned according to `policy`.
+  pub fn reserve_session(mut self, policy: ReportPolicy) -> Result<Self> {
+    let base = self.session.clone();
+    for i in 1.. {
+      let has_reports = match self.layout {
+        Layout::Flat => fs::read_dir(&self.dir_report)?
+          .flatten()
+          .any(|e| e.file_name().to_string_lossy().starts_with(&format!("{}-", self.session))),
+        // セッションのディレクトリは排他的に作成し、マーカーファイルとともに予約とする
+        Layout::Session => match fs::create_dir(self.dir_report.join(&self.session)) {
+          Ok(_) => false,
+          Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => true,
+          Err(err) => return Err(err.into()),
+        },
+      };
+      let marker = self.layout.marker(&self.dir_report, &self.session);
+      let reserved = !has_reports
+        && match fs::OpenOptions::new().write(true).create_new(true).open(&marker) {
+          Ok(_) => true,
+          Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => false,
+          Err(err) => return Err(err.into()),
+        };
+      match policy {
+        _ if reserved => break,
+        ReportPolicy::Overwrite => break,
+        ReportPolicy::Error => {
+          let dir = self.dir_report.to_string_lossy();
+          let msg = format!("the reports of session {base} already exist in {dir}, specify another --session");
+          return Err(BenchError::Config(msg));
+        }
+        ReportPolicy::Suffix => self.session = format!("{base}.{i}"),
+      }
+    }
+    self.create_session_dirs()?;
+    Ok(self)
+  }
+
+  /// Create the subdirectories of the session in the per-session layout.
+  fn create_session_dirs(&self) -> Result<()> {
+    if self.layout == Layout::Session {
+      for subdir in ["csv", "charts", "raw"] {
+        fs::create_dir_all(self.dir_report.join(&self.session).join(subdir))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// The experiment on another device (filesystem) whose databases are built under `dir`. The reports are tagged with
+  /// `label` as the session `{session}-{label}`, so that the results of the devices can be compared in a session.
+  pub fn on_device(&self, label: &str, dir: &Path) -> Result<Self> {
+    fs::create_dir_all(dir)?;
+    let device = Self {
+      session: format!("{}-{label}", self.session),
+      dir: dir.to_path_buf(),
+      dir_report: self.dir_report.clone(),
+      replay_trace: self.replay_trace.clone(),
+      scenarios: self.scenarios.clone(),
+      ..*self
+    };
+    device.create_session_dirs()?;
+    Ok(device)
+  }
+
+  /// Record the entries as `{key}={value}` lines in the `{session}.session` marker file, replacing the same keys.
+  pub fn set_metadata(&self, entries: &[(String, String)]) -> Result<()> {
+    set_metadata(&self.layout.marker(&self.dir_report, &self.session), entries)
+  }
+
+  /// Metadata recorded in the `{session}.session` marker file, empty if not recorded yet.
+  pub fn metadata(&self) -> Result<SessionMetadata> {
+    match fs::read_to_string(self.layout.marker(&self.dir_report, &self.session)) {
+      Ok(text) => Ok(SessionMetadata::parse(&self.session, &text)),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(SessionMetadata::parse(&self.session, "")),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  /// Append the failure of the CUT in the test unit (empty if out of the test units) to `{session}-failures.csv`.
+  pub fn record_failure(&self, cut: &str, unit: &str, err: &BenchError) -> Result<()> {
+    let path = self.report_path("failures.csv");
+    let time = Local::now().format("%Y-%m-%dT%H:%M:%S%z");
+    let err = err.to_string().replace('"', "\"\"").replace('\n', " ");
+    append_csv(&path, "TIME,CUT,UNIT,ERROR", &format!("{time},{cut},{unit},\"{err}\""))
+  }
+
+  pub fn session(&self) -> &str {
+    &self.session
+  }
+
+  pub fn dir_report(&self) -> &Path {
+    &self.dir_report
+  }
+
+  pub fn layout(&self) -> Layout {
+    self.layout
+  }
+
+  /// Path of the report `name` of the session, e.g. `alloc.csv`, according to the layout.
+  pub fn report_path(&self, name: &str) -> PathBuf {
+    self.layout.path(&self.dir_report, &self.session, name)
+  }
+
+  /// Names of the reports of the session with the extension, e.g. `append-slate-file.csv` for `csv`.
+  pub fn report_names(&self, extension: &str) -> Result<Vec<String>> {
+    let suffix = format!(".{extension}");
+    let (dir, prefix) = match self.layout {
+      Layout::Flat => (self.dir_report.clone(), format!("{}-", self.session)),
+      Layout::Session => (self.dir_report.join(&self.session).join(Layout::subdir(extension)), String::new()),
+    };
+    let mut names = fs::read_dir(dir)?
+      .flatten()
+      .filter(|e| e.path().is_file())
+      .filter_map(|e| e.file_name().to_string_lossy().strip_prefix(&prefix).map(String::from))
+      .filter(|name| name.ends_with(&suffix))
+      .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+  }
+
+  /// All the files of the session in the report directory, including the marker file.
+  pub fn report_files(&self) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    match self.layout {
+      Layout::Flat => {
+        for entry in fs::read_dir(&self.dir_report)? {
+          let path = entry?.path();
+          let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+          let marker = self.layout.marker(&self.dir_report, &self.session);
+          if path.is_file() && (name.starts_with(&format!("{}-", self.session)) || path == marker) {
+            files.push(path);
+          }
+        }
+      }
+      Layout::Session => {
+        let mut dirs = vec![self.dir_report.join(&self.session)];
+        while let Some(dir) = dirs.pop() {
+          for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            // 書き込み中の一時ファイルは含めない
+            if path.is_dir() {
+              dirs.push(path);
+            } else if !path.file_name().unwrap_or_default().to_string_lossy().starts_with('.') {
+              files.push(path);
+            }
+          }
+        }
+      }
+    }
+    files.sort();
+    Ok(files)
+  }
+
+  /// Write `manifest.json` with the metadata and the files of the session in the per-session layout, so that tools
+  /// find the reports without parsing the file names.
+  ///
+  /// ```json
+  /// {
+  ///   "session": "20250101000000",
+  ///   "metadata": {"tag.host": "nuc"},
+  ///   "files": [{"path": "csv/append-slate-file.csv", "bytes": 1234}]
+  /// }
+  /// ```
+  ///
+  /// ## Returns
+  /// - the path of the manifest, or `None` in the flat layout
+  pub fn write_manifest(&self) -> Result<Option<PathBuf>> {
+    if self.layout != Layout::Session {
+      return Ok(None);
+    }
+    let dir = self.dir_report.join(&self.session);
+    let path = dir.join("manifest.json");
+    let metadata = self.metadata()?.entries;
+    let files = self
+      .report_files()?
+      .into_iter()
+      .filter(|file| *file != path)
+      .map(|file| {
+        let name = file.strip_prefix(&dir).unwrap_or(&file).iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>();
+        json!({"path": name.join("/"), "bytes": file_size(&file)})
+      })
+      .collect::<Vec<_>>();
+    let slate = json!({"version": report::SLATE_VERSION, "commit": report::SLATE_COMMIT});
+    let manifest = json!({"session": self.session, "slate": slate, "metadata": metadata, "files": files});
+    write_atomically(&path, |writer| {
+      serde_json::to_writer_pretty(&mut *writer, &manifest).map_err(std::io::Error::from)?;
+      writeln!(writer)?;
+      Ok(())
+    })?;
+    Ok(Some(path))
+  }
+
+  pub fn case(&self) -> Result<Case> {
+    self.check()?;
+    let session = self.session.clone();
+    let dir = self.dir.clone();
+    let dir_report = self.dir_report.clone();
+    let scale = Scale::Linear;
+    let division = 100;
+
+    let stability_threshold = self.stability_threshold;
+    let min_trials = self.min_trials;
+    let max_trials = self.max_trials;
+    let max_duration = self.max_duration;
+    if self.profile == Profile::Simple {
+      const SIMPLE_DIVISION: usize = 10;
+      const SIMPLE_TRIALS: usize = 3;
+      return Ok(Case {
+        session,
+        dir,
+        dir_report,
+        layout: self.layout,
+        profile: self.profile,
+        perf: self.perf,
+        syscalls: self.syscalls,
+        raw_samples: self.raw_samples,
+        max_samples: self.max_samples,
+        quota: self.workdir_quota.map(|limit| WorkdirQuota::new(&self.dir, &self.session, limit)),
+        handle_mode: self.handle_mode,
+        soak: self.soak,
+        batch_min_time: self.batch_min_time,
+        discard_warmup: self.discard_warmup,
+        variance_components: self.variance_components,
+        abort_unreachable: self.abort_unreachable,
+        quiesce: self.quiesce,
+        scale,
+        division: SIMPLE_DIVISION,
+        cv_threshold: 0.0, // 変動係数による打ち切りを行わない
+        min_trials: SIMPLE_TRIALS,
+        max_trials: SIMPLE_TRIALS,
+        max_duration,
+      });
+    }
+    Ok(Case {
+      session,
+      dir,
+      dir_report,
+      layout: self.layout,
+      profile: self.profile,
+      perf: self.perf,
+      syscalls: self.syscalls,
+      raw_samples: self.raw_samples,
+      max_samples: self.max_samples,
+      quota: self.workdir_quota.map(|limit| WorkdirQuota::new(&self.dir, &self.session, limit)),
+      handle_mode: self.handle_mode,
+      soak: self.soak,
+      batch_min_time: self.batch_min_time,
+      discard_warmup: self.discard_warmup,
+      variance_components: self.variance_components,
+      abort_unreachable: self.abort_unreachable,
+      quiesce: self.quiesce,
+      scale,
+      division,
+      cv_threshold: stability_threshold,
+      min_trials,
+      max_trials,
+      max_duration,
+    })
+  }
+
+  pub fn work_dir(&self) -> Result<PathBuf> {
+    let path = self.dir.join(format!("slate_benchmark-{}", self.session));
+    if !path.exists() {
+      fs::create_dir_all(&path)?;
+    }
+    Ok(path)
+  }
+
+  pub fn clear(&self) -> Result<()> {
+    let work_dir = self.work_dir()?;
+    if work_dir.exists() {
+      for entry in fs::read_dir(&work_dir)? {
+        let e = entry?;
+        let path = e.path();
+        if e.file_type()?.is_dir() {
+          fs::remove_dir_all(e.path())?;
+          println!("directory removed: {}", path.to_string_lossy());
+        } else if e.file_type()?.is_file() {
+          fs::remove_file(e.path())?;
+          println!("file removed: {}", path.to_string_lossy());
+        } else {
+          println!("WARN: unrecognized file type: {}", path.to_string_lossy());
+        }
+      }
+    } else {
+      fs::create_dir_all(&work_dir)?;
+    }
+    Ok(())
+  }
+
+  /// Remove the working files of the experiments in the directory selected by the policy.
+  pub fn clean_experiments(&self, policy: &CleanPolicy) -> Result<()> {
+    let mut paths = Vec::new();
+    if self.dir.exists() {
+      for entry in fs::read_dir(&self.dir)? {
+        let e = entry?;
+        if e.file_name().to_string_lossy().starts_with("slate_benchmark-") {
+          paths.push(e.path());
+        }
+      }
+    }
+    policy.apply(&paths)
+  }
+
+  pub fn run_testunit_append<C: AppendCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self
+      .case()?
+      .division(10)
+      .min_trials(2)
+      .max_trials(10)
+      .measure_the_append_time_relative_to_the_data_amount(cut, ds)?;
+    Ok(self)
+  }
+
+  /// The appends that cascade the merges over many levels are occasionally expensive, which the cumulative time of
+  /// [`run_testunit_append`](Self::run_testunit_append) hides.
+  pub fn run_testunit_append_levels<C: AppendCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.min_trials(2).max_trials(20).measure_the_append_time_relative_to_the_merged_levels(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_biased_get<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.max_trials(500).measure_the_frequency_of_retrieval_against_positions_by_zipf(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_uniformed_get<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self
+      .case()?
+      .division(100)
+      .scale(Scale::WorstCase)
+      .max_trials(500)
+      .measure_the_retrieval_time_relative_to_the_position(cut, "get", 0, ds)?;
+    Ok(self)
+  }
+
+  /// The memory in use after the measurement of each level, while the CUT still holds its cache, is written to
+  /// `cache-memory{file_id}-{impl}.csv` with the increase from the level 0 as the approximate size of the cache. Both
+  /// are `unavailable` without the `alloc-stats` feature, which counts the heap in use.
+  pub fn run_testunit_cache_level<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    let mut memory = Vec::new();
+    self.sweep("cache-level", &Sweep::new().knob("cache", 0..=3), |point| {
+      let cache_level = point.parse("cache")?;
+      self
+        .case()?
+        .division(64)
+        .scale(Scale::WorstCase)
+        .max_trials(1000)
+        .measure_the_retrieval_time_relative_to_the_position(cut, &point.label(), cache_level, ds)?;
+      memory.push((cache_level, alloc::memory_in_use()));
+      Ok(())
+    })?;
+
+    // ヒープを計数しない場合は、不正確な値の代わりに計測できないことを記録する
+    let baseline = memory.first().and_then(|(_, bytes)| *bytes);
+    let id = format!("cache-memory{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    write_atomically(&path, |writer| {
+      writeln!(writer, "CACHE LEVEL,BYTES,CACHE BYTES")?;
+      for (cache_level, bytes) in memory.iter() {
+        match (bytes, baseline) {
+          (Some(bytes), Some(baseline)) => {
+            writeln!(writer, "{cache_level},{bytes},{}", bytes.saturating_sub(baseline))?
+          }
+          _ => writeln!(writer, "{cache_level},unavailable,unavailable")?,
+        }
+      }
+      Ok(())
+    })?;
+    if baseline.is_none() {
+      writer::println("WARN: the memory of the cache is unavailable without the alloc-stats feature");
+    }
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// Run `f` on each point of the sweep, whose reports are named after [`SweepPoint::label`]. The knob values of each
+  /// label are written to `{session}-sweep-{id}.csv` and the knobs to the session metadata as `sweep.{id}.{knob}`.
+  pub fn sweep<F>(&self, id: &str, sweep: &Sweep, mut f: F) -> Result<()>
+  where
+    F: FnMut(&SweepPoint) -> Result<()>,
+  {
+    let points = sweep.points();
+    let path = self.report_path(&format!("sweep-{id}.csv"));
+    write_atomically(&path, |writer| {
+      let knobs = sweep.knobs().iter().map(|knob| knob.name.as_str()).collect::<Vec<_>>();
+      writeln!(writer, "LABEL,{}", knobs.join(","))?;
+      for point in points.iter() {
+        let values = point.values().iter().map(|(_, value)| value.as_str()).collect::<Vec<_>>();
+        writeln!(writer, "{},{}", point.label(), values.join(","))?;
+      }
+      Ok(())
+    })?;
+    let metadata = sweep.knobs().iter().map(|knob| (format!("sweep.{id}.{}", knob.name), knob.values.join(",")));
+    self.set_metadata(&metadata.collect::<Vec<_>>())?;
+    for point in points.iter() {
+      f(point)?;
+    }
+    Ok(())
+  }
+
+  /// Unlike `biased-get`, the same positions are requested in the same order on every pass, so that the get time of
+  /// the later passes shows the steady state of the cache.
+  pub fn run_testunit_hot_set_get<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.sweep("hot-set-get", &Sweep::new().knob("cache", [0, 3]), |point| {
+      let action_id = format!("hot-set-get-{}", point.label());
+      let cache_level = point.parse("cache")?;
+      self.case()?.max_trials(50).measure_the_get_time_over_the_passes_on_a_hot_set(
+        cut,
+        &action_id,
+        cache_level,
+        ds,
+      )?;
+      Ok(())
+    })?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_range_scan<C: RangeCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self
+      .case()?
+      .division(32)
+      .scale(Scale::Log)
+      .max_trials(100)
+      .measure_the_scan_time_relative_to_the_range_length(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_concurrent_get<C: ConcurrentGetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.max_trials(100).measure_the_retrieval_time_relative_to_the_number_of_threads(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_multi_process_get<C: MultiProcessGetCUT>(
+    &self,
+    cut: &mut C,
+    ds: &DataSize,
+  ) -> Result<&Experiment> {
+    self.case()?.max_trials(100).measure_the_retrieval_time_relative_to_the_number_of_processes(cut, ds)?;
+    Ok(self)
+  }
+
+  /// Compare writing each record to the primary store only, to slate only, and to both in the same logical operation,
+  /// i.e. the cost of adding slate to an existing pipeline.
+  pub fn run_testunit_dual_write<C: DualWriteCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.min_trials(3).max_trials(30).measure_the_dual_write_cost(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_proof_serving<C: AuthPathCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.measure_the_proof_serving_time_with_the_auth_path_cache(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_prove<C: ProveCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.scale(Scale::WorstCase).measure_the_prove_time_relative_to_the_position(cut, ds)?;
+    Ok(self)
+  }
+
+  pub fn run_testunit_root_hash<C: RootHashCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.max_trials(100).measure_the_root_hash_time_relative_to_the_data_size(cut, ds)?;
+    Ok(self)
+  }
+
+  /// Run the get and append operations of the trace given by `replay_trace` and report the latency distribution of
+  /// each operation. Nothing is run without the trace.
+  pub fn run_testunit_workload<C: AppendCUT + GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    if let Some(path) = &self.replay_trace {
+      let records = trace::load(path)?;
+      self.case()?.measure_the_latency_distribution_of_the_workload(cut, ds, &records)?;
+    }
+    Ok(self)
+  }
+
+  /// Keep appending for the duration of `soak`, sampling the get and auth path latencies at each interval.
+  pub fn run_testunit_soak<C: AppendCUT + AuthPathCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.measure_the_stability_over_a_long_run(cut, ds)?;
+    Ok(self)
+  }
+
+  /// Run the steps of each scenario given by `scenarios`. Nothing is run without the scenarios.
+  pub fn run_testunit_scenario<C: AppendCUT + AuthPathCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    for scenario in self.scenarios.iter() {
+      self.case()?.measure_the_steps_of_the_scenario(cut, scenario, ds)?;
+    }
+    Ok(self)
+  }
+
+  /// Count the bytes that the CUT writes and reads on its storage per byte of the entries.
+  pub fn run_testunit_amplification<C: CountingCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    self.case()?.measure_the_storage_amplification(cut, ds)?;
+    Ok(self)
+  }
+
+  /// Replay the get operations of the trace given by `replay_trace`. Nothing is run without the trace.
+  pub fn run_testunit_replay<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
+    if let Some(path) = &self.replay_trace {
+      let records = trace::load(path)?;
+      self.case()?.measure_the_retrieval_time_along_the_trace(cut, ds, &records)?;
+    }
+    Ok(self)
+  }
+
+  /// ベンチマーク後のデータベースから every 件ごとと最後のエントリを読み直し、値の生成方法と一致することを確認する。
+  /// ベンチマークが残したデータベースをそのまま検証し、どのエントリも読めない場合のみ構築し直して検証する。
+  /// 不一致や欠けたエントリは `{session}-validation.csv` に記録され、CUT の失敗となる。
+  pub fn validate<C: GetCUT>(&self, cut: &mut C, ds: &DataSize, every: u64) -> Result<&Experiment> {
+    println!("\n=== Validation ({}) [{}] ===", cut.implementation(), report::slate_label());
+    let n = ds.size();
+    let mut positions = (1..=n).step_by(every.max(1) as usize).collect::<Vec<_>>();
+    if positions.last() != Some(&n) {
+      positions.push(n);
+    }
+    cut.set_cache_level(0)?;
+    let mut mismatches = validate_entries(cut, &positions)?;
+    if mismatches.len() == positions.len() {
+      // 後続のテストユニットのために破棄されたデータベースは、構築直後の状態を検証する
+      println!("No entry can be read; validating a newly prepared database");
+      self.case()?.prepare_database(cut, ds)?;
+      cut.set_cache_level(0)?;
+      mismatches = validate_entries(cut, &positions)?;
+    }
+    let checked = positions.len();
+
+    let path = self.report_path("validation.csv");
+    let row = format!("{},{},{checked},{}", cut.implementation(), ds.size(), mismatches.len());
+    append_csv(&path, "CUT,SIZE,CHECKED,MISMATCHES", &row)?;
+
+    if !mismatches.is_empty() {
+      for msg in mismatches.iter() {
+        eprintln!("  MISMATCH: {msg}");
+      }
+      let msg = format!("{} of {checked} entries don't match the generated values", mismatches.len());
+      return Err(BenchError::Validation(msg));
+    }
+    println!("{checked} entries validated");
+    Ok(self)
+  }
+
+  pub fn run_testunit_hash<H: HashAlgorithm>(&self) -> Result<&Experiment> {
+    self
+      .case()?
+      .scale(Scale::Log)
+      .division(17)
+      .max_trials(100)
+      .measure_the_hashing_time_relative_to_the_data_size::<H>()?;
+    Ok(self)
+  }
+
+  /// `node` creates the node of the implementation holding the given payload.
+  pub fn run_testunit_serialization<S, F>(&self, implementation: &str, node: F) -> Result<&Experiment>
+  where
+    S: Serializable,
+    F: Fn(&[u8]) -> Result<S>,
+  {
+    self
+      .case()?
+      .scale(Scale::Log)
+      .division(11)
+      .max_trials(100)
+      .measure_the_serialization_time_relative_to_the_payload_size(implementation, MAX_DATA_SIZE, node)?;
+    Ok(self)
+  }
+}
+
+impl Case {
+  property_decl!(division, usize, fixed_in_simple);
+  property_decl!(scale, Scale, fixed_in_simple);
+  property_decl!(cv_threshold, f64, fixed_in_simple);
+  property_decl!(min_trials, usize, fixed_in_simple);
+  property_decl!(max_trials, usize, fixed_in_simple);
+  property_decl!(max_duration, Duration);
+
+  /// CUT のデータベースを構築する。CUT はテストユニットをまたいで使い回されるため、同じデータサイズで構築済みの
+  /// データベースは再構築せずに共有される (キャッシュレベルの変更は CUT の開き直しのみで行う)。
+  ///
+  /// ## Returns
+  /// - whether the database has been built for this test unit, i.e. nothing has accessed it since
+  pub fn prepare_database<C: CUT>(&self, cut: &mut C, ds: &DataSize) -> Result<bool> {
+    let quota = self.quota();
+    let progress = PrepareProgress::new(ds.size());
+    cut.prepare(ds.size(), splitmix64, |i| {
+      progress.inc(i);
+      poll(quota)
+    })?;
+    let built = progress.finish(false);
+    if let Some(elapsed) = built {
+      self.record_prepare("prepare", &cut.implementation(), ds, elapsed)?;
+    }
+    // 構築時の書き戻しが最初の計測に重ならないようにする
+    if self.quiesce.is_enabled() {
+      self.quiesce.run()?;
+    }
+    Ok(built.is_some())
+  }
+
+  /// Record the time taken to build the databases of the CUT as `{name}{file_id}.{implementation}` in the session
+  /// metadata, in seconds.
+  fn record_prepare(&self, name: &str, implementation: &str, ds: &DataSize, elapsed: Duration) -> Result<()> {
+    writer::println(format!("Prepared in {:.1}s", elapsed.as_secs_f64()));
+    let key = format!("{name}{}.{implementation}", ds.file_id());
+    let path = self.layout.marker(&self.dir_report, &self.session);
+    set_metadata(&path, &[(key, format!("{:.3}", elapsed.as_secs_f64()))])
+  }
+
+  fn quota(&self) -> Option<&WorkdirQuota> {
+    self.quota.as_ref()
+  }
+
+  /// Give the turn to the next CUT if the trials are interleaved, quiesce if configured so and check the quota of the
+  /// working directories after a trial.
+  fn after_trial(&self) -> Result<()> {
+    trace::submit();
+    interleave::end_of_trial();
+    self.quiesce.after_trial()?;
+    self.quota().map_or(Ok(()), WorkdirQuota::check)
+  }
+
+  pub fn file(&self, id: &str, filename: &str) -> PathBuf {
+    self.dir_work(id).join(filename)
+  }
+
+  pub fn name(&self, id: &str) -> String {
+    format!("{}-{id}", self.session)
+  }
+
+  /// Path of the report `name` of the session according to the layout, e.g. `{session}-{name}` in the flat layout.
+  pub fn report_path(&self, name: &str) -> PathBuf {
+    self.layout.path(&self.dir_report, &self.session, name)
+  }
+
+  pub fn dir_work(&self, id: &str) -> PathBuf {
+    let dir_work = self.dir.join(format!("slate_benchmark-{}", self.name(id)));
+    if !dir_work.exists() {
+      fs::create_dir_all(&dir_work).unwrap();
+    }
+    dir_work
+  }
+
+  fn gauge(&self, n: Index) -> Vec<u64> {
+    let gauge = match self.scale {
+      Scale::Linear => linspace(1, n, self.division),
+      Scale::Log => logspace(1, n, self.division),
+      Scale::BestCase => {
+        let (_, ll) = entry_access_distance_limits(n);
+        ll.into_iter()
+          .enumerate()
+          .flat_map(|(d, range)| range.filter(move |k| entry_access_distance(*k, n).unwrap() == d as u8))
+          .collect::<Vec<_>>()
+      }
+      Scale::WorstCase => {
+        let (ul, _) = entry_access_distance_limits(n);
+        ul.into_iter()
+          .enumerate()
+          .flat_map(|(d, range)| range.filter(move |k| entry_access_distance(*k, n).unwrap() == d as u8))
+          .collect::<Vec<_>>()
+      }
+    };
+    // 丸めや距離の範囲によらず両端を必ず含める (結果の集計で n の計測値を参照するため)
+    let mut gauge = [1, n].into_iter().chain(gauge).filter(|x| (1..=n).contains(x)).collect::<Vec<_>>();
+    gauge.sort_unstable();
+    gauge.dedup();
+    gauge
+  }
+
+  /// 収束していない目盛りについて、試行を止める条件 (標本の CV が閾値未満) の成否が決まるまでの試行回数を推定して進捗に
+  /// 表示し、`abort_unreachable` の場合は CV が 95% の信頼度で閾値を上回り条件に到達しない目盛りの計測を打ち切ります。
+  fn estimate_required_trials(
+    &self,
+    gauge: Vec<u64>,
+    ss: &XYReport<u64, f64>,
+    timer: &mut ExpirationTimer,
+  ) -> Vec<u64> {
+    let required = gauge.iter().map(|x| (*x, ss.required_trials(x, self.cv_threshold))).collect::<Vec<_>>();
+    timer.set_required_trials(required.iter().filter_map(|(_, r)| *r).filter(|r| *r != usize::MAX).max());
+    if !self.abort_unreachable || self.cv_threshold <= 0.0 {
+      return gauge;
+    }
+    let (reachable, unreachable): (Vec<_>, Vec<_>) =
+      required.into_iter().partition(|(_, r)| r.is_none_or(|r| r != usize::MAX));
+    if !unreachable.is_empty() {
+      let xs = unreachable.iter().map(|(x, _)| x.to_string()).collect::<Vec<_>>();
+      writer::println(format!("** ABORTED: the CV of {} is significantly above the threshold **", xs.join(", ")));
+    }
+    reachable.into_iter().map(|(x, _)| x).collect()
+  }
+
+  /// データ量に対する追記時間を計測します。
+  pub fn measure_the_append_time_relative_to_the_data_amount<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: AppendCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Append Benchmark ({}) [{}] ===\n", cut.implementation(), report::slate_label());
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_ms();
+
+    let name = format!("volume{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{name}.csv"));
+    let mut space_complexity = XYReport::new(Unit::Bytes)
+      .with_output(path, "SIZE", "BYTES")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "volume", &cut.implementation())?);
+    let name = format!("append{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{name}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "SIZE", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "append", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components);
+    let mut perf = PerfRecorder::new(self.perf)?;
+    let mut syscalls = SyscallRecorder::new(self.syscalls)?;
+    let gauge = self.gauge(ds.size());
+    let mut pending = gauge.clone();
+    'trials: for trials in 0..self.max_trials {
+      cut.cleanup()?;
+      let (mut cum_time, mut appended) = (Duration::ZERO, 0);
+      for n in gauge.iter() {
+        let measurement = perf.record(*n, || syscalls.record(*n, *n - appended, || cut.append(*n, splitmix64)))?;
+        trace::record(Operation::Append, *n);
+        energy::count(*n - appended);
+        appended = *n;
+        if trials == 0 {
+          let Some(bytes) = measurement.bytes else {
+            return Err(BenchError::Validation(format!("{} doesn't report the storage size", cut.implementation())));
+          };
+          space_complexity.add(n, bytes);
+        }
+        cum_time += measurement.elapsed;
+        time_complexity.add(n, cum_time.as_nanos() as f64 / 1000.0 / 1000.0);
+        self.quiesce.after_point()?;
+
+        // 1 回の試行が長い場合も期限を守る。累積時間は途中の目盛りまでの値として有効なまま残る
+        if timer.expired() {
+          let s = time_complexity.calculate(n).unwrap();
+          timer.summary_ms(*n, s.mean, s.std_dev);
+          writer::println("** TIMED OUT **");
+          break 'trials;
+        }
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials {
+        // 累積時間のため試行は常にすべての目盛りを計測し、収束または打ち切った目盛りを以降の判定から除外する
+        pending = filter_cv_sufficient(&pending, &time_complexity, self.cv_threshold);
+        pending = self.estimate_required_trials(pending, &time_complexity, &mut timer);
+        if pending.is_empty() {
+          let s = time_complexity.calculate(&ds.size()).unwrap();
+          timer.summary_ms(ds.size(), s.mean, s.std_dev);
+          break;
+        }
+      }
+      if timer.carried_out(1) {
+        let s = time_complexity.calculate(&ds.size()).unwrap();
+        timer.summary_ms(ds.size(), s.mean, s.std_dev);
+      }
+    }
+
+    // write report
+    let path = space_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    let path = self.report_path(&format!("{name}_perf.csv"));
+    if perf.save_to_csv(&path, "SIZE")? {
+      println!("==> The results have been saved in: {}", path.to_string_lossy());
+    }
+    let path = self.report_path(&format!("{name}_syscalls.csv"));
+    if syscalls.save_to_csv(&path, "SIZE")? {
+      println!("==> The results have been saved in: {}", path.to_string_lossy());
+    }
+    Ok(self)
+  }
+
+  /// エントリを 1 件ずつ追加し、その追加で連鎖するマージの段数ごとに追加時間を計測します。
+  pub fn measure_the_append_time_relative_to_the_merged_levels<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: AppendCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Append Levels Benchmark ({}) [{}] ===\n", cut.implementation(), report::slate_label());
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let name = format!("append-levels{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{name}.csv"));
+    let mut time_by_levels = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "LEVELS", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "append-levels", &cut.implementation())?)
+      .with_max_samples(self.max_samples);
+    'trials: for trials in 0..self.max_trials {
+      cut.cleanup()?;
+      for i in 1..=ds.size() {
+        let measurement = cut.append(i, splitmix64)?;
+        trace::record(Operation::Append, i);
+        energy::count(1);
+        time_by_levels.add(&merged_levels(i), measurement.millis());
+
+        if timer.expired() {
+          timer.summary_max_cv(ds.size(), time_by_levels.max_cv());
+          writer::println("** TIMED OUT **");
+          break 'trials;
+        }
+      }
+
+      time_by_levels.end_pass();
+      self.after_trial()?;
+      // 上位の段数は 1 回の試行に数回しか現れないため、すべての段数の変動係数が収まるまで試行を繰り返す
+      if trials + 1 >= self.min_trials && time_by_levels.max_cv() <= self.cv_threshold {
+        timer.summary_max_cv(ds.size(), time_by_levels.max_cv());
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_by_levels.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_by_levels.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 各レコードをプライマリのストアのみ、slate のみ、両方に書き込むそれぞれの場合の、レコードごとの書き込み時間と
+  /// スループットを計測します。
+  pub fn measure_the_dual_write_cost<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: DualWriteCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Dual Write Benchmark ({}) [{}] ===\n", cut.implementation(), report::slate_label());
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let name = format!("dual-write{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{name}.csv"));
+    let mut latency = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "TARGET", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "dual-write", &cut.implementation())?)
+      .with_max_samples(self.max_samples);
+    // 対象ごとの書き込んだレコード数と計測時間の合計
+    let mut totals = HashMap::<WriteTarget, (u64, Duration)>::new();
+    let mut targets = WriteTarget::ALL;
+    'trials: for trials in 0..self.max_trials {
+      // 順序による偏りを避けるため、試行ごとに対象の順序をずらす
+      targets.rotate_left(1);
+      for target in targets.iter() {
+        cut.cleanup()?;
+        let total = totals.entry(*target).or_default();
+        for i in 1..=ds.size() {
+          let measurement = cut.write(*target, i, splitmix64(i))?;
+          trace::record(Operation::Append, i);
+          energy::count(1);
+          latency.add(&target.label(), measurement.millis());
+          *total = (total.0 + 1, total.1 + measurement.elapsed);
+
+          if timer.expired() {
+            timer.summary_max_cv(ds.size(), latency.max_cv());
+            writer::println("** TIMED OUT **");
+            break 'trials;
+          }
+        }
+      }
+
+      latency.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials && latency.max_cv() <= self.cv_threshold {
+        timer.summary_max_cv(ds.size(), latency.max_cv());
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), latency.max_cv());
+      }
+    }
+    cut.cleanup()?;
+
+    // write report
+    let path = latency.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    // スループットは書き込みの計測時間の合計あたりのレコード数で、ループ自体や後片付けの時間を含まない
+    let path = self.report_path(&format!("{name}_throughput.csv"));
+    write_atomically(&path, |writer| {
+      writeln!(writer, "TARGET,RECORDS,SUMMED LATENCY SECONDS,RECORDS PER SUMMED LATENCY SECOND")?;
+      for target in WriteTarget::ALL.iter() {
+        let (records, elapsed) = totals.get(target).copied().unwrap_or_default();
+        let seconds = elapsed.as_secs_f64();
+        let throughput = if seconds > 0.0 { records as f64 / seconds } else { 0.0 };
+        writeln!(writer, "{},{records},{seconds:.6},{throughput:.1}", target.label())?;
+      }
+      Ok(())
+    })?;
+    println!("==> The throughputs have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// Count the bytes written to the storage by the appends up to each point and read by the uniform gets at the point,
+  /// relative to the bytes of the entries appended or read, in `amplification-{impl}.csv`. The counts are the
+  /// serialized size of the entries and nodes that slate passes to the storage, not the bytes the backend writes to the
+  /// device such as the WAL and the compaction of RocksDB. Nothing is timed, so that the counting and the serialization
+  /// it takes don't affect the time of the other test units.
+  pub fn measure_the_storage_amplification<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: CountingCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Storage Amplification ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    let path = self.report_path(&format!("amplification{}-{}.csv", ds.file_id(), cut.implementation()));
+    let value_size = |i: Index| payload::payload(i).map_or(8, <[u8]>::len) as u64;
+    let mut state = AMPLIFICATION_SEED;
+    let mut uniform = |n: Index| {
+      state = splitmix64(state);
+      state % n + 1
+    };
+    cut.set_cache_level(0)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    cut.cleanup()?;
+    let counter = cut.storage_counter();
+    let (mut n, mut logical_written, mut written) = (0, 0u64, 0u64);
+    let mut rows = Vec::new();
+    let mut gauge = self.gauge(ds.size());
+    gauge.sort();
+    gauge.dedup();
+    for x in gauge {
+      counter.reset();
+      cut.append(x, splitmix64)?;
+      logical_written += (n + 1..=x).map(value_size).sum::<u64>();
+      written += counter.snapshot().put.bytes;
+      n = x;
+
+      counter.reset();
+      let gets = AMPLIFICATION_GETS.min(n);
+      let mut logical_read = 0;
+      for _ in 0..gets {
+        let i = uniform(n);
+        cut.get(i, splitmix64)?;
+        logical_read += value_size(i);
+      }
+      let read = counter.snapshot();
+      let (write_amplification, read_amplification) =
+        (written as f64 / logical_written as f64, read.read_amplification(logical_read));
+      writer::println(format!("{x}: write x{write_amplification:.1}, read x{read_amplification:.1} ({gets} gets)"));
+      let read_bytes = read.first.bytes + read.last.bytes + read.read.bytes;
+      rows.push(format!(
+        "{x},{logical_written},{written},{write_amplification},{gets},{logical_read},{read_bytes},{read_amplification}"
+      ));
+    }
+    write_atomically(&path, |writer| {
+      let header =
+        "SIZE,LOGICAL WRITE,STORAGE WRITE,WRITE AMPLIFICATION,GETS,LOGICAL READ,STORAGE READ,READ AMPLIFICATION";
+      writeln!(writer, "{header}")?;
+      for row in rows.iter() {
+        writeln!(writer, "{row}")?;
+      }
+      Ok(())
+    })?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 設定ファイルに定義したシナリオの手順を順に実行し、手順ごとの処理時間を計測します。
+  pub fn measure_the_steps_of_the_scenario<CUT>(self, cut: &mut CUT, scenario: &Scenario, ds: &DataSize) -> Result<Self>
+  where
+    CUT: AppendCUT + AuthPathCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Scenario {} ({}) [{}] ===", scenario.name, cut.implementation(), report::slate_label());
+    if let Some(description) = &scenario.description {
+      println!("{description}");
+    }
+
+    let action_id = format!("scenario-{}", scenario.name);
+    let path = self.report_path(&format!("{action_id}{}-{}.csv", ds.file_id(), cut.implementation()));
+    let mut time_by_step = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "STEP", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, &action_id, &cut.implementation())?)
+      .with_max_samples(self.max_samples);
+    cut.set_cache_level(0)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    cut.set_auth_path_cache(0)?;
+    let mut state = SCENARIO_SEED;
+    let mut uniform = |n: Index| {
+      state = splitmix64(state);
+      state % n + 1
+    };
+    let timer = ExpirationTimer::new(self.max_duration, 10, scenario.repeat, 10);
+    let timed_out = 'runs: {
+      for run in 1..=scenario.repeat {
+        writer::println(format!("Run {run}/{}", scenario.repeat));
+        cut.cleanup()?;
+        let mut n: Index = 0;
+        for (k, step) in scenario.steps.iter().enumerate() {
+          let label = step.label(k);
+          let start = Instant::now();
+          match *step {
+            Step::Prepare(size) => {
+              if size < n {
+                cut.cleanup()?;
+              }
+              cut.prepare(size, splitmix64, |_| Ok(()))?;
+              n = size;
+            }
+            Step::Append { count, rate } => {
+              for j in 0..count {
+                // 指定された頻度を超えないよう、j 件目の追加をその予定時刻まで待つ
+                if let Some(rate) = rate {
+                  let due = start + Duration::from_secs_f64(j as f64 / rate);
+                  std::thread::sleep(due.saturating_duration_since(Instant::now()));
+                }
+                n += 1;
+                let measurement = cut.append(n, splitmix64)?;
+                trace::record(Operation::Append, n);
+                energy::count(1);
+                time_by_step.add(&label, measurement.millis());
+                if timer.expired() {
+                  break 'runs true;
+                }
+              }
+            }
+            Step::Get { count, distribution } => {
+              ensure!(n > 0, "no entries to get at the step {label} of the scenario {}", scenario.name);
+              // 実行と手順ごとに異なる系列となるよう、その番号からシードを導く
+              let mut zipf = match distribution {
+                Distribution::Zipf(s) => {
+                  let seed = splitmix64(SCENARIO_SEED + ((run - 1) * scenario.steps.len() + k) as u64);
+                  Some(ZipfSampler::new(seed, s, n))
+                }
+                _ => None,
+              };
+              for _ in 0..count {
+                let i = match (distribution, zipf.as_mut()) {
+                  (Distribution::Zipf(_), Some(zipf)) => zipf.next_u64(),
+                  (Distribution::Latest, _) => n,
+                  _ => uniform(n),
+                };
+                let measurement = cut.get(i, splitmix64)?;
+                trace::record(Operation::Get, i);
+                energy::count(1);
+                time_by_step.add(&label, measurement.millis());
+                if timer.expired() {
+                  break 'runs true;
+                }
+              }
+            }
+            Step::Prove(count) => {
+              ensure!(n > 0, "no entries to prove at the step {label} of the scenario {}", scenario.name);
+              for _ in 0..count {
+                let measurement = cut.get_auth_path(uniform(n))?;
+                energy::count(1);
+                time_by_step.add(&label, measurement.millis());
+                if timer.expired() {
+                  break 'runs true;
+                }
+              }
+            }
+          }
+          writer::println(format!("{label}: {n} entries, {:.3}s", start.elapsed().as_secs_f64()));
+        }
+        time_by_step.end_pass();
+        self.after_trial()?;
+      }
+      false
+    };
+    if timed_out {
+      writer::println("** TIMED OUT **");
+    }
+    cut.cleanup()?;
+
+    // write report
+    let path = time_by_step.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// アクセス位置に対するデータ取得時間を計測します。
+  pub fn measure_the_retrieval_time_relative_to_the_position<CUT>(
+    self,
+    cut: &mut CUT,
+    action_id: &str,
+    cache_level: usize,
+    ds: &DataSize,
+  ) -> Result<Self>
+  where
+    CUT: GetCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Get Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    let fresh = self.prepare_database(cut, ds)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("{action_id}{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "DISTANCE", "ACCESS TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, action_id, &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components)
+      .with_first_access(fresh);
+    let mut perf = PerfRecorder::new(self.perf)?;
+    let mut syscalls = SyscallRecorder::new(self.syscalls)?;
+    let mut rng = rand::rng();
+    let mut gauge = self.gauge(ds.size());
+    // 位置ごとの、1 サンプルでまとめて計測する取得の回数
+    let mut batches = HashMap::<Index, u32>::new();
+    let n = ds.size();
+    cut.set_cache_level(cache_level)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    'trials: for trials in 0..self.max_trials {
+      gauge.shuffle(&mut rng);
+      for i in gauge.iter() {
+        // 最初のアクセスは 1 回ごとに計測する
+        let millis = match self.batch_min_time.filter(|_| trials > 0) {
+          Some(min_time) => {
+            let k = match batches.get(i) {
+              Some(k) => *k,
+              None => {
+                let k = batch_size(min_time, |k| Ok(cut.get_batch(&batch(*i, k, n), splitmix64)?.elapsed))?;
+                batches.insert(*i, k);
+                k
+              }
+            };
+            let is = batch(*i, k, n);
+            let measurement = cut.get_batch(&is, splitmix64)?;
+            is.iter().for_each(|i| trace::record(Operation::Get, *i));
+            energy::count(is.len() as u64);
+            measurement.millis() / is.len() as f64
+          }
+          None => {
+            let measurement = perf.record(*i, || syscalls.record(*i, 1, || cut.get(*i, splitmix64)))?;
+            trace::record(Operation::Get, *i);
+            energy::count(1);
+            measurement.millis()
+          }
+        };
+        // 構築直後の最初のアクセスはキャッシュが冷えているため、以降のアクセスとは別の系列とする
+        if fresh && trials == 0 {
+          time_complexity.add_first_access(i, millis);
+        } else {
+          time_complexity.add(i, millis);
+        }
+        self.quiesce.after_point()?;
+
+        if timer.expired() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          writer::println("** TIMED OUT **");
+          break 'trials;
+        }
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      // 最初のアクセスの試行は別の系列に記録されるため、最小試行回数はそれ以降の試行で数える
+      if trials + 1 >= self.min_trials + fresh as usize {
+        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
+        gauge = self.estimate_required_trials(gauge, &time_complexity, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          break;
+        }
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    let path = self.report_path(&format!("{id}_perf.csv"));
+    if perf.save_to_csv(&path, "DISTANCE")? {
+      println!("==> The results have been saved in: {}", path.to_string_lossy());
+    }
+    let path = self.report_path(&format!("{id}_syscalls.csv"));
+    if syscalls.save_to_csv(&path, "DISTANCE")? {
+      println!("==> The results have been saved in: {}", path.to_string_lossy());
+    }
+    Ok(self)
+  }
+
+  /// 連続して読み込む範囲の長さに対するデータ取得時間を計測します。
+  pub fn measure_the_scan_time_relative_to_the_range_length<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: RangeCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Range Scan Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    self.prepare_database(cut, ds)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("range-scan{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "LENGTH", "SCAN TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "range-scan", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components);
+    let mut rng = rand::rng();
+    let mut gauge = self.gauge(ds.size());
+    cut.set_cache_level(0)?;
+    'trials: for trials in 0..self.max_trials {
+      gauge.shuffle(&mut rng);
+      for m in gauge.iter() {
+        let i = rng.random_range(1..=ds.size() - m + 1);
+        let measurement = cut.scan(i, *m, splitmix64)?;
+        trace::record(Operation::Scan, i);
+        energy::count(1);
+        time_complexity.add(m, measurement.millis());
+        self.quiesce.after_point()?;
+
+        if timer.expired() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          writer::println("** TIMED OUT **");
+          break 'trials;
+        }
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials {
+        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
+        gauge = self.estimate_required_trials(gauge, &time_complexity, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          break;
+        }
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 並行して読み出すスレッド数に対する、一定数のランダムな位置のデータ取得にかかる時間を計測します。
+  pub fn measure_the_retrieval_time_relative_to_the_number_of_threads<CUT>(
+    self,
+    cut: &mut CUT,
+    ds: &DataSize,
+  ) -> Result<Self>
+  where
+    CUT: ConcurrentGetCUT,
+  {
+    const QUERIES: usize = 1024;
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Concurrent Get Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    let fresh = self.prepare_database(cut, ds)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("concurrent-get{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "THREADS", "GET TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "concurrent-get", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components)
+      .with_first_access(fresh);
+    let mut rng = rand::rng();
+    let parallelism = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as u64;
+    let mut gauge = (0..=parallelism.ilog2()).map(|e| 1u64 << e).collect::<Vec<_>>();
+    cut.set_cache_level(0)?;
+    'trials: for trials in 0..self.max_trials {
+      gauge.shuffle(&mut rng);
+      for threads in gauge.iter() {
+        let is = (0..QUERIES).map(|_| rng.random_range(1..=ds.size())).collect::<Vec<_>>();
+        let measurement = cut.concurrent_get(*threads as usize, &is, splitmix64)?;
+        energy::count(is.len() as u64);
+        if fresh && trials == 0 {
+          time_complexity.add_first_access(threads, measurement.millis());
+        } else {
+          time_complexity.add(threads, measurement.millis());
+        }
+        self.quiesce.after_point()?;
+
+        if timer.expired() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          writer::println("** TIMED OUT **");
+          break 'trials;
+        }
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials + fresh as usize {
+        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
+        gauge = self.estimate_required_trials(gauge, &time_complexity, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          break;
+        }
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 同じデータベースを読み取り専用で開いた複数のプロセスが同時にデータを取得したときの、プロセスごとの平均取得時間を
+  /// 計測します。
+  pub fn measure_the_retrieval_time_relative_to_the_number_of_processes<CUT>(
+    self,
+    cut: &mut CUT,
+    ds: &DataSize,
+  ) -> Result<Self>
+  where
+    CUT: MultiProcessGetCUT,
+  {
+    const QUERIES: usize = 1024;
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Multi-Process Get Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    self.prepare_database(cut, ds)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("multi-process-get{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "PROCESSES", "GET TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "multi-process-get", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components);
+    let mut rng = rand::rng();
+    let parallelism = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as u64;
+    let mut gauge = (0..=parallelism.ilog2()).map(|e| 1u64 << e).collect::<Vec<_>>();
+    'trials: for trials in 0..self.max_trials {
+      gauge.shuffle(&mut rng);
+      for processes in gauge.iter() {
+        let mut children = Vec::with_capacity(*processes as usize);
+        for _ in 0..*processes {
+          let mut command = cut.reader_command(ds.size(), QUERIES, rng.random())?;
+          children.push(command.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?);
+        }
+        for mean in run_readers(children, QUERIES)? {
+          time_complexity.add(processes, mean);
+          energy::count(QUERIES as u64);
+        }
+        self.quiesce.after_point()?;
+
+        if timer.expired() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          writer::println("** TIMED OUT **");
+          break 'trials;
+        }
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials {
+        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
+        gauge = self.estimate_required_trials(gauge, &time_complexity, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          break;
+        }
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// トレースに記録された順にデータを取得し、位置ごとのデータ取得時間を計測します。取得以外の操作は読み飛ばします。
+  pub fn measure_the_retrieval_time_along_the_trace<CUT>(
+    self,
+    cut: &mut CUT,
+    ds: &DataSize,
+    records: &[trace::Record],
+  ) -> Result<Self>
+  where
+    CUT: GetCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Replay Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    let gets = records.iter().filter(|r| r.operation == Operation::Get).map(|r| r.position).collect::<Vec<_>>();
+    if gets.len() < records.len() {
+      println!("{} operations other than get are skipped", records.len() - gets.len());
+    }
+    ensure!(!gets.is_empty(), "no get operation in the trace");
+    ensure!(gets.iter().all(|i| (1..=ds.size()).contains(i)), "the trace accesses a position out of 1..={}", ds.size());
+
+    let fresh = self.prepare_database(cut, ds)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, gets.len().max(10), 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("replay{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "DISTANCE", "ACCESS TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "replay", &cut.implementation())?)
+      .with_max_samples(self.max_samples)
+      .with_first_access(fresh);
+    cut.set_cache_level(0)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    let mut accessed = HashSet::new();
+    for i in gets.iter() {
+      let measurement = cut.get(*i, splitmix64)?;
+      trace::record(Operation::Get, *i);
+      energy::count(1);
+      if fresh && accessed.insert(*i) {
+        time_complexity.add_first_access(&(ds.size() - i + 1), measurement.millis());
+      } else {
+        time_complexity.add(&(ds.size() - i + 1), measurement.millis());
+      }
+
+      if timer.expired() {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// トレースに記録された取得と追記の操作を順に発行し、操作の種類ごとの処理時間の分布を計測します。追記は記録された
+  /// 位置までエントリを追加し、既に含まれる位置への追記や、それ以外の操作は読み飛ばします。
+  pub fn measure_the_latency_distribution_of_the_workload<CUT>(
+    self,
+    cut: &mut CUT,
+    ds: &DataSize,
+    records: &[trace::Record],
+  ) -> Result<Self>
+  where
+    CUT: AppendCUT + GetCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Workload Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    cut.cleanup()?;
+    self.prepare_database(cut, ds)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, records.len().max(10), 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("workload{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}_samples.csv"));
+    let mut latencies = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "OPERATION", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "workload", &cut.implementation())?)
+      .with_max_samples(self.max_samples);
+    cut.set_cache_level(0)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    let mut samples = HashMap::<Operation, Vec<f64>>::new();
+    let (mut n, mut skipped) = (ds.size(), 0);
+    for record in records.iter() {
+      let i = record.position;
+      let measurement = match record.operation {
+        Operation::Get => {
+          ensure!((1..=n).contains(&i), "the trace accesses position {i} out of 1..={n}");
+          cut.get(i, splitmix64)?
+        }
+        Operation::Append if i > n => {
+          n = i;
+          cut.append(i, splitmix64)?
+        }
+        _ => {
+          skipped += 1;
+          continue;
+        }
+      };
+      trace::record(record.operation, i);
+      energy::count(1);
+      latencies.add(&String::from(record.operation.name()), measurement.millis());
+      samples.entry(record.operation).or_default().push(measurement.millis());
+
+      if timer.expired() {
+        timer.summary_max_cv(n, latencies.max_cv());
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(n, latencies.max_cv());
+      }
+    }
+    if skipped > 0 {
+      writer::println(format!("{skipped} operations are skipped"));
+    }
+    if n > ds.size() {
+      // 後続のテストユニットが追記後のデータベースを共有しないように破棄する
+      cut.cleanup()?;
+    }
+
+    // write report
+    let path = latencies.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    let path = self.report_path(&format!("{id}.csv"));
+    write_atomically(&path, |writer| {
+      writeln!(writer, "OPERATION,COUNT,MEAN,P50,P90,P99,P99.9,MAX")?;
+      for operation in [Operation::Get, Operation::Append] {
+        if let Some(ys) = samples.get_mut(&operation) {
+          ys.sort_unstable_by(f64::total_cmp);
+          let mean = ys.iter().sum::<f64>() / ys.len() as f64;
+          let ps = [0.5, 0.9, 0.99, 0.999].map(|p| ys[((ys.len() - 1) as f64 * p).round() as usize]);
+          let ps = ps.map(|p| p.to_string()).join(",");
+          writeln!(writer, "{},{},{mean},{ps},{}", operation.name(), ys.len(), ys[ys.len() - 1])?;
+        }
+      }
+      Ok(())
+    })?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 指定された期間にわたってエントリを追加し続け、一定の間隔で取得と認証パスの処理時間を標本として計測します。収束した
+  /// 短時間の計測とは異なり、日単位の安定性を壁時計の時刻に対して記録します。
+  pub fn measure_the_stability_over_a_long_run<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: AppendCUT + AuthPathCUT,
+  {
+    const SAMPLES: usize = 100;
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Soak Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+    println!(
+      "Appending for {:.1}h, sampling every {:.1}min",
+      self.soak.duration.as_secs_f64() / 3600.0,
+      self.soak.interval.as_secs_f64() / 60.0
+    );
+
+    cut.cleanup()?;
+    self.prepare_database(cut, ds)?;
+    cut.set_cache_level(0)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    cut.set_auth_path_cache(0)?;
+
+    let id = format!("soak{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    // 中断されてもそれまでの標本が残るよう、標本ごとに書き出す
+    let mut file = fs::File::create(&path)?;
+    let header = "TIME,ELAPSED_SECONDS,ENTRIES,BYTES,APPENDS_PER_SEC,GET_MEAN,GET_MAX,PROVE_MEAN,PROVE_MAX\n";
+    file.write_all(report::csv(header).as_bytes())?;
+    let mean = |xs: &[f64]| xs.iter().sum::<f64>() / xs.len() as f64;
+    let max = |xs: &[f64]| xs.iter().copied().fold(0.0, f64::max);
+
+    let quota = self.quota();
+    let mut rng = rand::rng();
+    let (mut n, mut bytes) = (ds.size(), None);
+    let start = Instant::now();
+    let (mut next, mut sampled_at, mut sampled_n) = (start, start, n);
+    loop {
+      let now = Instant::now();
+      if now >= next {
+        let rate = (n - sampled_n) as f64 / now.duration_since(sampled_at).as_secs_f64().max(f64::EPSILON);
+        let (mut gets, mut proves) = (Vec::with_capacity(SAMPLES), Vec::with_capacity(SAMPLES));
+        for _ in 0..SAMPLES {
+          let i = rng.random_range(1..=n);
+          gets.push(cut.get(i, splitmix64)?.millis());
+          proves.push(cut.get_auth_path(i)?.millis());
+          energy::count(2);
+        }
+        let time = Local::now().format("%Y-%m-%dT%H:%M:%S%z");
+        let elapsed = now.duration_since(start);
+        let size = bytes.map(|bytes: u64| bytes.to_string()).unwrap_or_default();
+        let (get, prove) = ((mean(&gets), max(&gets)), (mean(&proves), max(&proves)));
+        let row =
+          format!("{time},{},{n},{size},{rate},{},{},{},{}\n", elapsed.as_secs(), get.0, get.1, prove.0, prove.1);
+        file.write_all(report::csv(&row).as_bytes())?;
+        writer::println(format!(
+          "{time}: {n} entries, {rate:.0} appends/s, get {:.3}ms, prove {:.3}ms",
+          get.0, prove.0
+        ));
+        if elapsed >= self.soak.duration {
+          break;
+        }
+        // 標本の計測時間は追加の速度に含めない
+        (sampled_at, sampled_n) = (Instant::now(), n);
+        while next <= sampled_at {
+          next += self.soak.interval;
+        }
+      }
+      n += 1;
+      bytes = cut.append(n, splitmix64)?.bytes.or(bytes);
+      energy::count(1);
+      poll(quota)?;
+    }
+    // 後続のテストユニットが追記後のデータベースを共有しないように破棄する
+    cut.cleanup()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 最近のエントリに偏った (Zipf 分布に従う) 認証パスの要求を、認証パスのキャッシュなしとありで同じ順に処理し、1 件
+  /// あたりの処理時間を比較します。
+  pub fn measure_the_proof_serving_time_with_the_auth_path_cache<CUT>(
+    self,
+    cut: &mut CUT,
+    ds: &DataSize,
+  ) -> Result<Self>
+  where
+    CUT: AuthPathCUT,
+  {
+    const REQUESTS: usize = 1000;
+    const CACHE_CAPACITY: usize = 1024;
+    const S: f64 = 1.5;
+
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Proof Serving Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    self.prepare_database(cut, ds)?;
+    cut.set_cache_level(0)?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("proof-serving{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "AUTH PATH CACHE", "PROOF TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "proof-serving", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components);
+    let modes = [(String::from("cold"), 0), (String::from("cached"), CACHE_CAPACITY)];
+    for trials in 0..self.max_trials {
+      let mut sampler = ZipfSampler::new(trials as u64, S, ds.size());
+      let requests = (0..REQUESTS).map(|_| sampler.next_u64()).collect::<Vec<_>>();
+      for (mode, capacity) in modes.iter() {
+        // キャッシュの容量の変更は、それまでにキャッシュされた認証パスを破棄する
+        cut.set_auth_path_cache(*capacity)?;
+        let mut elapsed = Duration::ZERO;
+        for i in requests.iter() {
+          elapsed += cut.get_auth_path(*i)?.elapsed;
+          trace::record(Operation::Prove, *i);
+          energy::count(1);
+        }
+        time_complexity.add(mode, elapsed.as_secs_f64() * 1000.0 / REQUESTS as f64);
+        self.quiesce.after_point()?;
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials
+        && modes.iter().all(|(mode, _)| time_complexity.is_cv_sufficient(mode.clone(), self.cv_threshold))
+      {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+        break;
+      }
+      if timer.expired() {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+    cut.set_auth_path_cache(0)?;
+    for (mode, _) in modes.iter() {
+      if let Some(stat) = time_complexity.calculate(mode) {
+        writer::println(format!("{mode}: {:.0} proofs/sec", 1000.0 / stat.mean));
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 固定したホットセットの位置を Zipf 分布に従って繰り返し取得し、パスごとのデータ取得時間の推移を計測します。
+  pub fn measure_the_get_time_over_the_passes_on_a_hot_set<CUT>(
+    self,
+    cut: &mut CUT,
+    action_id: &str,
+    cache_level: usize,
+    ds: &DataSize,
+  ) -> Result<Self>
+  where
+    CUT: GetCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!(
+      "=== Hot Set Get Benchmark ({}, cache level {cache_level}) [{}] ===",
+      cut.implementation(),
+      report::slate_label()
+    );
+
+    let fresh = self.prepare_database(cut, ds)?;
+
+    let id = format!("{action_id}{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_over_passes = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "PASS", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, action_id, &cut.implementation())?)
+      .with_max_samples(self.max_samples)
+      .with_first_access(fresh);
+    let hot_set = hot_set(ds.size(), HOT_SET_SIZE, HOT_SET_SEED);
+    writer::println(format!("Hot set: {} positions, {HOT_SET_REQUESTS} gets per pass", hot_set.len()));
+    cut.set_cache_level(cache_level)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_ms();
+    let mut accessed = HashSet::new();
+    for pass in 1..=self.max_trials as u64 {
+      // パスごとに同じ順序で要求する
+      let mut sampler = ZipfSampler::new(HOT_SET_SEED, HOT_SET_SHAPE, hot_set.len() as u64);
+      for _ in 0..HOT_SET_REQUESTS {
+        let i = hot_set[sampler.next_u64() as usize - 1];
+        let measurement = cut.get(i, splitmix64)?;
+        trace::record(Operation::Get, i);
+        energy::count(1);
+        if fresh && accessed.insert(i) {
+          time_over_passes.add_first_access(&pass, measurement.millis());
+        } else {
+          time_over_passes.add(&pass, measurement.millis());
+        }
+      }
+
+      let (mean, std_dev) = mean_and_std_dev(&time_over_passes, &pass);
+      if timer.expired() {
+        timer.summary_ms(ds.size(), mean, std_dev);
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_ms(ds.size(), mean, std_dev);
+      }
+    }
+
+    // write report
+    let path = time_over_passes.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// Zipf 分布に従うアクセス位置に対するデータ取得時間の頻度を計測します。
+  pub fn measure_the_frequency_of_retrieval_against_positions_by_zipf<CUT>(
+    self,
+    cut: &mut CUT,
+    ds: &DataSize,
+  ) -> Result<Self>
+  where
+    CUT: GetCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Zipf Get Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+
+    let fresh = self.prepare_database(cut, ds)?;
+
+    let id = format!("biased-get{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}_x.csv"));
+    let mut position_frequency = XYReport::new(Unit::Bytes)
+      .with_output(path, "ZIPF", "POSITION")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "biased-get-position", &cut.implementation())?);
+    let path = self.report_path(&format!("{id}_y.csv"));
+    let mut time_frequency = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "ZIPF", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "biased-get", &cut.implementation())?)
+      .with_max_samples(self.max_samples)
+      .with_first_access(fresh);
+    cut.set_cache_level(0)?;
+    cut.set_handle_mode(self.handle_mode)?;
+    let mut accessed = HashSet::new();
+    for s in [0.5, 1.2, 1.5, 2.0] {
+      let x_label = format!("{s:.1}");
+      writer::println(format!("\nShape = {x_label}"));
+      let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+      ExpirationTimer::heading_ms();
+
+      let mut sampler = ZipfSampler::new(100, s, ds.size() - 1);
+      let accuracy = sampler.accuracy_report();
+      writer::println(format!("Head CDF: {} positions, tail mass {:.4}", accuracy.cutoff, accuracy.tail_mass));
+      for _ in 0..self.max_trials {
+        let position = sampler.next_u64();
+        let measurement = cut.get(position, splitmix64)?;
+        trace::record(Operation::Get, position);
+        energy::count(1);
+        if fresh && accessed.insert(position) {
+          time_frequency.add_first_access(&x_label, measurement.millis());
+        } else {
+          time_frequency.add(&x_label, measurement.millis());
+        }
+        position_frequency.add(&x_label, position);
+
+        if timer.expired() {
+          let (mean, std_dev) = mean_and_std_dev(&time_frequency, &x_label);
+          timer.summary_ms(ds.size(), mean, std_dev);
+          writer::println("** TIMED OUT **");
+          break;
+        }
+        if timer.carried_out(1) {
+          let (mean, std_dev) = mean_and_std_dev(&time_frequency, &x_label);
+          timer.summary_ms(ds.size(), mean, std_dev);
+        }
+      }
+    }
+
+    // write report
+    let path = position_frequency.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    let path = time_frequency.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// データ差異の位置に対する差分検出時間を計測します。
+  pub fn measure_the_prove_time_relative_to_the_position<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: ProveCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Prove Benchmark ({}) [{}] ===", cut.implementation(), report::slate_label());
+    let mut gauge = self.gauge(ds.size());
+
+    println!("Preparing {} databases each with a different for location...", gauge.len() + 1);
+    let quota = self.quota();
+    let progress = PrepareProgress::new((1 + gauge.len()) as u64 * ds.size());
+    cut.prepare(ds.size(), splitmix64, |i| {
+      progress.inc(i);
+      poll(quota)
+    })?;
+    progress.reset_elapsed();
+    let (mut errs, targets): (Vec<BenchError>, Vec<_>) = gauge
+      .iter()
+      .copied()
+      .map(|i| (i, cut.alternate()))
+      .par_bridge()
+      .map(|(i, alt)| match alt {
+        Ok(mut alt) => {
+          alt.prepare(
+            ds.size(),
+            |k| {
+              let value = splitmix64(k);
+              if i == k { splitmix64(value) } else { value }
+            },
+            |_i| {
+              progress.inc(1);
+              poll(quota)
+            },
+          )?;
+          Ok((i, alt))
+        }
+        Err(err) => Err(err),
+      })
+      .partition_map(|target| match target {
+        Ok(target) => Either::Right(target),
+        Err(err) => Either::Left(err),
+      });
+    if let Some(elapsed) = progress.finish(false) {
+      self.record_prepare("prepare-prove", &cut.implementation(), ds, elapsed)?;
+    }
+    if !errs.is_empty() {
+      drop(targets);
+      for err in errs.iter() {
+        eprintln!("ERROR: {err:?}");
+      }
+      return Err(errs.pop().unwrap());
+    }
+    let cuts = targets.into_iter().collect::<HashMap<_, _>>();
+    println!("preparation completed\n");
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let mut rng = rand::rng();
+    let id = format!("prove{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "DISTANCE", "DETECT TIME")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "prove", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components);
+    for trials in 0..self.max_trials {
+      gauge.shuffle(&mut rng);
+      for i in gauge.iter().cloned() {
+        let other = cuts.get(&i).unwrap();
+        let (result, measurement) = cut.prove(other)?;
+        trace::record(Operation::Prove, i);
+        energy::count(1);
+        ensure!(result == Some(i), "the difference is detected at {result:?}, expected {i}");
+        time_complexity.add(&(ds.size() - i + 1), measurement.millis());
+        self.quiesce.after_point()?;
+      }
+
+      time_complexity.end_pass();
+      self.after_trial()?;
+      if trials + 1 >= self.min_trials {
+        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
+        gauge = self.estimate_required_trials(gauge, &time_complexity, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+          break;
+        }
+      }
+      if timer.expired() {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
+      }
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// データ量に対する現在のルートハッシュの取得時間を計測します。固定長の木は 2 の冪のサイズでしか構築できないため、
+  /// データ量は 2 の冪ごととし、各データ量でデータベースを拡張 (または再構築) してから試行を繰り返します。
+  pub fn measure_the_root_hash_time_relative_to_the_data_size<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
+  where
+    CUT: RootHashCUT,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Root Hash Benchmark ({}) [{}] ===\n", cut.implementation(), report::slate_label());
+
+    let sizes = (0..=ds.size().ilog2()).map(|k| 1u64 << k).collect::<Vec<_>>();
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials * sizes.len(), 10);
+    ExpirationTimer::heading_ms();
+
+    let id = format!("root-hash{}-{}", ds.file_id(), cut.implementation());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "SIZE", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "root-hash", &cut.implementation())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples);
+    let quota = self.quota();
+    'sizes: for n in sizes.iter() {
+      let progress = PrepareProgress::new(*n);
+      cut.prepare(*n, splitmix64, |i| {
+        progress.inc(i);
+        poll(quota)
+      })?;
+      progress.finish(true);
+      if self.quiesce.is_enabled() {
+        self.quiesce.run()?;
+      }
+      for trials in 0..self.max_trials {
+        let measurement = cut.root_hash()?;
+        energy::count(1);
+        time_complexity.add(n, measurement.millis());
+        self.after_trial()?;
+        timer.carried_out(1);
+        if timer.expired() {
+          let s = time_complexity.calculate(n).unwrap();
+          timer.summary_ms(*n, s.mean, s.std_dev);
+          writer::println("** TIMED OUT **");
+          break 'sizes;
+        }
+        if trials + 1 >= self.min_trials {
+          let pending = filter_cv_sufficient(&[*n], &time_complexity, self.cv_threshold);
+          if self.estimate_required_trials(pending, &time_complexity, &mut timer).is_empty() {
+            break;
+          }
+        }
+      }
+      let s = time_complexity.calculate(n).unwrap();
+      timer.summary_ms(*n, s.mean, s.std_dev);
+    }
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// 入力データサイズに対するハッシュ関数の計算時間を計測します。
+  pub fn measure_the_hashing_time_relative_to_the_data_size<H: HashAlgorithm>(self) -> Result<Self> {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Hash Benchmark ({}) [{}] ===", H::name(), report::slate_label());
+
+    // 1 回の計算はタイマーの分解能より短いため、複数回の平均を 1 サンプルとする
+    const REPEAT: u32 = 1024;
+    const MAX_DATA_SIZE: u64 = 64 * 1024;
+    let data = (0..MAX_DATA_SIZE).map(|i| splitmix64(i) as u8).collect::<Vec<_>>();
+    let (left, right) = (H::hash(&data[..8]), H::hash(&data[8..16]));
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let id = format!("hash-{}", H::name());
+    let path = self.report_path(&format!("{id}.csv"));
+    let mut time_complexity = XYReport::new(Unit::Milliseconds)
+      .with_output(path, "BYTES", "MILLISECONDS")
+      .with_raw_samples(RawSamples::new(self.raw_samples, "hash", &H::name())?)
+      .with_warmup_detection(self.discard_warmup)
+      .with_max_samples(self.max_samples)
+      .with_variance_decomposition(self.variance_components);
+    let mut combine_time = XYReport::new(Unit::Milliseconds);
+    let mut gauge = self.gauge(MAX_DATA_SIZE);
+    for trials in 0..self.max_trials {
+      for size in gauge.iter() {
+        let input = &data[..*size as usize];
+        let start = Stopwatch::start();
+        for _ in 0..REPEAT {
+          black_box(H::hash(black_box(input)));
+        }
+        let elapsed = start.elapsed() / REPEAT;
+        time_complexity.add(size, elapsed.as_nanos() as f64 / 1000.0 / 1000.0);
+      }
+      let start = Stopwatch::start();
+      for _ in 0..REPEAT {
+        black_box(H::combine(black_box(&left), black_box(&right)));
+      }
+      let elapsed = start.elapsed() / REPEAT;
+      combine_time.add(&0, elapsed.as_nanos() as f64 / 1000.0 / 1000.0);
+      time_complexity.end_pass();
+
+      if trials + 1 >= self.min_trials {
+        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
+        gauge = self.estimate_required_trials(gauge, &time_complexity, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(MAX_DATA_SIZE, time_complexity.max_cv());
+          break;
+        }
+      }
+      if timer.expired() {
+        timer.summary_max_cv(MAX_DATA_SIZE, time_complexity.max_cv());
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(MAX_DATA_SIZE, time_complexity.max_cv());
+      }
+    }
+    writer::println(format!("combine: {}", combine_time.calculate(&0).unwrap()));
+
+    // write report
+    let path = time_complexity.save()?;
+    println!("==> The results have been saved in: {}", path.to_string_lossy());
+    Ok(self)
+  }
+
+  /// `max_payload_size` までのペイロードのサイズに対するノードの直列化 (write) と復元 (read) の時間を、ストレージを介さず
+  /// メモリ上で計測します。
+  pub fn measure_the_serialization_time_relative_to_the_payload_size<S, F>(
+    self,
+    implementation: &str,
+    max_payload_size: usize,
+    node: F,
+  ) -> Result<Self>
+  where
+    S: Serializable,
+    F: Fn(&[u8]) -> Result<S>,
+  {
+    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
+    println!("=== Serialization Benchmark ({implementation}) [{}] ===", report::slate_label());
+
+    // 1 回の処理はタイマーの分解能より短いため、複数回の平均を 1 サンプルとする
+    const REPEAT: u32 = 1024;
+    let max_payload_size = max_payload_size as u64;
+    let data = (0..max_payload_size).map(|i| splitmix64(i) as u8).collect::<Vec<_>>();
+    let mut gauge = self.gauge(max_payload_size);
+    let nodes =
+      gauge.iter().map(|size| Ok((*size, node(&data[..*size as usize])?))).collect::<Result<HashMap<_, _>>>()?;
+
+    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
+    ExpirationTimer::heading_max_cv();
+
+    let report = |kind: &str| -> Result<XYReport<u64, f64>> {
+      let path = self.report_path(&format!("{kind}-{implementation}.csv"));
+      Ok(
+        XYReport::new(Unit::Milliseconds)
+          .with_output(path, "BYTES", "MILLISECONDS")
+          .with_raw_samples(RawSamples::new(self.raw_samples, kind, implementation)?)
+          .with_warmup_detection(self.discard_warmup)
+          .with_max_samples(self.max_samples)
+          .with_variance_decomposition(self.variance_components),
+      )
+    };
+    let mut write_time = report("serialize")?;
+    let mut read_time = report("deserialize")?;
+    let mut buffer = Vec::with_capacity(2 * max_payload_size as usize);
+    let max_cv = |w: &XYReport<u64, f64>, r: &XYReport<u64, f64>| w.max_cv().max(r.max_cv());
+    for trials in 0..self.max_trials {
+      for size in gauge.iter() {
+        let node = &nodes[size];
+        let start = Stopwatch::start();
+        for _ in 0..REPEAT {
+          buffer.clear();
+          black_box(node.write(black_box(&mut buffer))?);
+        }
+        let elapsed = start.elapsed() / REPEAT;
+        write_time.add(size, elapsed.as_nanos() as f64 / 1000.0 / 1000.0);
+
+        let start = Stopwatch::start();
+        for _ in 0..REPEAT {
+          black_box(S::read(&mut Cursor::new(black_box(buffer.as_slice())), 0)?);
+        }
+        let elapsed = start.elapsed() / REPEAT;
+        read_time.add(size, elapsed.as_nanos() as f64 / 1000.0 / 1000.0);
+      }
+      write_time.end_pass();
+      read_time.end_pass();
+
+      if trials + 1 >= self.min_trials {
+        let cv = self.cv_threshold;
+        gauge.retain(|x| !write_time.is_cv_sufficient(*x, cv) || !read_time.is_cv_sufficient(*x, cv));
+        gauge = self.estimate_required_trials(gauge, &read_time, &mut timer);
+        if gauge.is_empty() {
+          timer.summary_max_cv(max_payload_size, max_cv(&write_time, &read_time));
+          break;
+        }
+      }
+      if timer.expired() {
+        timer.summary_max_cv(max_payload_size, max_cv(&write_time, &read_time));
+        writer::println("** TIMED OUT **");
+        break;
+      }
+      if timer.carried_out(1) {
+        timer.summary_max_cv(max_payload_size, max_cv(&write_time, &read_time));
+      }
+    }
+
+    // write report
+    for time_complexity in [write_time, read_time] {
+      let path = time_complexity.save()?;
+      println!("==> The results have been saved in: {}", path.to_string_lossy());
+    }
+    Ok(self)
+  }
+}
+
+/// データベースのエントリ数。レポートのファイル名はサイズの区分で区別される。
+pub enum DataSize {
+  Large(u64),
+  Small(u64),
+}
+
+impl DataSize {
+  pub fn size(&self) -> u64 {
+    match self {
+      DataSize::Small(len) => *len,
+      DataSize::Large(len) => *len,
+    }
+  }
+  pub fn file_id(&self) -> String {
+    match self {
+      DataSize::Small(_) => String::from(""),
+      DataSize::Large(_) => String::from("_large"),
+    }
+  }
+}
+
+/// Number of the levels merged by appending the entry at position `i`, i.e. the carries of the binary counter from
+/// i - 1 to i, which is the number of the trailing ones of i - 1.
+fn merged_levels(i: Index) -> u64 {
+  u64::from((i - 1).trailing_ones())
+}
+
+/// Seed of the positions of the gets and the auth paths in the scenarios
+const SCENARIO_SEED: u64 = 100;
+
+/// Seed of the positions of the gets of [`Case::measure_the_storage_amplification`]
+const AMPLIFICATION_SEED: u64 = 200;
+
+/// Number of the gets at each point of [`Case::measure_the_storage_amplification`]
+const AMPLIFICATION_GETS: u64 = 1000;
+
+/// Number of the positions in the hot set of [`Case::measure_the_get_time_over_the_passes_on_a_hot_set`]
+const HOT_SET_SIZE: usize = 1024;
+/// Number of the gets in a pass over the hot set
+const HOT_SET_REQUESTS: usize = 1000;
+/// Shape of the Zipf distribution of the gets over the hot set
+const HOT_SET_SHAPE: f64 = 1.2;
+const HOT_SET_SEED: u64 = 100;
+
+/// `size` distinct positions (at most `n`) drawn uniformly from 1..=n with the seed.
+fn hot_set(n: Index, size: usize, seed: u64) -> Vec<Index> {
+  let size = size.min(n as usize);
+  let (mut positions, mut drawn) = (Vec::with_capacity(size), HashSet::with_capacity(size));
+  let mut state = seed;
+  while positions.len() < size {
+    state = splitmix64(state);
+    let i = state % n + 1;
+    if drawn.insert(i) {
+      positions.push(i);
+    }
+  }
+  positions
+}
+
+/// Maximum number of the operations timed in a sample by [`batch_size`]
+const MAX_BATCH: u32 = 1 << 20;
+
+/// Number of the operations to time in a sample so that it takes at least `min_time`, doubled from 1 as Criterion
+/// does, since the time of a single operation far below a microsecond is quantized by the clock. `operation` returns
+/// the time of the given number of the operations.
+fn batch_size<F: FnMut(u32) -> Result<Duration>>(min_time: Duration, mut operation: F) -> Result<u32> {
+  let mut k = 1;
+  loop {
+    if operation(k)? >= min_time || k >= MAX_BATCH {
+      return Ok(k);
+    }
+    k *= 2;
+  }
+}
+
+/// k distinct positions of a batch for position i: i and the preceding ones, wrapping around to n, so that the batch
+/// doesn't repeat a position warmed up by itself.
+fn batch(i: Index, k: u32, n: Index) -> Vec<Index> {
+  (0..(k as u64).min(n)).map(|j| (i - 1 + n - j) % n + 1).collect()
+}
+
+/// Read the entries at `positions` and check them against the generated values. An interruption aborts the check.
+///
+/// ## Returns
+/// - the messages of the entries that don't match or can't be read
+fn validate_entries<C: GetCUT>(cut: &mut C, positions: &[Index]) -> Result<Vec<String>> {
+  let mut mismatches = Vec::new();
+  for i in positions.iter() {
+    match cut.get(*i, splitmix64) {
+      Ok(_) => (),
+      Err(BenchError::Validation(msg)) => mismatches.push(msg),
+      Err(err @ BenchError::Interrupted(_)) => return Err(err),
+      Err(err) => mismatches.push(format!("entry {i} can't be read: {err}")),
+    }
+  }
+  Ok(mismatches)
+}
+
+/// Start the reader processes of [`MultiProcessGetCUT`] all at once after every one has opened the database, and return
+/// the mean get time of each in milliseconds.
+fn run_readers(mut children: Vec<Child>, queries: usize) -> Result<Vec<f64>> {
+  // 応答しない読み取りプロセスを待ち続けないよう、準備完了の通知は別スレッドで読み期限付きで待つ
+  let handshakes = children
+    .iter_mut()
+    .map(|child| {
+      let mut reader = BufReader::new(child.stdout.take().unwrap());
+      let (tx, rx) = std::sync::mpsc::channel();
+      std::thread::spawn(move || {
+        let mut line = String::new();
+        let _ = tx.send(reader.read_line(&mut line).map(|_| (reader, line)));
+      });
+      rx
+    })
+    .collect::<Vec<_>>();
+  let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+  let mut readers = Vec::with_capacity(children.len());
+  for handshake in handshakes {
+    let Ok(result) = handshake.recv_timeout(deadline.saturating_duration_since(Instant::now())) else {
+      children.iter_mut().for_each(|child| drop(child.kill()));
+      let msg = format!("the reader process didn't open the database in {}s", HANDSHAKE_TIMEOUT.as_secs());
+      return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, msg).into());
+    };
+    let (reader, line) = result?;
+    ensure!(line.trim() == "ready", "the reader process failed to open the database");
+    readers.push(reader);
+  }
+  // 標準入力を閉じると一斉に取得を開始する
+  for child in children.iter_mut() {
+    drop(child.stdin.take());
+  }
+  // 読み取りプロセスの計測値には、このプロセスで較正したオーバーヘッドを差し引く
+  let overhead = clock::overhead_subtraction().as_nanos() as u64;
+  let mut means = Vec::with_capacity(children.len());
+  for (child, reader) in children.iter_mut().zip(readers) {
+    let mut total = 0u64;
+    let mut count = 0;
+    for line in reader.lines() {
+      let line = line?;
+      let nanos = line.trim().parse::<u64>();
+      let nanos =
+        nanos.map_err(|_| BenchError::Validation(format!("unexpected output of the reader process: {line}")))?;
+      total += nanos.saturating_sub(overhead);
+      count += 1;
+    }
+    let status = child.wait()?;
+    ensure!(status.success() && count == queries, "the reader process exited with {status} after {count} gets");
+    means.push(total as f64 / count as f64 / 1000.0 / 1000.0);
+  }
+  Ok(means)
+}
+
+/// Record the entries as `{key}={value}` lines in the session marker file at `path`, replacing the same keys.
+fn set_metadata(path: &Path, entries: &[(String, String)]) -> Result<()> {
+  let mut lines = match fs::read_to_string(path) {
+    Ok(text) => text.lines().map(String::from).collect::<Vec<_>>(),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+    Err(err) => return Err(err.into()),
+  };
+  lines.retain(|line| !entries.iter().any(|(key, _)| line.split_once('=').is_some_and(|(k, _)| k == *key)));
+  lines.extend(entries.iter().map(|(key, value)| format!("{key}={value}")));
+  write_atomically(path, |writer| {
+    for line in lines.iter() {
+      writeln!(writer, "{line}")?;
+    }
+    Ok(())
+  })
+}
+
+fn filter_cv_sufficient(gauge: &[u64], ss: &stat::XYReport<u64, f64>, cv: f64) -> Vec<u64> {
+  gauge.iter().filter(|i| !ss.is_cv_sufficient(**i, cv)).cloned().collect::<Vec<_>>()
+}
+
+/// Mean and standard deviation at x for the summary, NaN if x has only the first accesses.
+fn mean_and_std_dev<X: std::fmt::Display + Clone + std::hash::Hash + Eq + Ord>(
+  report: &XYReport<X, f64>,
+  x: &X,
+) -> (f64, f64) {
+  report.calculate(x).map_or((f64::NAN, f64::NAN), |s| (s.mean, s.std_dev))
+}
+
+/// Check the quota of the working directories, if any, from the progress of the database preparation.
+fn poll(quota: Option<&WorkdirQuota>) -> Result<()> {
+  quota.map_or(Ok(()), WorkdirQuota::poll)
+}
+
+// プログレスバーの準備
+fn create_progress_bar(n: u64) -> ProgressBar {
+  let pb = ProgressBar::with_draw_target(Some(n), ProgressDrawTarget::stdout_with_hz(1));
+  pb.set_style(
+    ProgressStyle::default_bar()
+      .template("Preparing: {spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+      .unwrap()
+      .progress_chars("#>-"),
+  );
+  if pb.is_hidden() {
+    println!("(progress bar is hidden, the progress is printed every {}s)", PREPARE_REPORT_INTERVAL.as_secs());
+  }
+  pb
+}
+
+/// Interval of the progress lines of the database preparation while the progress bar is hidden.
+const PREPARE_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Progress of the database preparation. The ETA is estimated from the append rate measured since the first report,
+/// so that the entries already in a shared database (reported all at once) don't make it optimistic.
+struct PrepareProgress {
+  pb: ProgressBar,
+  start: Instant,
+  /// Time and position of the first report
+  origin: OnceLock<(Instant, u64)>,
+  last_report: Mutex<Instant>,
+}
+
+impl PrepareProgress {
+  fn new(n: u64) -> Self {
+    let now = Instant::now();
+    PrepareProgress { pb: create_progress_bar(n), start: now, origin: OnceLock::new(), last_report: Mutex::new(now) }
+  }
+
+  fn inc(&self, delta: u64) {
+    self.pb.inc(delta);
+    let position = self.pb.position();
+    let (start, base) = *self.origin.get_or_init(|| (Instant::now(), position));
+    let interval = if self.pb.is_hidden() { PREPARE_REPORT_INTERVAL } else { Duration::from_secs(1) };
+    let now = Instant::now();
+    {
+      let mut last_report = self.last_report.lock().unwrap();
+      if now.duration_since(*last_report) < interval {
+        return;
+      }
+      *last_report = now;
+    }
+    let rate = (position - base) as f64 / now.duration_since(start).as_secs_f64();
+    let n = self.pb.length().unwrap_or(position);
+    let eta = if rate > 0.0 {
+      stat::format_eta(now + Duration::from_secs_f64(n.saturating_sub(position) as f64 / rate))
+    } else {
+      String::from("-")
+    };
+    let message = format!("({rate:.0} entries/s, ETA {eta})");
+    if self.pb.is_hidden() {
+      writer::println(format!("Preparing: {position}/{n} {message}"));
+    } else {
+      self.pb.set_message(message);
+    }
+  }
+
+  fn reset_elapsed(&self) {
+    self.pb.reset_elapsed();
+  }
+
+  /// Finish the progress and return the time taken, or `None` if no entries were appended (the database was shared).
+  fn finish(self, clear: bool) -> Option<Duration> {
+    if clear {
+      self.pb.finish_and_clear();
+    } else {
+      self.pb.finish();
+    }
+    let (_, base) = self.origin.get().copied()?;
+    (self.pb.position() > base).then(|| self.start.elapsed())
+  }
+}
+
+/// Typed output of a single operation on a CUT.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Measurement {
+  pub elapsed: Duration,
+  /// Storage size after the operation, if it's relevant
+  pub bytes: Option<u64>,
+  /// Number of entries processed by the operation, if it's relevant
+  pub count: Option<u64>,
+}
+
+impl Measurement {
+  pub fn new(elapsed: Duration) -> Self {
+    Self { elapsed: elapsed.saturating_sub(clock::overhead_subtraction()), bytes: None, count: None }
+  }
+
+  pub fn with_bytes(mut self, bytes: u64) -> Self {
+    self.bytes = Some(bytes);
+    self
+  }
+
+  pub fn with_count(mut self, count: u64) -> Self {
+    self.count = Some(count);
+    self
+  }
+
+  pub fn millis(&self) -> f64 {
+    self.elapsed.as_nanos() as f64 / 1000.0 / 1000.0
+  }
+}
+
+/// Lifecycle of a CUT: `setup` → (`prepare` | `append`...) → operations → `cleanup` → ... → `teardown`.
+pub trait CUT {
+  fn implementation(&self) -> String;
+
+  /// Called once before the first test unit runs on this CUT.
+  fn setup(&mut self) -> Result<()> {
+    Ok(())
+  }
+
+  /// Build the database with n entries, reusing the entries already built. An error from `progress` aborts the build.
+  fn prepare<V: Fn(u64) -> u64, F: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: F) -> Result<()>;
+
+  /// Discard all entries so that the next `prepare` or `append` starts from an empty database.
+  fn cleanup(&mut self) -> Result<()>;
+
+  /// Called once after the last test unit has run on this CUT.
+  fn teardown(&mut self) -> Result<()> {
+    Ok(())
+  }
+}
+
+pub trait GetCUT: CUT {
+  fn set_cache_level(&mut self, cache_size: usize) -> Result<()>;
+  /// Whether the backend is closed and reopened before each `get`, out of the measured time.
+  fn set_handle_mode(&mut self, mode: HandleMode) -> Result<()>;
+  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Measurement>;
+
+  /// Get the values at all positions `is` in a single timed section, for the gets far below a microsecond whose time
+  /// is quantized by the clock. The values are checked after the section. The default sums the times of `get`, which
+  /// include a measurement per get.
+  ///
+  /// ## Returns
+  /// - the time to get all values with the number of values
+  fn get_batch<V: Fn(u64) -> u64>(&mut self, is: &[Index], values: V) -> Result<Measurement> {
+    let mut elapsed = Duration::ZERO;
+    for i in is.iter() {
+      elapsed += self.get(*i, &values)?.elapsed;
+    }
+    Ok(Measurement { elapsed, bytes: None, count: Some(is.len() as u64) })
+  }
+}
+
+pub trait AppendCUT: CUT {
+  /// Append the entries until the database has n entries.
+  ///
+  /// ## Returns
+  /// - the elapsed time with the storage size
+  fn append<V: Fn(u64) -> u64>(&mut self, n: Index, values: V) -> Result<Measurement>;
+}
+
+pub trait RangeCUT: GetCUT {
+  /// Read the m consecutive values from position i.
+  ///
+  /// ## Returns
+  /// - the time to read all values with the number of values
+  fn scan<V: Fn(u64) -> u64>(&mut self, i: Index, m: Index, values: V) -> Result<Measurement>;
+}
+
+pub trait ConcurrentGetCUT: GetCUT {
+  /// Retrieve the values at all positions `is`, distributing them over `threads` threads with a reader each.
+  ///
+  /// ## Returns
+  /// - the time until all threads finish with the number of values
+  fn concurrent_get<V: Fn(u64) -> u64 + Sync>(
+    &mut self,
+    threads: usize,
+    is: &[Index],
+    values: V,
+  ) -> Result<Measurement>;
+}
+
+pub trait ProveCUT: GetCUT + Sync + Send {
+  fn prove(&self, other: &Self) -> Result<(Option<u64>, Measurement)>;
+  fn alternate(&self) -> Result<Self>
+  where
+    Self: std::marker::Sized;
+}
+
+pub trait RootHashCUT: CUT {
+  /// Obtain the root hash of the current entries.
+  fn root_hash(&mut self) -> Result<Measurement>;
+}
+
+/// Time to wait for the reader processes of [`MultiProcessGetCUT`] to open the database, and for a reader process to
+/// be told to start the gets after that.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// CUT whose database can be read by other processes at the same time, e.g. verifiers reading a log written by another
+/// process.
+pub trait MultiProcessGetCUT: GetCUT {
+  /// Command of a reader process that opens the database read-only and prints `ready`, and when its stdin is closed,
+  /// gets `count` entries at uniformly random positions in `1..=n` by `seed` and prints the time of each get in
+  /// nanoseconds, one per line.
+  fn reader_command(&self, n: Index, count: usize, seed: u64) -> Result<std::process::Command>;
+}
+
+/// Store written by [`DualWriteCUT::write`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WriteTarget {
+  Primary,
+  Slate,
+  /// The primary store and then slate in the same logical operation
+  Both,
+}
+
+impl WriteTarget {
+  pub const ALL: [WriteTarget; 3] = [WriteTarget::Primary, WriteTarget::Slate, WriteTarget::Both];
+
+  pub fn label(&self) -> String {
+    String::from(match self {
+      WriteTarget::Primary => "primary-only",
+      WriteTarget::Slate => "slate-only",
+      WriteTarget::Both => "dual",
+    })
+  }
+}
+
+/// CUT that writes each record to a primary KV store and appends it to slate, as an application that adds slate to
+/// its existing pipeline.
+pub trait DualWriteCUT: CUT {
+  /// Write the record at position `i`, the one following the records already written to the target.
+  fn write(&mut self, target: WriteTarget, i: Index, value: u64) -> Result<Measurement>;
+}
+
+pub trait AuthPathCUT: GetCUT {
+  /// Memoize up to `capacity` auth paths computed recently, discarding the cached ones. 0 disables the cache.
+  fn set_auth_path_cache(&mut self, capacity: usize) -> Result<()>;
+  fn get_auth_path(&mut self, i: Index) -> Result<Measurement>;
+}
+
+/// CUT whose storage calls are counted by [`CountingStorage`](crate::counting::CountingStorage).
+pub trait CountingCUT: AppendCUT + GetCUT {
+  /// Counters of the calls on the storages of the CUT, accumulated over the storages it opens again.
+  fn storage_counter(&self) -> StorageCounter;
+}
+
+pub trait IntoFloat: Copy {
+  fn into_f64(self) -> f64;
+}
+
+impl IntoFloat for u64 {
+  fn into_f64(self) -> f64 {
+    self as f64
+  }
+}
+
+impl IntoFloat for f64 {
+  fn into_f64(self) -> f64 {
+    self
+  }
+}
+
+fn linspace(min: u64, max: u64, n: usize) -> Vec<u64> {
+  assert!(n > 1);
+  let step = (max - min) as f64 / (n - 1) as f64;
+  (0..n)
+    .map(|i| {
+      let val = min as f64 + step * i as f64;
+      val.round() as u64
+    })
+    .collect()
+}
+
+fn logspace(min: u64, max: u64, n: usize) -> Vec<u64> {
+  assert!(min > 0, "min must be positive for logspace");
+  assert!(n > 1);
+  let log_min = (min as f64).ln();
+  let log_max = (max as f64).ln();
+  let step = (log_max - log_min) / (n - 1) as f64;
+  (0..n)
+    .map(|i| {
+      let val = (log_min + step * i as f64).exp();
+      val.round() as u64
+    })
+    .collect()
+}
@@ -0,0 +1,282 @@
+use super::*;
+
+/// CUT that keeps the values in memory, with a corrupted entry if `broken` is specified.
+struct VecCUT {
+  values: Vec<u64>,
+  broken: Option<Index>,
+}
+
+impl CUT for VecCUT {
+  fn implementation(&self) -> String {
+    String::from("vec")
+  }
+
+  fn prepare<V: Fn(u64) -> u64, F: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: F) -> Result<()> {
+    for i in self.values.len() as Index + 1..=n {
+      let value = values(i);
+      self.values.push(if self.broken == Some(i) { !value } else { value });
+      progress(1)?;
+    }
+    Ok(())
+  }
+
+  fn cleanup(&mut self) -> Result<()> {
+    self.values.clear();
+    Ok(())
+  }
+}
+
+impl GetCUT for VecCUT {
+  fn set_cache_level(&mut self, _cache_size: usize) -> Result<()> {
+    Ok(())
+  }
+
+  fn set_handle_mode(&mut self, _mode: HandleMode) -> Result<()> {
+    Ok(())
+  }
+
+  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Measurement> {
+    let start = Instant::now();
+    ensure!(i as usize <= self.values.len(), "{i} is out of {}", self.values.len());
+    let value = self.values[i as usize - 1];
+    let elapsed = start.elapsed();
+    ensure!(value == values(i), "unexpected value {value} at {i}");
+    Ok(Measurement::new(elapsed))
+  }
+}
+
+#[test]
+fn run_testunit_on_custom_cut() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment = Experiment::new("test", dir.path(), dir.path()).unwrap().profile(Profile::Simple);
+  let mut cut = VecCUT { values: Vec::new(), broken: None };
+  experiment.run_testunit_uniformed_get(&mut cut, &DataSize::Small(64)).unwrap();
+  assert_eq!(64, cut.values.len());
+  assert!(dir.path().join("test-get-vec.csv").exists());
+
+  // 構築直後の最初のアクセスは同じレポートの first 系列に目盛りごとに 1 つだけ記録される
+  let report = std::fs::read_to_string(dir.path().join("test-get-vec.csv")).unwrap();
+  let mut lines = report.lines();
+  assert_eq!(Some("DISTANCE,SERIES,ACCESS TIME"), lines.next());
+  let rows = lines.map(|line| line.split(',').collect::<Vec<_>>()).collect::<Vec<_>>();
+  let first = rows.iter().filter(|row| row[1] == stat::FIRST_ACCESS).collect::<Vec<_>>();
+  assert!(!first.is_empty());
+  assert!(first.iter().all(|row| row.len() == 3));
+  assert!(rows.iter().any(|row| row[1] == stat::WARM_ACCESS));
+}
+
+#[test]
+fn validate_detects_mismatch() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment = Experiment::new("test", dir.path(), dir.path()).unwrap();
+  let mut cut = VecCUT { values: Vec::new(), broken: None };
+  experiment.validate(&mut cut, &DataSize::Small(100), 10).unwrap();
+
+  let mut cut = VecCUT { values: Vec::new(), broken: Some(21) };
+  assert!(matches!(experiment.validate(&mut cut, &DataSize::Small(100), 10), Err(BenchError::Validation(_))));
+
+  // 構築の途中で止まったデータベースは構築し直さずに検証し、最後のエントリも読み直す
+  let mut cut = VecCUT { values: (1..=50).map(splitmix64).collect(), broken: None };
+  assert!(matches!(experiment.validate(&mut cut, &DataSize::Small(100), 10), Err(BenchError::Validation(_))));
+  assert_eq!(50, cut.values.len());
+  let report = fs::read_to_string(dir.path().join("test-validation.csv")).unwrap();
+  let expected = vec!["CUT,SIZE,CHECKED,MISMATCHES", "vec,100,11,0", "vec,100,11,1", "vec,100,11,6"];
+  assert_eq!(expected, report.lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn space() {
+  assert_eq!(vec![1, 4, 7, 10], linspace(1, 10, 4));
+  assert_eq!(vec![1, 10, 100, 1000], logspace(1, 1000, 4));
+}
+
+#[test]
+fn reserve_session_with_existing_reports() {
+  let dir = tempfile::tempdir().unwrap();
+  let new = || Experiment::new("test", dir.path(), dir.path()).unwrap();
+  assert_eq!("test", new().reserve_session(ReportPolicy::Suffix).unwrap().session());
+  assert_eq!("test.1", new().reserve_session(ReportPolicy::Suffix).unwrap().session());
+  fs::write(dir.path().join("test.2-get-vec.csv"), "").unwrap();
+  assert_eq!("test.3", new().reserve_session(ReportPolicy::Suffix).unwrap().session());
+  assert!(matches!(new().reserve_session(ReportPolicy::Error), Err(BenchError::Config(_))));
+  assert_eq!("test", new().reserve_session(ReportPolicy::Overwrite).unwrap().session());
+}
+
+#[test]
+fn set_metadata_replaces_same_keys() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment =
+    Experiment::new("test", dir.path(), dir.path()).unwrap().reserve_session(ReportPolicy::Error).unwrap();
+  let entry = |key: &str, value: &str| (String::from(key), String::from(value));
+  experiment.set_metadata(&[entry("a", "1"), entry("b", "2")]).unwrap();
+  experiment.set_metadata(&[entry("a", "3")]).unwrap();
+  assert_eq!("b=2\na=3\n", fs::read_to_string(dir.path().join("test.session")).unwrap());
+}
+
+#[test]
+fn reports_in_session_directory() {
+  let dir = tempfile::tempdir().unwrap();
+  let new = || Experiment::new("test", dir.path(), dir.path()).unwrap().layout(Layout::Session);
+  let experiment = new().reserve_session(ReportPolicy::Suffix).unwrap();
+  assert_eq!("test.1", new().reserve_session(ReportPolicy::Suffix).unwrap().session());
+  assert_eq!(dir.path().join("test/csv/get-vec.csv"), experiment.report_path("get-vec.csv"));
+  assert_eq!(dir.path().join("test/raw/get-vec.parquet"), experiment.report_path("get-vec.parquet"));
+  assert_eq!(dir.path().join("test/stdout.log"), experiment.report_path("stdout.log"));
+
+  fs::write(experiment.report_path("get-vec.csv"), "DISTANCE,ACCESS TIME\n").unwrap();
+  experiment.set_metadata(&[(String::from("tag.host"), String::from("nuc"))]).unwrap();
+  assert_eq!(vec!["get-vec.csv"], experiment.report_names("csv").unwrap());
+  let path = experiment.write_manifest().unwrap().unwrap();
+  let manifest = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(path).unwrap()).unwrap();
+  assert_eq!("nuc", manifest["metadata"]["tag.host"]);
+  let files = manifest["files"].as_array().unwrap().iter().map(|f| f["path"].as_str().unwrap()).collect::<Vec<_>>();
+  assert_eq!(vec!["csv/get-vec.csv", "test.session"], files);
+  assert_eq!(None, Experiment::new("test", dir.path(), dir.path()).unwrap().write_manifest().unwrap());
+}
+
+#[test]
+fn gauge_includes_both_ends() {
+  let experiment = Experiment::new("test", &std::env::temp_dir(), &std::env::temp_dir()).unwrap();
+  for n in [2, 3, 100, 1000, 65536] {
+    for scale in [Scale::Linear, Scale::Log, Scale::BestCase, Scale::WorstCase] {
+      let gauge = experiment.case().unwrap().scale(scale).division(7).gauge(n);
+      assert_eq!(Some(&1), gauge.first(), "n={n}");
+      assert_eq!(Some(&n), gauge.last(), "n={n}");
+      assert!(gauge.windows(2).all(|w| w[0] < w[1]), "n={n}: {gauge:?}");
+    }
+  }
+}
+
+#[test]
+fn clean_policy_selects_old_and_oversized_entries() {
+  let now = SystemTime::now();
+  let day = Duration::from_secs(24 * 60 * 60);
+  let entries = [
+    (PathBuf::from("new"), now, 300),
+    (PathBuf::from("old"), now - 10 * day, 100),
+    (PathBuf::from("recent"), now - 2 * day, 200),
+  ];
+  let select = |older_than: Option<Duration>, max_total_size: Option<u64>| {
+    let policy = CleanPolicy { older_than, max_total_size };
+    policy.select(&entries, now).into_iter().map(|(path, _, _)| path.to_string_lossy().into_owned()).collect::<Vec<_>>()
+  };
+  assert_eq!(vec!["new", "old", "recent"], select(None, None));
+  assert_eq!(vec!["old"], select(Some(7 * day), None));
+  assert_eq!(vec!["old", "recent"], select(None, Some(300)));
+  assert_eq!(vec!["old"], select(Some(7 * day), Some(500)));
+  assert!(select(Some(30 * day), Some(600)).is_empty());
+}
+
+#[test]
+fn record_failures_as_csv() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment = Experiment::new("session", dir.path(), dir.path()).unwrap();
+  experiment.record_failure("slate-file", "Append", &BenchError::Validation(String::from("\"x\", y"))).unwrap();
+  experiment.record_failure("slate-file", "", &BenchError::Config(String::from("z"))).unwrap();
+  let csv = fs::read_to_string(dir.path().join("session-failures.csv")).unwrap();
+  let lines = csv.lines().map(|line| line.split_once(',').unwrap().1).collect::<Vec<_>>();
+  let expected = [
+    "CUT,UNIT,ERROR",
+    "slate-file,Append,\"validation error: \"\"x\"\", y\"",
+    "slate-file,,\"configuration error: z\"",
+  ];
+  assert_eq!(expected.to_vec(), lines);
+}
+
+#[test]
+fn run_testunit_serialization_reports_write_and_read() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment = Experiment::new("test", dir.path(), dir.path()).unwrap().profile(Profile::Simple);
+  let node =
+    |data: &[u8]| Ok(crate::hashtree::binary::Node::new_leaf::<crate::hashtree::hash::Blake3>(1, 1, data.to_vec()));
+  // 計測の手順のみを確かめるため、ペイロードは小さくする
+  experiment
+    .case()
+    .unwrap()
+    .measure_the_serialization_time_relative_to_the_payload_size("binarytree", 64, node)
+    .unwrap();
+  assert!(dir.path().join("test-serialize-binarytree.csv").exists());
+  assert!(dir.path().join("test-deserialize-binarytree.csv").exists());
+}
+
+#[test]
+fn double_the_batch_until_the_minimum_time() {
+  assert_eq!(1, batch_size(Duration::ZERO, |_| Ok(Duration::ZERO)).unwrap());
+  assert_eq!(MAX_BATCH, batch_size(Duration::from_secs(3600), |_| Ok(Duration::ZERO)).unwrap());
+
+  let mut batches = Vec::new();
+  let k = batch_size(Duration::from_micros(3), |k| {
+    batches.push(k);
+    Ok(Duration::from_nanos(k as u64 * 1000))
+  })
+  .unwrap();
+  assert_eq!(4, k);
+  assert_eq!(vec![1, 2, 4], batches);
+
+  let result = batch_size(Duration::from_secs(1), |_| Err(BenchError::Validation(String::from("broken"))));
+  assert!(matches!(result, Err(BenchError::Validation(_))));
+}
+
+#[test]
+fn batch_the_distinct_positions() {
+  assert_eq!(vec![5, 4, 3], batch(5, 3, 8));
+  assert_eq!(vec![2, 1, 8, 7], batch(2, 4, 8));
+  // データサイズを超える回数は n 個の位置に制限される
+  assert_eq!(vec![1, 3, 2], batch(1, 16, 3));
+}
+
+#[test]
+fn draw_the_same_hot_set_for_the_seed() {
+  let hot_set = hot_set(1_000_000, 1024, 100);
+  assert_eq!(1024, hot_set.len());
+  assert_eq!(1024, hot_set.iter().collect::<HashSet<_>>().len());
+  assert!(hot_set.iter().all(|i| (1..=1_000_000).contains(i)));
+  assert_eq!(hot_set, super::hot_set(1_000_000, 1024, 100));
+
+  let mut all = super::hot_set(10, 1024, 100);
+  all.sort();
+  assert_eq!((1..=10).collect::<Vec<_>>(), all);
+}
+
+#[test]
+fn count_the_levels_merged_by_the_append() {
+  let levels = (1..=8).map(merged_levels).collect::<Vec<_>>();
+  assert_eq!(vec![0, 1, 0, 2, 0, 1, 0, 3], levels);
+}
+
+impl DualWriteCUT for VecCUT {
+  fn write(&mut self, target: WriteTarget, i: Index, value: u64) -> Result<Measurement> {
+    let start = Instant::now();
+    if target != WriteTarget::Primary {
+      ensure!(self.values.len() as Index + 1 == i, "{} is not followed by {i}", self.values.len());
+      self.values.push(value);
+    }
+    Ok(Measurement::new(start.elapsed()))
+  }
+}
+
+#[test]
+fn run_testunit_dual_write_reports_each_target() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment = Experiment::new("test", dir.path(), dir.path()).unwrap().profile(Profile::Simple);
+  let mut cut = VecCUT { values: Vec::new(), broken: None };
+  experiment.run_testunit_dual_write(&mut cut, &DataSize::Small(64)).unwrap();
+  assert!(dir.path().join("test-dual-write-vec.csv").exists());
+
+  let throughput = std::fs::read_to_string(dir.path().join("test-dual-write-vec_throughput.csv")).unwrap();
+  let targets = throughput.lines().skip(1).map(|row| row.split(',').next().unwrap()).collect::<Vec<_>>();
+  assert_eq!(vec!["primary-only", "slate-only", "dual"], targets);
+  assert!(throughput.lines().skip(1).all(|row| row.split(',').nth(1) != Some("0")), "{throughput}");
+}
+
+#[test]
+fn reject_batch_min_time_with_reopen() {
+  let dir = tempfile::tempdir().unwrap();
+  let experiment = Experiment::new("test", dir.path(), dir.path())
+    .unwrap()
+    .handle_mode(HandleMode::Reopen)
+    .batch_min_time(Some(Duration::from_micros(10)));
+  assert!(matches!(experiment.check(), Err(BenchError::Config(_))));
+  assert!(matches!(experiment.case(), Err(BenchError::Config(_))));
+  assert!(experiment.handle_mode(HandleMode::Persistent).case().is_ok());
+}
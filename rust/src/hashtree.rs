@@ -1,16 +1,127 @@
 use slate::{Entry, Slate, Storage};
+use std::io::{Error, ErrorKind};
+
+use crate::hashtree::hash::{HashAlgorithm, HashValue};
 
 pub mod binary;
+pub mod hash;
+pub mod implicit;
+pub mod leveled;
+pub mod mmr;
 
 /// Core hash tree abstraction
 pub trait HashTree {
   type Error;
 
+  /// Inclusion proof of a leaf, e.g. [`AuthPath`] for the complete binary trees
+  type Proof;
+
   /// Get the current size (number of leaf nodes)
   fn size(&self) -> u64;
 
   /// Retrieve data by index
   fn get(&mut self, index: u64) -> Result<Option<Vec<u8>>, Self::Error>;
+
+  /// Get the hash of the root node, or `None` if the tree is empty
+  fn root_hash(&mut self) -> Result<Option<HashValue>, Self::Error>;
+
+  /// Get the inclusion proof of the leaf by index
+  fn prove(&mut self, index: u64) -> Result<Option<Self::Proof>, Self::Error>;
+
+  /// Append data as a new leaf and return the new size
+  fn append(&mut self, data: &[u8]) -> Result<u64, Self::Error>;
+}
+
+/// Authentication path from a leaf to the root of a complete binary hash tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthPath {
+  /// Leaf number k ∈ {1,2,...}
+  pub index: u64,
+  pub leaf: HashValue,
+  /// Hashes of the sibling nodes, from the leaf level to the level just below the root.
+  pub siblings: Vec<HashValue>,
+}
+
+impl AuthPath {
+  /// Compute the root hash that this path proves.
+  pub fn root<H: HashAlgorithm>(&self) -> HashValue {
+    let mut hash = self.leaf;
+    let mut i = self.index - 1;
+    for sibling in self.siblings.iter() {
+      hash = if i & 1 == 0 { H::combine(&hash, sibling) } else { H::combine(sibling, &hash) };
+      i >>= 1;
+    }
+    hash
+  }
+}
+
+/// Node whose stored hash differs from the hash recomputed from its value or children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+  /// Position of the node in the storage
+  pub position: u64,
+  /// Node index i ∈ {1,2,...} in breadth-first order
+  pub index: u64,
+  pub stored: HashValue,
+  pub computed: HashValue,
+}
+
+/// Error for the operations that the static, fixed-height trees can't support.
+pub(crate) fn unsupported(operation: &str) -> slate::error::Error {
+  Error::new(ErrorKind::Unsupported, format!("{operation} is not supported by a fixed-height hash tree")).into()
 }
 
+/// Adapter to treat slate as a [`HashTree`] alongside the baseline implementations.
 pub struct SlateHashTree<S: Storage<Entry>>(Slate<S>);
+
+impl<S: Storage<Entry>> SlateHashTree<S> {
+  pub fn new(slate: Slate<S>) -> Self {
+    Self(slate)
+  }
+
+  pub fn into_inner(self) -> Slate<S> {
+    self.0
+  }
+}
+
+impl<S: Storage<Entry>> HashTree for SlateHashTree<S> {
+  type Error = slate::error::Error;
+  type Proof = slate::AuthPath;
+
+  fn size(&self) -> u64 {
+    self.0.n()
+  }
+
+  fn get(&mut self, index: u64) -> slate::Result<Option<Vec<u8>>> {
+    if index == 0 || index > self.0.n() {
+      return Ok(None);
+    }
+    self.0.snapshot().query()?.get(index)
+  }
+
+  fn root_hash(&mut self) -> slate::Result<Option<HashValue>> {
+    // the auth path of the latest entry reaches the root of the whole tree
+    match self.prove(self.0.n())? {
+      Some(auth_path) => {
+        let root: &[u8] = auth_path.root().hash.as_ref();
+        Ok(Some(HashValue::try_from(root).map_err(|e| Error::new(ErrorKind::InvalidData, e))?))
+      }
+      None => Ok(None),
+    }
+  }
+
+  fn prove(&mut self, index: u64) -> slate::Result<Option<slate::AuthPath>> {
+    if index == 0 || index > self.0.n() {
+      return Ok(None);
+    }
+    self.0.snapshot().query()?.get_auth_path(index)
+  }
+
+  fn append(&mut self, data: &[u8]) -> slate::Result<u64> {
+    self.0.append(data)?;
+    Ok(self.0.n())
+  }
+}
+
+#[cfg(test)]
+mod test;
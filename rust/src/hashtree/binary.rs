@@ -1,19 +1,34 @@
-use blake3::{Hash, Hasher, OUT_LEN};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use rayon::prelude::*;
 use slate::file::FileDevice;
 use slate::formula::pow2e;
+#[cfg(feature = "rocksdb")]
+use slate::rocksdb::RocksDBStorage;
 use slate::{BlockStorage, Index, Position, Reader, Result, Serializable, Storage};
 use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::io::{Cursor, Read, Seek, Write};
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 
-use crate::hashtree::HashTree;
+use crate::hashtree::hash::{Blake3, HASH_SIZE, HashAlgorithm, HashValue};
+use crate::hashtree::{AuthPath, HashTree, Mismatch, unsupported};
+#[cfg(feature = "rocksdb")]
+use crate::knobs::Knobs;
+#[cfg(feature = "rocksdb")]
+use crate::open_rocksdb_storage_with;
+#[cfg(feature = "rocksdb")]
+use crate::platform;
 use crate::{MemKVS, splitmix64};
 
 pub const MAX_DATA_SIZE: usize = 1024;
 
+/// Number of leaves hashed in parallel at once while creating a tree; the nodes are written in order after each chunk.
+const CREATE_CHUNK: u64 = 16 * 1024;
+
 #[derive(Debug, Clone)]
 pub enum NodeKind {
   Leaf { data: Vec<u8> },
@@ -25,18 +40,18 @@ pub enum NodeKind {
 pub struct Node {
   pub position: Position,
   pub index: u64,
-  pub hash: Hash,
+  pub hash: HashValue,
   pub kind: NodeKind,
 }
 
 impl Node {
-  pub fn new_leaf(position: u64, index: u64, data: Vec<u8>) -> Self {
-    let hash = blake3::hash(&data);
+  pub fn new_leaf<H: HashAlgorithm>(position: u64, index: u64, data: Vec<u8>) -> Self {
+    let hash = H::hash(&data);
     let leaf = NodeKind::Leaf { data };
     Node { position, index, hash, kind: leaf }
   }
 
-  pub fn new_internal(position: u64, index: u64, hash: Hash, left: Position, right: Position) -> Self {
+  pub fn new_internal(position: u64, index: u64, hash: HashValue, left: Position, right: Position) -> Self {
     let branch = NodeKind::Branch { left, right };
     Node { position, index, hash, kind: branch }
   }
@@ -49,43 +64,94 @@ impl Node {
   }
 }
 
+/// Upper limit of the serialized node body, used to reject a broken length prefix.
+const MAX_NODE_SIZE: usize = 8 + HASH_SIZE + 1 + 4 + MAX_DATA_SIZE;
+
+/// Error for a node that can't be restored from the storage, e.g. by a torn write.
+#[derive(Debug, thiserror::Error)]
+pub enum CorruptedNode {
+  #[error("node @{position} is truncated")]
+  Truncated { position: Position },
+  #[error("node @{position} has an invalid length: {length}")]
+  Length { position: Position, length: usize },
+  #[error("node @{position} has a checksum mismatch: stored {stored:08x}, computed {computed:08x}")]
+  Checksum { position: Position, stored: u32, computed: u32 },
+}
+
+impl From<CorruptedNode> for slate::error::Error {
+  fn from(err: CorruptedNode) -> Self {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, err).into()
+  }
+}
+
+fn checksum(body: &[u8]) -> u32 {
+  let hash = blake3::hash(body);
+  u32::from_le_bytes(hash.as_bytes()[..4].try_into().unwrap())
+}
+
+/// ノードは `[length (4)][body (length)][checksum (4)]` の形式で保存し、読み込み時に長さとチェックサムを検証する。
 impl Serializable for Node {
   fn write<W: Write>(&self, w: &mut W) -> slate::Result<usize> {
+    let mut body = Vec::with_capacity(8 + HASH_SIZE + 1 + 8 + 8);
+
     // Index (8 bytes)
-    w.write_u64::<LittleEndian>(self.index)?;
+    body.write_u64::<LittleEndian>(self.index)?;
 
     // Hash (32 bytes)
-    debug_assert_eq!(OUT_LEN, self.hash.as_bytes().len());
-    w.write_all(self.hash.as_bytes())?;
+    body.write_all(&self.hash)?;
 
     // MetaData (1 byte)
-    w.write_u8(if self.is_leaf() { 1 } else { 0 })?;
+    body.write_u8(if self.is_leaf() { 1 } else { 0 })?;
 
-    let len = match &self.kind {
+    match &self.kind {
       NodeKind::Leaf { data } => {
         // Data length and data (if leaf)
-        w.write_u32::<LittleEndian>(data.len() as u32)?;
-        w.write_all(data)?;
-        4 + data.len()
+        debug_assert!(data.len() <= MAX_DATA_SIZE, "data too large: {}", data.len());
+        body.write_u32::<LittleEndian>(data.len() as u32)?;
+        body.write_all(data)?;
       }
       NodeKind::Branch { left, right } => {
         // Children indices (8 bytes each)
-        w.write_u64::<LittleEndian>(*left).unwrap();
-        w.write_u64::<LittleEndian>(*right).unwrap();
-        8 + 8
+        body.write_u64::<LittleEndian>(*left)?;
+        body.write_u64::<LittleEndian>(*right)?;
       }
-    };
-    Ok(8 + OUT_LEN + 1 + len)
+    }
+
+    w.write_u32::<LittleEndian>(body.len() as u32)?;
+    w.write_all(&body)?;
+    w.write_u32::<LittleEndian>(checksum(&body))?;
+    Ok(4 + body.len() + 4)
   }
 
   fn read<R: Read + Seek>(r: &mut R, position: slate::Position) -> slate::Result<Self> {
+    let truncated = |err: std::io::Error| -> slate::error::Error {
+      if err.kind() == std::io::ErrorKind::UnexpectedEof {
+        CorruptedNode::Truncated { position }.into()
+      } else {
+        err.into()
+      }
+    };
+
+    // Length, body and checksum
+    let length = r.read_u32::<LittleEndian>().map_err(truncated)? as usize;
+    if !(8 + HASH_SIZE + 1 + 4..=MAX_NODE_SIZE).contains(&length) {
+      return Err(CorruptedNode::Length { position, length }.into());
+    }
+    let mut body = vec![0u8; length];
+    r.read_exact(&mut body).map_err(truncated)?;
+    let stored = r.read_u32::<LittleEndian>().map_err(truncated)?;
+    let computed = checksum(&body);
+    if stored != computed {
+      return Err(CorruptedNode::Checksum { position, stored, computed }.into());
+    }
+    let r = &mut Cursor::new(body);
+
     // Index
     let index = r.read_u64::<LittleEndian>()?;
 
     // Hash
-    let mut hash_bytes = [0u8; OUT_LEN];
-    r.read_exact(&mut hash_bytes)?;
-    let hash = Hash::from(hash_bytes);
+    let mut hash = [0u8; HASH_SIZE];
+    r.read_exact(&mut hash)?;
 
     // Metadata
     let is_leaf = r.read_u8()? != 0;
@@ -93,6 +159,9 @@ impl Serializable for Node {
     let kind = if is_leaf {
       // Data
       let data_len = r.read_u32::<LittleEndian>()? as usize;
+      if 8 + HASH_SIZE + 1 + 4 + data_len != length {
+        return Err(CorruptedNode::Length { position, length }.into());
+      }
       let mut data = vec![0u8; data_len];
       r.read_exact(&mut data)?;
       NodeKind::Leaf { data }
@@ -128,100 +197,113 @@ impl Serializable for MetaInfo {
 
 /// 1. 配列インデックス方式
 ///
-/// 完全二分木の性質を利用し、ノードを配列インデックスで管理：
-/// インデックス関係:
+/// 子ノードへのポインタを持たず、インデックスから位置を算出する。[`ImplicitHashTree`] として実装。
 ///
-/// - 親ノード i の左の子: 2i + 1
-/// - 親ノード i の右の子: 2i + 2  
-/// - 子ノード i の親: (i-1)/2
+/// [`ImplicitHashTree`]: crate::hashtree::implicit::ImplicitHashTree
 ///
 /// 3. 階層化方式
 ///
-/// レベル単位でノードをグループ化：
-/// [ヘッダ][レベル0][レベル1]...[レベルn][データ領域]
+/// レベル単位でノードをグループ化し、葉のデータを別領域に配置する。[`LeveledHashTree`] として実装。
 ///
-/// 各レベル:
-/// - ノード数 (4bytes)
-/// - ノード配列 (ノード数 × ノードサイズ)
+/// [`LeveledHashTree`]: crate::hashtree::leveled::LeveledHashTree
 ///
 /// Binary Hash Tree implementation with file-based storage
-pub struct BinaryHashTree<S>
+pub struct BinaryHashTree<S, H = Blake3>
 where
   S: Storage<Node>,
+  H: HashAlgorithm,
 {
   storage: S,
   root: Position,
   height: u8,
   cache: Cache, // In-memory cache
+  counter: AccessCounter,
+  _hash: PhantomData<H>,
 }
 
-impl<S> BinaryHashTree<S>
+impl<S, H> BinaryHashTree<S, H>
 where
   S: Storage<Node>,
+  H: HashAlgorithm,
 {
-  fn create<V>(storage: &mut S, h: u8, values: V) -> Result<()>
+  /// Write all the nodes of a tree of height `h`, call `sync` to make them durable and then commit the metadata.
+  fn create<V, F>(storage: &mut S, h: u8, values: V, sync: F, counter: &AccessCounter) -> Result<()>
   where
-    V: Fn(u64) -> Vec<u8>,
+    V: Fn(u64) -> Result<Vec<u8>>,
+    F: FnOnce() -> Result<()>,
   {
     debug_assert!(h > 0);
     let (node, position) = storage.first()?;
     debug_assert!(node.is_none());
 
-    // メタ情報の保存 (位置を特定するために空のデータを書き込み)
+    // メタ情報の位置を確保 (高さ 0 は構築が完了していないことを示す)
     let position_metadata = position;
-    let metadata = MetaInfo { root: 0, height: 0 };
-    let mut buffer = Vec::new();
-    metadata.write(&mut buffer)?;
-    let meta = Node::new_leaf(position_metadata, 0, buffer);
-    let position_root = storage.put(position_metadata, &meta)?;
-
-    // メタ情報の保存
-    let metadata = MetaInfo { root: position_root, height: h };
-    let mut buffer = Vec::new();
-    metadata.write(&mut buffer)?;
-    let meta = Node::new_leaf(position_metadata, 0, buffer);
-    let position_root2 = storage.put(position_metadata, &meta)?;
-    assert_eq!(position_root, position_root2);
+    let position_root = Self::put_metadata(storage, position_metadata, MetaInfo { root: 0, height: 0 })?;
+    counter.write(1);
 
     // すべてのノードを書き込み
-    Self::create_for_level(storage, position_root, h, 0, values)?;
+    Self::create_for_level(storage, position_root, h, 0, values, counter)?;
+
+    // すべてのノードを永続化してからメタ情報を確定する。構築が中断された木は高さ 0 のまま残り、open で検出される
+    sync()?;
+    Self::put_metadata(storage, position_metadata, MetaInfo { root: position_root, height: h })?;
+    counter.write(1);
     Ok(())
   }
 
-  fn create_for_level<V>(storage: &mut S, mut current: Position, h: u8, level: u8, values: V) -> Result<Vec<Node>>
+  fn put_metadata(storage: &mut S, position: Position, metadata: MetaInfo) -> Result<Position> {
+    let mut buffer = Vec::new();
+    metadata.write(&mut buffer)?;
+    storage.put(position, &Node::new_leaf::<H>(position, 0, buffer))
+  }
+
+  fn create_for_level<V>(
+    storage: &mut S,
+    mut current: Position,
+    h: u8,
+    level: u8,
+    values: V,
+    counter: &AccessCounter,
+  ) -> Result<Vec<Node>>
   where
-    V: Fn(u64) -> Vec<u8>,
+    V: Fn(u64) -> Result<Vec<u8>>,
   {
     let offset = pow2e(level);
     let length = pow2e(level);
     let mut nodes = Vec::with_capacity(length as usize);
+    if level + 1 == h {
+      // 葉のハッシュ値はチャンク単位で並列に計算し、ノードの書き込みは位置の順に行う
+      for first in (0..length).step_by(CREATE_CHUNK as usize) {
+        let last = (first + CREATE_CHUNK).min(length);
+        let chunk = (first..last).map(|k| values(k + 1)).collect::<Result<Vec<_>>>()?;
+        let hashes = chunk.par_iter().map(|data| H::hash(data)).collect::<Vec<_>>();
+        for ((k, data), hash) in (first..last).zip(chunk).zip(hashes) {
+          let node = Node { position: current, index: offset + k, hash, kind: NodeKind::Leaf { data } };
+          current = storage.put(current, &node)?;
+          counter.write(1);
+          nodes.push(node);
+        }
+      }
+      return Ok(nodes);
+    }
     for k in 0..length {
-      let index = offset + k;
-      let node = if level + 1 == h {
-        let value = values(k + 1);
-        Node::new_leaf(current, index, value)
-      } else {
-        let mut hasher = Hasher::new();
-        hasher.update(&[]);
-        Node::new_internal(current, index, hasher.finalize(), u64::MAX, u64::MAX)
-      };
+      let node = Node::new_internal(current, offset + k, H::hash(&[]), u64::MAX, u64::MAX);
       current = storage.put(current, &node)?;
+      counter.write(1);
       nodes.push(node);
     }
-    if level + 1 < h {
-      let subnodes = Self::create_for_level(storage, current, h, level + 1, values)?;
-      for (k, node) in nodes.iter_mut().enumerate() {
-        let left = subnodes.get(2 * k).unwrap();
-        let right = subnodes.get(2 * k + 1).unwrap();
-        node.hash = Self::combine(&left.hash, &right.hash);
-        node.kind = NodeKind::Branch { left: left.position, right: right.position };
-        storage.put(node.position, node)?;
-      }
+    let subnodes = Self::create_for_level(storage, current, h, level + 1, values, counter)?;
+    let hashes = subnodes.par_chunks(2).map(|pair| H::combine(&pair[0].hash, &pair[1].hash)).collect::<Vec<_>>();
+    for ((node, pair), hash) in nodes.iter_mut().zip(subnodes.chunks(2)).zip(hashes) {
+      node.hash = hash;
+      node.kind = NodeKind::Branch { left: pair[0].position, right: pair[1].position };
+      storage.put(node.position, node)?;
+      counter.write(1);
     }
     Ok(nodes)
   }
 
-  fn create_cache(storage: &mut S, height: u8, root: Position, limit: usize) -> Result<Cache> {
+  fn create_cache(storage: &mut S, height: u8, root: Position, limit: usize, counter: &AccessCounter) -> Result<Cache> {
     let mut cache = HashMap::with_capacity(limit);
     let mut queue = VecDeque::new();
     let mut reader = storage.reader()?;
@@ -230,6 +312,7 @@ where
       for _ in 0..pow2e(level) {
         let position = queue.pop_front().unwrap();
         let node = reader.read(position)?;
+        counter.read(false);
         if cache.len() + queue.len() < limit
           && let Node { kind: NodeKind::Branch { left, right }, .. } = &node
         {
@@ -246,18 +329,234 @@ where
   }
 
   fn load(&self, reader: &mut Box<dyn Reader<Node>>, position: Position) -> Result<Node> {
-    if let Some(node) = self.cache.get(position) { Ok(node.clone()) } else { Ok(reader.read(position)?) }
+    let cached = self.cache.get(position);
+    self.counter.read(cached.is_some());
+    #[cfg(feature = "tracing")]
+    tracing::trace!(position, cached = cached.is_some(), "read_node");
+    if let Some(node) = cached { Ok(node.clone()) } else { Ok(reader.read(position)?) }
+  }
+
+  /// Returns the number of node reads, writes and cache hits since the tree was opened or the counters were reset.
+  /// Writes are only counted when the tree has been created by this instance.
+  pub fn counters(&self) -> Counters {
+    self.counter.snapshot()
   }
 
-  fn combine(left: &Hash, right: &Hash) -> Hash {
-    let mut hasher = blake3::Hasher::new();
-    hasher.update(left.as_bytes());
-    hasher.update(right.as_bytes());
-    hasher.finalize()
+  pub fn reset_counters(&self) {
+    self.counter.reset();
   }
+
+  /// Returns an iterator over the leaves k ∈ `range` in ascending order. Each node on the paths to the leaves is read
+  /// only once, so scanning m consecutive leaves reads O(m + h) nodes instead of O(m·h) with repeated [`get`].
+  ///
+  /// [`get`]: HashTree::get
+  pub fn leaves(&self, range: RangeInclusive<Index>) -> Result<Leaves<'_, S, H>> {
+    let first = (*range.start()).max(1);
+    let last = (*range.end()).min(pow2e(self.height - 1));
+    let stack = if first <= last { vec![self.root] } else { vec![] };
+    let reader = self.storage.reader()?;
+    Ok(Leaves { tree: self, reader, stack, first, last })
+  }
+
+  /// Returns a read handle with its own storage reader. The handles share the node cache and the access counters of
+  /// this tree, so multiple threads can read the same tree concurrently with a handle each.
+  pub fn reader(&self) -> Result<TreeReader<'_, S, H>> {
+    let reader = self.storage.reader()?;
+    Ok(TreeReader { tree: self, reader })
+  }
+
+  /// Replace the value of the leaf k in place and recompute the hashes of its ancestors. The new value must have the
+  /// same length as the current one because the nodes are overwritten at the same positions.
+  ///
+  /// ## Returns
+  /// - the previous value, or `None` if the leaf k doesn't exist
+  pub fn update(&mut self, k: Index, value: &[u8]) -> Result<Option<Vec<u8>>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+
+    // ルートから葉までのパスを記録する
+    let mut reader = self.storage.reader()?;
+    let mut path = Vec::with_capacity(self.height as usize);
+    let mut current = self.load(&mut reader, self.root)?;
+    while let Node { kind: NodeKind::Branch { left, right }, .. } = &current {
+      let position = if move_left(self.height, &current, k) { *left } else { *right };
+      let next = self.load(&mut reader, position)?;
+      path.push(current);
+      current = next;
+    }
+    let previous = match &current.kind {
+      NodeKind::Leaf { data } if data.len() == value.len() => data.clone(),
+      NodeKind::Leaf { data } => {
+        let msg = format!("the value size of leaf {k} is {}, but {} was specified", data.len(), value.len());
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, msg).into());
+      }
+      NodeKind::Branch { .. } => unreachable!(),
+    };
+
+    // 葉から順にハッシュ値を再計算して同じ位置に上書きする
+    let mut node = Node::new_leaf::<H>(current.position, current.index, value.to_vec());
+    while let Some(mut parent) = path.pop() {
+      self.store(&node)?;
+      let NodeKind::Branch { left, right } = parent.kind else { unreachable!() };
+      let sibling = if left == node.position { right } else { left };
+      let sibling = self.load(&mut reader, sibling)?.hash;
+      parent.hash =
+        if left == node.position { H::combine(&node.hash, &sibling) } else { H::combine(&sibling, &node.hash) };
+      node = parent;
+    }
+    self.store(&node)?;
+    Ok(Some(previous))
+  }
+
+  fn store(&mut self, node: &Node) -> Result<()> {
+    self.storage.put(node.position, node)?;
+    self.counter.write(1);
+    self.cache.update(node);
+    Ok(())
+  }
+
+  /// Read all nodes from the storage, bypassing the cache, and recompute their hashes bottom-up. Each node is checked
+  /// against the stored hashes of its children, so corruption is reported at the node (and its parent if the stored
+  /// hash itself was altered) instead of along the path to the root.
+  pub fn verify(&self) -> Result<Vec<Mismatch>> {
+    let mut reader = self.storage.reader()?;
+    let mut mismatches = Vec::new();
+    self.verify_subtree(&mut reader, self.root, &mut mismatches)?;
+    Ok(mismatches)
+  }
+
+  fn verify_subtree(
+    &self,
+    reader: &mut Box<dyn Reader<Node>>,
+    position: Position,
+    mismatches: &mut Vec<Mismatch>,
+  ) -> Result<HashValue> {
+    let node = reader.read(position)?;
+    self.counter.read(false);
+    let computed = match &node.kind {
+      NodeKind::Leaf { data } => H::hash(data),
+      NodeKind::Branch { left, right } => {
+        let left = self.verify_subtree(reader, *left, mismatches)?;
+        let right = self.verify_subtree(reader, *right, mismatches)?;
+        H::combine(&left, &right)
+      }
+    };
+    if computed != node.hash {
+      mismatches.push(Mismatch { position, index: node.index, stored: node.hash, computed });
+    }
+    Ok(node.hash)
+  }
+}
+
+/// Read handle of a [`BinaryHashTree`], see [`BinaryHashTree::reader`].
+pub struct TreeReader<'a, S, H>
+where
+  S: Storage<Node>,
+  H: HashAlgorithm,
+{
+  tree: &'a BinaryHashTree<S, H>,
+  reader: Box<dyn Reader<Node>>,
 }
 
-impl BinaryHashTree<BlockStorage<FileDevice>> {
+impl<S, H> TreeReader<'_, S, H>
+where
+  S: Storage<Node>,
+  H: HashAlgorithm,
+{
+  /// Retrieve the value of the leaf k ∈ {1,2,...,2^(h-1)}.
+  pub fn get(&mut self, k: Index) -> Result<Option<Vec<u8>>> {
+    let tree = self.tree;
+    if k == 0 || k > tree.size() {
+      return Ok(None);
+    }
+    let mut current = tree.load(&mut self.reader, tree.root)?;
+    loop {
+      match &current {
+        Node { kind: NodeKind::Branch { left, right }, .. } => {
+          let position = if move_left(tree.height, &current, k) { *left } else { *right };
+          current = tree.load(&mut self.reader, position)?;
+        }
+        Node { kind: NodeKind::Leaf { data }, .. } => {
+          debug_assert_eq!(k, index_to_leaf_number(current.index, tree.height), "{}, {}", current.index, tree.height);
+          debug_assert_eq!(k, index_to_level_position(current.index).1);
+          break Ok(Some(data.clone()));
+        }
+      }
+    }
+  }
+
+  /// Get the authentication path of the leaf k ∈ {1,2,...,2^(h-1)}.
+  pub fn prove(&mut self, k: Index) -> Result<Option<AuthPath>> {
+    let tree = self.tree;
+    if k == 0 || k > tree.size() {
+      return Ok(None);
+    }
+    let mut siblings = Vec::with_capacity(tree.height as usize - 1);
+    let mut current = tree.load(&mut self.reader, tree.root)?;
+    while let Node { kind: NodeKind::Branch { left, right }, .. } = &current {
+      let (next, sibling) = if move_left(tree.height, &current, k) { (*left, *right) } else { (*right, *left) };
+      siblings.push(tree.load(&mut self.reader, sibling)?.hash);
+      current = tree.load(&mut self.reader, next)?;
+    }
+    siblings.reverse();
+    Ok(Some(AuthPath { index: k, leaf: current.hash, siblings }))
+  }
+}
+
+/// Iterator over a range of leaves of a [`BinaryHashTree`], see [`BinaryHashTree::leaves`].
+pub struct Leaves<'a, S, H>
+where
+  S: Storage<Node>,
+  H: HashAlgorithm,
+{
+  tree: &'a BinaryHashTree<S, H>,
+  reader: Box<dyn Reader<Node>>,
+  stack: Vec<Position>,
+  first: Index,
+  last: Index,
+}
+
+impl<S, H> Iterator for Leaves<'_, S, H>
+where
+  S: Storage<Node>,
+  H: HashAlgorithm,
+{
+  type Item = Result<(Index, Vec<u8>)>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some(position) = self.stack.pop() {
+      let node = match self.tree.load(&mut self.reader, position) {
+        Ok(node) => node,
+        Err(err) => {
+          self.stack.clear();
+          return Some(Err(err));
+        }
+      };
+      match node.kind {
+        NodeKind::Leaf { data } => {
+          return Some(Ok((index_to_leaf_number(node.index, self.tree.height), data)));
+        }
+        NodeKind::Branch { left, right } => {
+          // 範囲と重なる部分木のみを右、左の順に積むことで葉を昇順に取り出す
+          let (level, position_in_level) = index_to_level_position(node.index);
+          let leaves_per_subtree = 1 << (self.tree.height - level - 1);
+          let first_leaf = (position_in_level - 1) * leaves_per_subtree + 1;
+          let boundary = first_leaf + (leaves_per_subtree / 2);
+          if self.last >= boundary {
+            self.stack.push(right);
+          }
+          if self.first < boundary {
+            self.stack.push(left);
+          }
+        }
+      }
+    }
+    None
+  }
+}
+
+impl<H: HashAlgorithm> BinaryHashTree<BlockStorage<FileDevice>, H> {
   /// Create a new binary hash tree with file storage
   pub fn from_file<P: AsRef<Path>>(path: P, cache_limit: usize) -> Result<Self> {
     let storage = BlockStorage::from_file(path, false)?;
@@ -268,78 +567,119 @@ impl BinaryHashTree<BlockStorage<FileDevice>> {
   pub fn create_on_file<P, V>(path: P, h: u8, cache_limit: usize, values: V) -> Result<Self>
   where
     P: AsRef<Path>,
-    V: Fn(u64) -> Vec<u8>,
+    V: Fn(u64) -> Result<Vec<u8>>,
   {
     if path.as_ref().exists() {
       fs::remove_file(&path)?;
     }
-    let mut storage = BlockStorage::from_file(path, false)?;
-    Self::create(&mut storage, h, values)?;
+    let mut storage = BlockStorage::from_file(path.as_ref(), false)?;
+    let counter = AccessCounter::default();
+    let sync = || -> Result<()> { Ok(fs::File::open(path.as_ref())?.sync_all()?) };
+    Self::create(&mut storage, h, values, sync, &counter)?;
+    Self::open(storage, cache_limit, counter)
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl<H: HashAlgorithm> BinaryHashTree<RocksDBStorage, H> {
+  /// Open a binary hash tree stored in the RocksDB database directory with the `rocksdb.*` knobs
+  pub fn from_rocksdb<P: AsRef<Path>>(path: P, cache_limit: usize, knobs: &Knobs) -> Result<Self> {
+    let storage = open_rocksdb_storage_with(path.as_ref(), knobs)?;
     Self::new(storage, cache_limit)
   }
+
+  /// Create a new binary hash tree with RocksDB storage with the `rocksdb.*` knobs
+  pub fn create_on_rocksdb<P, V>(path: P, h: u8, cache_limit: usize, knobs: &Knobs, values: V) -> Result<Self>
+  where
+    P: AsRef<Path>,
+    V: Fn(u64) -> Result<Vec<u8>>,
+  {
+    if path.as_ref().is_dir() {
+      fs::remove_dir_all(&path)?;
+    } else if path.as_ref().exists() {
+      fs::remove_file(&path)?;
+    }
+    let mut storage = open_rocksdb_storage_with(path.as_ref(), knobs)?;
+    let counter = AccessCounter::default();
+    // RocksDB の WAL とメモテーブルのフラッシュ結果を含め、書き戻されていないページをすべて永続化する
+    let sync = || {
+      platform::sync();
+      Ok(())
+    };
+    Self::create(&mut storage, h, values, sync, &counter)?;
+    Self::open(storage, cache_limit, counter)
+  }
 }
 
-impl BinaryHashTree<MemKVS<Node>> {
+impl<H: HashAlgorithm> BinaryHashTree<MemKVS<Node>, H> {
   /// Create a new binary hash tree with file storage
   pub fn create_on_memory(h: u8) -> Result<Self> {
     let mut storage = MemKVS::new();
-    Self::create(&mut storage, h, |i| splitmix64(i).to_le_bytes().to_vec())?;
-    Self::new(storage, 1)
+    let counter = AccessCounter::default();
+    Self::create(&mut storage, h, |i| Ok(splitmix64(i).to_le_bytes().to_vec()), || Ok(()), &counter)?;
+    Self::open(storage, 1, counter)
   }
 
   pub fn create_on_memory_with_kvs(h: u8, kvs: Arc<RwLock<HashMap<Position, Node>>>) -> Result<Self> {
     let mut storage = MemKVS::with_kvs(kvs);
-    Self::create(&mut storage, h, |i| splitmix64(i).to_le_bytes().to_vec())?;
-    Self::new(storage, 1)
+    let counter = AccessCounter::default();
+    Self::create(&mut storage, h, |i| Ok(splitmix64(i).to_le_bytes().to_vec()), || Ok(()), &counter)?;
+    Self::open(storage, 1, counter)
   }
 }
 
-impl<S> BinaryHashTree<S>
+impl<S, H> BinaryHashTree<S, H>
 where
   S: Storage<Node>,
+  H: HashAlgorithm,
 {
   /// Create a new binary hash tree with file storage
-  pub fn new(mut storage: S, cache_limit: usize) -> Result<Self> {
+  pub fn new(storage: S, cache_limit: usize) -> Result<Self> {
+    Self::open(storage, cache_limit, AccessCounter::default())
+  }
+
+  fn open(mut storage: S, cache_limit: usize, counter: AccessCounter) -> Result<Self> {
     let (metadata, _) = storage.first()?;
     if let Some(Node { kind: NodeKind::Leaf { mut data }, .. }) = metadata {
       let meta = MetaInfo::read(&mut Cursor::new(&mut data), 0)?;
+      if meta.height == 0 {
+        let msg = "the binary hash tree is incomplete, its creation has been interrupted";
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, msg).into());
+      }
       let root = meta.root;
       let height = meta.height;
-      let cache = Self::create_cache(&mut storage, height, root, cache_limit)?;
-      Ok(BinaryHashTree { storage, root, height, cache })
+      let cache = Self::create_cache(&mut storage, height, root, cache_limit, &counter)?;
+      Ok(BinaryHashTree { storage, root, height, cache, counter, _hash: PhantomData })
     } else {
-      panic!()
+      let msg = "the storage has no metadata of a binary hash tree";
+      Err(std::io::Error::new(std::io::ErrorKind::InvalidData, msg).into())
     }
   }
 }
 
-impl<S: Storage<Node>> HashTree for BinaryHashTree<S> {
+impl<S: Storage<Node>, H: HashAlgorithm> HashTree for BinaryHashTree<S, H> {
   type Error = slate::error::Error;
+  type Proof = AuthPath;
 
   fn size(&self) -> u64 {
     pow2e(self.height - 1)
   }
 
   fn get(&mut self, k: u64) -> Result<Option<Vec<u8>>> {
-    if k == 0 || k > self.size() {
-      Ok(None)
-    } else {
-      let mut reader = self.storage.reader()?;
-      let mut current = self.load(&mut reader, self.root)?;
-      loop {
-        match &current {
-          Node { kind: NodeKind::Branch { left, right }, .. } => {
-            let position = if move_left(self.height, &current, k) { *left } else { *right };
-            current = self.load(&mut reader, position)?;
-          }
-          Node { kind: NodeKind::Leaf { data }, .. } => {
-            debug_assert_eq!(k, index_to_leaf_number(current.index, self.height), "{}, {}", current.index, self.height);
-            debug_assert_eq!(k, index_to_level_position(current.index).1);
-            break Ok(Some(data.clone()));
-          }
-        }
-      }
-    }
+    self.reader()?.get(k)
+  }
+
+  fn root_hash(&mut self) -> Result<Option<HashValue>> {
+    let mut reader = self.storage.reader()?;
+    Ok(Some(self.load(&mut reader, self.root)?.hash))
+  }
+
+  fn prove(&mut self, k: u64) -> Result<Option<AuthPath>> {
+    self.reader()?.prove(k)
+  }
+
+  fn append(&mut self, _data: &[u8]) -> Result<u64> {
+    Err(unsupported("append"))
   }
 }
 
@@ -386,6 +726,56 @@ impl Cache {
   fn get(&self, position: u64) -> Option<&Node> {
     self.cache.get(&position)
   }
+
+  /// Replace the node only if it's cached.
+  fn update(&mut self, node: &Node) {
+    if let Some(cached) = self.cache.get_mut(&node.position) {
+      *cached = node.clone();
+    }
+  }
+}
+
+/// Number of node accesses performed by a [`BinaryHashTree`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+  pub reads: u64,
+  pub writes: u64,
+  pub cache_hits: u64,
+}
+
+#[derive(Default)]
+struct AccessCounter {
+  reads: AtomicU64,
+  writes: AtomicU64,
+  cache_hits: AtomicU64,
+}
+
+impl AccessCounter {
+  fn read(&self, cache_hit: bool) {
+    if cache_hit {
+      self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    } else {
+      self.reads.fetch_add(1, Ordering::Relaxed);
+    }
+  }
+
+  fn write(&self, count: u64) {
+    self.writes.fetch_add(count, Ordering::Relaxed);
+  }
+
+  fn snapshot(&self) -> Counters {
+    Counters {
+      reads: self.reads.load(Ordering::Relaxed),
+      writes: self.writes.load(Ordering::Relaxed),
+      cache_hits: self.cache_hits.load(Ordering::Relaxed),
+    }
+  }
+
+  fn reset(&self) {
+    self.reads.store(0, Ordering::Relaxed);
+    self.writes.store(0, Ordering::Relaxed);
+    self.cache_hits.store(0, Ordering::Relaxed);
+  }
 }
 
 #[cfg(test)]
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
 use super::*;
+use crate::hashtree::test::verify_hashtree;
 
 #[test]
 fn verify_binary_tree() {
   for height in 1..=8 {
     println!("🌲{height}");
     let kvs = Arc::new(RwLock::new(HashMap::new()));
-    BinaryHashTree::create_on_memory_with_kvs(height, kvs.clone()).unwrap();
+    BinaryHashTree::<_>::create_on_memory_with_kvs(height, kvs.clone()).unwrap();
 
     let mut kvs = kvs.read().unwrap().clone();
     let meta = if let NodeKind::Leaf { data } = &kvs.remove(&1).unwrap().kind {
@@ -46,18 +47,184 @@ fn verify_binary_tree() {
   }
 }
 
+#[test]
+fn create_across_chunks() {
+  // 葉の数が並列にハッシュ値を計算するチャンクの大きさを超える木
+  let height = 16;
+  assert!(pow2e(height - 1) > CREATE_CHUNK);
+  let tree = BinaryHashTree::<_>::create_on_memory(height).unwrap();
+  assert!(tree.verify().unwrap().is_empty());
+  let leaves = tree.leaves(1..=pow2e(height - 1)).unwrap().collect::<Result<Vec<_>>>().unwrap();
+  assert!(leaves.iter().all(|(k, data)| *data == splitmix64(*k).to_le_bytes().to_vec()));
+}
+
+#[test]
+fn reject_interrupted_creation() {
+  let kvs = Arc::new(RwLock::new(HashMap::new()));
+  let mut storage = MemKVS::with_kvs(kvs.clone());
+  let values = |k: u64| if k < 5 { Ok(splitmix64(k).to_le_bytes().to_vec()) } else { panic!("interrupted") };
+  let counter = AccessCounter::default();
+  let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    BinaryHashTree::<_, Blake3>::create(&mut storage, 4, values, || Ok(()), &counter)
+  }));
+  assert!(result.is_err());
+  assert!(BinaryHashTree::<_, Blake3>::new(MemKVS::with_kvs(kvs.clone()), 0).is_err());
+
+  // 同じストレージに構築し直せば開くことができる
+  kvs.write().unwrap().clear();
+  let mut storage = MemKVS::with_kvs(kvs.clone());
+  let values = |k: u64| Ok(splitmix64(k).to_le_bytes().to_vec());
+  BinaryHashTree::<_, Blake3>::create(&mut storage, 4, values, || Ok(()), &counter).unwrap();
+  let tree = BinaryHashTree::<_, Blake3>::new(MemKVS::with_kvs(kvs), 0).unwrap();
+  assert!(tree.verify().unwrap().is_empty());
+}
+
 #[test]
 fn test_basic_operations() {
   for height in 1..=8 {
-    let mut tree = BinaryHashTree::create_on_memory(height).unwrap();
-    assert_eq!(pow2e(height - 1), tree.size());
+    let mut tree = BinaryHashTree::<_>::create_on_memory(height).unwrap();
+    verify_hashtree::<_, Blake3>(&mut tree, height);
+  }
+}
+
+#[test]
+fn count_node_accesses() {
+  let mut tree = BinaryHashTree::<_>::create_on_memory(4).unwrap();
+  let counters = tree.counters();
+  assert_eq!(2 + 15 + 7, counters.writes);
+  assert_eq!(1, counters.reads);
 
-    // Test retrieval
-    assert_eq!(tree.get(0).unwrap(), None);
-    for k in 1..=tree.size() {
-      assert_eq!(tree.get(k).unwrap(), Some(splitmix64(k).to_le_bytes().to_vec()), "{k}");
+  tree.reset_counters();
+  tree.get(1).unwrap();
+  assert_eq!(Counters { reads: 3, writes: 0, cache_hits: 1 }, tree.counters());
+}
+
+#[test]
+fn iterate_leaves_in_range() {
+  let height = 6;
+  let tree = BinaryHashTree::<_>::create_on_memory(height).unwrap();
+  let n = tree.size();
+  for first in 0..=n + 1 {
+    for last in first..=n + 1 {
+      tree.reset_counters();
+      let leaves = tree.leaves(first..=last).unwrap().map(|r| r.unwrap()).collect::<Vec<_>>();
+      let expected =
+        (first.max(1)..=last.min(n)).map(|k| (k, splitmix64(k).to_le_bytes().to_vec())).collect::<Vec<_>>();
+      assert_eq!(expected, leaves, "{first}..={last}");
+
+      // 範囲内の各ノードは一度だけ読み込まれる
+      let counters = tree.counters();
+      assert!(counters.reads + counters.cache_hits <= 2 * leaves.len() as u64 + 2 * height as u64, "{counters:?}");
+    }
+  }
+}
+
+#[test]
+fn concurrent_readers() {
+  let mut tree = BinaryHashTree::<_>::create_on_memory(6).unwrap();
+  let root = tree.root_hash().unwrap().unwrap();
+  let tree = &tree;
+  std::thread::scope(|scope| {
+    for t in 0..4 {
+      scope.spawn(move || {
+        let mut reader = tree.reader().unwrap();
+        for k in (1 + t..=tree.size()).step_by(4) {
+          assert_eq!(Some(splitmix64(k).to_le_bytes().to_vec()), reader.get(k).unwrap());
+          assert_eq!(root, reader.prove(k).unwrap().unwrap().root::<Blake3>());
+        }
+        assert_eq!(None, reader.get(tree.size() + 1).unwrap());
+      });
+    }
+  });
+
+  // 各葉につき get で 6 ノード、prove でルートと 5 レベル × 2 ノードを読み、それに open と root_hash の 2 回が加わる
+  let counters = tree.counters();
+  assert_eq!(tree.size() * (6 + 1 + 5 * 2) + 2, counters.reads + counters.cache_hits);
+}
+
+#[test]
+fn update_leaves_in_place() {
+  for height in 1..=6 {
+    let kvs = Arc::new(RwLock::new(HashMap::new()));
+    let mut tree = BinaryHashTree::<_>::create_on_memory_with_kvs(height, kvs.clone()).unwrap();
+    let nodes = kvs.read().unwrap().len();
+    let n = tree.size();
+    assert_eq!(None, tree.update(0, &[0u8; 8]).unwrap());
+    assert_eq!(None, tree.update(n + 1, &[0u8; 8]).unwrap());
+    assert!(tree.update(1, &[0u8; 4]).is_err());
+
+    for k in 1..=n {
+      let previous = tree.update(k, &splitmix64(n + k).to_le_bytes()).unwrap();
+      assert_eq!(Some(splitmix64(k).to_le_bytes().to_vec()), previous);
+    }
+    assert_eq!(nodes, kvs.read().unwrap().len());
+    assert!(tree.verify().unwrap().is_empty());
+
+    // 同じ値で作成した木と同じルートハッシュとなる
+    let root = tree.root_hash().unwrap().unwrap();
+    let mut storage = MemKVS::new();
+    let values = |k| Ok(splitmix64(n + k).to_le_bytes().to_vec());
+    BinaryHashTree::<_, Blake3>::create(&mut storage, height, values, || Ok(()), &AccessCounter::default()).unwrap();
+    let mut expected = BinaryHashTree::<_, Blake3>::new(storage, 0).unwrap();
+    assert_eq!(root, expected.root_hash().unwrap().unwrap());
+    for k in 1..=n {
+      assert_eq!(Some(splitmix64(n + k).to_le_bytes().to_vec()), tree.get(k).unwrap());
+      assert_eq!(root, tree.prove(k).unwrap().unwrap().root::<Blake3>());
+    }
+  }
+}
+
+#[test]
+fn detect_corrupted_nodes() {
+  let kvs = Arc::new(RwLock::new(HashMap::new()));
+  let tree = BinaryHashTree::<_>::create_on_memory_with_kvs(4, kvs.clone()).unwrap();
+  assert!(tree.verify().unwrap().is_empty());
+
+  // 葉の値と中間ノードのハッシュ値を改ざんする
+  let (leaf, branch) = {
+    let mut kvs = kvs.write().unwrap();
+    let leaf = kvs.values_mut().find(|node| node.index == 11).unwrap();
+    leaf.kind = NodeKind::Leaf { data: vec![0u8; 8] };
+    let leaf = leaf.position;
+    let branch = kvs.values_mut().find(|node| node.index == 3).unwrap();
+    branch.hash = [0u8; HASH_SIZE];
+    (leaf, branch.position)
+  };
+  let mut mismatches = tree.verify().unwrap();
+  mismatches.sort_by_key(|m| m.index);
+  assert_eq!(
+    vec![(2, 1), (branch, 3), (leaf, 11)],
+    mismatches.iter().map(|m| (m.position, m.index)).collect::<Vec<_>>()
+  );
+  assert_eq!([0u8; HASH_SIZE], mismatches[1].stored);
+}
+
+#[test]
+fn reject_corrupted_node() {
+  let leaf = Node::new_leaf::<Blake3>(0, 8, splitmix64(1).to_le_bytes().to_vec());
+  let branch = Node::new_internal(0, 1, Blake3::hash(b"branch"), 10, 20);
+  for node in [leaf, branch] {
+    let mut buffer = Vec::new();
+    let size = node.write(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), size);
+    let restored = Node::read(&mut Cursor::new(&buffer), 0).unwrap();
+    assert_eq!((node.index, node.hash), (restored.index, restored.hash));
+
+    // 途中で途切れた書き込み、長さの破損、本体のビット反転をそれぞれ検出する
+    for length in [0, 3, 4, size - 1] {
+      let err = Node::read(&mut Cursor::new(&buffer[..length]), 0).unwrap_err();
+      assert!(format!("{err:?}").contains("Truncated"), "{length}: {err:?}");
+    }
+    let mut broken = buffer.clone();
+    broken[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = Node::read(&mut Cursor::new(&broken), 0).unwrap_err();
+    assert!(format!("{err:?}").contains("Length"), "{err:?}");
+    for i in 4..size - 4 {
+      let mut broken = buffer.clone();
+      broken[i] ^= 0x01;
+      let err = Node::read(&mut Cursor::new(&broken), 0).unwrap_err();
+      assert!(format!("{err:?}").contains("Checksum"), "{i}: {err:?}");
     }
-    assert_eq!(tree.get(tree.size() + 1).unwrap(), None);
   }
 }
 
@@ -113,6 +280,5 @@ fn verify_move_left() {
 }
 
 fn inode(index: u64) -> Node {
-  let hash = Hash::from_bytes([0u8; OUT_LEN]);
-  Node::new_internal(0, index, hash, 0, 0)
+  Node::new_internal(0, index, [0u8; HASH_SIZE], 0, 0)
 }
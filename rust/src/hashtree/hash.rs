@@ -0,0 +1,77 @@
+use sha2::Digest as _;
+
+/// Size of the hash value in bytes. All algorithms used in the benchmark produce 256-bit values so that the node
+/// layout on storage is identical regardless of the algorithm.
+pub const HASH_SIZE: usize = 32;
+
+pub type HashValue = [u8; HASH_SIZE];
+
+/// Hash function used to compute leaf and branch hashes of the hash trees.
+pub trait HashAlgorithm {
+  /// Name used to identify the algorithm in the CUT implementation names and reports.
+  fn name() -> &'static str;
+
+  /// Hash the data of a leaf node.
+  fn hash(data: &[u8]) -> HashValue;
+
+  /// Hash the concatenation of the two child hashes of a branch node.
+  fn combine(left: &HashValue, right: &HashValue) -> HashValue;
+}
+
+pub struct Blake3;
+
+impl HashAlgorithm for Blake3 {
+  fn name() -> &'static str {
+    "blake3"
+  }
+
+  fn hash(data: &[u8]) -> HashValue {
+    *blake3::hash(data).as_bytes()
+  }
+
+  fn combine(left: &HashValue, right: &HashValue) -> HashValue {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+  }
+}
+
+pub struct Sha256;
+
+impl HashAlgorithm for Sha256 {
+  fn name() -> &'static str {
+    "sha256"
+  }
+
+  fn hash(data: &[u8]) -> HashValue {
+    sha2::Sha256::digest(data).into()
+  }
+
+  fn combine(left: &HashValue, right: &HashValue) -> HashValue {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+  }
+}
+
+/// SHA-512/256 is faster than SHA-256 on 64-bit CPUs without SHA extensions.
+pub struct Sha512_256;
+
+impl HashAlgorithm for Sha512_256 {
+  fn name() -> &'static str {
+    "sha512_256"
+  }
+
+  fn hash(data: &[u8]) -> HashValue {
+    sha2::Sha512_256::digest(data).into()
+  }
+
+  fn combine(left: &HashValue, right: &HashValue) -> HashValue {
+    let mut hasher = sha2::Sha512_256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+  }
+}
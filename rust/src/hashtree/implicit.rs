@@ -0,0 +1,254 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use slate::formula::pow2e;
+use slate::{Index, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::hashtree::hash::{Blake3, HASH_SIZE, HashAlgorithm, HashValue};
+use crate::hashtree::{AuthPath, HashTree, unsupported};
+
+const MAGIC: &[u8; 4] = b"BHTI";
+const HEADER_SIZE: u64 = 4 + 1 + 4 + 1;
+
+/// Size of the block that [`Alignment::Padded`] and [`Alignment::Blocked`] align nodes to.
+pub const BLOCK_SIZE: u64 = 4096;
+
+/// ノードのファイル上の配置方法。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+  /// ノードを隙間なく連続して配置する。ノードがブロック境界をまたぐことがある。
+  Packed,
+  /// 各ノードをブロック境界から配置し、1 ブロックに 1 ノードのみを格納する。
+  Padded,
+  /// ブロック境界をまたがない範囲で複数のノードを 1 ブロックに詰めて配置する。
+  Blocked,
+}
+
+impl Alignment {
+  fn from_u8(value: u8) -> Self {
+    match value {
+      0 => Alignment::Packed,
+      1 => Alignment::Padded,
+      2 => Alignment::Blocked,
+      _ => panic!("unknown alignment: {value}"),
+    }
+  }
+
+  fn to_u8(self) -> u8 {
+    match self {
+      Alignment::Packed => 0,
+      Alignment::Padded => 1,
+      Alignment::Blocked => 2,
+    }
+  }
+
+  /// Offset of the node i in the file.
+  fn offset(self, i: u64, node_size: u64) -> u64 {
+    let nodes_per_block = BLOCK_SIZE / node_size;
+    match self {
+      Alignment::Packed => HEADER_SIZE + i * node_size,
+      Alignment::Blocked if nodes_per_block > 1 => {
+        BLOCK_SIZE + (i / nodes_per_block) * BLOCK_SIZE + (i % nodes_per_block) * node_size
+      }
+      Alignment::Padded | Alignment::Blocked => BLOCK_SIZE + i * node_size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE,
+    }
+  }
+}
+
+/// 配列インデックス方式の二分ハッシュ木。
+///
+/// 完全二分木の性質を利用し、ノードを配列インデックスで管理する。ノードは固定長で、インデックス i のノードは
+/// ファイル上のオフセット `HEADER_SIZE + i * node_size` に配置されるため子ノードへのポインタを必要としない。
+/// [`Alignment`] を指定した場合はヘッダの後の最初のブロック境界から、ブロック境界に合わせてノードを配置する。
+///
+/// - 親ノード i の左の子: 2i + 1
+/// - 親ノード i の右の子: 2i + 2
+/// - 子ノード i の親: (i-1)/2
+///
+/// ```text
+/// [MAGIC (4)][height (1)][value size (4)][alignment (1)][node 0][node 1]...[node 2^h-2]
+/// node = [hash (32)][data (value size, zero for branch nodes)]
+/// ```
+pub struct ImplicitHashTree<H: HashAlgorithm = Blake3> {
+  file: File,
+  height: u8,
+  value_size: usize,
+  alignment: Alignment,
+  cache: HashMap<u64, HashValue>,
+  _hash: PhantomData<H>,
+}
+
+impl<H: HashAlgorithm> ImplicitHashTree<H> {
+  /// Create a new hash tree of height `h` on the file, where all leaf values are `value_size` bytes.
+  pub fn create_on_file<P, V>(path: P, h: u8, value_size: usize, cache_limit: usize, values: V) -> Result<Self>
+  where
+    P: AsRef<Path>,
+    V: Fn(u64) -> Result<Vec<u8>>,
+  {
+    Self::create_on_file_with_alignment(path, h, value_size, Alignment::Packed, cache_limit, values)
+  }
+
+  /// Create a new hash tree of height `h` on the file, placing the nodes with the specified alignment.
+  pub fn create_on_file_with_alignment<P, V>(
+    path: P,
+    h: u8,
+    value_size: usize,
+    alignment: Alignment,
+    cache_limit: usize,
+    values: V,
+  ) -> Result<Self>
+  where
+    P: AsRef<Path>,
+    V: Fn(u64) -> Result<Vec<u8>>,
+  {
+    debug_assert!(h > 0);
+    let file = OpenOptions::new().create(true).truncate(true).read(true).write(true).open(&path)?;
+    let mut w = BufWriter::new(file);
+    w.write_all(MAGIC)?;
+    w.write_u8(h)?;
+    w.write_u32::<LittleEndian>(value_size as u32)?;
+    w.write_u8(alignment.to_u8())?;
+
+    // 葉から順にハッシュ値を算出し、ファイル上の位置へ書き込む
+    let node_size = (HASH_SIZE + value_size) as u64;
+    let mut current = HEADER_SIZE;
+    let mut write_node = |w: &mut BufWriter<File>, i: u64, hash: &HashValue, data: &[u8]| -> Result<()> {
+      // BufWriter は seek のたびにバッファを書き出すため、位置が連続している場合は seek しない
+      let offset = alignment.offset(i, node_size);
+      if offset != current {
+        w.seek(SeekFrom::Start(offset))?;
+      }
+      w.write_all(hash)?;
+      w.write_all(data)?;
+      current = offset + node_size;
+      Ok(())
+    };
+    let mut hashes = Vec::with_capacity(pow2e(h - 1) as usize);
+    let leaf_offset = pow2e(h - 1) - 1;
+    for k in 1..=pow2e(h - 1) {
+      let value = values(k)?;
+      assert_eq!(value_size, value.len(), "the value size of leaf {k} is not {value_size}");
+      let hash = H::hash(&value);
+      write_node(&mut w, leaf_offset + k - 1, &hash, &value)?;
+      hashes.push(hash);
+    }
+    let padding = vec![0u8; value_size];
+    for level in (0..h - 1).rev() {
+      hashes = hashes.chunks_exact(2).map(|pair| H::combine(&pair[0], &pair[1])).collect::<Vec<_>>();
+      for (j, hash) in hashes.iter().enumerate() {
+        write_node(&mut w, pow2e(level) - 1 + j as u64, hash, &padding)?;
+      }
+    }
+
+    // 最後のブロックの末尾までファイルを確保する
+    if alignment != Alignment::Packed && current % BLOCK_SIZE != 0 {
+      w.seek(SeekFrom::Start(current.next_multiple_of(BLOCK_SIZE) - 1))?;
+      w.write_u8(0)?;
+    }
+    w.flush()?;
+    drop(w);
+    Self::from_file(path, cache_limit)
+  }
+
+  /// Open the hash tree stored on the file, caching the hashes of the first `cache_limit` nodes.
+  pub fn from_file<P: AsRef<Path>>(path: P, cache_limit: usize) -> Result<Self> {
+    let mut file = OpenOptions::new().read(true).open(&path)?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    assert_eq!(MAGIC, &magic, "not an implicit hash tree file");
+    let height = file.read_u8()?;
+    let value_size = file.read_u32::<LittleEndian>()? as usize;
+    let alignment = Alignment::from_u8(file.read_u8()?);
+
+    // ノードは上位レベルから順に並んでいるため、先頭から cache_limit 個が上位のノードとなる
+    let mut tree = Self { file, height, value_size, alignment, cache: HashMap::new(), _hash: PhantomData };
+    let n = (pow2e(height) - 1).min(cache_limit as u64);
+    for i in 0..n {
+      let (hash, _) = tree.read_node(i)?;
+      tree.cache.insert(i, hash);
+    }
+    Ok(tree)
+  }
+
+  pub fn alignment(&self) -> Alignment {
+    self.alignment
+  }
+
+  fn node_size(&self) -> u64 {
+    (HASH_SIZE + self.value_size) as u64
+  }
+
+  fn hash_of(&mut self, i: u64) -> Result<HashValue> {
+    match self.cache.get(&i) {
+      Some(hash) => Ok(*hash),
+      None => Ok(self.read_node(i)?.0),
+    }
+  }
+
+  fn read_node(&mut self, i: u64) -> Result<(HashValue, Vec<u8>)> {
+    self.file.seek(SeekFrom::Start(self.alignment.offset(i, self.node_size())))?;
+    let mut hash = [0u8; HASH_SIZE];
+    self.file.read_exact(&mut hash)?;
+    let mut data = vec![0u8; self.value_size];
+    self.file.read_exact(&mut data)?;
+    Ok((hash, data))
+  }
+}
+
+impl<H: HashAlgorithm> HashTree for ImplicitHashTree<H> {
+  type Error = slate::error::Error;
+  type Proof = AuthPath;
+
+  fn size(&self) -> u64 {
+    pow2e(self.height - 1)
+  }
+
+  fn get(&mut self, k: Index) -> Result<Option<Vec<u8>>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+
+    // BinaryHashTree と同様にルートから葉までのノードを辿る (子ノードの位置はインデックスから算出できる)
+    let leaf = pow2e(self.height - 1) - 1 + (k - 1);
+    let mut i = 0;
+    for level in 0..self.height - 1 {
+      if !self.cache.contains_key(&i) {
+        self.read_node(i)?;
+      }
+      let bit = ((k - 1) >> (self.height - 2 - level)) & 1;
+      i = 2 * i + 1 + bit;
+    }
+    debug_assert_eq!(leaf, i);
+    let (_, data) = self.read_node(i)?;
+    Ok(Some(data))
+  }
+
+  fn root_hash(&mut self) -> Result<Option<HashValue>> {
+    Ok(Some(self.hash_of(0)?))
+  }
+
+  fn prove(&mut self, k: Index) -> Result<Option<AuthPath>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+    let mut i = pow2e(self.height - 1) - 1 + (k - 1);
+    let leaf = self.hash_of(i)?;
+    let mut siblings = Vec::with_capacity(self.height as usize - 1);
+    while i > 0 {
+      let sibling = if i % 2 == 1 { i + 1 } else { i - 1 };
+      siblings.push(self.hash_of(sibling)?);
+      i = (i - 1) / 2;
+    }
+    Ok(Some(AuthPath { index: k, leaf, siblings }))
+  }
+
+  fn append(&mut self, _data: &[u8]) -> Result<u64> {
+    Err(unsupported("append"))
+  }
+}
+
+#[cfg(test)]
+mod test;
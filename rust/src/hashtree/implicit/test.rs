@@ -0,0 +1,52 @@
+use super::*;
+use crate::hashtree::test::verify_hashtree;
+use crate::splitmix64;
+
+#[test]
+fn test_basic_operations() {
+  let dir = tempfile::tempdir().unwrap();
+  for height in 1..=8 {
+    let path = dir.path().join(format!("implicit-{height}.db"));
+    let mut tree =
+      ImplicitHashTree::<Blake3>::create_on_file(&path, height, 8, 3, |i| Ok(splitmix64(i).to_le_bytes().to_vec()))
+        .unwrap();
+    verify_hashtree::<_, Blake3>(&mut tree, height);
+  }
+}
+
+#[test]
+fn aligned_nodes() {
+  let dir = tempfile::tempdir().unwrap();
+  for alignment in [Alignment::Packed, Alignment::Padded, Alignment::Blocked] {
+    for height in 1..=8 {
+      let path = dir.path().join(format!("implicit-{alignment:?}-{height}.db"));
+      let mut tree = ImplicitHashTree::<Blake3>::create_on_file_with_alignment(&path, height, 8, alignment, 3, |i| {
+        Ok(splitmix64(i).to_le_bytes().to_vec())
+      })
+      .unwrap();
+      assert_eq!(alignment, tree.alignment());
+      verify_hashtree::<_, Blake3>(&mut tree, height);
+      drop(tree);
+
+      let tree = ImplicitHashTree::<Blake3>::from_file(&path, 0).unwrap();
+      assert_eq!(alignment, tree.alignment());
+      let size = std::fs::metadata(&path).unwrap().len();
+      if alignment != Alignment::Packed {
+        assert_eq!(0, size % BLOCK_SIZE, "{alignment:?}, h={height}");
+      }
+    }
+  }
+}
+
+#[test]
+fn node_offsets() {
+  // 1 ノード 40 バイトのとき 1 ブロックに 102 ノードが収まる
+  assert_eq!(HEADER_SIZE + 40 * 102, Alignment::Packed.offset(102, 40));
+  assert_eq!(BLOCK_SIZE + 102 * BLOCK_SIZE, Alignment::Padded.offset(102, 40));
+  assert_eq!(BLOCK_SIZE + 101 * 40, Alignment::Blocked.offset(101, 40));
+  assert_eq!(2 * BLOCK_SIZE, Alignment::Blocked.offset(102, 40));
+
+  // ブロックより大きいノードは Padded と同じ配置になる
+  assert_eq!(BLOCK_SIZE + 3 * 2 * BLOCK_SIZE, Alignment::Padded.offset(3, BLOCK_SIZE + 1));
+  assert_eq!(Alignment::Padded.offset(3, BLOCK_SIZE + 1), Alignment::Blocked.offset(3, BLOCK_SIZE + 1));
+}
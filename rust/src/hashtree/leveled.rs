@@ -0,0 +1,173 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use slate::formula::pow2e;
+use slate::{Index, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::hashtree::hash::{Blake3, HASH_SIZE, HashAlgorithm, HashValue};
+use crate::hashtree::{AuthPath, HashTree, unsupported};
+
+const MAGIC: &[u8; 4] = b"BHTL";
+const HEADER_SIZE: u64 = 4 + 1 + 4;
+
+/// 階層化方式の二分ハッシュ木。
+///
+/// レベル単位でノードをグループ化し、ルートから順にレベルごとに連続して配置する。ノードはハッシュ値のみを持ち、
+/// 葉のデータはすべてのレベルの後ろのデータ領域にまとめて配置される。
+///
+/// ```text
+/// [MAGIC (4)][height (1)][value size (4)][レベル0][レベル1]...[レベルh-1][データ領域]
+/// レベル = [ノード数 (4)][ノード配列 (ノード数 × 32)]
+/// データ領域 = [葉 1 のデータ (value size)]...[葉 2^(h-1) のデータ]
+/// ```
+pub struct LeveledHashTree<H: HashAlgorithm = Blake3> {
+  file: File,
+  height: u8,
+  value_size: usize,
+  cache: HashMap<(u8, u64), HashValue>,
+  _hash: PhantomData<H>,
+}
+
+impl<H: HashAlgorithm> LeveledHashTree<H> {
+  /// Create a new hash tree of height `h` on the file, where all leaf values are `value_size` bytes.
+  pub fn create_on_file<P, V>(path: P, h: u8, value_size: usize, cache_limit: usize, values: V) -> Result<Self>
+  where
+    P: AsRef<Path>,
+    V: Fn(u64) -> Result<Vec<u8>>,
+  {
+    debug_assert!(h > 0);
+    let file = OpenOptions::new().create(true).truncate(true).read(true).write(true).open(&path)?;
+    let mut w = BufWriter::new(file);
+    w.write_all(MAGIC)?;
+    w.write_u8(h)?;
+    w.write_u32::<LittleEndian>(value_size as u32)?;
+
+    // データ領域と葉のレベルを書き込み、上位のレベルへ向かってハッシュ値を算出する
+    let mut hashes = Vec::with_capacity(pow2e(h - 1) as usize);
+    w.seek(SeekFrom::Start(data_offset(h)))?;
+    for k in 1..=pow2e(h - 1) {
+      let value = values(k)?;
+      assert_eq!(value_size, value.len(), "the value size of leaf {k} is not {value_size}");
+      hashes.push(H::hash(&value));
+      w.write_all(&value)?;
+    }
+    for level in (0..h).rev() {
+      if level + 1 < h {
+        hashes = hashes.chunks_exact(2).map(|pair| H::combine(&pair[0], &pair[1])).collect::<Vec<_>>();
+      }
+      w.seek(SeekFrom::Start(level_offset(level)))?;
+      w.write_u32::<LittleEndian>(hashes.len() as u32)?;
+      for hash in hashes.iter() {
+        w.write_all(hash)?;
+      }
+    }
+    w.flush()?;
+    drop(w);
+    Self::from_file(path, cache_limit)
+  }
+
+  /// Open the hash tree stored on the file, caching the hashes of the first `cache_limit` nodes in level order.
+  pub fn from_file<P: AsRef<Path>>(path: P, cache_limit: usize) -> Result<Self> {
+    let mut file = OpenOptions::new().read(true).open(&path)?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    assert_eq!(MAGIC, &magic, "not a leveled hash tree file");
+    let height = file.read_u8()?;
+    let value_size = file.read_u32::<LittleEndian>()? as usize;
+
+    let mut tree = Self { file, height, value_size, cache: HashMap::new(), _hash: PhantomData };
+    'cache_read: for level in 0..height {
+      tree.file.seek(SeekFrom::Start(level_offset(level)))?;
+      let count = tree.file.read_u32::<LittleEndian>()? as u64;
+      debug_assert_eq!(pow2e(level), count);
+      for i in 0..count {
+        if tree.cache.len() >= cache_limit {
+          break 'cache_read;
+        }
+        let mut hash = [0u8; HASH_SIZE];
+        tree.file.read_exact(&mut hash)?;
+        tree.cache.insert((level, i), hash);
+      }
+    }
+    Ok(tree)
+  }
+
+  fn hash_of(&mut self, level: u8, i: u64) -> Result<HashValue> {
+    match self.cache.get(&(level, i)) {
+      Some(hash) => Ok(*hash),
+      None => self.read_node(level, i),
+    }
+  }
+
+  fn read_node(&mut self, level: u8, i: u64) -> Result<HashValue> {
+    self.file.seek(SeekFrom::Start(level_offset(level) + 4 + i * HASH_SIZE as u64))?;
+    let mut hash = [0u8; HASH_SIZE];
+    self.file.read_exact(&mut hash)?;
+    Ok(hash)
+  }
+}
+
+impl<H: HashAlgorithm> HashTree for LeveledHashTree<H> {
+  type Error = slate::error::Error;
+  type Proof = AuthPath;
+
+  fn size(&self) -> u64 {
+    pow2e(self.height - 1)
+  }
+
+  fn get(&mut self, k: Index) -> Result<Option<Vec<u8>>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+
+    // BinaryHashTree と同様にルートから葉までのノードを辿ってからデータ領域を読み込む
+    for level in 0..self.height {
+      let i = (k - 1) >> (self.height - 1 - level);
+      if !self.cache.contains_key(&(level, i)) {
+        self.read_node(level, i)?;
+      }
+    }
+    let mut data = vec![0u8; self.value_size];
+    self.file.seek(SeekFrom::Start(data_offset(self.height) + (k - 1) * self.value_size as u64))?;
+    self.file.read_exact(&mut data)?;
+    Ok(Some(data))
+  }
+
+  fn root_hash(&mut self) -> Result<Option<HashValue>> {
+    Ok(Some(self.hash_of(0, 0)?))
+  }
+
+  fn prove(&mut self, k: Index) -> Result<Option<AuthPath>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+    let mut i = k - 1;
+    let leaf = self.hash_of(self.height - 1, i)?;
+    let mut siblings = Vec::with_capacity(self.height as usize - 1);
+    for level in (1..self.height).rev() {
+      siblings.push(self.hash_of(level, i ^ 1)?);
+      i >>= 1;
+    }
+    Ok(Some(AuthPath { index: k, leaf, siblings }))
+  }
+
+  fn append(&mut self, _data: &[u8]) -> Result<u64> {
+    Err(unsupported("append"))
+  }
+}
+
+/// レベル `level` の先頭位置。
+fn level_offset(level: u8) -> u64 {
+  HEADER_SIZE + 4 * level as u64 + (pow2e(level) - 1) * HASH_SIZE as u64
+}
+
+/// 高さ `height` の木のデータ領域の先頭位置。
+fn data_offset(height: u8) -> u64 {
+  level_offset(height)
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,23 @@
+use super::*;
+use crate::hashtree::test::verify_hashtree;
+use crate::splitmix64;
+
+#[test]
+fn test_basic_operations() {
+  let dir = tempfile::tempdir().unwrap();
+  for height in 1..=8 {
+    let path = dir.path().join(format!("leveled-{height}.db"));
+    let mut tree =
+      LeveledHashTree::<Blake3>::create_on_file(&path, height, 8, 3, |i| Ok(splitmix64(i).to_le_bytes().to_vec()))
+        .unwrap();
+    verify_hashtree::<_, Blake3>(&mut tree, height);
+  }
+}
+
+#[test]
+fn verify_offset() {
+  assert_eq!(HEADER_SIZE, level_offset(0));
+  assert_eq!(HEADER_SIZE + 4 + 32, level_offset(1));
+  assert_eq!(HEADER_SIZE + 4 + 32 + 4 + 2 * 32, level_offset(2));
+  assert_eq!(level_offset(3), data_offset(3));
+}
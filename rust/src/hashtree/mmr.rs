@@ -0,0 +1,292 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use slate::file::FileDevice;
+use slate::{BlockStorage, Index, Position, Reader, Result, Serializable, Storage};
+use std::fs;
+use std::io::{Read, Seek, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::MemKVS;
+use crate::hashtree::HashTree;
+use crate::hashtree::hash::{Blake3, HASH_SIZE, HashAlgorithm, HashValue};
+
+/// Position that indicates there is no node (the storage never places a node at the end of the address space).
+const NONE: Position = Position::MAX;
+
+#[derive(Debug, Clone)]
+pub enum MmrNodeKind {
+  Leaf { data: Vec<u8> },
+  Branch { left: Position, right: Position },
+}
+
+/// Node of the Merkle Mountain Range.
+///
+/// Every node is a peak at the time it's written, so each node holds the position of the peak to its left at that
+/// time. Following them from the last node written restores all the current peaks.
+#[derive(Debug, Clone)]
+pub struct MmrNode {
+  pub position: Position,
+  pub height: u8,
+  pub hash: HashValue,
+  pub prev_peak: Position,
+  pub kind: MmrNodeKind,
+}
+
+impl Serializable for MmrNode {
+  fn write<W: Write>(&self, w: &mut W) -> Result<usize> {
+    w.write_u8(self.height)?;
+    w.write_all(&self.hash)?;
+    w.write_u64::<LittleEndian>(self.prev_peak)?;
+    let len = match &self.kind {
+      MmrNodeKind::Leaf { data } => {
+        w.write_u32::<LittleEndian>(data.len() as u32)?;
+        w.write_all(data)?;
+        4 + data.len()
+      }
+      MmrNodeKind::Branch { left, right } => {
+        w.write_u64::<LittleEndian>(*left)?;
+        w.write_u64::<LittleEndian>(*right)?;
+        8 + 8
+      }
+    };
+    Ok(1 + HASH_SIZE + 8 + len)
+  }
+
+  fn read<R: Read + Seek>(r: &mut R, position: Position) -> Result<Self> {
+    let height = r.read_u8()?;
+    let mut hash = [0u8; HASH_SIZE];
+    r.read_exact(&mut hash)?;
+    let prev_peak = r.read_u64::<LittleEndian>()?;
+    let kind = if height == 0 {
+      let len = r.read_u32::<LittleEndian>()? as usize;
+      let mut data = vec![0u8; len];
+      r.read_exact(&mut data)?;
+      MmrNodeKind::Leaf { data }
+    } else {
+      let left = r.read_u64::<LittleEndian>()?;
+      let right = r.read_u64::<LittleEndian>()?;
+      MmrNodeKind::Branch { left, right }
+    };
+    Ok(MmrNode { position, height, hash, prev_peak, kind })
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Peak {
+  height: u8,
+  hash: HashValue,
+  position: Position,
+}
+
+/// Inclusion proof of a leaf in a [`MerkleMountainRange`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmrProof {
+  /// Leaf number k ∈ {1,2,...}
+  pub index: Index,
+  pub leaf: HashValue,
+  /// Hashes of the sibling nodes, from the leaf level to the level just below the peak.
+  pub siblings: Vec<HashValue>,
+  /// Hashes of all peaks from left to right.
+  pub peaks: Vec<HashValue>,
+  /// Index of the peak in `peaks` under which the leaf is located.
+  pub peak: usize,
+}
+
+impl MmrProof {
+  /// Compute the bagged root hash that this proof proves, or `None` if the siblings don't lead to the peak.
+  pub fn root<H: HashAlgorithm>(&self) -> Option<HashValue> {
+    let mut hash = self.leaf;
+    let mut i = self.position_in_peak();
+    for sibling in self.siblings.iter() {
+      hash = if i & 1 == 0 { H::combine(&hash, sibling) } else { H::combine(sibling, &hash) };
+      i >>= 1;
+    }
+    if hash == self.peaks[self.peak] { Some(bag::<H>(&self.peaks)) } else { None }
+  }
+
+  /// Leaf position relative to the first leaf under the peak. The leaves under the preceding peaks are 2^h each with
+  /// strictly decreasing heights greater than the height of this peak, so the position is the lower bits of k-1.
+  fn position_in_peak(&self) -> u64 {
+    (self.index - 1) & ((1u64 << self.siblings.len()) - 1)
+  }
+}
+
+/// Fold the peaks from right to left into a single root hash.
+pub fn bag<H: HashAlgorithm>(peaks: &[HashValue]) -> HashValue {
+  let mut iter = peaks.iter().rev();
+  let mut root = *iter.next().unwrap();
+  for peak in iter {
+    root = H::combine(peak, &root);
+  }
+  root
+}
+
+/// Merkle Mountain Range: an append-only list of perfect binary hash trees with strictly decreasing heights.
+///
+/// Appending a leaf writes the leaf and then one branch node for each merge of two peaks of the same height, so the
+/// amortized write cost is 2 nodes per leaf. Retrieval descends from the peak that covers the leaf.
+pub struct MerkleMountainRange<S, H = Blake3>
+where
+  S: Storage<MmrNode>,
+  H: HashAlgorithm,
+{
+  storage: S,
+  next: Position,
+  peaks: Vec<Peak>,
+  _hash: PhantomData<H>,
+}
+
+impl<S, H> MerkleMountainRange<S, H>
+where
+  S: Storage<MmrNode>,
+  H: HashAlgorithm,
+{
+  /// Open the MMR stored in the storage, restoring the peaks from the last node.
+  pub fn new(mut storage: S) -> Result<Self> {
+    let (last, next) = storage.last()?;
+    let mut peaks = Vec::new();
+    if let Some(mut node) = last {
+      let mut reader = storage.reader()?;
+      loop {
+        peaks.push(Peak { height: node.height, hash: node.hash, position: node.position });
+        if node.prev_peak == NONE {
+          break;
+        }
+        node = reader.read(node.prev_peak)?;
+      }
+      peaks.reverse();
+    }
+    Ok(Self { storage, next, peaks, _hash: PhantomData })
+  }
+
+  pub fn peaks(&self) -> usize {
+    self.peaks.len()
+  }
+
+  fn put(&mut self, mut node: MmrNode) -> Result<Peak> {
+    node.position = self.next;
+    self.next = self.storage.put(node.position, &node)?;
+    Ok(Peak { height: node.height, hash: node.hash, position: node.position })
+  }
+
+  fn locate(&self, k: Index) -> (usize, u64) {
+    let mut first = 1;
+    for (i, peak) in self.peaks.iter().enumerate() {
+      let leaves = 1u64 << peak.height;
+      if k < first + leaves {
+        return (i, k - first);
+      }
+      first += leaves;
+    }
+    unreachable!("leaf {k} is out of range")
+  }
+
+  /// Descend from the peak to the leaf, calling `sibling` with the sibling node position at each level.
+  fn descend<F>(&self, k: Index, mut sibling: F) -> Result<(usize, MmrNode)>
+  where
+    F: FnMut(&mut Box<dyn Reader<MmrNode>>, Position) -> Result<()>,
+  {
+    let (i, offset) = self.locate(k);
+    let mut reader = self.storage.reader()?;
+    let mut node = reader.read(self.peaks[i].position)?;
+    while let MmrNodeKind::Branch { left, right } = node.kind {
+      let go_right = (offset >> (node.height - 1)) & 1 == 1;
+      let (next, other) = if go_right { (right, left) } else { (left, right) };
+      sibling(&mut reader, other)?;
+      node = reader.read(next)?;
+    }
+    Ok((i, node))
+  }
+}
+
+impl<H: HashAlgorithm> MerkleMountainRange<BlockStorage<FileDevice>, H> {
+  /// Open the MMR with file storage
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let storage = BlockStorage::from_file(path, false)?;
+    Self::new(storage)
+  }
+
+  /// Create a new empty MMR with file storage
+  pub fn create_on_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+    if path.as_ref().exists() {
+      fs::remove_file(&path)?;
+    }
+    Self::from_file(path)
+  }
+}
+
+impl<H: HashAlgorithm> MerkleMountainRange<MemKVS<MmrNode>, H> {
+  /// Create a new empty MMR with memory storage
+  pub fn create_on_memory() -> Result<Self> {
+    Self::new(MemKVS::new())
+  }
+}
+
+impl<S, H> HashTree for MerkleMountainRange<S, H>
+where
+  S: Storage<MmrNode>,
+  H: HashAlgorithm,
+{
+  type Error = slate::error::Error;
+  type Proof = MmrProof;
+
+  fn size(&self) -> u64 {
+    self.peaks.iter().map(|peak| 1u64 << peak.height).sum()
+  }
+
+  fn get(&mut self, k: Index) -> Result<Option<Vec<u8>>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+    let (_, leaf) = self.descend(k, |_, _| Ok(()))?;
+    match leaf.kind {
+      MmrNodeKind::Leaf { data } => Ok(Some(data)),
+      MmrNodeKind::Branch { .. } => unreachable!(),
+    }
+  }
+
+  fn root_hash(&mut self) -> Result<Option<HashValue>> {
+    if self.peaks.is_empty() {
+      Ok(None)
+    } else {
+      Ok(Some(bag::<H>(&self.peaks.iter().map(|peak| peak.hash).collect::<Vec<_>>())))
+    }
+  }
+
+  fn prove(&mut self, k: Index) -> Result<Option<MmrProof>> {
+    if k == 0 || k > self.size() {
+      return Ok(None);
+    }
+    let mut siblings = Vec::new();
+    let (peak, leaf) = self.descend(k, |reader, position| {
+      siblings.push(reader.read(position)?.hash);
+      Ok(())
+    })?;
+    siblings.reverse();
+    let peaks = self.peaks.iter().map(|peak| peak.hash).collect();
+    Ok(Some(MmrProof { index: k, leaf: leaf.hash, siblings, peaks, peak }))
+  }
+
+  fn append(&mut self, data: &[u8]) -> Result<u64> {
+    let prev_peak = self.peaks.last().map(|peak| peak.position).unwrap_or(NONE);
+    let kind = MmrNodeKind::Leaf { data: data.to_vec() };
+    let leaf = MmrNode { position: NONE, height: 0, hash: H::hash(data), prev_peak, kind };
+    let peak = self.put(leaf)?;
+    self.peaks.push(peak);
+
+    // 同じ高さのピークを結合する
+    while self.peaks.len() >= 2 && self.peaks[self.peaks.len() - 2].height == self.peaks[self.peaks.len() - 1].height {
+      let right = self.peaks.pop().unwrap();
+      let left = self.peaks.pop().unwrap();
+      let prev_peak = self.peaks.last().map(|peak| peak.position).unwrap_or(NONE);
+      let hash = H::combine(&left.hash, &right.hash);
+      let kind = MmrNodeKind::Branch { left: left.position, right: right.position };
+      let peak = self.put(MmrNode { position: NONE, height: left.height + 1, hash, prev_peak, kind })?;
+      self.peaks.push(peak);
+    }
+    Ok(self.size())
+  }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,71 @@
+use super::*;
+use crate::splitmix64;
+
+fn value(k: u64) -> Vec<u8> {
+  splitmix64(k).to_le_bytes().to_vec()
+}
+
+/// Compute the bagged root of the first n leaves without the MMR.
+fn expected_root(n: u64) -> HashValue {
+  let mut peaks = Vec::new();
+  let mut first = 1;
+  for height in (0..u64::BITS - n.leading_zeros()).rev() {
+    if n & (1 << height) != 0 {
+      let mut hashes = (first..first + (1 << height)).map(|k| Blake3::hash(&value(k))).collect::<Vec<_>>();
+      while hashes.len() > 1 {
+        hashes = hashes.chunks_exact(2).map(|pair| Blake3::combine(&pair[0], &pair[1])).collect();
+      }
+      peaks.push(hashes[0]);
+      first += 1 << height;
+    }
+  }
+  bag::<Blake3>(&peaks)
+}
+
+fn verify<S: Storage<MmrNode>>(mmr: &mut MerkleMountainRange<S>, n: u64) {
+  assert_eq!(n, mmr.size());
+  assert_eq!(n.count_ones() as usize, mmr.peaks());
+  assert_eq!(mmr.get(0).unwrap(), None);
+  assert_eq!(mmr.get(n + 1).unwrap(), None);
+  let root = mmr.root_hash().unwrap();
+  assert_eq!(if n == 0 { None } else { Some(expected_root(n)) }, root);
+  for k in 1..=n {
+    assert_eq!(Some(value(k)), mmr.get(k).unwrap(), "{k}/{n}");
+    let proof = mmr.prove(k).unwrap().unwrap();
+    assert_eq!(root, proof.root::<Blake3>(), "{k}/{n}");
+  }
+}
+
+#[test]
+fn test_basic_operations() {
+  let mut mmr = MerkleMountainRange::<_>::create_on_memory().unwrap();
+  verify(&mut mmr, 0);
+  for n in 1..=70 {
+    assert_eq!(n, mmr.append(&value(n)).unwrap());
+    verify(&mut mmr, n);
+  }
+}
+
+#[test]
+fn reopen_restores_peaks() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("mmr.db");
+  MerkleMountainRange::<_>::create_on_file(&path).unwrap();
+  for n in 1..=40 {
+    let mut mmr = MerkleMountainRange::<_>::from_file(&path).unwrap();
+    assert_eq!(n - 1, mmr.size());
+    mmr.append(&value(n)).unwrap();
+    verify(&mut mmr, n);
+  }
+}
+
+#[test]
+fn tampered_proof_is_rejected() {
+  let mut mmr = MerkleMountainRange::<_>::create_on_memory().unwrap();
+  for n in 1..=11 {
+    mmr.append(&value(n)).unwrap();
+  }
+  let mut proof = mmr.prove(6).unwrap().unwrap();
+  proof.leaf = Blake3::hash(&value(7));
+  assert_eq!(None, proof.root::<Blake3>());
+}
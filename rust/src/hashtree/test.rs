@@ -0,0 +1,90 @@
+use std::fmt::Debug;
+
+use super::*;
+use crate::hashtree::binary::BinaryHashTree;
+use crate::hashtree::hash::{Blake3, Sha256};
+use crate::hashtree::implicit::ImplicitHashTree;
+use crate::hashtree::leveled::LeveledHashTree;
+use crate::{MemKVS, splitmix64};
+
+/// Verify the tree of height `height` whose k-th leaf is `splitmix64(k)`.
+pub(crate) fn verify_hashtree<T, H>(tree: &mut T, height: u8)
+where
+  T: HashTree<Proof = AuthPath>,
+  T::Error: Debug,
+  H: HashAlgorithm,
+{
+  assert_eq!(1 << (height - 1), tree.size());
+
+  // Test retrieval
+  assert_eq!(tree.get(0).unwrap(), None);
+  for k in 1..=tree.size() {
+    assert_eq!(tree.get(k).unwrap(), Some(splitmix64(k).to_le_bytes().to_vec()), "{k}");
+  }
+  assert_eq!(tree.get(tree.size() + 1).unwrap(), None);
+
+  // Test root hash
+  let mut hashes = (1..=tree.size()).map(|k| H::hash(&splitmix64(k).to_le_bytes())).collect::<Vec<_>>();
+  while hashes.len() > 1 {
+    hashes = hashes.chunks_exact(2).map(|pair| H::combine(&pair[0], &pair[1])).collect();
+  }
+  let root = tree.root_hash().unwrap();
+  assert_eq!(Some(hashes[0]), root);
+
+  // Test authentication paths
+  assert_eq!(tree.prove(0).unwrap(), None);
+  for k in 1..=tree.size() {
+    let path = tree.prove(k).unwrap().unwrap();
+    assert_eq!(k, path.index);
+    assert_eq!(H::hash(&splitmix64(k).to_le_bytes()), path.leaf);
+    assert_eq!(height as usize - 1, path.siblings.len());
+    assert_eq!(root, Some(path.root::<H>()), "{k}");
+  }
+  assert_eq!(tree.prove(tree.size() + 1).unwrap(), None);
+}
+
+#[test]
+fn all_layouts_have_the_same_proofs() {
+  let dir = tempfile::tempdir().unwrap();
+  let values = |i: u64| -> slate::Result<Vec<u8>> { Ok(splitmix64(i).to_le_bytes().to_vec()) };
+  for height in 1..=6 {
+    let mut binary = BinaryHashTree::<_, Sha256>::create_on_memory(height).unwrap();
+    let mut implicit =
+      ImplicitHashTree::<Sha256>::create_on_file(dir.path().join("implicit.db"), height, 8, 2, values).unwrap();
+    let mut leveled =
+      LeveledHashTree::<Sha256>::create_on_file(dir.path().join("leveled.db"), height, 8, 2, values).unwrap();
+    let root = binary.root_hash().unwrap();
+    assert_eq!(root, implicit.root_hash().unwrap());
+    assert_eq!(root, leveled.root_hash().unwrap());
+    for k in 1..=binary.size() {
+      let path = binary.prove(k).unwrap();
+      assert_eq!(path, implicit.prove(k).unwrap());
+      assert_eq!(path, leveled.prove(k).unwrap());
+    }
+    assert_ne!(root, BinaryHashTree::<_, Blake3>::create_on_memory(height).unwrap().root_hash().unwrap());
+  }
+}
+
+#[test]
+fn slate_as_hashtree() {
+  let mut tree = SlateHashTree::new(Slate::new(MemKVS::new()).unwrap());
+  assert_eq!(tree.root_hash().unwrap(), None);
+  for k in 1..=32 {
+    assert_eq!(k, tree.append(&splitmix64(k).to_le_bytes()).unwrap());
+    assert!(tree.root_hash().unwrap().is_some());
+  }
+  assert_eq!(tree.get(0).unwrap(), None);
+  for k in 1..=tree.size() {
+    assert_eq!(tree.get(k).unwrap(), Some(splitmix64(k).to_le_bytes().to_vec()), "{k}");
+    assert!(tree.prove(k).unwrap().is_some());
+  }
+  assert_eq!(tree.get(tree.size() + 1).unwrap(), None);
+  assert!(tree.prove(tree.size() + 1).unwrap().is_none());
+}
+
+#[test]
+fn fixed_height_trees_refuse_append() {
+  let mut tree = BinaryHashTree::<_>::create_on_memory(3).unwrap();
+  assert!(tree.append(&[0u8; 8]).is_err());
+  assert_eq!(4, tree.size());
+}
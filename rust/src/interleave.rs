@@ -0,0 +1,90 @@
+//! Turns of the CUTs running a test unit side by side, one thread each, so that their trials interleave (A, B, A, B,
+//! ..) and the time-dependent bias such as the thermal drift spreads evenly over them. Only the thread holding the
+//! turn runs; it passes the turn to the next CUT at the end of each trial.
+use std::cell::{Cell, RefCell};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+pub struct Turns {
+  state: Mutex<State>,
+  changed: Condvar,
+}
+
+struct State {
+  /// Index of the participant holding the turn
+  current: usize,
+  /// Participants that haven't finished yet
+  active: Vec<bool>,
+}
+
+thread_local! {
+  /// Turns the current thread takes part in, with its index
+  static TURN: RefCell<Option<(Arc<Turns>, usize)>> = const { RefCell::new(None) };
+  /// Total time the current thread has waited for its turn
+  static WAITED: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+impl Turns {
+  /// Turns of n participants, starting from the first one.
+  pub fn new(n: usize) -> Arc<Self> {
+    Arc::new(Turns { state: Mutex::new(State { current: 0, active: vec![true; n] }), changed: Condvar::new() })
+  }
+
+  /// Run `f` on the current thread as the participant `index` once its turn comes. The participant leaves the turns
+  /// when `f` returns or panics.
+  pub fn run<T, F: FnOnce() -> T>(self: &Arc<Self>, index: usize, f: F) -> T {
+    struct Leave<'a>(&'a Turns, usize);
+    impl Drop for Leave<'_> {
+      fn drop(&mut self) {
+        TURN.with(|turn| turn.borrow_mut().take());
+        let mut state = self.0.lock();
+        state.active[self.1] = false;
+        self.0.pass(&mut state, self.1);
+      }
+    }
+
+    self.wait(self.lock(), index);
+    TURN.with(|turn| *turn.borrow_mut() = Some((self.clone(), index)));
+    let _leave = Leave(self, index);
+    f()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  fn wait(&self, mut state: MutexGuard<'_, State>, index: usize) {
+    let start = Instant::now();
+    while state.current != index {
+      state = self.changed.wait(state).unwrap_or_else(PoisonError::into_inner);
+    }
+    WAITED.with(|waited| waited.set(waited.get() + start.elapsed()));
+  }
+
+  /// Give the turn to the next active participant after `index`, if any.
+  fn pass(&self, state: &mut State, index: usize) {
+    let n = state.active.len();
+    if let Some(next) = (1..=n).map(|k| (index + k) % n).find(|i| state.active[*i]) {
+      state.current = next;
+      self.changed.notify_all();
+    }
+  }
+}
+
+/// Give the turn to the next CUT at the end of a trial and wait for it to come back. Nothing happens unless the
+/// current thread runs in [`Turns::run`].
+pub fn end_of_trial() {
+  if let Some((turns, index)) = TURN.with(|turn| turn.borrow().clone()) {
+    let mut state = turns.lock();
+    turns.pass(&mut state, index);
+    turns.wait(state, index);
+  }
+}
+
+/// Total time the current thread has waited for its turn, to be excluded from the time limits of the test units.
+pub fn waited() -> Duration {
+  WAITED.with(Cell::get)
+}
+
+#[cfg(test)]
+mod test;
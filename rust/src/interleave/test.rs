@@ -0,0 +1,30 @@
+use std::thread;
+
+use super::*;
+
+#[test]
+fn trials_interleave_until_each_finishes() {
+  let turns = Turns::new(2);
+  let log = Mutex::new(Vec::new());
+  thread::scope(|scope| {
+    for (index, trials) in [(0, 3), (1, 1)] {
+      let (turns, log) = (&turns, &log);
+      scope.spawn(move || {
+        turns.run(index, || {
+          for trial in 0..trials {
+            log.lock().unwrap().push((index, trial));
+            end_of_trial();
+          }
+        })
+      });
+    }
+  });
+  // 終了した CUT は順番から外れる
+  assert_eq!(vec![(0, 0), (1, 0), (0, 1), (0, 2)], log.into_inner().unwrap());
+}
+
+#[test]
+fn end_of_trial_outside_turns() {
+  end_of_trial();
+  assert_eq!(Duration::ZERO, waited());
+}
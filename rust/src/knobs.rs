@@ -0,0 +1,52 @@
+//! Engine options of the storage backends given by `--knob BACKEND.KEY=VALUE`, so that a tuning experiment doesn't
+//! require recompiling the factories. The values are recorded in the session metadata as `knob.BACKEND.KEY`.
+use std::collections::BTreeMap;
+
+use crate::error::{BenchError, Result};
+
+#[cfg(test)]
+mod test;
+
+/// Knobs understood by the factories. Slate's `FileStorage` takes no engine options, so there is no knob for the file
+/// storage.
+pub const KNOBS: [(&str, &str); 3] = [
+  ("rocksdb.block-cache-size", "bytes of the LRU block cache of RocksDB"),
+  ("rocksdb.write-buffer-size", "bytes of a memtable of RocksDB"),
+  ("memkvs.capacity", "entries preallocated in the hash map of MemKVS, the data size by default"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Knobs {
+  values: BTreeMap<String, u64>,
+}
+
+impl Knobs {
+  /// Knobs of the `KEY=VALUE` pairs. An unknown key is rejected so that a typo doesn't silently run the defaults.
+  pub fn new(pairs: &[(String, String)]) -> Result<Self> {
+    let mut values = BTreeMap::new();
+    for (key, value) in pairs {
+      if !KNOBS.iter().any(|(known, _)| known == key) {
+        let known = KNOBS.iter().map(|(known, _)| *known).collect::<Vec<_>>().join(", ");
+        return Err(BenchError::Config(format!("unknown knob {key}, must be one of {known}")));
+      }
+      let value = value
+        .parse::<u64>()
+        .map_err(|_| BenchError::Config(format!("the knob {key} must be a non-negative integer: {value}")))?;
+      values.insert(key.clone(), value);
+    }
+    Ok(Self { values })
+  }
+
+  pub fn get(&self, key: &str) -> Option<u64> {
+    debug_assert!(KNOBS.iter().any(|(known, _)| *known == key), "undefined knob: {key}");
+    self.values.get(key).copied()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.values.is_empty()
+  }
+
+  pub fn metadata(&self) -> Vec<(String, String)> {
+    self.values.iter().map(|(key, value)| (format!("knob.{key}"), value.to_string())).collect()
+  }
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+fn pair(key: &str, value: &str) -> (String, String) {
+  (String::from(key), String::from(value))
+}
+
+#[test]
+fn parse_the_knobs() {
+  let knobs = Knobs::new(&[pair("rocksdb.block-cache-size", "1073741824"), pair("memkvs.capacity", "1024")]).unwrap();
+  assert_eq!(Some(1073741824), knobs.get("rocksdb.block-cache-size"));
+  assert_eq!(Some(1024), knobs.get("memkvs.capacity"));
+  assert_eq!(None, knobs.get("rocksdb.write-buffer-size"));
+  assert_eq!(
+    vec![
+      (String::from("knob.memkvs.capacity"), String::from("1024")),
+      (String::from("knob.rocksdb.block-cache-size"), String::from("1073741824")),
+    ],
+    knobs.metadata()
+  );
+  assert!(Knobs::new(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn reject_invalid_knobs() {
+  for (key, value) in [("rocksdb.block-cache", "1024"), ("file.block-size", "4096"), ("memkvs.capacity", "1k")] {
+    let err = Knobs::new(&[pair(key, value)]).unwrap_err();
+    assert!(matches!(err, BenchError::Config(_)), "{key}={value}: {err:?}");
+  }
+}
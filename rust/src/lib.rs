@@ -2,17 +2,74 @@ use core::f64;
 use std::collections::HashMap;
 use std::fs::{OpenOptions, metadata, read_dir};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, PoisonError, RwLock};
 
+#[cfg(feature = "rocksdb")]
+use rocksdb::{BlockBasedOptions, Cache, DB, DBCompressionType, Options};
+#[cfg(feature = "rocksdb")]
+use slate::rocksdb::RocksDBStorage;
 use slate::{Position, Result, Serializable, Storage};
 
+#[cfg(feature = "rocksdb")]
+use crate::knobs::Knobs;
+
+#[macro_use]
+pub mod error;
+
+pub mod alloc;
+pub mod calibration;
+pub mod clock;
+pub mod counting;
+pub mod cpu;
+pub mod dashboard;
+pub mod energy;
+pub mod experiment;
 pub mod hashtree;
+pub mod interleave;
+pub mod knobs;
+pub mod monitor;
+pub mod payload;
+pub mod perf;
+pub mod platform;
+pub mod quiesce;
+pub mod quota;
+pub mod report;
+pub mod samples;
+pub mod scenario;
+pub mod slatecut;
+pub mod stat;
+pub mod sweep;
+pub mod syscall;
+pub mod trace;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod writer;
+
+#[cfg(test)]
+mod test;
+
+pub use experiment::{
+  AppendCUT, AuthPathCUT, CUT, Case, CleanPolicy, ConcurrentGetCUT, CountingCUT, DataSize, DualWriteCUT, Experiment,
+  GetCUT, HandleMode, IntoFloat, Layout, Measurement, MultiProcessGetCUT, Profile, ProveCUT, RangeCUT, ReportPolicy,
+  RootHashCUT, Scale, Soak, WriteTarget,
+};
+pub use slatecut::{ClosureFactory, CountingFactory, FileFactory, SlateCUT, StorageFactory, memkvs_factory};
+#[cfg(feature = "rocksdb")]
+pub use slatecut::{DualWriteRocksDBCUT, RocksDBFactory};
 
+/// In-memory [`Storage`] on a map shared by the instances. The entries may be written at arbitrary positions; `first`
+/// and `last` return the entries at the smallest and largest positions, with the position following the largest one.
 #[derive(Debug)]
 pub struct MemKVS<S: Serializable + Clone + 'static> {
   kvs: Arc<RwLock<HashMap<Position, S>>>,
+  /// Smallest and largest positions written with the number of the entries when they were known, tracked so that
+  /// `first` and `last` don't scan the keys unless another instance has added entries since
+  range: (usize, PositionRange),
 }
 
+/// Smallest and largest positions in a [`MemKVS`], or `None` if empty.
+type PositionRange = Option<(Position, Position)>;
+
 struct MemKVSReader<S: Serializable + 'static> {
   kvs: Arc<RwLock<HashMap<Position, S>>>,
 }
@@ -22,8 +79,33 @@ impl<S: Serializable + Clone + 'static> MemKVS<S> {
     Self::with_kvs(Default::default())
   }
 
+  /// Open the storage on the shared map. The entries added through the other instances are seen by this one, but no
+  /// entry may be removed from the map while this instance is in use.
   pub fn with_kvs(kvs: Arc<RwLock<HashMap<Position, S>>>) -> Self {
-    Self { kvs }
+    let range = Self::scan(&kvs.read().unwrap_or_else(PoisonError::into_inner));
+    Self { kvs, range }
+  }
+
+  fn scan(kvs: &HashMap<Position, S>) -> (usize, PositionRange) {
+    (kvs.len(), kvs.keys().min().copied().zip(kvs.keys().max().copied()))
+  }
+
+  /// The range of the positions in the map, scanned again only if the number of the entries has changed.
+  fn range(cache: &mut (usize, PositionRange), kvs: &HashMap<Position, S>) -> PositionRange {
+    if cache.0 != kvs.len() {
+      *cache = Self::scan(kvs);
+    }
+    cache.1
+  }
+
+  /// Whether an entry is written at the position.
+  pub fn contains(&self, position: Position) -> Result<bool> {
+    Ok(self.kvs.read()?.contains_key(&position))
+  }
+
+  /// Read the entry at the position, or `None` if nothing is written there.
+  pub fn try_read(&self, position: Position) -> Result<Option<S>> {
+    Ok(self.kvs.read()?.get(&position).cloned())
   }
 }
 
@@ -36,20 +118,27 @@ impl<S: Serializable + Clone + 'static> Default for MemKVS<S> {
 impl<S: Serializable + Clone + 'static> Storage<S> for MemKVS<S> {
   fn first(&mut self) -> Result<(Option<S>, slate::Position)> {
     let kvs = self.kvs.read()?;
-    let n = kvs.len() as Position;
-    Ok((kvs.get(&n).cloned(), n + 1))
+    let range = Self::range(&mut self.range, &kvs);
+    let first = range.and_then(|(min, _)| kvs.get(&min).cloned());
+    Ok((first, next_position(range)))
   }
 
   fn last(&mut self) -> Result<(Option<S>, slate::Position)> {
     let kvs = self.kvs.read()?;
-    let n = kvs.len() as Position;
-    if n == 0 { Ok((None, 1)) } else { Ok((kvs.get(&n).cloned(), n + 1)) }
+    let range = Self::range(&mut self.range, &kvs);
+    let last = range.and_then(|(_, max)| kvs.get(&max).cloned());
+    Ok((last, next_position(range)))
   }
 
   fn put(&mut self, position: Position, data: &S) -> Result<slate::Position> {
     let mut kvs = self.kvs.write()?;
+    let range = match Self::range(&mut self.range, &kvs) {
+      Some((min, max)) => Some((min.min(position), max.max(position))),
+      None => Some((position, position)),
+    };
     kvs.insert(position, data.clone());
-    Ok(kvs.len() as Position + 1)
+    self.range = (kvs.len(), range);
+    Ok(next_position(range))
   }
 
   fn reader(&self) -> Result<Box<dyn slate::Reader<S>>> {
@@ -57,10 +146,20 @@ impl<S: Serializable + Clone + 'static> Storage<S> for MemKVS<S> {
   }
 }
 
+fn next_position(range: PositionRange) -> Position {
+  range.map(|(_, max)| max + 1).unwrap_or(1)
+}
+
 impl<S: Serializable + Clone> slate::Reader<S> for MemKVSReader<S> {
   fn read(&mut self, position: Position) -> Result<S> {
     let kvs = self.kvs.read()?;
-    Ok(kvs.get(&position).cloned().unwrap())
+    match kvs.get(&position) {
+      Some(value) => Ok(value.clone()),
+      None => {
+        let msg = format!("no entry at position {position} in MemKVS");
+        Err(std::io::Error::new(std::io::ErrorKind::NotFound, msg).into())
+      }
+    }
   }
 }
 
@@ -71,6 +170,30 @@ pub struct ZipfSampler {
   tails: f64,
 }
 
+/// Accuracy of the head CDF precomputed by [`ZipfSampler`]. The positions after the head are sampled uniformly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZipfAccuracy {
+  /// Minimum number of the positions in the head
+  pub min_samples: usize,
+  /// The head ends where the relative decrease of the probability falls below this
+  pub convergence_threshold: f64,
+}
+
+impl Default for ZipfAccuracy {
+  fn default() -> Self {
+    Self { min_samples: 1000, convergence_threshold: 1.0 / 1000.0 }
+  }
+}
+
+/// Resulting cutoff of the head CDF of [`ZipfSampler`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZipfAccuracyReport {
+  /// Number of the positions in the head
+  pub cutoff: u64,
+  /// Probability mass of the positions after the head, which are sampled uniformly
+  pub tail_mass: f64,
+}
+
 impl ZipfSampler {
   /// パラメータ s の効果：
   /// 0.5: 軽微な偏り
@@ -78,14 +201,20 @@ impl ZipfSampler {
   /// 1.5: 強い偏り (推奨)
   /// 2.0: 非常に強い偏り
   pub fn new(seed: u64, s: f64, n: u64) -> Self {
+    Self::with_accuracy(seed, s, n, ZipfAccuracy::default())
+  }
+
+  /// s が 0.5 に近く n が巨大な場合、既定の精度ではテールが一様分布として扱われる割合が大きくなるため、
+  /// `min_samples` を増やすか `convergence_threshold` を小さくする。
+  pub fn with_accuracy(seed: u64, s: f64, n: u64, accuracy: ZipfAccuracy) -> Self {
     assert!(s > 0.0);
     assert!(n >= 1);
+    assert!(accuracy.convergence_threshold > 0.0);
 
     // n=2G のような巨大なデータセットに対して事前計算するため、前方のみの CDF を算出し、ほとんど変化のない
-    // テールは固定値として保持する。s=0.5～2.0 では数千個程度の値が保持される
-    let min_samples = 1000;
-    let convergence_threshold = 1.0 / 1000.0;
-    let mut head_cdf = Vec::with_capacity(min_samples);
+    // テールは固定値として保持する。既定の精度で s=0.5～2.0 では数千個程度の値が保持される
+    let ZipfAccuracy { min_samples, convergence_threshold } = accuracy;
+    let mut head_cdf = Vec::with_capacity(min_samples.min(n as usize));
     let mut cumulative = 0.0;
     let mut prev_p = f64::INFINITY;
     for i in 1..=n {
@@ -111,6 +240,11 @@ impl ZipfSampler {
     Self { state: seed, n, head_cdf, tails }
   }
 
+  /// Cutoff of the head CDF and the probability mass of the uniformly sampled tail.
+  pub fn accuracy_report(&self) -> ZipfAccuracyReport {
+    ZipfAccuracyReport { cutoff: self.head_cdf.len() as u64, tail_mass: 1.0 - self.tails }
+  }
+
   pub fn next_u64(&mut self) -> u64 {
     // (0, 1] 範囲の一様乱数を生成
     self.state = splitmix64(self.state);
@@ -131,6 +265,34 @@ impl ZipfSampler {
   }
 }
 
+/// Removes the file or the directory when dropped, also while unwinding by a panic.
+pub struct RemoveOnDrop(PathBuf);
+
+impl RemoveOnDrop {
+  pub fn new(path: PathBuf) -> Self {
+    Self(path)
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for RemoveOnDrop {
+  fn drop(&mut self) {
+    let result = if self.0.is_dir() {
+      std::fs::remove_dir_all(&self.0)
+    } else if self.0.exists() {
+      std::fs::remove_file(&self.0)
+    } else {
+      Ok(())
+    };
+    if let Err(err) = result {
+      eprintln!("WARN: fail to remove {}: {err}", self.0.to_string_lossy());
+    }
+  }
+}
+
 pub fn unique_file(dir: &Path, prefix: &str, suffix: &str) -> PathBuf {
   for i in 0..=usize::MAX {
     let name = if i == 0 { format!("{prefix}{suffix}") } else { format!("{prefix}_{i}{suffix}") };
@@ -160,6 +322,46 @@ pub fn file_size<P: AsRef<Path>>(path: P) -> u64 {
   }
 }
 
+/// Open (or create) the RocksDB database in `path` with the compression disabled, so that the storage size and access
+/// time are comparable with the other storages.
+#[cfg(feature = "rocksdb")]
+pub fn open_rocksdb_storage(path: &Path) -> Result<RocksDBStorage> {
+  open_rocksdb_storage_with(path, &Knobs::default())
+}
+
+/// [`open_rocksdb_storage`] with the `rocksdb.*` knobs applied.
+#[cfg(feature = "rocksdb")]
+pub fn open_rocksdb_storage_with(path: &Path, knobs: &Knobs) -> Result<RocksDBStorage> {
+  match DB::open(&rocksdb_options(knobs), path) {
+    Ok(db) => {
+      let db = Arc::new(RwLock::new(db));
+      Ok(RocksDBStorage::new(db, &[], false))
+    }
+    Err(err) => {
+      eprintln!("ERROR: fail to open RocksDB: {path:?}");
+      Err(err)?
+    }
+  }
+}
+
+/// Options of RocksDB with the compression disabled and the `rocksdb.*` knobs applied.
+#[cfg(feature = "rocksdb")]
+pub fn rocksdb_options(knobs: &Knobs) -> Options {
+  let mut opts = Options::default();
+  opts.create_if_missing(true);
+  opts.set_compression_type(DBCompressionType::None);
+  opts.set_compression_per_level(&[DBCompressionType::None; 7]);
+  if let Some(size) = knobs.get("rocksdb.block-cache-size") {
+    let mut table = BlockBasedOptions::default();
+    table.set_block_cache(&Cache::new_lru_cache(size as usize));
+    opts.set_block_based_table_factory(&table);
+  }
+  if let Some(size) = knobs.get("rocksdb.write-buffer-size") {
+    opts.set_write_buffer_size(size as usize);
+  }
+  opts
+}
+
 pub fn splitmix64(x: u64) -> u64 {
   let mut z = x;
   z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
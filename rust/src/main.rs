@@ -1,33 +1,73 @@
-use ::slate::error::Error;
-use ::slate::formula::{entry_access_distance, entry_access_distance_limits};
-use ::slate::{Index, Result};
 use chrono::Local;
-use clap::Parser;
-use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
-use rand::seq::SliceRandom;
-use rayon::iter::Either;
-use rayon::prelude::*;
-use slate_benchmark::{ZipfSampler, file_size, splitmix64};
-use std::collections::{HashMap, HashSet};
+use clap::{Parser, Subcommand, ValueEnum};
+use slate_benchmark::calibration::{Calibration, HashCost};
+use slate_benchmark::clock::{self, ClockCost, ClockSource};
+use slate_benchmark::cpu;
+use slate_benchmark::dashboard;
+use slate_benchmark::energy;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::hashtree::binary::Node;
+use slate_benchmark::hashtree::hash::{Blake3, HashAlgorithm, Sha256, Sha512_256};
+use slate_benchmark::knobs::Knobs;
+use slate_benchmark::payload::{self, Dataset, RecordFormat};
+use slate_benchmark::quiesce::{Quiesce, QuiesceAction, QuiesceInterval};
+use slate_benchmark::report::{self, CsvFormat, Delimiter, ExternalReport, SessionMetadata};
+use slate_benchmark::samples::{Compression, RawFormat, RawOutput};
+use slate_benchmark::slatecut::{entry_with_payload, read_shared};
+use slate_benchmark::trace;
+use slate_benchmark::writer;
+use slate_benchmark::{
+  CleanPolicy, CountingFactory, DataSize, Experiment, FileFactory, HandleMode, Layout, Profile, RemoveOnDrop,
+  ReportPolicy, SlateCUT, Soak, memkvs_factory,
+};
+#[cfg(feature = "rocksdb")]
+use slate_benchmark::{DualWriteRocksDBCUT, RocksDBFactory};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::panic;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use crate::binarytree::FileBinaryTreeCUT;
+#[cfg(feature = "rocksdb")]
+use crate::binarytree::RocksDBBinaryTreeCUT;
+use crate::binarytree::{
+  FileBinaryTreeCUT, FileBlockedImplicitTreeCUT, FileImplicitTreeCUT, FileLeveledTreeCUT, FilePaddedImplicitTreeCUT,
+};
+use crate::cgroup::MemoryLimit;
+use crate::config::Config;
+use crate::dataset::DatasetCache;
+use crate::mmr::FileMmrCUT;
+use crate::notify::{Notifier, REGRESSION_THRESHOLD};
+use crate::registry::{Env, Failure, Filter, OnFailure, Order, Registry, Retry, Schedule, TestUnit};
 use crate::seqfile::SeqFileCUT;
-use crate::slate::{FileFactory, MemKVSFactory, RocksDBFactory, SlateCUT};
-use crate::stat::{ExpirationTimer, Unit, XYReport};
+use crate::upload::Destination;
 
+mod affinity;
 mod binarytree;
+mod cgroup;
+mod config;
+mod dataset;
+mod mmr;
+mod notify;
+#[macro_use]
+mod registry;
 mod seqfile;
-mod slate;
-mod stat;
+mod upload;
+mod verify;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static GLOBAL: slate_benchmark::alloc::CountingAllocator = slate_benchmark::alloc::CountingAllocator;
 
 #[derive(Parser)]
 #[command(name = "slate-bench")]
 #[command(author, version, about = "Slateベンチマークツール - ファイル操作のパフォーマンステストを実行します")]
+#[command(args_conflicts_with_subcommands = true, args_override_self = true)]
 struct Args {
+  #[command(subcommand)]
+  command: Option<Command>,
+
   /// ベンチマークで使用するデータサイズ（エントリ数）
   #[arg(default_value_t = 256u64)]
   data_size: u64,
@@ -36,9 +76,20 @@ struct Args {
   #[arg(default_value_t = 65536u64)]
   data_size_large: u64,
 
-  /// ベンチマーク実行時の作業用一時ファイルを格納するディレクトリ
-  #[arg(short, long, default_value_t = std::env::temp_dir().to_string_lossy().into_owned())]
-  dir: String,
+  /// ベンチマーク実行時の作業用一時ファイルを格納するディレクトリ。複数指定するとファイルを使用する CUT をディレクトリ
+  /// ごとに実行し、そのレポートを {session}-{LABEL} として出力する (LABEL の省略時はディレクトリ名)
+  #[arg(
+    short,
+    long = "dir",
+    value_name = "[LABEL=]DIR",
+    default_values_t = [std::env::temp_dir().to_string_lossy().into_owned()]
+  )]
+  dirs: Vec<String>,
+
+  /// ファイルを使用する CUT を --dir のディスクと /dev/shm の tmpfs の両方で実行し、その比から I/O のコストを分離した
+  /// レポート ({session}-io-ratio-*.csv) を出力する
+  #[arg(long, default_value_t = false)]
+  tmpfs: bool,
 
   /// ベンチマーク結果（CSVファイル）を出力するディレクトリ
   #[arg(short, long, default_value_t = {std::env::current_dir().unwrap().to_string_lossy().into_owned()})]
@@ -48,671 +99,1165 @@ struct Args {
   #[arg(short, long, default_value_t = Local::now().format("%Y%m%d%H%M%S").to_string())]
   session: String,
 
-  /// 作業用ディレクトリをクリーンアップして終了
-  #[arg(short, long, default_value_t = false)]
-  clean: bool,
-
-  /// ベンチマークの最大実行時間（秒）
-  #[arg(short = 't', long, default_value_t = 600)]
-  timeout: u64,
-}
-
-fn main() -> Result<()> {
-  let args = Args::parse();
-  if args.data_size_large <= args.data_size {
-    eprintln!("ERROR: The small data size {} is larger than large data size {}", args.data_size, args.data_size_large);
-    return Ok(());
-  }
-  println!("Data size (small): {}", args.data_size);
-  println!("Data size (large): {}", args.data_size_large);
-
-  // 作業ディレクトリ作成
-  let root = PathBuf::from_str(&args.dir).unwrap();
-  fs::create_dir_all(&root)?;
-  println!("Working directory: {:?}", &root);
+  /// 同じセッション ID のレポートが既に存在する場合の扱い
+  #[arg(long, value_enum, default_value_t = ReportPolicy::Suffix)]
+  on_existing_report: ReportPolicy,
 
-  let experiment = Experiment::new(&args)?;
+  /// レポートの配置 (session は {session}/ 以下の csv/, charts/, raw/ と manifest.json に出力する)
+  #[arg(long, value_enum, default_value_t = Layout::Flat)]
+  layout: Layout,
 
-  if args.clean {
-    experiment.clean_all_experiments()?;
-    return Ok(());
-  }
+  /// CSV レポートの区切り文字 (tab の場合も拡張子は .csv のまま)
+  #[arg(long, value_enum, default_value_t = Delimiter::Comma)]
+  csv_delimiter: Delimiter,
 
-  let dir = experiment.work_dir()?;
-  let small = DataSize::Small(args.data_size);
-  let large = DataSize::Large(args.data_size_large);
+  /// CSV レポートの数値の小数点をカンマで出力する (--csv-delimiter semicolon または tab が必要)
+  #[arg(long, default_value_t = false)]
+  decimal_comma: bool,
 
-  {
-    let mut cut = SlateCUT::new(FileFactory::new(&dir))?;
-    experiment
-      .run_testunit_append(&mut cut, &small)?
-      .run_testunit_biased_get(&mut cut, &small)?
-      .run_testunit_uniformed_get(&mut cut, &small)?
-      .run_testunit_cache_level(&mut cut, &small)?
-      .run_testunit_prove(&mut cut, &small)?
-      .run_testunit_biased_get(&mut cut, &large)?
-      .run_testunit_uniformed_get(&mut cut, &large)?
-      .run_testunit_cache_level(&mut cut, &large)?
-      .clear()?;
-  }
-
-  fn run_testsuite<C>(experiment: &Experiment, ds: &DataSize, cut: &mut C) -> Result<()>
-  where
-    C: GetCUT + AppendCUT,
-  {
-    experiment
-      .run_testunit_append(cut, ds)?
-      .run_testunit_biased_get(cut, ds)?
-      .run_testunit_uniformed_get(cut, ds)?
-      .run_testunit_cache_level(cut, ds)?
-      .clear()?;
-    Ok(())
-  }
-  run_testsuite(&experiment, &small, &mut SlateCUT::new(MemKVSFactory::new(args.data_size as usize))?)?;
-  run_testsuite(&experiment, &small, &mut SlateCUT::new(RocksDBFactory::new(&dir))?)?;
-  run_testsuite(&experiment, &small, &mut SeqFileCUT::new(&dir)?)?;
-
-  {
-    let mut cut = FileBinaryTreeCUT::new(&dir, args.data_size)?;
-    experiment
-      .run_testunit_biased_get(&mut cut, &small)?
-      .run_testunit_uniformed_get(&mut cut, &small)?
-      .run_testunit_cache_level(&mut cut, &small)?
-      .clear()?;
-  }
+  /// 作業用ディレクトリをクリーンアップして終了
+  #[arg(short, long, default_value_t = false)]
+  clean: bool,
 
-  fs::remove_dir_all(&dir)?;
-  Ok(())
-}
+  /// --clean で最終更新から指定した日数を経過したもののみを削除する
+  #[arg(long, value_name = "DAYS", requires = "clean", value_parser = parse_non_negative)]
+  older_than: Option<f64>,
 
-pub enum Scale {
-  Linear,
-  Log,
-  BestCase,
-  WorstCase,
-}
+  /// --clean でディレクトリごとの合計が指定したサイズ (GB) に収まるまで古いものから削除する
+  #[arg(long, value_name = "GB", requires = "clean", value_parser = parse_non_negative)]
+  max_total_size: Option<f64>,
 
-struct Experiment {
-  session: String,
-  dir: PathBuf,
-  dir_report: PathBuf,
+  /// ベンチマークの最大実行時間（秒）
+  #[arg(short = 't', long, default_value_t = 600)]
+  timeout: u64,
 
-  stability_threshold: f64, // 例: 0.10 (=10%)
-  min_trials: usize,        // 例: 5
-  max_trials: usize,        // 例: 100
-  max_duration: Duration,   // 例: Duration::from_secs(30),
+  /// 取得系のテストユニットで、取得操作ごとにバックエンドを閉じて開き直すか、開いたハンドルを使い回すか (すべての CUT
+  /// で共通)
+  #[arg(long, value_enum, value_name = "MODE", default_value_t = HandleMode::Persistent)]
+  get_handle: HandleMode,
+
+  /// 計測区間の時刻の取得元 (サブマイクロ秒の操作ではクロックのオーバーヘッドと分解能が計測値に占める割合が大きい)
+  #[arg(long, value_enum, default_value_t = ClockSource::Instant)]
+  clock: ClockSource,
+
+  /// 位置に対する取得時間の計測で、1 サンプルがこの時間 (マイクロ秒) 以上になるよう連続した取得をまとめて計測し、
+  /// 回数で割る (サブマイクロ秒の取得でクロックの分解能による量子化を避ける)
+  #[arg(long, value_name = "MICROSECONDS")]
+  batch_min_time: Option<f64>,
+
+  /// 起動時に計測した空の計測区間の処理時間 (中央値) を各計測値から差し引く
+  #[arg(long, default_value_t = false)]
+  subtract_overhead: bool,
+
+  /// Soak テストユニットでエントリを追加し続ける時間 (時間)
+  #[arg(long, value_name = "HOURS", default_value_t = 24.0)]
+  soak_hours: f64,
+
+  /// Soak テストユニットで取得と認証パスの処理時間を計測する間隔 (分)
+  #[arg(long, value_name = "MINUTES", default_value_t = 10.0)]
+  soak_interval: f64,
+
+  /// 作業ディレクトリ内のこのセッションのデータベースの合計サイズの上限 (GB)。構築中と試行ごとに確認し、前回の確認からの
+  /// 増加が続くと次の確認までに超える場合は、超える前にその CUT のテストユニットを中断する
+  #[arg(long, value_name = "GB", value_parser = parse_positive)]
+  workdir_quota: Option<f64>,
+
+  /// 実行する CUT を名前の部分一致で限定する（複数指定可）
+  #[arg(long = "cut", value_name = "NAME")]
+  cuts: Vec<String>,
+
+  /// 実行するテストユニットを限定する（複数指定可）
+  #[arg(long = "unit", value_enum, value_name = "UNIT")]
+  units: Vec<TestUnit>,
+
+  /// 計測のプロファイル
+  #[arg(long, value_enum, default_value_t = Profile::Standard)]
+  profile: Profile,
+
+  /// 構築済みのデータベースをセッションをまたいで再利用するためのキャッシュディレクトリ
+  #[arg(long, value_name = "DIR")]
+  dataset_cache: Option<PathBuf>,
+
+  /// CUT の構築やテストユニットが一時的なエラー (ロックの競合、ディスクの空き不足など) で失敗した場合に再試行する回数
+  #[arg(long, value_name = "N", default_value_t = 0)]
+  retries: usize,
+
+  /// 最初の再試行までの待機秒数 (再試行ごとに倍になる)
+  #[arg(long, value_name = "SECS", default_value_t = 5)]
+  retry_backoff: u64,
+
+  /// テストユニットが失敗した場合にその CUT の残りのテストユニットを実行しないか、失敗したテストユニットのみを飛ばすか
+  /// (失敗は {session}-failures.csv に記録され、他の CUT は常に実行される)
+  #[arg(long, value_enum, default_value_t = OnFailure::SkipCut)]
+  on_failure: OnFailure,
+
+  /// ベンチマーク後に各 CUT のデータベースを指定した件数おきに読み直して検証する
+  #[arg(long, value_name = "EVERY")]
+  validate: Option<u64>,
+
+  /// セッションの終了時にレポートとマニフェストをアップロードする先 (s3://bucket/prefix, gs://bucket/prefix または
+  /// file:///path)
+  #[arg(long, value_name = "URL")]
+  upload: Option<String>,
+
+  /// セッションの終了時や中断時に結果の概要を POST する Webhook の URL (Slack の Incoming Webhook など)
+  #[arg(long, value_name = "URL")]
+  notify_url: Option<String>,
+
+  /// 通知に含める性能の低下を検出するため、同じ --output と --layout のレポートと比較する基準のセッション。平均が 10%
+  /// を超えて増えたレポートを大きい順に通知する
+  #[arg(long, value_name = "SESSION", requires = "notify_url")]
+  notify_baseline: Option<String>,
+
+  /// CUT に発行したすべての操作をトレースとして記録するファイル
+  #[arg(long, value_name = "FILE")]
+  record_trace: Option<PathBuf>,
+
+  /// 記録済み、または外部から与えられたトレースを Replay (取得操作のみ) と Workload テストユニットで再生する
+  #[arg(long, value_name = "FILE")]
+  replay_trace: Option<PathBuf>,
+
+  /// Scenario テストユニットで実行する、設定ファイルの [scenarios.NAME] に定義したシナリオの名前 (複数指定可)
+  #[arg(long = "scenario", value_name = "NAME")]
+  scenarios: Vec<String>,
+
+  /// slate のストレージの呼び出しを数える *-counted の CUT で、エントリのバイト数に対するストレージへの書き込みと読み込み
+  /// のバイト数 (書き込み・読み込み増幅) を時間を計測せずに amplification-*.csv に出力する
+  #[arg(long, default_value_t = false)]
+  count_storage: bool,
+
+  /// --scenario のシナリオを定義した TOML の設定ファイル
+  #[arg(long, value_name = "FILE", default_value = config::DEFAULT_CONFIG)]
+  scenario_config: PathBuf,
+
+  /// セッションに付与するタグ (例: slate=v0.4.0, host=nuc, fs=xfs、複数指定可)。{session}.session に記録され、sessions
+  /// サブコマンドでセッションを絞り込み、グループ化できる
+  #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+  tags: Vec<(String, String)>,
+
+  /// slate の CUT に splitmix64 の値の代わりに追加する、実際のペイロードのレコードのファイル。データベースがレコード数より
+  /// 大きい場合は先頭から繰り返す
+  #[arg(long, value_name = "FILE")]
+  append_dataset: Option<PathBuf>,
+
+  /// --append-dataset のファイルのレコードの形式
+  #[arg(long, value_enum, default_value_t = RecordFormat::Lines)]
+  append_dataset_format: RecordFormat,
+
+  /// ファクトリに渡すバックエンドのエンジンのオプション (例: rocksdb.block-cache-size=1073741824、複数指定可)。
+  /// rocksdb.block-cache-size, rocksdb.write-buffer-size, memkvs.capacity を指定でき、セッションのメタデータに記録される
+  #[arg(long = "knob", value_name = "KEY=VALUE", value_parser = parse_tag)]
+  knobs: Vec<(String, String)>,
+
+  /// 計測点ごとに試行の順のサンプルから Pettitt 検定で定常状態への変化点を検出し、それ以前のウォームアップのサンプルを
+  /// 統計とレポートから除外する (除外した数は *_warmup.csv に記録される)
+  #[arg(long, default_value_t = false)]
+  discard_warmup: bool,
+
+  /// 計測点ごとに保持するサンプルの上限。超えた分はリザーバーサンプリングで一様に間引き、中央値以外の統計はすべての
+  /// サンプルから逐次に求める (ウォームアップは上限に達するまでのサンプルから検出する)
+  #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+  max_samples: Option<u32>,
+
+  /// 目盛りを繰り返し走査するテストユニットで、計測点ごとの分散を走査間 (環境の変動) と走査内 (操作自体のばらつき) に
+  /// 分解して *_variance.csv に出力する
+  #[arg(long, default_value_t = false)]
+  variance_components: bool,
+
+  /// 最小試行回数の後、標本の CV が 95% の信頼度で CV の閾値を上回り、試行を重ねても止める条件に到達しない計測点を
+  /// 打ち切る (条件の成否が決まるまでの試行回数の推定は常に進捗の Required 列に表示される)
+  #[arg(long, default_value_t = false)]
+  abort_unreachable: bool,
+
+  /// データベースの構築後と計測の試行 (または目盛り) の間に、直前の書き込みの書き戻しが後続の読み込みの計測に重ならない
+  /// よう sync(2) を行う (drop-caches はさらにページキャッシュを破棄する、Linux の root のみ)
+  #[arg(long, value_enum, value_name = "ACTION")]
+  quiesce: Option<QuiesceAction>,
+
+  /// --quiesce の後 (または単独で) 指定したミリ秒だけ待機する
+  #[arg(long, value_name = "MILLIS", default_value_t = 0)]
+  quiesce_sleep: u64,
+
+  /// --quiesce と --quiesce-sleep を行う間隔
+  #[arg(long, value_enum, default_value_t = QuiesceInterval::Trial)]
+  quiesce_interval: QuiesceInterval,
+
+  /// テストユニットごとに powercap/RAPL から CPU パッケージの消費電力量を計測し、{session}-energy.csv に操作あたりの
+  /// ジュールを記録する (Linux/Intel のみ、通常は root 権限を要する)
+  #[arg(long, default_value_t = false)]
+  energy: bool,
+
+  /// テストユニットの前に作業ディレクトリのストレージで順次書き込み、4KiB のランダム読み込み、fsync のレイテンシを
+  /// 計測し、セッションのメタデータ ({session}.session) に記録する
+  #[arg(long, default_value_t = false)]
+  calibrate: bool,
+
+  /// CUT を実行する順序。random と interleaved ではテストユニットごとにすべての CUT を実行し、熱や page cache の状態の
+  /// ような時間に依存する偏りが特定の CUT に有利に働かないようにする (CUT はテストユニットごとに構築し直される)。
+  /// interleaved ではテストユニット内の試行を CUT の間で交互に実行する (--isolate と同時には指定できない)
+  #[arg(long, value_enum, default_value_t = Order::Registered)]
+  order: Order,
+
+  /// --order random のシャッフルのシード (省略時は時刻から決定し、{session}.session に記録される)
+  #[arg(long, value_name = "SEED")]
+  order_seed: Option<u64>,
+
+  /// 登録されている CUT とテストユニットを表示して終了
+  #[arg(long, default_value_t = false)]
+  list: bool,
+
+  /// CUT ごとに子プロセスを起動して実行し、アロケータやページキャッシュの状態、クラッシュが他の CUT に影響しないようにする
+  #[arg(long, default_value_t = false)]
+  isolate: bool,
+
+  /// CUT の子プロセスを cgroup v2 の memory.max (例: 512M) で制限して実行する (Linux のみ、--isolate を伴う)
+  #[arg(long, value_name = "BYTES")]
+  memory_max: Option<String>,
+
+  /// 指定したアドレス (例: 127.0.0.1:8080) で実行中の進捗と最新の計測結果を表示する HTTP サーバを起動する
+  #[arg(long, value_name = "ADDR")]
+  dashboard: Option<String>,
+
+  /// 標準出力が端末でもターミナル UI を表示せず、計測結果を逐次出力する (tui フィーチャーが有効な場合のみ)
+  #[arg(long, default_value_t = false)]
+  #[cfg_attr(not(feature = "tui"), allow(dead_code))]
+  no_tui: bool,
+
+  /// 実行中の CPU 周波数と温度を記録し、スロットリングが発生した計測に注記する。実行中のスロットリングはセッションの
+  /// メタデータ cpu.throttling にも記録する
+  #[arg(long, default_value_t = false)]
+  monitor_cpu: bool,
+
+  /// 取得・追記の操作ごとにハードウェアカウンタ (命令数、サイクル数、キャッシュミス、分岐ミス) を記録する
+  /// (Linux で perf フィーチャーが有効な場合のみ)。互いのオーバーヘッドを計数しないよう --syscalls とは併用できない
+  #[arg(long, default_value_t = false, conflicts_with = "syscalls")]
+  perf: bool,
+
+  /// 取得・追記の操作ごとに read/write システムコールの回数を記録する (Linux のみ)
+  #[arg(long, default_value_t = false)]
+  syscalls: bool,
+
+  /// 計測した生のサンプルを各レポートと同じ名前のファイルにも書き出す
+  #[arg(long, default_value_t = false)]
+  raw_samples: bool,
+
+  /// 生のサンプルの出力形式 (parquet は parquet フィーチャーが有効な場合のみで、その場合の既定、それ以外は csv が既定)
+  #[arg(long, value_enum, default_value_t = RawFormat::DEFAULT, requires = "raw_samples")]
+  raw_format: RawFormat,
+
+  /// csv と ndjson の生のサンプルを書き出しながら圧縮する
+  #[arg(long, value_enum, default_value_t = Compression::None, requires = "raw_samples")]
+  raw_compression: Compression,
+
+  /// 計測スレッドを固定する CPU コア番号 (レポートの出力スレッドは --pin-prepare の先頭か他のコアに固定する)
+  #[arg(long, value_name = "CORE")]
+  pin: Option<usize>,
+
+  /// データベースを並列に構築するスレッドを固定する CPU コア番号（カンマ区切り）
+  #[arg(long, value_name = "CORES", value_delimiter = ',')]
+  pin_prepare: Vec<usize>,
+
+  /// 子プロセスとして指定された名前の CUT のみを実行する (--isolate の内部で使用)
+  #[arg(long, value_name = "NAME", hide = true)]
+  isolated_cut: Option<String>,
+
+  /// 子プロセスとして指定されたテストユニットのみを実行する (--isolate と --order の内部で使用)
+  #[arg(long, value_enum, value_name = "UNIT", hide = true)]
+  isolated_unit: Option<TestUnit>,
 }
 
-pub struct Case {
-  pub session: String,
-  pub dir: PathBuf,
-  pub dir_report: PathBuf,
-  scale: Scale,
-  division: usize,
-  cv_threshold: f64,      // 例: 0.10 (=10%)
-  min_trials: usize,      // 例: 5
-  max_trials: usize,      // 例: 100
-  max_duration: Duration, // 例: Duration::from_secs(30),
+#[derive(Subcommand)]
+enum Command {
+  /// 格納済みの木のハッシュ値を再計算して完全性を検証する
+  Verify {
+    /// 検証するファイルまたはディレクトリ
+    path: PathBuf,
+
+    /// 木の格納形式
+    #[arg(short, long, value_enum, default_value_t = verify::Format::Hashtree)]
+    format: verify::Format,
+  },
+
+  /// セッションの作業ディレクトリに残るすべてのデータベースを開き直し、ルートハッシュと標本の位置の値が一致することを
+  /// 確認する
+  VerifySession {
+    /// 検証するセッションの識別子
+    session: String,
+
+    /// セッションの作業ディレクトリを含むディレクトリ (複数指定可)
+    #[arg(long = "dir", value_name = "DIR", default_values_t = [std::env::temp_dir().to_string_lossy().into_owned()])]
+    dirs: Vec<String>,
+
+    /// 値を比較する位置の数
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    samples: usize,
+  },
+
+  /// 構築済みデータベースのキャッシュ (ハッシュ木の CUT のみ) を tar アーカイブに書き出す
+  Export {
+    /// データベースのキャッシュディレクトリ
+    #[arg(long, value_name = "DIR")]
+    dataset_cache: PathBuf,
+
+    /// 書き出すアーカイブ
+    archive: PathBuf,
+
+    /// 書き出すデータベースのキー（省略時はすべて）
+    keys: Vec<String>,
+  },
+
+  /// tar アーカイブからデータベースのキャッシュを取り込む
+  Import {
+    /// データベースのキャッシュディレクトリ
+    #[arg(long, value_name = "DIR")]
+    dataset_cache: PathBuf,
+
+    /// 取り込むアーカイブ
+    archive: PathBuf,
+  },
+
+  /// 他の実装 (Scala/JVM 版の slate など) の計測結果 (CSV または JSON) をレポートとして取り込み、このベンチマークの
+  /// 結果と並べてプロットできるようにする
+  ImportResults {
+    /// 取り込む計測結果のファイル
+    file: PathBuf,
+
+    /// レポートの種類と CUT 名 (例: append-scala-file)。`{session}-{name}.csv` として保存される
+    #[arg(long)]
+    name: String,
+
+    /// ベンチマーク結果（CSVファイル）を出力するディレクトリ
+    #[arg(short, long, default_value_t = {std::env::current_dir().unwrap().to_string_lossy().into_owned()})]
+    output: String,
+
+    /// ベンチマークセッションの識別子（ファイル名に使用されます）
+    #[arg(short, long, default_value_t = Local::now().format("%Y%m%d%H%M%S").to_string())]
+    session: String,
+
+    /// レポートの配置
+    #[arg(long, value_enum, default_value_t = Layout::Flat)]
+    layout: Layout,
+
+    /// 取り込む CSV の区切り文字
+    #[arg(long, value_enum, default_value_t = Delimiter::Comma)]
+    csv_delimiter: Delimiter,
+
+    /// 取り込む CSV の数値の小数点がカンマ
+    #[arg(long, default_value_t = false)]
+    decimal_comma: bool,
+  },
+
+  /// レポートディレクトリのセッションをタグとともに一覧する
+  Sessions {
+    /// ベンチマーク結果（CSVファイル）を出力するディレクトリ
+    #[arg(short, long, default_value_t = {std::env::current_dir().unwrap().to_string_lossy().into_owned()})]
+    output: String,
+
+    /// 指定したすべてのタグを持つセッションに限定する（複数指定可）
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    tags: Vec<(String, String)>,
+
+    /// 指定したタグの値ごとにセッションをまとめる
+    #[arg(long, value_name = "KEY")]
+    group_by: Option<String>,
+  },
+
+  /// マルチプロセスのテストユニットが起動する、slate のファイルを読み取り専用で開いて取得時間を出力するプロセス
+  #[command(hide = true)]
+  SharedReader {
+    path: PathBuf,
+    n: u64,
+    count: usize,
+    seed: u64,
+
+    /// 親プロセスと同じ計測区間の時刻の取得元
+    #[arg(long, value_enum, default_value_t = ClockSource::Instant)]
+    clock: ClockSource,
+
+    /// 親プロセスと同じ、追加したレコードのデータセット
+    #[arg(long, value_name = "FILE")]
+    append_dataset: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = RecordFormat::Lines)]
+    append_dataset_format: RecordFormat,
+
+    /// 親プロセスが読み込んだデータセットの BLAKE3 ハッシュ。計測中にファイルが変更された場合は失敗する
+    #[arg(long, value_name = "HEX")]
+    append_dataset_blake3: Option<String>,
+  },
+
+  /// 設定ファイルに定義した名前付きの実験を実行する。名前を省略すると実験を一覧する
+  Run {
+    /// 実行する実験の名前
+    name: Option<String>,
+
+    /// 実験を定義した TOML の設定ファイル
+    #[arg(long, value_name = "FILE", default_value = config::DEFAULT_CONFIG)]
+    config: PathBuf,
+
+    /// 実験の定義に追加する (または上書きする) オプション
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<OsString>,
+  },
 }
 
-impl Experiment {
-  fn new(args: &Args) -> Result<Self> {
-    let session = args.session.clone();
-    let dir = PathBuf::from(&args.dir);
-    let dir_report = PathBuf::from(&args.output);
-
-    if !dir.exists() {
-      fs::create_dir_all(&dir)?;
-    }
-    if !dir_report.exists() {
-      fs::create_dir_all(&dir)?;
+fn main() -> Result<()> {
+  // エラーで途中で戻っても出力スレッドに残った行を失わないようにする
+  let _flush = writer::FlushOnDrop;
+  let mut args = Args::parse();
+  if let Some(Command::Run { name, config: path, args: extra }) = &args.command {
+    let config = Config::load(path)?;
+    let Some(name) = name else {
+      for (name, description) in config.experiments() {
+        println!("{name}: {}", description.unwrap_or(""));
+      }
+      return Ok(());
+    };
+    // 子プロセスには `run NAME` に続けて --session などが渡されるため、定義の後ろに追加して上書きさせる
+    let mut argv = vec![OsString::from("slate-bench")];
+    argv.extend(["--scenario-config".into(), path.into()]);
+    argv.extend(config.args(name)?);
+    argv.extend(extra.iter().cloned());
+    args = Args::try_parse_from(argv).unwrap_or_else(|err| err.exit());
+    if args.command.is_some() {
+      return Err(BenchError::Config(format!("the experiment {name} must not contain a subcommand")));
     }
-
-    let stability_threshold = 0.05;
-    let min_trials = 5;
-    let max_trials = 1000;
-    let max_duration = Duration::from_secs(args.timeout);
-    Ok(Self { session, dir, dir_report, stability_threshold, min_trials, max_trials, max_duration })
-  }
-
-  pub fn case(&self) -> Result<Case> {
-    let session = self.session.clone();
-    let dir = self.dir.clone();
-    let dir_report = self.dir_report.clone();
-    let scale = Scale::Linear;
-    let division = 100;
-
-    let stability_threshold = self.stability_threshold;
-    let min_trials = self.min_trials;
-    let max_trials = self.max_trials;
-    let max_duration = self.max_duration;
-    Ok(Case {
-      session,
-      dir,
-      dir_report,
-      scale,
-      division,
-      cv_threshold: stability_threshold,
-      min_trials,
-      max_trials,
-      max_duration,
-    })
+    println!("Experiment: {name}");
   }
-
-  fn work_dir(&self) -> Result<PathBuf> {
-    let path = self.dir.join(format!("slate_benchmark-{}", self.session));
-    if !path.exists() {
-      fs::create_dir_all(&path)?;
+  clock::set_clock(args.clock)?;
+  match &args.command {
+    Some(Command::Verify { path, format }) => {
+      if !verify::verify(path, *format)? {
+        std::process::exit(1);
+      }
+      return Ok(());
+    }
+    Some(Command::VerifySession { session, dirs, samples }) => {
+      let dirs = parse_dirs(dirs)?.into_iter().map(|(_, dir)| dir).collect::<Vec<_>>();
+      if !verify::verify_session(&dirs, session, *samples)? {
+        std::process::exit(1);
+      }
+      return Ok(());
     }
-    Ok(path)
-  }
-
-  fn clear(&self) -> Result<()> {
-    let work_dir = self.work_dir()?;
-    if work_dir.exists() {
-      for entry in fs::read_dir(&work_dir)? {
-        let e = entry?;
-        let path = e.path();
-        if e.file_type()?.is_dir() {
-          fs::remove_dir_all(e.path()).unwrap();
-          println!("directory removed: {}", path.to_string_lossy());
-        } else if e.file_type()?.is_file() {
-          fs::remove_file(e.path()).unwrap();
-          println!("file removed: {}", path.to_string_lossy());
-        } else {
-          println!("WARN: unrecognized file type: {}", path.to_string_lossy());
+    Some(Command::Export { dataset_cache, archive, keys }) => {
+      DatasetCache::new(dataset_cache)?.export(keys, archive)?;
+      println!("==> The datasets have been exported to: {}", archive.to_string_lossy());
+      return Ok(());
+    }
+    Some(Command::Import { dataset_cache, archive }) => {
+      for key in DatasetCache::new(dataset_cache)?.import(archive)? {
+        println!("imported: {key}");
+      }
+      return Ok(());
+    }
+    Some(Command::ImportResults { file, name, output, session, layout, csv_delimiter, decimal_comma }) => {
+      let path = layout.path(Path::new(output), session, &format!("{name}.csv"));
+      fs::create_dir_all(path.parent().unwrap_or(Path::new(output)))?;
+      let report = ExternalReport::load(file, CsvFormat { delimiter: *csv_delimiter, decimal_comma: *decimal_comma })?;
+      // 既存のセッションに取り込む場合は、そのセッションのレポートと同じ形式で保存する
+      let marker = layout.marker(Path::new(output), session);
+      if let Ok(text) = fs::read_to_string(&marker) {
+        report::set_csv_format(CsvFormat::of(&SessionMetadata::parse(session, &text))?)?;
+      }
+      report.save(&path)?;
+      println!("==> The results have been saved in: {}", path.to_string_lossy());
+      return Ok(());
+    }
+    Some(Command::Sessions { output, tags, group_by }) => {
+      let sessions = SessionMetadata::load_all(Path::new(output))?;
+      let sessions = sessions.iter().filter(|s| s.matches(tags));
+      let mut groups = BTreeMap::<Option<&str>, Vec<&SessionMetadata>>::new();
+      for session in sessions {
+        groups.entry(group_by.as_deref().and_then(|key| session.tag(key))).or_default().push(session);
+      }
+      for (value, sessions) in groups.iter() {
+        if let Some(key) = group_by {
+          println!("{key}={}:", value.unwrap_or("(none)"));
+        }
+        let indent = if group_by.is_some() { "  " } else { "" };
+        for session in sessions.iter() {
+          let tags = session.tags().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>();
+          println!("{indent}{} {}", session.session, tags.join(" "));
         }
       }
-    } else {
-      fs::create_dir_all(&work_dir)?;
+      return Ok(());
     }
-    Ok(())
-  }
-
-  fn clean_all_experiments(&self) -> Result<()> {
-    let mut total = 0u64;
-    let mut count = 0;
-    if self.dir.exists() {
-      for entry in fs::read_dir(&self.dir)? {
-        let e = entry?;
-        if e.file_name().to_str().unwrap().starts_with("slate_benchmark-") {
-          let path = e.path();
-          let size = file_size(&path);
-          println!("Removing: {} ({} bytes)", path.display(), size);
-          if e.file_type()?.is_dir() {
-            fs::remove_dir_all(&path)?;
-          } else if e.file_type()?.is_file() {
-            fs::remove_file(&path)?;
-          }
-          total += size;
-          count += 1;
+    Some(Command::SharedReader {
+      path,
+      n,
+      count,
+      seed,
+      clock: source,
+      append_dataset,
+      append_dataset_format,
+      append_dataset_blake3,
+    }) => {
+      clock::set_clock(*source)?;
+      if let Some(dataset) = append_dataset {
+        let dataset = Dataset::load(dataset, *append_dataset_format)?;
+        if let Some(expected) = append_dataset_blake3.as_deref().filter(|hash| *hash != dataset.fingerprint()) {
+          let msg = format!("the dataset has changed from blake3 {expected} to {}", dataset.fingerprint());
+          return Err(BenchError::Validation(msg));
         }
+        payload::set_dataset(dataset);
       }
+      read_shared(path, *n, *count, *seed)?;
+      return Ok(());
     }
-    eprintln!("{count} files are removed, total {total} bytes");
-    Ok(())
-  }
-
-  fn run_testunit_append<C: AppendCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
-    self
-      .case()?
-      .division(10)
-      .min_trials(2)
-      .max_trials(10)
-      .measure_the_append_time_relative_to_the_data_amount(cut, ds)?;
-    Ok(self)
-  }
-
-  fn run_testunit_biased_get<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
-    self.case()?.max_trials(500).measure_the_frequency_of_retrieval_against_positions_by_zipf(cut, ds)?;
-    Ok(self)
-  }
-
-  fn run_testunit_uniformed_get<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
-    self
-      .case()?
-      .division(100)
-      .scale(Scale::WorstCase)
-      .max_trials(500)
-      .measure_the_retrieval_time_relative_to_the_position(cut, "get", 0, ds)?;
-    Ok(self)
-  }
-
-  fn run_testunit_cache_level<C: GetCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
-    for level in 0..=3 {
-      self
-        .case()?
-        .division(64)
-        .scale(Scale::WorstCase)
-        .max_trials(1000)
-        .measure_the_retrieval_time_relative_to_the_position(cut, &format!("cache{level}"), level, ds)?;
+    Some(Command::Run { .. }) | None => (),
+  }
+  if args.list {
+    for registration in registry(args.data_size).registrations() {
+      println!("{}: {:?}", registration.name, registration.units);
     }
-    Ok(self)
+    return Ok(());
   }
-
-  fn run_testunit_prove<C: ProveCUT>(&self, cut: &mut C, ds: &DataSize) -> Result<&Experiment> {
-    self.case()?.scale(Scale::WorstCase).measure_the_prove_time_relative_to_the_position(cut, ds)?;
-    Ok(self)
+  // 長時間のセッションの終了後に失敗しないよう、アップロード先は最初に検証する
+  let upload = args.upload.as_deref().filter(|_| args.isolated_cut.is_none()).map(Destination::parse).transpose()?;
+  if args.data_size_large <= args.data_size {
+    let msg = format!("the small data size {} is larger than large data size {}", args.data_size, args.data_size_large);
+    return Err(BenchError::Config(msg));
   }
-}
+  println!("Data size (small): {}", args.data_size);
+  println!("Data size (large): {}", args.data_size_large);
 
-macro_rules! property_decl {
-  ($name:ident, $type:ident) => {
-    pub fn $name(mut self, $name: $type) -> Self {
-      self.$name = $name;
-      self
+  // 作業ディレクトリ作成
+  let mut dirs = parse_dirs(&args.dirs)?;
+  if args.tmpfs {
+    if dirs.len() != 1 {
+      return Err(BenchError::Config(String::from("--tmpfs compares only one --dir of the disk with tmpfs")));
+    } else if dirs[0].0 == TMPFS_LABEL || !Path::new(SHM_DIR).is_dir() {
+      let msg = format!("--tmpfs requires {SHM_DIR} and a --dir labeled other than {TMPFS_LABEL}");
+      return Err(BenchError::Config(msg));
     }
+    dirs.push((String::from(TMPFS_LABEL), PathBuf::from(SHM_DIR)));
+  }
+  for (label, dir) in dirs.iter() {
+    fs::create_dir_all(dir)?;
+    println!("Working directory: {dir:?} ({label})");
+  }
+  let root = dirs[0].1.clone();
+
+  let quiesce =
+    Quiesce { action: args.quiesce, sleep: Duration::from_millis(args.quiesce_sleep), interval: args.quiesce_interval };
+  quiesce.check()?;
+  let csv_format = CsvFormat { delimiter: args.csv_delimiter, decimal_comma: args.decimal_comma };
+  report::set_csv_format(csv_format)?;
+  if !(args.soak_hours >= 0.0 && args.soak_interval > 0.0) {
+    return Err(BenchError::Config(String::from("--soak-hours must be >= 0 and --soak-interval must be > 0")));
+  }
+  if args.batch_min_time.is_some_and(|min_time| !(min_time > 0.0)) {
+    return Err(BenchError::Config(String::from("--batch-min-time must be > 0")));
+  }
+  let knobs = Knobs::new(&args.knobs)?;
+  if let Some(path) = &args.append_dataset {
+    let dataset = Dataset::load(path, args.append_dataset_format)?;
+    let (records, bytes) = (dataset.records(), dataset.bytes());
+    println!(
+      "Dataset: {} ({records} records, {bytes} bytes, blake3 {})",
+      path.to_string_lossy(),
+      dataset.fingerprint()
+    );
+    payload::set_dataset(dataset);
+  }
+  let scenarios = if args.scenarios.is_empty() {
+    Vec::new()
+  } else {
+    let config = Config::load(&args.scenario_config)?;
+    args.scenarios.iter().map(|name| config.scenario(name)).collect::<Result<Vec<_>>>()?
   };
-}
-
-impl Case {
-  property_decl!(division, usize);
-  property_decl!(scale, Scale);
-  property_decl!(cv_threshold, f64);
-  property_decl!(min_trials, usize);
-  property_decl!(max_trials, usize);
-  property_decl!(max_duration, Duration);
-
-  pub fn file(&self, id: &str, filename: &str) -> PathBuf {
-    self.dir_work(id).join(filename)
+  let raw_samples =
+    args.raw_samples.then_some(RawOutput { format: args.raw_format, compression: args.raw_compression });
+  let experiment = Experiment::new(&args.session, &root, &PathBuf::from(&args.output))?
+    .layout(args.layout)
+    .profile(args.profile)
+    .perf(args.perf)
+    .syscalls(args.syscalls)
+    .raw_samples(raw_samples)
+    .energy(args.energy)
+    .discard_warmup(args.discard_warmup)
+    .max_samples(args.max_samples.map(|n| n as usize))
+    .handle_mode(args.get_handle)
+    .batch_min_time(args.batch_min_time.map(|us| Duration::from_secs_f64(us / 1000.0 / 1000.0)))
+    .soak(Soak {
+      duration: Duration::from_secs_f64(args.soak_hours * 60.0 * 60.0),
+      interval: Duration::from_secs_f64(args.soak_interval * 60.0),
+    })
+    .workdir_quota(args.workdir_quota.map(|gb| (gb * 1000.0 * 1000.0 * 1000.0) as u64))
+    .variance_components(args.variance_components)
+    .abort_unreachable(args.abort_unreachable)
+    .quiesce(quiesce)
+    .replay_trace(args.replay_trace.clone())
+    .scenarios(scenarios)
+    .max_duration(Duration::from_secs(args.timeout));
+  experiment.check()?;
+
+  if args.energy {
+    energy::check()?;
+  }
+  if let Some(raw_samples) = raw_samples {
+    raw_samples.check()?;
+  }
+  cpu::check_frequency_scaling();
+  if args.monitor_cpu {
+    cpu::start_monitor();
   }
 
-  pub fn name(&self, id: &str) -> String {
-    format!("{}-{id}", self.session)
+  // スケジューラによるコア間の移動は計測のばらつきとなり、CV の基準を満たすための試行を増やす
+  if let Some(core) = args.pin {
+    // 出力スレッドは計測スレッドのアフィニティを継承しないよう、固定する前に別のコアで開始する
+    affinity::pin_writer_thread(core, &args.pin_prepare)?;
+    affinity::pin_current(core)?;
+  }
+  if !args.pin_prepare.is_empty() {
+    affinity::pin_prepare_threads(&args.pin_prepare)?;
   }
 
-  pub fn dir_work(&self, id: &str) -> PathBuf {
-    let dir_work = self.dir.join(format!("slate_benchmark-{}", self.name(id)));
-    if !dir_work.exists() {
-      fs::create_dir_all(&dir_work).unwrap();
-    }
-    dir_work
-  }
-
-  fn gauge(&self, n: Index) -> Vec<u64> {
-    let gauge = match self.scale {
-      Scale::Linear => linspace(1, n, self.division),
-      Scale::Log => logspace(1, n, self.division),
-      Scale::BestCase => {
-        let (_, ll) = entry_access_distance_limits(n);
-        ll.into_iter()
-          .enumerate()
-          .flat_map(|(d, range)| range.filter(move |k| entry_access_distance(*k, n).unwrap() == d as u8))
-          .collect::<Vec<_>>()
-      }
-      Scale::WorstCase => {
-        let (ul, _) = entry_access_distance_limits(n);
-        ul.into_iter()
-          .enumerate()
-          .flat_map(|(d, range)| range.filter(move |k| entry_access_distance(*k, n).unwrap() == d as u8))
-          .collect::<Vec<_>>()
-      }
+  if args.clean {
+    let policy = CleanPolicy {
+      older_than: args.older_than.map(|days| Duration::from_secs_f64(days * 24.0 * 60.0 * 60.0)),
+      max_total_size: args.max_total_size.map(|gb| (gb * 1000.0 * 1000.0 * 1000.0) as u64),
     };
-    // remove duplicates
-    let mut seen = HashSet::new();
-    gauge.into_iter().filter(|x| seen.insert(*x)).collect::<Vec<_>>()
-  }
-
-  /// データ量に対する追記時間を計測します。
-  pub fn measure_the_append_time_relative_to_the_data_amount<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
-  where
-    CUT: AppendCUT,
-  {
-    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
-    println!("=== Append Benchmark ({}) ===\n", cut.implementation());
-
-    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
-    ExpirationTimer::heading_ms();
-
-    let mut space_complexity = stat::XYReport::new(stat::Unit::Bytes);
-    let mut time_complexity = stat::XYReport::new(stat::Unit::Milliseconds);
-    let gauge = self.gauge(ds.size());
-    for trials in 0..self.max_trials {
-      cut.clear()?;
-      let mut cum_time = Duration::ZERO;
-      for n in gauge.iter() {
-        let (size, time) = cut.append(*n, splitmix64)?;
-        if trials == 0 {
-          space_complexity.add(n, size);
-        }
-        cum_time += time;
-        time_complexity.add(n, cum_time.as_nanos() as f64 / 1000.0 / 1000.0);
-      }
-
-      if trials + 1 >= self.min_trials && filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold).is_empty() {
-        let s = time_complexity.calculate(&ds.size()).unwrap();
-        timer.summary_ms(ds.size(), s.mean, s.std_dev);
-        break;
-      }
-      if timer.expired() {
-        let s = time_complexity.calculate(&ds.size()).unwrap();
-        timer.summary_ms(ds.size(), s.mean, s.std_dev);
-        println!("** TIMED OUT **");
-        break;
-      }
-      if timer.carried_out(1) {
-        let s = time_complexity.calculate(&ds.size()).unwrap();
-        timer.summary_ms(ds.size(), s.mean, s.std_dev);
-      }
+    for (label, dir) in dirs.iter() {
+      experiment.on_device(label, dir)?.clean_experiments(&policy)?;
     }
-
-    // write report
-    let name = format!("{}-volume{}-{}", self.session, ds.file_id(), cut.implementation());
-    let path = self.dir_report.join(format!("{name}.csv"));
-    space_complexity.save_xy_to_csv(&path, "SIZE", "BYTES")?;
-    println!("==> The results have been saved in: {}", path.to_string_lossy());
-    let name = format!("{}-append{}-{}", self.session, ds.file_id(), cut.implementation());
-    let path = self.dir_report.join(format!("{name}.csv"));
-    time_complexity.save_xy_to_csv(&path, "SIZE", "MILLISECONDS")?;
-    println!("==> The results have been saved in: {}", path.to_string_lossy());
-    Ok(self)
-  }
-
-  /// アクセス位置に対するデータ取得時間を計測します。
-  pub fn measure_the_retrieval_time_relative_to_the_position<CUT>(
-    self,
-    cut: &mut CUT,
-    action_id: &str,
-    cache_level: usize,
-    ds: &DataSize,
-  ) -> Result<Self>
-  where
-    CUT: GetCUT,
-  {
-    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
-    println!("=== Get Benchmark ({}) ===", cut.implementation());
-
-    // データベースを作成
-    let pb = create_progress_bar(ds.size());
-    cut.prepare(ds.size(), splitmix64, |i| pb.inc(i))?;
-    pb.finish();
-
-    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
-    ExpirationTimer::heading_max_cv();
-
-    let mut time_complexity = stat::XYReport::new(stat::Unit::Milliseconds);
-    let mut rng = rand::rng();
-    let mut gauge = self.gauge(ds.size());
-    cut.set_cache_level(cache_level)?;
-    'trials: for trials in 0..self.max_trials {
-      gauge.shuffle(&mut rng);
-      for i in gauge.iter() {
-        let duration = cut.get(*i, splitmix64)?;
-        time_complexity.add(i, duration.as_nanos() as f64 / 1000.0 / 1000.0);
-
-        if timer.expired() {
-          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
-          println!("** TIMED OUT **");
-          break 'trials;
-        }
-      }
-
-      if trials + 1 >= self.min_trials {
-        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
-        if gauge.is_empty() {
-          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
-          break;
-        }
-      }
-      if timer.carried_out(1) {
-        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
-      }
+    // 再利用するデータベースのキャッシュは、保持する条件が指定された場合のみ整理する
+    if let Some(dir) =
+      args.dataset_cache.as_deref().filter(|_| args.older_than.is_some() || args.max_total_size.is_some())
+    {
+      DatasetCache::new(dir)?.clean(&policy)?;
     }
+    return Ok(());
+  }
 
-    // write report
-    let id = format!("{action_id}{}-{}", ds.file_id(), cut.implementation());
-    let path = self.dir_report.join(format!("{}.csv", self.name(&id)));
-    time_complexity.save_xy_to_csv(&path, "DISTANCE", "ACCESS TIME")?;
-    println!("==> The results have been saved in: {}", path.to_string_lossy());
-    Ok(self)
-  }
-
-  /// Zipf 分布に従うアクセス位置に対するデータ取得時間の頻度を計測します。
-  pub fn measure_the_frequency_of_retrieval_against_positions_by_zipf<CUT>(
-    self,
-    cut: &mut CUT,
-    ds: &DataSize,
-  ) -> Result<Self>
-  where
-    CUT: GetCUT,
-  {
-    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
-    println!("=== Zipf Get Benchmark ({}) ===", cut.implementation());
-
-    // データベースを作成
-    let pb = create_progress_bar(ds.size());
-    cut.prepare(ds.size(), splitmix64, |i| pb.inc(i))?;
-    pb.finish();
-
-    let mut position_frequency = XYReport::new(Unit::Bytes);
-    let mut time_frequency = XYReport::new(Unit::Milliseconds);
-    cut.set_cache_level(0)?;
-    for s in [0.5, 1.2, 1.5, 2.0] {
-      let x_label = format!("{s:.1}");
-      println!("\nShape = {x_label}");
-      let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
-      ExpirationTimer::heading_ms();
-
-      let mut sampler = ZipfSampler::new(100, s, ds.size() - 1);
-      for _ in 0..self.max_trials {
-        let position = sampler.next_u64();
-        let d = cut.get(position, splitmix64)?;
-        time_frequency.add(&x_label, d.as_nanos() as f64 / 1000.0 / 1000.0);
-        position_frequency.add(&x_label, position);
-
-        if timer.expired() {
-          let s = time_frequency.calculate(&x_label).unwrap();
-          timer.summary_ms(ds.size(), s.mean, s.std_dev);
-          println!("** TIMED OUT **");
-          break;
-        }
-        if timer.carried_out(1) {
-          let s = time_frequency.calculate(&x_label).unwrap();
-          timer.summary_ms(ds.size(), s.mean, s.std_dev);
-        }
+  // 子プロセスは親プロセスが確保したセッション ID を使用する
+  let experiment =
+    if args.isolated_cut.is_none() { experiment.reserve_session(args.on_existing_report)? } else { experiment };
+  println!("Session: {}", experiment.session());
+  println!("slate: {} ({})", report::SLATE_VERSION, report::SLATE_COMMIT.unwrap_or("-"));
+  if args.isolated_cut.is_none() {
+    experiment.set_metadata(&report::slate_metadata())?;
+    experiment.set_metadata(&csv_format.metadata())?;
+  }
+  let clock_cost = ClockCost::measure(100_000);
+  println!(
+    "Clock: {} (overhead {:.1}ns, resolution {}ns, empty section {}ns{})",
+    args.clock.name(),
+    clock_cost.overhead.as_secs_f64() * 1e9,
+    clock_cost.resolution.as_nanos(),
+    clock_cost.empty_section.as_nanos(),
+    if args.subtract_overhead { " subtracted from the measurements" } else { "" }
+  );
+  if args.subtract_overhead {
+    clock::set_overhead_subtraction(clock_cost.empty_section);
+  }
+  if args.isolated_cut.is_none() {
+    experiment.set_metadata(&clock_cost.metadata())?;
+  }
+  if !args.tags.is_empty() && args.isolated_cut.is_none() {
+    let tags = args.tags.iter().map(|(key, value)| (format!("tag.{key}"), value.clone())).collect::<Vec<_>>();
+    experiment.set_metadata(&tags)?;
+  }
+  if !knobs.is_empty() && args.isolated_cut.is_none() {
+    experiment.set_metadata(&knobs.metadata())?;
+  }
+  if let Some(dataset) = payload::dataset().filter(|_| args.isolated_cut.is_none()) {
+    experiment.set_metadata(&dataset.metadata())?;
+  }
+  let notifier = args.notify_url.as_deref().filter(|_| args.isolated_cut.is_none());
+  let notifier = notifier.map(|url| Notifier::new(url, experiment.session()));
+
+  // エラーやパニックで中断された場合も、子プロセスを含めてセッションの終了時に作業ディレクトリを削除する
+  let work_dir = RemoveOnDrop::new(experiment.work_dir()?);
+  let dir = work_dir.path().to_path_buf();
+  // 複数のディレクトリが指定された場合、ファイルを使用する CUT はディレクトリごとのセッションで実行する
+  let devices = if dirs.len() > 1 {
+    let devices = dirs.iter().map(|(label, dir)| {
+      let device = experiment.on_device(label, dir)?;
+      if args.isolated_cut.is_none() {
+        device.set_metadata(&csv_format.metadata())?;
       }
+      let work_dir = RemoveOnDrop::new(device.work_dir()?);
+      Ok((label.clone(), device, work_dir))
+    });
+    devices.collect::<Result<Vec<_>>>()?
+  } else {
+    Vec::new()
+  };
+  if args.calibrate && args.isolated_cut.is_none() {
+    let targets = if devices.is_empty() {
+      vec![(None, dir.as_path())]
+    } else {
+      devices.iter().map(|(label, _, work_dir)| (Some(label.as_str()), work_dir.path())).collect()
+    };
+    for (label, dir) in targets {
+      let calibration = Calibration::run(dir)?;
+      println!(
+        "Calibration{}: sequential write {:.1} MB/s, random 4KiB read {:.0} IOPS, fsync {:.3} ms",
+        label.map(|label| format!(" ({label})")).unwrap_or_default(),
+        calibration.sequential_write,
+        calibration.random_read,
+        calibration.fsync_latency
+      );
+      experiment.set_metadata(&calibration.metadata(label))?;
     }
-
-    // write report
-    let id = format!("biased-get{}-{}", ds.file_id(), cut.implementation());
-    let path = self.dir_report.join(format!("{}_x.csv", self.name(&id)));
-    position_frequency.save_xy_to_csv(&path, "ZIPF", "POSITION")?;
-    println!("==> The results have been saved in: {}", path.to_string_lossy());
-    let path = self.dir_report.join(format!("{}_y.csv", self.name(&id)));
-    time_frequency.save_xy_to_csv(&path, "ZIPF", "MILLISECONDS")?;
-    println!("==> The results have been saved in: {}", path.to_string_lossy());
-    Ok(self)
-  }
-
-  // データ差異の位置に対する差分検出時間を計測します。
-  fn measure_the_prove_time_relative_to_the_position<CUT>(self, cut: &mut CUT, ds: &DataSize) -> Result<Self>
-  where
-    CUT: ProveCUT,
-  {
-    println!("\n{}", Local::now().format("%Y-%m-%d %H:%M:%S %Z"));
-    println!("=== Prove Benchmark ({}) ===", cut.implementation());
-    let mut gauge = self.gauge(ds.size());
-
-    println!("Preparing {} databases each with a different for location...", gauge.len() + 1);
-    let pb = create_progress_bar((1 + gauge.len()) as u64 * ds.size());
-    cut.prepare(ds.size(), splitmix64, |i| pb.inc(i))?;
-    pb.reset_elapsed();
-    let (mut errs, targets): (Vec<Error>, Vec<_>) = gauge
+  }
+  let cpu_report = experiment.report_path(&match &args.isolated_cut {
+    Some(cut) => format!("cpu-{cut}.csv"),
+    None => String::from("cpu.csv"),
+  });
+  if args.monitor_cpu {
+    // パニックで中断されてもそれまでのサンプルを残す
+    let (path, default_hook) = (cpu_report.clone(), panic::take_hook());
+    panic::set_hook(Box::new(move |info| {
+      default_hook(info);
+      if let Err(err) = cpu::save_samples(&path) {
+        eprintln!("WARN: fail to save the CPU samples: {err}");
+      }
+    }));
+  }
+  // CUT ごとに子プロセスを起動する場合は、計測を行う子プロセスがそれぞれダッシュボードを提供する
+  let isolate = (args.isolate || args.memory_max.is_some()) && args.isolated_cut.is_none();
+  if isolate && args.order == Order::Interleaved {
+    let msg =
+      "--order interleaved runs the CUTs side by side in a process, which --isolate and --memory-max don't allow";
+    return Err(BenchError::Config(String::from(msg)));
+  }
+  if let Some(addr) = args.dashboard.as_deref().filter(|_| !isolate) {
+    dashboard::start(addr)?;
+  }
+  #[cfg(feature = "tui")]
+  let tui = if !isolate && !args.no_tui && std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+    let log = experiment.report_path("stdout.log");
+    Some((slate_benchmark::tui::Tui::start(&log)?, log))
+  } else {
+    None
+  };
+  // トレースが与えられない場合、Replay と Workload はすべてのテストユニットの選択に含めない。シナリオが与えられない
+  // 場合の Scenario と、--count-storage が指定されない場合の Amplification も同様。長時間を要する Soak は明示的に指定
+  // された場合のみ実行する
+  let traced = [TestUnit::Replay, TestUnit::Workload];
+  let scenario = |unit: &TestUnit| *unit != TestUnit::Scenario || !args.scenarios.is_empty();
+  let counted = |unit: &TestUnit| *unit != TestUnit::Amplification || args.count_storage;
+  let units = match (&args.replay_trace, args.units.is_empty()) {
+    (trace, true) => TestUnit::value_variants()
       .iter()
       .copied()
-      .map(|i| (i, cut.alternate()))
-      .par_bridge()
-      .map(|(i, alt)| match alt {
-        Ok(mut alt) => {
-          alt.prepare(
-            ds.size(),
-            |k| {
-              let value = splitmix64(k);
-              if i == k { splitmix64(value) } else { value }
-            },
-            |_i| pb.inc(1),
-          )?;
-          Ok((i, alt))
-        }
-        Err(err) => Err(err),
-      })
-      .partition_map(|target| match target {
-        Ok(target) => Either::Right(target),
-        Err(err) => Either::Left(err),
-      });
-    pb.finish();
-    if !errs.is_empty() {
-      drop(targets);
-      for err in errs.iter() {
-        eprintln!("ERROR: {err:?}");
-      }
-      return Err(errs.pop().unwrap());
+      .filter(|unit| *unit != TestUnit::Soak && (trace.is_some() || !traced.contains(unit)) && scenario(unit))
+      .filter(counted)
+      .collect(),
+    (None, false) if args.units.iter().any(|unit| traced.contains(unit)) => {
+      return Err(BenchError::Config(String::from("--unit replay and workload require --replay-trace")));
     }
-    let cuts = targets.into_iter().collect::<HashMap<_, _>>();
-    println!("preparation completed\n");
-
-    let mut timer = ExpirationTimer::new(self.max_duration, 10, self.max_trials, 10);
-    ExpirationTimer::heading_max_cv();
-
-    let mut rng = rand::rng();
-    let mut time_complexity = stat::XYReport::new(stat::Unit::Milliseconds);
-    for trials in 0..self.max_trials {
-      gauge.shuffle(&mut rng);
-      for i in gauge.iter().cloned() {
-        let other = cuts.get(&i).unwrap();
-        let (result, elapse) = cut.prove(other)?;
-        assert_eq!(Some(i), result);
-        time_complexity.add(&(ds.size() - i + 1), elapse.as_nanos() as f64 / 1000.0 / 1000.0);
-      }
-
-      if trials + 1 >= self.min_trials {
-        gauge = filter_cv_sufficient(&gauge, &time_complexity, self.cv_threshold);
-        if gauge.is_empty() {
-          timer.summary_max_cv(ds.size(), time_complexity.max_cv());
-          break;
-        }
-      }
-      if timer.expired() {
-        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
-        println!("** TIMED OUT **");
-        break;
-      }
-      if timer.carried_out(1) {
-        timer.summary_max_cv(ds.size(), time_complexity.max_cv());
-      }
+    (_, false) if !args.units.iter().all(scenario) => {
+      return Err(BenchError::Config(String::from("--unit scenario requires --scenario")));
     }
-
-    // write report
-    let id = format!("prove{}-{}", ds.file_id(), cut.implementation());
-    let path = self.dir_report.join(format!("{}.csv", self.name(&id)));
-    time_complexity.save_xy_to_csv(&path, "DISTANCE", "DETECT TIME")?;
-    println!("==> The results have been saved in: {}", path.to_string_lossy());
-    Ok(self)
+    (_, false) if !args.units.iter().all(counted) => {
+      return Err(BenchError::Config(String::from("--unit amplification requires --count-storage")));
+    }
+    _ => args.units.clone(),
+  };
+  let units = args.isolated_unit.map(|unit| vec![unit]).unwrap_or(units);
+  if let Some(path) = args.record_trace.as_ref().filter(|_| !isolate) {
+    // 子プロセスはそれぞれ CUT 名を付けたファイルに記録する
+    let path = match &args.isolated_cut {
+      Some(cut) => PathBuf::from(format!("{}-{cut}", path.to_string_lossy())),
+      None => path.clone(),
+    };
+    trace::start(&path)?;
   }
-}
-
-pub enum DataSize {
-  Large(u64),
-  Small(u64),
-}
+  let payload = args.append_dataset.is_some();
+  let filter = match &args.isolated_cut {
+    Some(name) => Filter { cuts: vec![name.clone()], units, exact: true, in_memory: None, payload },
+    None => Filter { cuts: args.cuts.clone(), units, exact: false, in_memory: None, payload },
+  };
 
-impl DataSize {
-  pub fn size(&self) -> u64 {
-    match self {
-      DataSize::Small(len) => *len,
-      DataSize::Large(len) => *len,
+  if filter.accepts_unit(TestUnit::Hash) && args.isolated_cut.is_none() {
+    experiment.run_testunit_hash::<Blake3>()?.run_testunit_hash::<Sha256>()?.run_testunit_hash::<Sha512_256>()?;
+  }
+  if filter.accepts_unit(TestUnit::HashCost) && args.isolated_cut.is_none() {
+    for (name, cost) in [
+      (Blake3::name(), HashCost::run::<Blake3>()),
+      (Sha256::name(), HashCost::run::<Sha256>()),
+      (Sha512_256::name(), HashCost::run::<Sha512_256>()),
+    ] {
+      println!("Hash cost ({name}): {:.1}[MB/s], combine {:.1}[nsec]", cost.throughput, cost.combine);
+      experiment.set_metadata(&cost.metadata(name))?;
     }
   }
-  pub fn file_id(&self) -> String {
-    match self {
-      DataSize::Small(_) => String::from(""),
-      DataSize::Large(_) => String::from("_large"),
+  if filter.accepts_unit(TestUnit::Serialization) && args.isolated_cut.is_none() {
+    experiment
+      .run_testunit_serialization("binarytree", |data| Ok(Node::new_leaf::<Blake3>(1, 1, data.to_vec())))?
+      .run_testunit_serialization("slate", entry_with_payload)?;
+  }
+
+  let env = |dir: &Path| Env {
+    dir: dir.to_path_buf(),
+    small: DataSize::Small(args.data_size),
+    large: DataSize::Large(args.data_size_large),
+    dataset_cache: args.dataset_cache.clone(),
+    validate: args.validate,
+    retry: Retry { attempts: args.retries, backoff: Duration::from_secs(args.retry_backoff) },
+    on_failure: args.on_failure,
+    knobs: knobs.clone(),
+  };
+  let seed = args.order_seed.unwrap_or_else(|| Local::now().timestamp_nanos_opt().unwrap_or_default() as u64);
+  let schedule = Schedule { order: args.order, seed };
+  if args.order != Order::Registered && args.isolated_cut.is_none() {
+    let order = args.order.to_possible_value().unwrap().get_name().to_string();
+    println!("Order: {order} (seed {seed})");
+    experiment.set_metadata(&[(String::from("order"), order), (String::from("order.seed"), seed.to_string())])?;
+  }
+  if args.get_handle != HandleMode::Persistent && args.isolated_cut.is_none() {
+    let mode = args.get_handle.to_possible_value().unwrap().get_name().to_string();
+    experiment.set_metadata(&[(String::from("get-handle"), mode)])?;
+  }
+  let registry = registry(args.data_size);
+  if filter.payload && args.isolated_cut.is_none() {
+    // データセットのレコードを追加しない CUT は、生成した値での結果と混ざらないよう実行しない
+    let all = Filter { payload: false, ..filter.clone() };
+    let skipped = registry.registrations().iter().filter(|r| !r.payload && all.accepts(r));
+    for registration in skipped {
+      println!("SKIP: {} doesn't append the records of --append-dataset", registration.name);
     }
   }
-}
+  let failures = if isolate {
+    let mut child_args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    child_args.extend(["--session".into(), experiment.session().into()]);
+    let memory_limit = args.memory_max.as_deref().map(MemoryLimit::new).transpose()?;
+    registry.run_isolated(&experiment, &filter, &schedule, &child_args, memory_limit.as_ref())?
+  } else if devices.is_empty() {
+    registry.run(&experiment, &env(&dir), &filter, &schedule)
+  } else {
+    let in_memory = Filter { in_memory: Some(true), ..filter.clone() };
+    let mut failures = registry.run(&experiment, &env(&dir), &in_memory, &schedule);
+    for (label, device, work_dir) in devices.iter() {
+      let file_backed = Filter { in_memory: Some(false), ..filter.clone() };
+      let device_failures = registry.run(device, &env(work_dir.path()), &file_backed, &schedule);
+      failures.extend(device_failures.into_iter().map(|f| Failure { cut: format!("{}@{label}", f.cut), ..f }));
+    }
+    failures
+  };
 
-fn filter_cv_sufficient(gauge: &[u64], ss: &stat::XYReport<u64, f64>, cv: f64) -> Vec<u64> {
-  gauge.iter().filter(|i| !ss.is_cv_sufficient(**i, cv)).cloned().collect::<Vec<_>>()
-}
+  trace::finish()?;
+  if args.monitor_cpu {
+    cpu::save_samples(&cpu_report)?;
+    experiment.set_metadata(&cpu::metadata(args.isolated_cut.as_deref()))?;
+    println!("==> The CPU frequency and temperature have been saved in: {}", cpu_report.to_string_lossy());
+  }
 
-// プログレスバーの準備
-fn create_progress_bar(n: u64) -> ProgressBar {
-  let pb = ProgressBar::with_draw_target(Some(n), ProgressDrawTarget::stdout_with_hz(1));
-  pb.set_style(
-    ProgressStyle::default_bar()
-      .template("Preparing: {spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
-      .unwrap()
-      .progress_chars("#>-"),
-  );
-  if pb.is_hidden() {
-    println!("(progress bar is hidden)");
+  #[cfg(feature = "tui")]
+  if let Some((tui, log)) = tui {
+    drop(tui);
+    println!("==> The output has been saved in: {}", log.to_string_lossy());
   }
-  pb
-}
 
-// Component under Test.
+  if args.tmpfs && args.isolated_cut.is_none() {
+    let (disk, tmpfs) = (&devices[0].1, &devices[1].1);
+    for path in report::write_io_ratios(disk, tmpfs, &experiment)? {
+      println!("==> The ratio of the disk to tmpfs has been saved in: {}", path.to_string_lossy());
+    }
+  }
 
-pub trait CUT {
-  fn implementation(&self) -> String;
-}
+  if args.isolated_cut.is_none() {
+    for session in devices.iter().map(|(_, device, _)| device).chain([&experiment]) {
+      if let Some(path) = session.write_manifest()? {
+        println!("==> The manifest of the session has been saved in: {}", path.to_string_lossy());
+      }
+    }
+  }
 
-pub trait GetCUT: CUT {
-  fn set_cache_level(&mut self, cache_size: usize) -> Result<()>;
-  fn prepare<V: Fn(u64) -> u64, F: Fn(Index)>(&mut self, n: Index, values: V, progress: F) -> Result<()>;
-  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Duration>;
-}
+  // CUT が失敗した場合も、それ以外のレポートをアップロードする
+  if let Some(upload) = &upload {
+    let sessions = [&experiment].into_iter().chain(devices.iter().map(|(_, device, _)| device)).collect::<Vec<_>>();
+    let count = upload.upload(&sessions)?;
+    println!("==> {count} files have been uploaded to: {}", args.upload.as_deref().unwrap_or_default());
+  }
 
-pub trait AppendCUT: CUT {
-  /// ## Returns
-  /// - (storage size, duration)
-  fn append<V: Fn(u64) -> u64>(&mut self, n: Index, values: V) -> Result<(u64, Duration)>;
-  fn clear(&mut self) -> Result<()>;
-}
+  if let Some(notifier) = notifier {
+    let cuts = registry.registrations().iter().filter(|r| filter.accepts(r)).flat_map(|r| {
+      if devices.is_empty() || r.in_memory {
+        vec![r.name.clone()]
+      } else {
+        devices.iter().map(|(label, _, _)| format!("{}@{label}", r.name)).collect()
+      }
+    });
+    let regressions = match &args.notify_baseline {
+      Some(baseline) => {
+        let baseline = Experiment::new(baseline, &root, &PathBuf::from(&args.output))?.layout(args.layout);
+        report::regressions(&experiment, &baseline, REGRESSION_THRESHOLD).unwrap_or_else(|err| {
+          eprintln!("WARN: fail to compare the reports with the baseline session: {err}");
+          Vec::new()
+        })
+      }
+      None => Vec::new(),
+    };
+    notifier.finish(&cuts.collect::<Vec<_>>(), &failures, &regressions);
+  }
 
-pub trait ProveCUT: GetCUT + Sync + Send {
-  fn prove(&self, other: &Self) -> Result<(Option<u64>, Duration)>;
-  fn alternate(&self) -> Result<Self>
-  where
-    Self: std::marker::Sized;
+  writer::flush();
+  // exit はデストラクタを実行しないため、その前に作業ディレクトリを削除する
+  drop(devices);
+  drop(work_dir);
+  if !failures.is_empty() {
+    let cuts = failures.iter().map(|f| f.cut.as_str()).collect::<BTreeSet<_>>();
+    eprintln!("ERROR: {} failures in {} CUTs, the reports of the others have been saved", failures.len(), cuts.len());
+    for failure in failures.iter() {
+      eprintln!("  {failure}");
+    }
+    std::process::exit(1);
+  }
+  Ok(())
 }
 
-pub trait IntoFloat: Copy {
-  fn into_f64(self) -> f64;
+/// `KEY=VALUE` 形式のタグを解析する。
+fn parse_tag(tag: &str) -> std::result::Result<(String, String), String> {
+  match tag.split_once('=') {
+    Some((key, value)) if !key.is_empty() && !key.contains(char::is_whitespace) && !value.contains('\n') => {
+      Ok((String::from(key), String::from(value)))
+    }
+    _ => Err(format!("the tag must be KEY=VALUE: {tag}")),
+  }
 }
 
-impl IntoFloat for u64 {
-  fn into_f64(self) -> f64 {
-    self as f64
+/// 正の有限の数値を解析する。負数や NaN が整数への変換で 0 などにならないようにする。
+fn parse_positive(value: &str) -> std::result::Result<f64, String> {
+  match value.parse::<f64>() {
+    Ok(x) if x.is_finite() && x > 0.0 => Ok(x),
+    _ => Err(format!("the value must be a positive number: {value}")),
   }
 }
 
-impl IntoFloat for f64 {
-  fn into_f64(self) -> f64 {
-    self
+/// 0 以上の有限の数値を解析する。負数や NaN が期間やサイズへの変換でパニックや 0 にならないようにする。
+fn parse_non_negative(value: &str) -> std::result::Result<f64, String> {
+  match value.parse::<f64>() {
+    Ok(x) if x.is_finite() && x >= 0.0 => Ok(x),
+    _ => Err(format!("the value must be a non-negative number: {value}")),
   }
 }
 
-fn linspace(min: u64, max: u64, n: usize) -> Vec<u64> {
-  assert!(n > 1);
-  let step = (max - min) as f64 / (n - 1) as f64;
-  (0..n)
-    .map(|i| {
-      let val = min as f64 + step * i as f64;
-      val.round() as u64
-    })
-    .collect()
+/// --tmpfs でディスクと比較する tmpfs のディレクトリとそのラベル
+const SHM_DIR: &str = "/dev/shm";
+const TMPFS_LABEL: &str = "tmpfs";
+
+/// `[LABEL=]DIR` 形式の作業ディレクトリを解析する。LABEL はレポートのセッション名に使用されるため重複してはならない。
+fn parse_dirs(dirs: &[String]) -> Result<Vec<(String, PathBuf)>> {
+  let mut parsed = Vec::<(String, PathBuf)>::new();
+  for dir in dirs.iter() {
+    let (label, dir) = match dir.split_once('=') {
+      Some((label, dir)) => (String::from(label), PathBuf::from(dir)),
+      None => {
+        let label = PathBuf::from(dir).file_name().map(|name| name.to_string_lossy().into_owned());
+        (label.unwrap_or(String::from("root")), PathBuf::from(dir))
+      }
+    };
+    if parsed.iter().any(|(l, _)| *l == label) {
+      return Err(BenchError::Config(format!("the label {label} of --dir is duplicated, specify it as LABEL=DIR")));
+    }
+    parsed.push((label, dir));
+  }
+  Ok(parsed)
 }
 
-fn logspace(min: u64, max: u64, n: usize) -> Vec<u64> {
-  assert!(min > 0, "min must be positive for logspace");
-  assert!(n > 1);
-  let log_min = (min as f64).ln();
-  let log_max = (max as f64).ln();
-  let step = (log_max - log_min) / (n - 1) as f64;
-  (0..n)
-    .map(|i| {
-      let val = (log_min + step * i as f64).exp();
-      val.round() as u64
-    })
-    .collect()
+/// ベンチマーク対象のすべての CUT を登録する。
+fn registry(data_size: u64) -> Registry {
+  let mut registry = Registry::default();
+  register!(
+    registry,
+    "slate-file",
+    payload,
+    |env| SlateCUT::new(FileFactory::new(&env.dir))?,
+    [
+      Append(small),
+      AppendLevels(small),
+      BiasedGet(small),
+      UniformedGet(small),
+      Replay(small),
+      Workload(small),
+      CacheLevel(small),
+      HotSetGet(small),
+      RangeScan(small),
+      ConcurrentGet(small),
+      MultiProcessGet(small),
+      Prove(small),
+      ProofServing(small),
+      RootHash(small),
+      Scenario(small),
+      Soak(small),
+      BiasedGet(large),
+      UniformedGet(large),
+      CacheLevel(large),
+    ]
+  );
+  register!(
+    registry,
+    "slate-file-preallocated",
+    payload,
+    |env| SlateCUT::new(FileFactory::preallocated(&env.dir, data_size)?)?,
+    [Append(small)]
+  );
+  register!(
+    registry,
+    "slate-memkvs",
+    in_memory,
+    payload,
+    |env| SlateCUT::new(memkvs_factory(env.knobs.get("memkvs.capacity").unwrap_or(data_size) as usize))?,
+    [
+      Append(small),
+      AppendLevels(small),
+      BiasedGet(small),
+      UniformedGet(small),
+      Replay(small),
+      Workload(small),
+      CacheLevel(small),
+      HotSetGet(small),
+      ProofServing(small),
+      RootHash(small),
+      Scenario(small),
+      Soak(small),
+    ]
+  );
+  #[cfg(feature = "rocksdb")]
+  register!(
+    registry,
+    "slate-rocksdb",
+    payload,
+    |env| SlateCUT::new(RocksDBFactory::new(&env.dir, &env.knobs))?,
+    [
+      Append(small),
+      AppendLevels(small),
+      BiasedGet(small),
+      UniformedGet(small),
+      Replay(small),
+      Workload(small),
+      CacheLevel(small),
+      HotSetGet(small),
+      ProofServing(small),
+      RootHash(small),
+      Scenario(small),
+      Soak(small),
+    ]
+  );
+  register!(
+    registry,
+    "slate-file-counted",
+    payload,
+    |env| SlateCUT::new(CountingFactory::new(FileFactory::new(&env.dir)))?,
+    [Amplification(small)]
+  );
+  register!(
+    registry,
+    "slate-memkvs-counted",
+    in_memory,
+    payload,
+    |env| SlateCUT::new(CountingFactory::new(memkvs_factory(
+      env.knobs.get("memkvs.capacity").unwrap_or(data_size) as usize
+    )))?,
+    [Amplification(small)]
+  );
+  #[cfg(feature = "rocksdb")]
+  register!(
+    registry,
+    "slate-rocksdb-counted",
+    payload,
+    |env| SlateCUT::new(CountingFactory::new(RocksDBFactory::new(&env.dir, &env.knobs)))?,
+    [Amplification(small)]
+  );
+  #[cfg(feature = "rocksdb")]
+  register!(
+    registry,
+    "dual-write-rocksdb",
+    payload,
+    |env| DualWriteRocksDBCUT::new(&env.dir, &env.knobs)?,
+    [DualWrite(small)]
+  );
+  register!(
+    registry,
+    "seqfile-file",
+    |env| SeqFileCUT::new(&env.dir)?,
+    [Append(small), BiasedGet(small), UniformedGet(small), Replay(small), Workload(small), CacheLevel(small)]
+  );
+  register!(
+    registry,
+    "seqfile-file-preallocated",
+    |env| SeqFileCUT::preallocated(&env.dir, data_size)?,
+    [Append(small)]
+  );
+  register!(
+    registry,
+    "seqfile-indexed-file",
+    |env| SeqFileCUT::indexed(&env.dir)?,
+    [BiasedGet(small), UniformedGet(small), Replay(small), Workload(small)]
+  );
+  register!(
+    registry,
+    "mmr-file",
+    |env| FileMmrCUT::<Blake3>::new(&env.dir)?,
+    [
+      Append(small),
+      AppendLevels(small),
+      BiasedGet(small),
+      UniformedGet(small),
+      Replay(small),
+      Workload(small),
+      CacheLevel(small),
+      HotSetGet(small),
+      RootHash(small),
+    ]
+  );
+  register!(
+    registry,
+    "hashtree-file",
+    |env| FileBinaryTreeCUT::<Blake3>::new(&env.dir, data_size)?.with_dataset_cache(env.dataset_cache()?),
+    [
+      BiasedGet(small),
+      UniformedGet(small),
+      Replay(small),
+      CacheLevel(small),
+      HotSetGet(small),
+      RangeScan(small),
+      ConcurrentGet(small),
+      RootHash(small),
+    ]
+  );
+  #[cfg(feature = "rocksdb")]
+  register!(
+    registry,
+    "hashtree-rocksdb",
+    |env| RocksDBBinaryTreeCUT::<Blake3>::new(&env.dir, data_size)?
+      .with_knobs(&env.knobs)
+      .with_dataset_cache(env.dataset_cache()?),
+    [BiasedGet(small), UniformedGet(small), Replay(small), CacheLevel(small), ConcurrentGet(small), RootHash(small)]
+  );
+  register!(
+    registry,
+    "hashtree-implicit",
+    |env| FileImplicitTreeCUT::<Blake3>::new(&env.dir, data_size)?.with_dataset_cache(env.dataset_cache()?),
+    [BiasedGet(small), UniformedGet(small), Replay(small), CacheLevel(small), RootHash(small)]
+  );
+  register!(
+    registry,
+    "hashtree-implicit-padded",
+    |env| FilePaddedImplicitTreeCUT::<Blake3>::new(&env.dir, data_size)?.with_dataset_cache(env.dataset_cache()?),
+    [BiasedGet(small), UniformedGet(small), Replay(small), CacheLevel(small), RootHash(small)]
+  );
+  register!(
+    registry,
+    "hashtree-implicit-blocked",
+    |env| FileBlockedImplicitTreeCUT::<Blake3>::new(&env.dir, data_size)?.with_dataset_cache(env.dataset_cache()?),
+    [BiasedGet(small), UniformedGet(small), Replay(small), CacheLevel(small), RootHash(small)]
+  );
+  register!(
+    registry,
+    "hashtree-leveled",
+    |env| FileLeveledTreeCUT::<Blake3>::new(&env.dir, data_size)?.with_dataset_cache(env.dataset_cache()?),
+    [BiasedGet(small), UniformedGet(small), Replay(small), CacheLevel(small), RootHash(small)]
+  );
+  register!(
+    registry,
+    "hashtree-file-sha256",
+    |env| FileBinaryTreeCUT::<Sha256>::new(&env.dir, data_size)?.with_dataset_cache(env.dataset_cache()?),
+    [UniformedGet(small), Replay(small)]
+  );
+  registry
 }
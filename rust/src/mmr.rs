@@ -0,0 +1,114 @@
+use std::fs::remove_file;
+use std::path::{Path, PathBuf};
+
+use slate::file::FileDevice;
+use slate::{BlockStorage, Index};
+use slate_benchmark::clock::Stopwatch;
+use slate_benchmark::ensure;
+use slate_benchmark::error::Result;
+use slate_benchmark::hashtree::HashTree;
+use slate_benchmark::hashtree::hash::{Blake3, HashAlgorithm};
+use slate_benchmark::hashtree::mmr::MerkleMountainRange;
+use slate_benchmark::{AppendCUT, CUT, GetCUT, HandleMode, Measurement, RootHashCUT, file_size, unique_file};
+
+pub struct FileMmrCUT<H: HashAlgorithm = Blake3> {
+  path: PathBuf,
+  mmr: Option<MerkleMountainRange<BlockStorage<FileDevice>, H>>,
+  handle_mode: HandleMode,
+}
+
+impl<H: HashAlgorithm> FileMmrCUT<H> {
+  pub fn new(dir: &Path) -> Result<Self> {
+    let path = unique_file(dir, "mmr-file", ".db");
+    let mmr = Some(MerkleMountainRange::create_on_file(&path)?);
+    Ok(Self { path, mmr, handle_mode: HandleMode::Persistent })
+  }
+}
+
+impl<H: HashAlgorithm> Drop for FileMmrCUT<H> {
+  fn drop(&mut self) {
+    drop(self.mmr.take());
+    if self.path.exists() {
+      if let Err(e) = remove_file(&self.path) {
+        eprintln!("WARN: fail to remove file {:?}: {}", self.path, e);
+      }
+    }
+  }
+}
+
+impl<H: HashAlgorithm> CUT for FileMmrCUT<H> {
+  fn implementation(&self) -> String {
+    if H::name() == Blake3::name() { String::from("mmr-file") } else { format!("mmr-file-{}", H::name()) }
+  }
+
+  fn prepare<V: Fn(u64) -> u64, P: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
+    let mmr = self.mmr.as_mut().unwrap();
+    ensure!(mmr.size() <= n, "mmr {} is larger than {n}", mmr.size());
+    (progress)(mmr.size())?;
+    while mmr.size() < n {
+      mmr.append(&values(mmr.size() + 1).to_le_bytes())?;
+      (progress)(1)?;
+    }
+    Ok(())
+  }
+
+  fn cleanup(&mut self) -> Result<()> {
+    drop(self.mmr.take());
+    self.mmr = Some(MerkleMountainRange::create_on_file(&self.path)?);
+    Ok(())
+  }
+}
+
+impl<H: HashAlgorithm> AppendCUT for FileMmrCUT<H> {
+  #[inline(never)]
+  fn append<V: Fn(u64) -> u64>(&mut self, n: Index, values: V) -> Result<Measurement> {
+    let mmr = self.mmr.as_mut().unwrap();
+    ensure!(mmr.size() <= n, "mmr {} is larger than {n}", mmr.size());
+    let begin = mmr.size();
+    let start = Stopwatch::start();
+    while mmr.size() < n {
+      mmr.append(&values(mmr.size() + 1).to_le_bytes())?;
+    }
+    let elapse = start.elapsed();
+    Ok(Measurement::new(elapse).with_bytes(file_size(&self.path)).with_count(n - begin))
+  }
+}
+
+impl<H: HashAlgorithm> GetCUT for FileMmrCUT<H> {
+  fn set_cache_level(&mut self, _cache_size: usize) -> Result<()> {
+    Ok(())
+  }
+
+  fn set_handle_mode(&mut self, mode: HandleMode) -> Result<()> {
+    self.handle_mode = mode;
+    Ok(())
+  }
+
+  #[inline(never)]
+  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Measurement> {
+    if self.handle_mode == HandleMode::Reopen {
+      self.mmr = None;
+      self.mmr = Some(MerkleMountainRange::from_file(&self.path)?);
+    }
+    let mmr = self.mmr.as_mut().unwrap();
+    ensure!(mmr.size() >= i, "n={} less than i={}", mmr.size(), i);
+    let start = Stopwatch::start();
+    let value = mmr.get(i)?;
+    let elapsed = start.elapsed();
+    let value = value.map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+    ensure!(value == Some(values(i)), "unexpected value {value:?} at {i}");
+    Ok(Measurement::new(elapsed))
+  }
+}
+
+impl<H: HashAlgorithm> RootHashCUT for FileMmrCUT<H> {
+  #[inline(never)]
+  fn root_hash(&mut self) -> Result<Measurement> {
+    let mmr = self.mmr.as_mut().unwrap();
+    let start = Stopwatch::start();
+    let root = mmr.root_hash()?;
+    let elapsed = start.elapsed();
+    ensure!(root.is_some(), "no root hash of {} entries", mmr.size());
+    Ok(Measurement::new(elapsed))
+  }
+}
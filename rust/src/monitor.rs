@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::stat::Stat;
+
+/// Number of the latest samples kept for each test unit on each CUT
+const RECENT_SAMPLES: usize = 32;
+
+/// Progress of the running session by the test units and the CUTs, shown by the TUI.
+pub struct State {
+  pub started: Instant,
+  pub trials: usize,
+  pub max_trials: usize,
+  pub eta: String,
+  /// Test units and CUTs in the order they're started
+  pub units: Vec<String>,
+  pub cuts: Vec<String>,
+  /// Results of each test unit on each CUT, by the unit and the CUT
+  pub cells: HashMap<(String, String), Cell>,
+}
+
+impl State {
+  pub fn cell(&self, unit: &str, cut: &str) -> Option<&Cell> {
+    self.cells.get(&(String::from(unit), String::from(cut)))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Status {
+  #[default]
+  Running,
+  Done,
+  Failed,
+}
+
+/// Results of a test unit on a CUT.
+#[derive(Default)]
+pub struct Cell {
+  pub status: Status,
+  /// Number of the samples and CV of the x-value measured last
+  pub count: usize,
+  pub cv: f64,
+  pub recent: VecDeque<f64>,
+}
+
+static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+
+thread_local! {
+  /// Test unit and CUT running on the current thread, to which the samples added on it belong
+  static CURRENT: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+}
+
+/// Start collecting the progress from the reports and the timers of the test units until the process exits.
+pub fn enable() {
+  let _ = STATE.set(Mutex::new(State {
+    started: Instant::now(),
+    trials: 0,
+    max_trials: 0,
+    eta: String::new(),
+    units: Vec::new(),
+    cuts: Vec::new(),
+    cells: HashMap::new(),
+  }));
+}
+
+/// Read the state, if the monitor is enabled.
+pub fn with<T, F: FnOnce(&State) -> T>(f: F) -> Option<T> {
+  STATE.get().map(|state| f(&state.lock().unwrap()))
+}
+
+/// Start the test unit on the CUT on the current thread, to which the following samples on it belong.
+pub fn start_unit(unit: &str, cut: &str) {
+  let key = (String::from(unit), String::from(cut));
+  CURRENT.with(|current| *current.borrow_mut() = Some(key.clone()));
+  if STATE.get().is_some() {
+    crate::writer::submit(move || {
+      let mut state = STATE.get().unwrap().lock().unwrap();
+      if !state.units.contains(&key.0) {
+        state.units.push(key.0.clone());
+      }
+      if !state.cuts.contains(&key.1) {
+        state.cuts.push(key.1.clone());
+      }
+      state.cells.insert(key, Cell::default());
+    });
+  }
+}
+
+/// End the test unit started on the current thread by [`start_unit`].
+pub fn finish_unit(succeeded: bool) {
+  let Some(key) = CURRENT.with(|current| current.borrow_mut().take()) else {
+    return;
+  };
+  if STATE.get().is_some() {
+    crate::writer::submit(move || {
+      if let Some(cell) = STATE.get().unwrap().lock().unwrap().cells.get_mut(&key) {
+        cell.status = if succeeded { Status::Done } else { Status::Failed };
+      }
+    });
+  }
+}
+
+/// Update the curve of the report on the [dashboard](crate::dashboard) and the test unit running on the current
+/// thread with the samples added to `x` and their statistics. They're updated on the [writer](crate::writer) thread
+/// so that the lock isn't contended between the measurements.
+pub(crate) fn update<X: Display, I: IntoIterator<Item = f64>>(report: &Path, x: &X, stat: &Stat, ys: I) {
+  if STATE.get().is_none() {
+    return;
+  }
+  let (report, x) = (report.to_path_buf(), x.to_string());
+  let current = CURRENT.with(|current| current.borrow().clone());
+  let (stat, ys) = (stat.clone(), ys.into_iter().collect::<Vec<_>>());
+  crate::writer::submit(move || {
+    crate::dashboard::update(&report, x, &stat);
+    let Some(key) = current else {
+      return;
+    };
+    let mut state = STATE.get().unwrap().lock().unwrap();
+    let cell = state.cells.entry(key).or_default();
+    (cell.count, cell.cv) = (stat.count, stat.cv());
+    cell.recent.extend(ys);
+    let overflow = cell.recent.len().saturating_sub(RECENT_SAMPLES);
+    cell.recent.drain(..overflow);
+  });
+}
+
+/// Update the number of the trials carried out and the ETA of the running test unit.
+pub(crate) fn progress(trials: usize, max_trials: usize, eta: String) {
+  crate::dashboard::progress(trials, max_trials, eta.clone());
+  if let Some(state) = STATE.get() {
+    let mut state = state.lock().unwrap();
+    (state.trials, state.max_trials, state.eta) = (trials, max_trials, eta);
+  }
+}
@@ -0,0 +1,100 @@
+use std::io::{Error, Write};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use serde_json::json;
+use slate_benchmark::error::Result;
+use slate_benchmark::report::Regression;
+
+use crate::registry::Failure;
+
+/// Increase of the mean from the baseline session regarded as a regression, 10%
+pub const REGRESSION_THRESHOLD: f64 = 0.1;
+
+/// Number of the largest regressions from the baseline session listed in the text of the notification
+const TOP_REGRESSIONS: usize = 5;
+
+/// POSTs the summary of the session to a webhook (e.g. a Slack incoming webhook) with curl when it finishes. If the
+/// session is aborted by an error or a panic, it's notified when dropped. The URL is given to curl on its stdin, not on
+/// its command line, which other users can see.
+///
+/// ```text
+/// {"text": "...", "session": "...", "status": "completed|failed|aborted|panicked", "duration_secs": 3600,
+///  "cuts": [{"name": "slate-file", "status": "ok|failed", "error": null}],
+///  "regressions": [{"report": "get-slate-file.csv", "x": "1024", "ratio": 1.25}]}
+/// ```
+pub struct Notifier {
+  url: String,
+  session: String,
+  started: Instant,
+  sent: bool,
+}
+
+impl Notifier {
+  pub fn new(url: &str, session: &str) -> Self {
+    Self { url: String::from(url), session: String::from(session), started: Instant::now(), sent: false }
+  }
+
+  /// Notify the result of each CUT run in the session, with the first failure of the failed ones, and the regressions
+  /// from the baseline session if compared.
+  pub fn finish(mut self, cuts: &[String], failures: &[Failure], regressions: &[Regression]) {
+    let status = if failures.is_empty() { "completed" } else { "failed" };
+    let cuts = cuts
+      .iter()
+      .map(|name| match failures.iter().find(|f| f.cut == *name) {
+        Some(failure) => json!({"name": name, "status": "failed", "error": failure.to_string()}),
+        None => json!({"name": name, "status": "ok", "error": null}),
+      })
+      .collect::<Vec<_>>();
+    let succeeded = cuts.iter().filter(|cut| cut["status"] == "ok").count();
+    let mut text = format!("{succeeded}/{} CUTs succeeded", cuts.len());
+    if !regressions.is_empty() {
+      let top = regressions.iter().take(TOP_REGRESSIONS).map(|r| format!("{} at {} x{:.2}", r.report, r.x, r.ratio));
+      text.push_str(&format!(", {} regressions: {}", regressions.len(), top.collect::<Vec<_>>().join(", ")));
+    }
+    let regressions =
+      regressions.iter().map(|r| json!({"report": r.report, "x": r.x, "ratio": r.ratio})).collect::<Vec<_>>();
+    self.send(status, &text, cuts, regressions);
+  }
+
+  fn send(&mut self, status: &str, detail: &str, cuts: Vec<serde_json::Value>, regressions: Vec<serde_json::Value>) {
+    self.sent = true;
+    let elapsed = self.started.elapsed().as_secs();
+    let duration = format!("{}h{:02}m{:02}s", elapsed / 3600, elapsed % 3600 / 60, elapsed % 60);
+    let text = format!("slate-benchmark session {} {status} in {duration}: {detail}", self.session);
+    let payload = json!({
+      "text": text, "session": self.session, "status": status, "duration_secs": elapsed, "cuts": cuts,
+      "regressions": regressions,
+    });
+    // 通知の失敗でセッションの結果を失敗にはしない
+    if let Err(err) = post(&self.url, &payload.to_string()) {
+      eprintln!("WARN: fail to notify the result to the webhook: {err}");
+    }
+  }
+}
+
+impl Drop for Notifier {
+  fn drop(&mut self) {
+    if !self.sent {
+      let status = if std::thread::panicking() { "panicked" } else { "aborted" };
+      self.send(status, "see the log on the host", Vec::new(), Vec::new());
+    }
+  }
+}
+
+fn post(url: &str, body: &str) -> Result<()> {
+  // URL は ps で他のユーザーから見えないよう、本文とともに設定として標準入力から渡す
+  let config = format!("url = {}\ndata-binary = {}\n", quote(url), quote(body));
+  let mut command = Command::new("curl");
+  command.args(["-fsS", "-X", "POST", "-H", "Content-Type: application/json", "--config", "-"]);
+  let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).spawn()?;
+  child.stdin.take().unwrap().write_all(config.as_bytes())?;
+  let status = child.wait()?;
+  if status.success() { Ok(()) } else { Err(Error::other(format!("curl failed: {status}")).into()) }
+}
+
+/// Quote the value of a curl config file.
+fn quote(value: &str) -> String {
+  let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+  format!("\"{escaped}\"")
+}
@@ -0,0 +1,137 @@
+//! Records of a user-supplied dataset appended to the slate CUTs in place of the splitmix64 values, selected by
+//! `--append-dataset`. The payload sizes and entropy of real records change both the storage volume and the hashing
+//! cost.
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use slate::Index;
+
+use crate::error::{BenchError, Result};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RecordFormat {
+  /// 改行で区切られたレコード (改行と CRLF の CR は含まない)
+  #[default]
+  Lines,
+  /// 32 ビットのリトルエンディアンの長さに続くレコード
+  LengthPrefixed,
+}
+
+impl RecordFormat {
+  pub fn name(self) -> String {
+    self.to_possible_value().unwrap().get_name().to_string()
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+  path: PathBuf,
+  format: RecordFormat,
+  data: Vec<u8>,
+  /// Start of each record in `data`, followed by the end of the last one
+  offsets: Vec<usize>,
+  /// BLAKE3 hash of the file to identify the dataset in the reports
+  fingerprint: String,
+}
+
+impl Dataset {
+  pub fn load(path: &Path, format: RecordFormat) -> Result<Self> {
+    let bytes = std::fs::read(path)
+      .map_err(|err| BenchError::Config(format!("fail to read the dataset {}: {err}", path.to_string_lossy())))?;
+    Self::parse(path, format, bytes)
+  }
+
+  fn parse(path: &Path, format: RecordFormat, bytes: Vec<u8>) -> Result<Self> {
+    let fingerprint = blake3::hash(&bytes).to_hex().to_string();
+    let err = |msg: String| BenchError::Config(format!("{msg} in the dataset {}", path.to_string_lossy()));
+    let mut data = Vec::with_capacity(bytes.len());
+    let mut offsets = vec![0];
+    match format {
+      RecordFormat::Lines => {
+        let bytes = bytes.strip_suffix(b"\n").unwrap_or(&bytes);
+        for line in bytes.split(|b| *b == b'\n').filter(|_| !bytes.is_empty()) {
+          data.extend_from_slice(line.strip_suffix(b"\r").unwrap_or(line));
+          offsets.push(data.len());
+        }
+      }
+      RecordFormat::LengthPrefixed => {
+        let mut rest = &bytes[..];
+        while !rest.is_empty() {
+          let Some((length, tail)) = rest.split_first_chunk::<4>() else {
+            return Err(err(format!("truncated length at {}", bytes.len() - rest.len())));
+          };
+          let length = u32::from_le_bytes(*length) as usize;
+          if tail.len() < length {
+            return Err(err(format!("truncated record of {length} bytes at {}", bytes.len() - rest.len())));
+          }
+          data.extend_from_slice(&tail[..length]);
+          offsets.push(data.len());
+          rest = &tail[length..];
+        }
+      }
+    }
+    if offsets.len() == 1 {
+      return Err(err(String::from("no records")));
+    }
+    Ok(Self { path: path.to_path_buf(), format, data, offsets, fingerprint })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  pub fn format(&self) -> RecordFormat {
+    self.format
+  }
+
+  pub fn fingerprint(&self) -> &str {
+    &self.fingerprint
+  }
+
+  pub fn records(&self) -> usize {
+    self.offsets.len() - 1
+  }
+
+  /// Total bytes of the records
+  pub fn bytes(&self) -> usize {
+    self.data.len()
+  }
+
+  /// Payload of the i-th entry (1-based), looping over the records if the database is larger than the dataset.
+  pub fn payload(&self, i: Index) -> &[u8] {
+    let k = ((i - 1) % self.records() as u64) as usize;
+    &self.data[self.offsets[k]..self.offsets[k + 1]]
+  }
+
+  pub fn metadata(&self) -> Vec<(String, String)> {
+    vec![
+      (String::from("dataset.path"), self.path.to_string_lossy().to_string()),
+      (String::from("dataset.format"), self.format.name()),
+      (String::from("dataset.records"), self.records().to_string()),
+      (String::from("dataset.bytes"), self.bytes().to_string()),
+      (String::from("dataset.blake3"), self.fingerprint.clone()),
+    ]
+  }
+}
+
+static DATASET: OnceLock<Dataset> = OnceLock::new();
+
+/// Append the records of the dataset to the slate CUTs instead of the generated values. Only the first call takes
+/// effect.
+pub fn set_dataset(dataset: Dataset) {
+  let _ = DATASET.set(dataset);
+}
+
+pub fn dataset() -> Option<&'static Dataset> {
+  DATASET.get()
+}
+
+/// Payload of the i-th entry if `--append-dataset` is given.
+#[inline(always)]
+pub fn payload(i: Index) -> Option<&'static [u8]> {
+  DATASET.get().map(|dataset| dataset.payload(i))
+}
@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn loop_over_the_lines() {
+  let dataset = Dataset::parse(Path::new("lines"), RecordFormat::Lines, b"alpha\n\nbeta\n".to_vec()).unwrap();
+  assert_eq!(3, dataset.records());
+  assert_eq!(b"alpha", dataset.payload(1));
+  assert_eq!(b"", dataset.payload(2));
+  assert_eq!(b"beta", dataset.payload(3));
+  assert_eq!(b"alpha", dataset.payload(4));
+  assert_eq!(b"beta", dataset.payload(6));
+
+  let dataset = Dataset::parse(Path::new("crlf"), RecordFormat::Lines, b"alpha\r\nbeta\r\n".to_vec()).unwrap();
+  assert_eq!((2, &b"alpha"[..], &b"beta"[..]), (dataset.records(), dataset.payload(1), dataset.payload(2)));
+}
+
+#[test]
+fn parse_the_length_prefixed_records() {
+  let bytes = [&3u32.to_le_bytes()[..], b"abc", &0u32.to_le_bytes(), &2u32.to_le_bytes(), b"\n\0"].concat();
+  let dataset = Dataset::parse(Path::new("records"), RecordFormat::LengthPrefixed, bytes.clone()).unwrap();
+  assert_eq!(3, dataset.records());
+  assert_eq!(b"abc", dataset.payload(1));
+  assert_eq!(b"", dataset.payload(2));
+  assert_eq!(b"\n\0", dataset.payload(3));
+  let metadata = dataset.metadata();
+  assert!(metadata.contains(&(String::from("dataset.blake3"), blake3::hash(&bytes).to_hex().to_string())));
+
+  for bytes in [vec![], bytes[..bytes.len() - 1].to_vec(), bytes[..2].to_vec()] {
+    let err = Dataset::parse(Path::new("records"), RecordFormat::LengthPrefixed, bytes).unwrap_err();
+    assert!(matches!(err, BenchError::Config(_)), "{err:?}");
+  }
+  assert!(Dataset::parse(Path::new("lines"), RecordFormat::Lines, vec![]).is_err());
+}
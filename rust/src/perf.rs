@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::clock;
+use crate::error::{BenchError, Result};
+use crate::report::write_atomically;
+
+/// Hardware counters of a measured operation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Counts {
+  pub instructions: u64,
+  pub cycles: u64,
+  pub cache_misses: u64,
+  pub branch_misses: u64,
+}
+
+impl Counts {
+  fn add(&mut self, other: &Counts) {
+    self.instructions += other.instructions;
+    self.cycles += other.cycles;
+    self.cache_misses += other.cache_misses;
+    self.branch_misses += other.branch_misses;
+  }
+}
+
+/// Records the hardware counters of each operation by the position, if enabled by `--perf`.
+pub struct PerfRecorder {
+  counters: Option<sys::PerfCounters>,
+  counts: BTreeMap<u64, Vec<Counts>>,
+}
+
+/// Counters armed by [`PerfRecorder::record`] on a thread with the counts of the timed sections so far.
+struct Armed {
+  counters: sys::PerfCounters,
+  counts: Counts,
+  sections: usize,
+  running: bool,
+  error: Option<BenchError>,
+}
+
+thread_local! {
+  static ARMED: RefCell<Option<Armed>> = const { RefCell::new(None) };
+}
+
+impl PerfRecorder {
+  pub fn new(enabled: bool) -> Result<Self> {
+    let counters = if enabled { Some(sys::PerfCounters::new()?) } else { None };
+    Ok(Self { counters, counts: BTreeMap::new() })
+  }
+
+  /// Run the operation and record at `x` the counters of this thread in the timed sections of the
+  /// [`Stopwatch`](crate::clock::Stopwatch)es within it, so that the verification of the results isn't counted.
+  pub fn record<T, F: FnOnce() -> Result<T>>(&mut self, x: u64, f: F) -> Result<T> {
+    let Some(counters) = self.counters.take() else {
+      return f();
+    };
+    let armed = Armed { counters, counts: Counts::default(), sections: 0, running: false, error: None };
+    ARMED.with(|cell| *cell.borrow_mut() = Some(armed));
+    let result = clock::armed(f);
+    let armed = ARMED.with(|cell| cell.borrow_mut().take()).unwrap();
+    self.counters = Some(armed.counters);
+    if let Some(err) = armed.error {
+      return Err(err);
+    }
+    if result.is_ok() && armed.sections > 0 {
+      self.counts.entry(x).or_default().push(armed.counts);
+    }
+    result
+  }
+
+  /// Save the mean of the counters at each position. Nothing is saved if disabled.
+  pub fn save_to_csv(&self, path: &Path, x_label: &str) -> Result<bool> {
+    if self.counters.is_none() {
+      return Ok(false);
+    }
+    write_atomically(path, |writer| {
+      writeln!(writer, "{x_label},INSTRUCTIONS,CYCLES,CACHE MISSES,BRANCH MISSES")?;
+      for (x, counts) in self.counts.iter() {
+        let mean = |f: fn(&Counts) -> u64| counts.iter().map(f).sum::<u64>() as f64 / counts.len() as f64;
+        let (instructions, cycles) = (mean(|c| c.instructions), mean(|c| c.cycles));
+        let (cache_misses, branch_misses) = (mean(|c| c.cache_misses), mean(|c| c.branch_misses));
+        writeln!(writer, "{x},{instructions:.1},{cycles:.1},{cache_misses:.1},{branch_misses:.1}")?;
+      }
+      Ok(())
+    })?;
+    Ok(true)
+  }
+}
+
+/// Enable the counters armed on this thread at the start of a timed section.
+pub(crate) fn enter() {
+  ARMED.with(|cell| {
+    if let Some(armed) = cell.borrow_mut().as_mut()
+      && !armed.running
+      && armed.error.is_none()
+    {
+      match armed.counters.start() {
+        Ok(()) => armed.running = true,
+        Err(err) => armed.error = Some(err),
+      }
+    }
+  })
+}
+
+/// Disable the counters armed on this thread at the end of a timed section and add them up.
+pub(crate) fn leave() {
+  ARMED.with(|cell| {
+    if let Some(armed) = cell.borrow_mut().as_mut()
+      && armed.running
+    {
+      armed.running = false;
+      match armed.counters.stop() {
+        Ok(counts) => {
+          armed.counts.add(&counts);
+          armed.sections += 1;
+        }
+        Err(err) => armed.error = Some(err),
+      }
+    }
+  })
+}
+
+#[cfg(all(feature = "perf", target_os = "linux"))]
+mod sys {
+  use std::fs::File;
+  use std::io::{Error, Read};
+  use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+  use perf_event_open_sys::bindings::{
+    perf_event_attr, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP, perf_event_read_format_PERF_FORMAT_GROUP,
+    perf_hw_id_PERF_COUNT_HW_BRANCH_MISSES, perf_hw_id_PERF_COUNT_HW_CACHE_MISSES, perf_hw_id_PERF_COUNT_HW_CPU_CYCLES,
+    perf_hw_id_PERF_COUNT_HW_INSTRUCTIONS, perf_type_id_PERF_TYPE_HARDWARE,
+  };
+  use perf_event_open_sys::{ioctls, perf_event_open};
+
+  use super::Counts;
+  use crate::error::{BenchError, Result};
+
+  /// 同時に有効化・読み出しするため、命令数をリーダーとするグループとして開く
+  const EVENTS: [u32; 4] = [
+    perf_hw_id_PERF_COUNT_HW_INSTRUCTIONS,
+    perf_hw_id_PERF_COUNT_HW_CPU_CYCLES,
+    perf_hw_id_PERF_COUNT_HW_CACHE_MISSES,
+    perf_hw_id_PERF_COUNT_HW_BRANCH_MISSES,
+  ];
+
+  pub struct PerfCounters {
+    leader: File,
+    _members: Vec<OwnedFd>,
+  }
+
+  impl PerfCounters {
+    pub fn new() -> Result<Self> {
+      let mut fds = Vec::with_capacity(EVENTS.len());
+      for event in EVENTS {
+        let mut attr = perf_event_attr {
+          type_: perf_type_id_PERF_TYPE_HARDWARE,
+          size: std::mem::size_of::<perf_event_attr>() as u32,
+          config: event as u64,
+          read_format: perf_event_read_format_PERF_FORMAT_GROUP as u64,
+          ..Default::default()
+        };
+        attr.set_disabled(if fds.is_empty() { 1 } else { 0 });
+        attr.set_exclude_kernel(1);
+        attr.set_exclude_hv(1);
+        let group = fds.first().map(|leader: &OwnedFd| leader.as_raw_fd()).unwrap_or(-1);
+        // 計測スレッドのみを対象とする (pid=0, cpu=-1)
+        let fd = unsafe { perf_event_open(&mut attr, 0, -1, group, 0) };
+        if fd < 0 {
+          let err = Error::last_os_error();
+          return Err(BenchError::Config(format!("perf_event_open failed, check kernel.perf_event_paranoid: {err}")));
+        }
+        fds.push(unsafe { OwnedFd::from_raw_fd(fd) });
+      }
+      let mut fds = fds.into_iter();
+      let leader = File::from(fds.next().unwrap());
+      Ok(Self { leader, _members: fds.collect() })
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+      let fd = self.leader.as_raw_fd();
+      unsafe {
+        check(ioctls::RESET(fd, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP))?;
+        check(ioctls::ENABLE(fd, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP))?;
+      }
+      Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<Counts> {
+      let fd = self.leader.as_raw_fd();
+      unsafe { check(ioctls::DISABLE(fd, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP))? };
+      // PERF_FORMAT_GROUP: { u64 nr; u64 values[nr]; }
+      let mut buffer = [0u8; 8 * (1 + EVENTS.len())];
+      self.leader.read_exact(&mut buffer)?;
+      let value = |i: usize| u64::from_ne_bytes(buffer[8 * (1 + i)..8 * (2 + i)].try_into().unwrap());
+      Ok(Counts { instructions: value(0), cycles: value(1), cache_misses: value(2), branch_misses: value(3) })
+    }
+  }
+
+  fn check(result: i32) -> Result<()> {
+    if result < 0 { Err(Error::last_os_error().into()) } else { Ok(()) }
+  }
+}
+
+#[cfg(not(all(feature = "perf", target_os = "linux")))]
+mod sys {
+  use super::Counts;
+  use crate::error::{BenchError, Result};
+
+  pub struct PerfCounters;
+
+  impl PerfCounters {
+    pub fn new() -> Result<Self> {
+      Err(BenchError::Config(String::from("--perf requires the `perf` feature on Linux")))
+    }
+    pub fn start(&mut self) -> Result<()> {
+      Ok(())
+    }
+    pub fn stop(&mut self) -> Result<Counts> {
+      Ok(Counts::default())
+    }
+  }
+}
@@ -0,0 +1,290 @@
+//! Control of the OS page cache and the file allocation on Linux, macOS and Windows. The operations not supported on
+//! the platform do nothing but warn once, so that a session can still run with the other measurements.
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::error::Result;
+
+#[cfg_attr(any(target_os = "linux", target_os = "macos"), allow(dead_code))]
+static WARNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+#[cfg_attr(any(target_os = "linux", target_os = "macos"), allow(dead_code))]
+fn unsupported(operation: &'static str) {
+  let mut warned = WARNED.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+  if warned.insert(operation) {
+    eprintln!("WARN: {operation} is not supported on this platform and is skipped");
+  }
+}
+
+/// Write back the dirty pages of all the files.
+pub fn sync() {
+  sys::sync()
+}
+
+/// Check if [`drop_page_cache`] is permitted, so that a long session doesn't fail at the first use.
+pub fn check_drop_page_cache() -> Result<()> {
+  sys::check_drop_page_cache()
+}
+
+/// Drop the clean pages of the whole page cache, which requires the root privilege. Call [`sync`] first to drop the
+/// dirty pages too.
+pub fn drop_page_cache() -> Result<()> {
+  sys::drop_page_cache()
+}
+
+/// Drop the clean pages of the file from the page cache, so that the next reads go to the storage.
+pub fn drop_file_cache(file: &File) {
+  sys::drop_file_cache(file)
+}
+
+/// Open the file for reading and writing bypassing the page cache. The buffers, offsets and lengths of the I/O must be
+/// aligned to the logical block size on Linux and Windows.
+pub fn open_direct(path: &Path) -> Result<File> {
+  let mut options = OpenOptions::new();
+  options.read(true).write(true).create(true);
+  sys::open_direct(&mut options, path)
+}
+
+/// Allocate the storage for the first `len` bytes of the file, extending the file if it's shorter.
+pub fn preallocate(file: &File, len: u64) -> Result<()> {
+  sys::preallocate(file, len)
+}
+
+/// Allocate the storage for the first `len` bytes of the file without changing its size, so that the following appends
+/// don't allocate the extents. It's released when the file is truncated or removed.
+pub fn reserve(file: &File, len: u64) -> Result<()> {
+  sys::reserve(file, len)
+}
+
+/// Read exactly `buf.len()` bytes at the offset without moving the file position (Unix) or from the offset (Windows).
+pub fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> Result<()> {
+  sys::read_exact_at(file, buf, offset)
+}
+
+#[cfg(unix)]
+mod unix {
+  use std::fs::File;
+  use std::os::unix::fs::FileExt;
+
+  use crate::error::Result;
+
+  pub fn sync() {
+    unsafe { libc::sync() };
+  }
+
+  pub fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> Result<()> {
+    Ok(file.read_exact_at(buf, offset)?)
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+  use std::fs::{File, OpenOptions};
+  use std::os::fd::AsRawFd;
+  use std::os::unix::fs::OpenOptionsExt;
+  use std::path::Path;
+
+  pub use super::unix::{read_exact_at, sync};
+  use crate::error::{BenchError, Result};
+
+  const DROP_CACHES: &str = "/proc/sys/vm/drop_caches";
+
+  pub fn check_drop_page_cache() -> Result<()> {
+    OpenOptions::new().write(true).open(DROP_CACHES).map_err(|err| {
+      BenchError::Config(format!("dropping the page cache requires writable {DROP_CACHES} (as root): {err}"))
+    })?;
+    Ok(())
+  }
+
+  pub fn drop_page_cache() -> Result<()> {
+    // 1: ページキャッシュのみを破棄する (dentry と inode のキャッシュは残す)
+    std::fs::write(DROP_CACHES, "1")?;
+    Ok(())
+  }
+
+  /// 書き込んだばかりのページがキャッシュから読まれないよう破棄する (同期済みのページのみ破棄される)
+  pub fn drop_file_cache(file: &File) {
+    unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+  }
+
+  pub fn open_direct(options: &mut OpenOptions, path: &Path) -> Result<File> {
+    Ok(options.custom_flags(libc::O_DIRECT).open(path)?)
+  }
+
+  pub fn preallocate(file: &File, len: u64) -> Result<()> {
+    match unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, len as libc::off_t) } {
+      0 => Ok(()),
+      err => Err(std::io::Error::from_raw_os_error(err).into()),
+    }
+  }
+
+  pub fn reserve(file: &File, len: u64) -> Result<()> {
+    if unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, 0, len as libc::off_t) } == -1 {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+  use std::fs::{File, OpenOptions};
+  use std::os::fd::AsRawFd;
+  use std::path::Path;
+  use std::process::Command;
+
+  pub use super::unix::{read_exact_at, sync};
+  use crate::error::{BenchError, Result};
+
+  pub fn check_drop_page_cache() -> Result<()> {
+    if unsafe { libc::geteuid() } != 0 {
+      return Err(BenchError::Config(String::from("dropping the page cache by purge(8) requires the root privilege")));
+    }
+    Ok(())
+  }
+
+  pub fn drop_page_cache() -> Result<()> {
+    let status = Command::new("purge").status()?;
+    if !status.success() {
+      return Err(BenchError::Config(format!("purge(8) exited with {status}")));
+    }
+    Ok(())
+  }
+
+  /// macOS にはファイル単位でキャッシュを破棄する手段がないため、以降の I/O をキャッシュしないよう設定する
+  pub fn drop_file_cache(file: &File) {
+    unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) };
+  }
+
+  pub fn open_direct(options: &mut OpenOptions, path: &Path) -> Result<File> {
+    let file = options.open(path)?;
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } == -1 {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(file)
+  }
+
+  pub fn preallocate(file: &File, len: u64) -> Result<()> {
+    reserve(file, len)?;
+    // F_PREALLOCATE は領域を確保するのみでファイルサイズは変わらない
+    if file.metadata()?.len() < len {
+      file.set_len(len)?;
+    }
+    Ok(())
+  }
+
+  pub fn reserve(file: &File, len: u64) -> Result<()> {
+    let mut store = libc::fstore_t {
+      fst_flags: libc::F_ALLOCATEALL,
+      fst_posmode: libc::F_PEOFPOSMODE,
+      fst_offset: 0,
+      fst_length: len as libc::off_t,
+      fst_bytesalloc: 0,
+    };
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &mut store) } == -1 {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+  }
+}
+
+#[cfg(windows)]
+mod sys {
+  use std::fs::{File, OpenOptions};
+  use std::os::windows::fs::{FileExt, OpenOptionsExt};
+  use std::path::Path;
+
+  use super::unsupported;
+  use crate::error::{BenchError, Result};
+
+  const FILE_FLAG_NO_BUFFERING: u32 = 0x2000_0000;
+
+  pub fn sync() {
+    unsupported("sync of all the files");
+  }
+
+  pub fn check_drop_page_cache() -> Result<()> {
+    Err(BenchError::Config(String::from("dropping the page cache is not supported on Windows")))
+  }
+
+  pub fn drop_page_cache() -> Result<()> {
+    unsupported("dropping the page cache");
+    Ok(())
+  }
+
+  pub fn drop_file_cache(_file: &File) {
+    unsupported("dropping the page cache of a file");
+  }
+
+  pub fn open_direct(options: &mut OpenOptions, path: &Path) -> Result<File> {
+    Ok(options.custom_flags(FILE_FLAG_NO_BUFFERING).open(path)?)
+  }
+
+  /// Windows の SetEndOfFile は拡張した領域を確保する
+  pub fn preallocate(file: &File, len: u64) -> Result<()> {
+    if file.metadata()?.len() < len {
+      file.set_len(len)?;
+    }
+    Ok(())
+  }
+
+  pub fn reserve(_file: &File, _len: u64) -> Result<()> {
+    unsupported("reserving the storage of a file");
+    Ok(())
+  }
+
+  pub fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> Result<()> {
+    while !buf.is_empty() {
+      match file.seek_read(buf, offset)? {
+        0 => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+        n => {
+          buf = &mut buf[n..];
+          offset += n as u64;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod sys {
+  use std::fs::{File, OpenOptions};
+  use std::path::Path;
+
+  pub use super::unix::{read_exact_at, sync};
+  use super::unsupported;
+  use crate::error::{BenchError, Result};
+
+  pub fn check_drop_page_cache() -> Result<()> {
+    Err(BenchError::Config(String::from("dropping the page cache is not supported on this platform")))
+  }
+
+  pub fn drop_page_cache() -> Result<()> {
+    unsupported("dropping the page cache");
+    Ok(())
+  }
+
+  pub fn drop_file_cache(_file: &File) {
+    unsupported("dropping the page cache of a file");
+  }
+
+  pub fn open_direct(options: &mut OpenOptions, path: &Path) -> Result<File> {
+    unsupported("direct I/O");
+    Ok(options.open(path)?)
+  }
+
+  pub fn preallocate(file: &File, len: u64) -> Result<()> {
+    if file.metadata()?.len() < len {
+      file.set_len(len)?;
+    }
+    Ok(())
+  }
+
+  pub fn reserve(_file: &File, _len: u64) -> Result<()> {
+    unsupported("reserving the storage of a file");
+    Ok(())
+  }
+}
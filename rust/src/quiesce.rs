@@ -0,0 +1,79 @@
+//! Quiesce step between the measurements, so that the pending writeback of the previous appends or the database
+//! preparation doesn't pollute the following read measurements.
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::error::{BenchError, Result};
+use crate::platform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QuiesceAction {
+  /// sync(2) to write back the dirty pages
+  Sync,
+  /// sync(2) and drop the clean page cache (as root), see [`platform::drop_page_cache`]
+  DropCaches,
+}
+
+/// When to quiesce in the measurement loops. It's always done after the database is prepared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QuiesceInterval {
+  /// Between the trials
+  Trial,
+  /// Between the gauge points in a trial
+  Point,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quiesce {
+  /// Nothing but the sleep if `None`
+  pub action: Option<QuiesceAction>,
+  /// Wait after the action for the device to settle
+  pub sleep: Duration,
+  pub interval: QuiesceInterval,
+}
+
+impl Default for Quiesce {
+  fn default() -> Self {
+    Self { action: None, sleep: Duration::ZERO, interval: QuiesceInterval::Trial }
+  }
+}
+
+impl Quiesce {
+  pub fn is_enabled(&self) -> bool {
+    self.action.is_some() || !self.sleep.is_zero()
+  }
+
+  /// Check if the action is permitted, so that a long session doesn't fail at the first quiesce.
+  pub fn check(&self) -> Result<()> {
+    if self.action == Some(QuiesceAction::DropCaches) {
+      platform::check_drop_page_cache().map_err(|err| BenchError::Config(format!("--quiesce drop-caches: {err}")))?;
+    }
+    Ok(())
+  }
+
+  pub fn run(&self) -> Result<()> {
+    match self.action {
+      Some(QuiesceAction::Sync) => platform::sync(),
+      Some(QuiesceAction::DropCaches) => {
+        platform::sync();
+        platform::drop_page_cache()?;
+      }
+      None => (),
+    }
+    if !self.sleep.is_zero() {
+      std::thread::sleep(self.sleep);
+    }
+    Ok(())
+  }
+
+  /// Quiesce after a gauge point if configured so.
+  pub fn after_point(&self) -> Result<()> {
+    if self.is_enabled() && self.interval == QuiesceInterval::Point { self.run() } else { Ok(()) }
+  }
+
+  /// Quiesce after a trial if configured so.
+  pub fn after_trial(&self) -> Result<()> {
+    if self.is_enabled() && self.interval == QuiesceInterval::Trial { self.run() } else { Ok(()) }
+  }
+}
@@ -0,0 +1,87 @@
+//! Quota of the disk usage of the working directories of a session, so that a runaway preparation of a database
+//! doesn't fill the disk shared with the other jobs.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::error::{BenchError, Result};
+use crate::file_size;
+
+#[cfg(test)]
+mod test;
+
+/// Minimum interval of [`WorkdirQuota::poll`], since measuring the usage walks the directories.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct WorkdirQuota {
+  dir: PathBuf,
+  session: String,
+  /// Maximum total size in bytes
+  limit: u64,
+  last_poll: Mutex<Option<Instant>>,
+  /// Usage at the previous check, to project the growth until the next one
+  last_usage: Mutex<Option<u64>>,
+}
+
+impl WorkdirQuota {
+  pub fn new(dir: &Path, session: &str, limit: u64) -> Self {
+    let (dir, session) = (dir.to_path_buf(), String::from(session));
+    Self { dir, session, limit, last_poll: Mutex::new(None), last_usage: Mutex::new(None) }
+  }
+
+  /// Total size in bytes of the `slate_benchmark-{session}` and `slate_benchmark-{session}-*` directories.
+  pub fn usage(&self) -> u64 {
+    let prefix = format!("slate_benchmark-{}", self.session);
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return 0;
+    };
+    entries
+      .flatten()
+      .filter(|entry| {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        name.strip_prefix(&prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+      })
+      .map(|entry| file_size(entry.path()))
+      .sum()
+  }
+
+  /// Fail with [`BenchError::Quota`] if the usage would exceed the quota by the next check, assuming that it grows as
+  /// much as since the previous check, so that the test units are aborted before the quota is exceeded.
+  pub fn check(&self) -> Result<()> {
+    let usage = self.usage();
+    let growth = {
+      let mut last_usage = self.last_usage.lock().unwrap_or_else(PoisonError::into_inner);
+      let growth = last_usage.map_or(0, |last| usage.saturating_sub(last));
+      *last_usage = Some(usage);
+      growth
+    };
+    if usage.saturating_add(growth) > self.limit {
+      const GB: f64 = 1000.0 * 1000.0 * 1000.0;
+      return Err(BenchError::Quota(format!(
+        "the working directories of the session {} in {} use {:.2} GB, growing {:.2} GB since the previous check, \
+         which would exceed --workdir-quota {:.2} GB",
+        self.session,
+        self.dir.to_string_lossy(),
+        usage as f64 / GB,
+        growth as f64 / GB,
+        self.limit as f64 / GB
+      )));
+    }
+    Ok(())
+  }
+
+  /// [`check`](Self::check) at most once per second, for the progress callbacks called for each entry.
+  pub fn poll(&self) -> Result<()> {
+    {
+      let mut last_poll = self.last_poll.lock().unwrap_or_else(PoisonError::into_inner);
+      if last_poll.is_some_and(|last| last.elapsed() < POLL_INTERVAL) {
+        return Ok(());
+      }
+      *last_poll = Some(Instant::now());
+    }
+    self.check()
+  }
+}
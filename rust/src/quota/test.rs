@@ -0,0 +1,32 @@
+use std::fs;
+
+use super::WorkdirQuota;
+use crate::RemoveOnDrop;
+use crate::error::BenchError;
+
+#[test]
+fn usage_of_the_session_directories() {
+  let dir = RemoveOnDrop::new(std::env::temp_dir().join(format!("slate_benchmark-quota-{}", std::process::id())));
+  for (name, len) in [("slate_benchmark-s1", 100), ("slate_benchmark-s1-append", 20), ("slate_benchmark-s10", 5000)] {
+    fs::create_dir_all(dir.path().join(name)).unwrap();
+    fs::write(dir.path().join(name).join("data"), vec![0u8; len]).unwrap();
+  }
+
+  let quota = WorkdirQuota::new(dir.path(), "s1", 120);
+  assert_eq!(120, quota.usage());
+  quota.check().unwrap();
+  quota.poll().unwrap();
+
+  let quota = WorkdirQuota::new(dir.path(), "s1", 119);
+  assert!(matches!(quota.check(), Err(BenchError::Quota(_))));
+  assert!(matches!(quota.poll(), Err(BenchError::Quota(_))));
+  // 直前の計測から間もない場合は計測しない
+  quota.poll().unwrap();
+
+  // 前回の確認からの増加が続くと次の確認までに上限を超える場合は、超える前に中断する
+  let quota = WorkdirQuota::new(dir.path(), "s1", 150);
+  quota.check().unwrap();
+  fs::write(dir.path().join("slate_benchmark-s1").join("more"), vec![0u8; 20]).unwrap();
+  assert_eq!(140, quota.usage());
+  assert!(matches!(quota.check(), Err(BenchError::Quota(_))));
+}
@@ -0,0 +1,563 @@
+use std::any::Any;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::interleave::Turns;
+use slate_benchmark::knobs::Knobs;
+use slate_benchmark::{DataSize, Experiment, splitmix64};
+
+use crate::cgroup::MemoryLimit;
+use crate::dataset::DatasetCache;
+
+/// Test units that a CUT can be registered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TestUnit {
+  Hash,
+  /// Record the hash throughput and the combine cost of this machine in the session metadata
+  HashCost,
+  /// Write and read the node of each implementation in memory across the payload sizes
+  Serialization,
+  Append,
+  /// Append the entries one by one and break the append time down by the number of the levels merged by each
+  AppendLevels,
+  BiasedGet,
+  UniformedGet,
+  CacheLevel,
+  /// Get a fixed hot set of positions by the Zipf distribution pass after pass, without and with the cache
+  HotSetGet,
+  RangeScan,
+  ConcurrentGet,
+  /// Get the entries from several processes opening the same database read-only
+  MultiProcessGet,
+  Prove,
+  /// Serve the auth paths for the recent entries without and with the auth-path cache
+  ProofServing,
+  /// Write each record to a primary KV store only, to slate only, and to both in the same logical operation
+  DualWrite,
+  /// Obtain the root hash of the current entries, growing the database by powers of 2
+  RootHash,
+  /// Replay the trace given by `--replay-trace`
+  Replay,
+  /// Run the get and append operations of the trace given by `--replay-trace` and report the latency distributions
+  Workload,
+  /// Run the steps of the scenarios given by `--scenario` in the config
+  Scenario,
+  /// Keep appending for `--soak-hours`, sampling the get and auth path latencies every `--soak-interval` minutes
+  Soak,
+  /// Count the bytes that the `-counted` CUTs of `--count-storage` write and read on the storage, without timing
+  Amplification,
+}
+
+/// Environment given to the constructors of the registered CUTs.
+pub struct Env {
+  pub dir: PathBuf,
+  pub small: DataSize,
+  pub large: DataSize,
+  pub dataset_cache: Option<PathBuf>,
+  /// Validate every n-th entry of each database after the test units, if specified
+  pub validate: Option<u64>,
+  pub retry: Retry,
+  pub on_failure: OnFailure,
+  /// Engine options of the backends given by `--knob`
+  pub knobs: Knobs,
+}
+
+/// What to skip when a test unit of a CUT fails. The other CUTs always continue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnFailure {
+  /// Skip the remaining test units of the CUT
+  SkipCut,
+  /// Skip only the failed test unit, rebuilding the database for the next one
+  SkipUnit,
+}
+
+/// Failure of a CUT, in the test unit if it failed while running one.
+#[derive(Debug)]
+pub struct Failure {
+  pub cut: String,
+  pub unit: Option<TestUnit>,
+  pub error: BenchError,
+}
+
+impl Failure {
+  pub fn unit_name(&self) -> String {
+    self.unit.map(|unit| format!("{unit:?}")).unwrap_or_default()
+  }
+}
+
+impl std::fmt::Display for Failure {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.unit {
+      Some(unit) => write!(f, "{} ({unit:?}): {}", self.cut, self.error),
+      None => write!(f, "{}: {}", self.cut, self.error),
+    }
+  }
+}
+
+/// Retry of the CUT construction and the test units failed with a transient error.
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+  pub attempts: usize,
+  /// Wait before the first retry, doubled for each of the following ones
+  pub backoff: Duration,
+}
+
+impl Retry {
+  /// Run `f` with the number of the attempt (0 for the first), retrying it while it fails with a transient error.
+  pub fn run<T, F>(&self, what: &str, mut f: F) -> Result<T>
+  where
+    F: FnMut(usize) -> Result<T>,
+  {
+    let (mut attempt, mut backoff) = (0, self.backoff);
+    loop {
+      match f(attempt) {
+        Err(err) if attempt < self.attempts && err.is_transient() => {
+          attempt += 1;
+          eprintln!("WARN: {what} failed: {err}, retrying in {backoff:?} ({attempt}/{})", self.attempts);
+          std::thread::sleep(backoff);
+          backoff *= 2;
+        }
+        result => return result,
+      }
+    }
+  }
+}
+
+impl Env {
+  /// The cache of the prepared databases shared across sessions, if specified.
+  pub fn dataset_cache(&self) -> Result<Option<DatasetCache>> {
+    self.dataset_cache.as_deref().map(DatasetCache::new).transpose()
+  }
+}
+
+/// Selection of the CUTs and test units to run. An empty list selects all.
+#[derive(Clone, Default)]
+pub struct Filter {
+  pub cuts: Vec<String>,
+  pub units: Vec<TestUnit>,
+  /// Match the CUT names exactly instead of by substring
+  pub exact: bool,
+  /// Select only the in-memory (`true`) or the file-backed (`false`) CUTs, if specified
+  pub in_memory: Option<bool>,
+  /// Select only the CUTs appending the records of `--append-dataset`
+  pub payload: bool,
+}
+
+impl Filter {
+  /// CUT 名の部分一致 (`exact` の場合は完全一致) で判定する。
+  pub fn accepts_cut(&self, name: &str) -> bool {
+    self.cuts.is_empty()
+      || self.cuts.iter().any(|cut| if self.exact { name == cut } else { name.contains(cut.as_str()) })
+  }
+
+  pub fn accepts_unit(&self, unit: TestUnit) -> bool {
+    self.units.is_empty() || self.units.contains(&unit)
+  }
+
+  /// Whether the CUT is selected and has any test unit to run.
+  pub fn accepts(&self, registration: &Registration) -> bool {
+    self.accepts_cut(&registration.name)
+      && registration.units.iter().any(|unit| self.accepts_unit(*unit))
+      && self.in_memory.is_none_or(|in_memory| in_memory == registration.in_memory)
+      && (!self.payload || registration.payload)
+  }
+}
+
+/// Order to run the CUTs in. With `random` and `interleaved`, the CUTs run one test unit at a time, so that the
+/// time-dependent bias such as the thermal drift and the page cache state doesn't always favor the same CUT. Each CUT
+/// is constructed (and its database prepared) again for each test unit, and with `skip-cut` a CUT failed in a test unit
+/// skips the following ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Order {
+  /// Each CUT runs all its test units in the registration order
+  Registered,
+  /// For each test unit, the CUTs run in an order shuffled by the seed
+  Random,
+  /// For each test unit, the CUTs run side by side taking turns at each trial (A, B, C, A, B, C, ..), starting from
+  /// the registration order rotated by one from the previous unit
+  Interleaved,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Schedule {
+  pub order: Order,
+  /// Seed of the shuffle for `random`
+  pub seed: u64,
+}
+
+/// A CUT to run, limited to the test unit if scheduled one at a time.
+pub struct Slot<'a> {
+  pub registration: &'a Registration,
+  pub unit: Option<TestUnit>,
+}
+
+impl Slot<'_> {
+  fn filter(&self, filter: &Filter) -> Filter {
+    match self.unit {
+      Some(unit) => Filter { units: vec![unit], ..filter.clone() },
+      None => filter.clone(),
+    }
+  }
+
+  /// Whether to skip the test unit of the slot because its CUT has failed in a previous one, telling so if skipped.
+  fn skipped(&self, env: &Env, failures: &[Failure]) -> bool {
+    let failed = failures.iter().any(|failure| failure.cut == self.registration.name);
+    let skipped = self.unit.is_some() && env.on_failure == OnFailure::SkipCut && failed;
+    if skipped {
+      println!("SKIP: {} has failed in a previous test unit", self.registration.name);
+    }
+    skipped
+  }
+}
+
+/// Runs the test units of a CUT, pushing the failures of the units. An error is the failure out of the test units, such
+/// as the construction of the CUT.
+type Runner = Box<dyn Fn(&Experiment, &Env, &Filter, &mut Vec<Failure>) -> Result<()> + Send + Sync>;
+
+pub struct Registration {
+  pub name: String,
+  pub units: Vec<TestUnit>,
+  /// The CUT doesn't use the working directory
+  pub in_memory: bool,
+  /// The CUT appends the records of `--append-dataset` in place of the generated values
+  pub payload: bool,
+  run: Runner,
+}
+
+/// CUT とそのテストユニットの登録簿。既定では登録順に実行される。
+#[derive(Default)]
+pub struct Registry {
+  registrations: Vec<Registration>,
+}
+
+impl Registry {
+  /// Register a CUT with the test units it supports. Use `register!` instead of calling this directly.
+  pub fn register<F>(&mut self, name: &str, units: &[TestUnit], run: F)
+  where
+    F: Fn(&Experiment, &Env, &Filter, &mut Vec<Failure>) -> Result<()> + Send + Sync + 'static,
+  {
+    let registration = Registration {
+      name: String::from(name),
+      units: units.to_vec(),
+      in_memory: false,
+      payload: false,
+      run: Box::new(run),
+    };
+    self.registrations.push(registration);
+  }
+
+  /// Mark the CUT registered with the name as in-memory. Use `register!` with `in_memory` instead of calling this.
+  pub fn set_in_memory(&mut self, name: &str) {
+    self.registrations.iter_mut().filter(|r| r.name == name).for_each(|r| r.in_memory = true);
+  }
+
+  /// Mark the CUT registered with the name as appending the records of `--append-dataset`. Use `register!` with
+  /// `payload` instead of calling this.
+  pub fn set_payload(&mut self, name: &str) {
+    self.registrations.iter_mut().filter(|r| r.name == name).for_each(|r| r.payload = true);
+  }
+
+  pub fn registrations(&self) -> &[Registration] {
+    &self.registrations
+  }
+
+  /// The CUTs accepted by the filter in the order to run them.
+  pub fn schedule(&self, filter: &Filter, schedule: &Schedule) -> Vec<Slot<'_>> {
+    let accepted = self.registrations.iter().filter(|r| filter.accepts(r)).collect::<Vec<_>>();
+    if schedule.order == Order::Registered {
+      return accepted.into_iter().map(|registration| Slot { registration, unit: None }).collect();
+    }
+    let mut slots = Vec::new();
+    let mut state = schedule.seed;
+    let units = TestUnit::value_variants().iter().filter(|unit| filter.accepts_unit(**unit));
+    for (k, unit) in units.enumerate() {
+      let mut cuts = accepted.iter().filter(|r| r.units.contains(unit)).copied().collect::<Vec<_>>();
+      if cuts.is_empty() {
+        continue;
+      }
+      match schedule.order {
+        Order::Random => {
+          // Fisher-Yates
+          for i in (1..cuts.len()).rev() {
+            state = splitmix64(state);
+            cuts.swap(i, (state % (i as u64 + 1)) as usize);
+          }
+        }
+        Order::Interleaved => {
+          let len = cuts.len();
+          cuts.rotate_left(k % len);
+        }
+        Order::Registered => unreachable!(),
+      }
+      slots.extend(cuts.into_iter().map(|registration| Slot { registration, unit: Some(*unit) }));
+    }
+    slots
+  }
+
+  /// Construct each CUT accepted by the filter and run its test units, skipping the CUTs without any test unit to run.
+  /// A CUT that fails or panics doesn't stop the session; the reports of the other CUTs are still written. Each failure
+  /// is also recorded in `{session}-failures.csv`.
+  ///
+  /// ## Returns
+  /// - the failures of the CUTs and their test units
+  pub fn run(&self, experiment: &Experiment, env: &Env, filter: &Filter, schedule: &Schedule) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let slots = self.schedule(filter, schedule);
+    if schedule.order != Order::Interleaved {
+      for slot in slots.iter() {
+        if !slot.skipped(env, &failures) {
+          failures.extend(run_slot(experiment, env, filter, slot, true));
+        }
+      }
+      return failures;
+    }
+
+    // CUT ごとのスレッドで同じテストユニットを実行し、試行ごとに順番を回す
+    for slots in slots.chunk_by(|a, b| a.unit == b.unit) {
+      let slots = slots.iter().filter(|slot| !slot.skipped(env, &failures)).collect::<Vec<_>>();
+      let turns = Turns::new(slots.len());
+      let results = thread::scope(|scope| {
+        let handles = slots
+          .iter()
+          .enumerate()
+          .map(|(i, slot)| {
+            let turns = &turns;
+            scope.spawn(move || turns.run(i, || run_slot(experiment, env, filter, slot, false)))
+          })
+          .collect::<Vec<_>>();
+        handles.into_iter().zip(slots.iter()).map(|(handle, slot)| (handle.join(), slot)).collect::<Vec<_>>()
+      });
+      for (result, slot) in results {
+        failures.extend(result.unwrap_or_else(|_| {
+          let error = BenchError::Interrupted(String::from("the thread of the CUT panicked"));
+          vec![Failure { cut: slot.registration.name.clone(), unit: slot.unit, error }]
+        }));
+      }
+      if let Err(err) = experiment.clear() {
+        eprintln!("WARN: fail to clear the working directory: {err}");
+      }
+    }
+    failures
+  }
+
+  /// Run each CUT accepted by the filter in a child process of this executable with `args` and `--isolated-cut`, so
+  /// that the allocator state, the page cache and a crash of one CUT don't affect the others. The stdout of the child
+  /// is streamed with the CUT name as a prefix. With `memory_limit`, each child process runs in its own cgroup. A CUT
+  /// scheduled one test unit at a time runs in a child process for each unit, given by `--isolated-unit`.
+  ///
+  /// ## Returns
+  /// - the failed CUTs, including the child processes that crashed, whose errors are recorded by the child processes
+  ///   unless they're killed by a signal
+  pub fn run_isolated(
+    &self,
+    experiment: &Experiment,
+    filter: &Filter,
+    schedule: &Schedule,
+    args: &[OsString],
+    memory_limit: Option<&MemoryLimit>,
+  ) -> Result<Vec<(String, BenchError)>> {
+    let exe = std::env::current_exe()?;
+    let mut failures = Vec::new();
+    for slot in self.schedule(filter, schedule) {
+      let name = &slot.registration.name;
+      let scope = memory_limit.map(|limit| limit.scope(name)).transpose()?;
+      let mut command = Command::new(&exe);
+      command.args(args).arg("--isolated-cut").arg(name);
+      if let Some(unit) = slot.unit {
+        command.arg("--isolated-unit").arg(unit.to_possible_value().unwrap().get_name());
+      }
+      let mut child = command.stdout(Stdio::piped()).spawn()?;
+      if let Some(scope) = &scope {
+        // 子プロセスは作業ディレクトリの準備から始めるため、データベースの構築前に cgroup へ移動できる
+        if let Err(err) = scope.add(child.id()) {
+          let _ = child.kill();
+          let _ = child.wait();
+          return Err(err);
+        }
+      }
+      for line in BufReader::new(child.stdout.take().unwrap()).lines() {
+        println!("[{name}] {}", line?);
+      }
+      let status = child.wait()?;
+      if !status.success() {
+        eprintln!("ERROR: {name} failed: child process exited with {status}");
+        let error = BenchError::Interrupted(format!("child process exited with {status}"));
+        let failure = Failure { cut: name.clone(), unit: slot.unit, error };
+        if status.code().is_none() {
+          experiment.record_failure(&failure.cut, &failure.unit_name(), &failure.error)?;
+        }
+        failures.push(failure);
+      }
+    }
+    Ok(failures)
+  }
+}
+
+/// Run the CUT of the slot and record its failures in `{session}-failures.csv`. The working directory is cleared
+/// afterwards if `clear`, which the CUTs running side by side leave to the end of the test unit.
+fn run_slot(experiment: &Experiment, env: &Env, filter: &Filter, slot: &Slot, clear: bool) -> Vec<Failure> {
+  let (registration, filter) = (slot.registration, slot.filter(filter));
+  let mut unit_failures = Vec::new();
+  // パニックも CUT の失敗として扱い、巻き戻しで CUT が削除したファイルの残りは clear で削除する
+  let result =
+    panic::catch_unwind(AssertUnwindSafe(|| (registration.run)(experiment, env, &filter, &mut unit_failures)))
+      .unwrap_or_else(|payload| Err(BenchError::Interrupted(format!("panicked: {}", panic_message(&*payload)))));
+  if let Err(error) = result {
+    eprintln!("ERROR: {} failed: {error}", registration.name);
+    unit_failures.push(Failure { cut: registration.name.clone(), unit: None, error });
+  }
+  if clear && let Err(err) = experiment.clear() {
+    eprintln!("WARN: fail to clear the working directory: {err}");
+  }
+  for failure in unit_failures.iter() {
+    if let Err(err) = experiment.record_failure(&failure.cut, &failure.unit_name(), &failure.error) {
+      eprintln!("WARN: fail to record the failure of {}: {err}", failure.cut);
+    }
+  }
+  unit_failures
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+  match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+    (Some(msg), _) => msg,
+    (_, Some(msg)) => msg,
+    _ => "unknown cause",
+  }
+}
+
+/// Register a CUT constructed by the expression with the test units and the data sizes to run them on. A CUT that
+/// doesn't use `env.dir` is marked with `in_memory` so that it runs only once with multiple `--dir`, and a CUT that
+/// appends the records of `--append-dataset` with `payload` so that the others are skipped when it's given.
+///
+/// ```ignore
+/// register!(registry, "slate-file", payload, |env| SlateCUT::new(FileFactory::new(&env.dir))?, [Append(small)]);
+/// register!(registry, "slate-memkvs", in_memory, payload, |env| SlateCUT::new(memkvs_factory())?, [Append(small)]);
+/// ```
+macro_rules! register {
+  (@run $experiment:ident, Append, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_append($cut, $ds)?
+  };
+  (@run $experiment:ident, AppendLevels, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_append_levels($cut, $ds)?
+  };
+  (@run $experiment:ident, BiasedGet, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_biased_get($cut, $ds)?
+  };
+  (@run $experiment:ident, UniformedGet, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_uniformed_get($cut, $ds)?
+  };
+  (@run $experiment:ident, CacheLevel, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_cache_level($cut, $ds)?
+  };
+  (@run $experiment:ident, HotSetGet, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_hot_set_get($cut, $ds)?
+  };
+  (@run $experiment:ident, RangeScan, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_range_scan($cut, $ds)?
+  };
+  (@run $experiment:ident, ConcurrentGet, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_concurrent_get($cut, $ds)?
+  };
+  (@run $experiment:ident, MultiProcessGet, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_multi_process_get($cut, $ds)?
+  };
+  (@run $experiment:ident, Prove, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_prove($cut, $ds)?
+  };
+  (@run $experiment:ident, DualWrite, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_dual_write($cut, $ds)?
+  };
+  (@run $experiment:ident, ProofServing, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_proof_serving($cut, $ds)?
+  };
+  (@run $experiment:ident, RootHash, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_root_hash($cut, $ds)?
+  };
+  (@run $experiment:ident, Replay, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_replay($cut, $ds)?
+  };
+  (@run $experiment:ident, Workload, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_workload($cut, $ds)?
+  };
+  (@run $experiment:ident, Scenario, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_scenario($cut, $ds)?
+  };
+  (@run $experiment:ident, Soak, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_soak($cut, $ds)?
+  };
+  (@run $experiment:ident, Amplification, $cut:expr, $ds:expr) => {
+    $experiment.run_testunit_amplification($cut, $ds)?
+  };
+  (@validate $experiment:ident, Append, $cut:expr, $ds:expr, $every:expr, $validated:ident) => {};
+  (@validate $experiment:ident, $unit:ident, $cut:expr, $ds:expr, $every:expr, $validated:ident) => {
+    // 同じデータサイズのデータベースは一度だけ検証する
+    if $validated.insert($ds.size()) {
+      $experiment.validate($cut, $ds, $every)?;
+    }
+  };
+  ($registry:expr, $name:expr, in_memory, $($rest:tt)+) => {
+    register!($registry, $name, $($rest)+);
+    $registry.set_in_memory($name);
+  };
+  ($registry:expr, $name:expr, payload, $($rest:tt)+) => {
+    register!($registry, $name, $($rest)+);
+    $registry.set_payload($name);
+  };
+  ($registry:expr, $name:expr, |$env:ident| $new:expr, [$($unit:ident($ds:ident)),* $(,)?]) => {
+    $registry.register($name, &[$($crate::registry::TestUnit::$unit),*], move |experiment, $env, filter, failures| {
+      let mut cut = $env.retry.run($name, |_| Ok($new))?;
+      slate_benchmark::CUT::setup(&mut cut)?;
+      'units: {
+        $(
+          if filter.accepts_unit($crate::registry::TestUnit::$unit) {
+            let stats = slate_benchmark::alloc::AllocStats::start();
+            let io = slate_benchmark::syscall::UnitIoStats::start();
+            let energy = slate_benchmark::energy::UnitEnergy::start(experiment)?;
+            slate_benchmark::monitor::start_unit(stringify!($unit), $name);
+            let result = $env.retry.run(&format!("{} {}", $name, stringify!($unit)), |attempt| {
+              // 失敗した試行の途中の状態を残さないよう、データベースを構築し直す
+              if attempt > 0 {
+                slate_benchmark::CUT::cleanup(&mut cut)?;
+              }
+              register!(@run experiment, $unit, &mut cut, &$env.$ds);
+              Ok(())
+            });
+            slate_benchmark::monitor::finish_unit(result.is_ok());
+            match result {
+              Ok(()) => {
+                energy.finish(experiment, $name, stringify!($unit))?;
+                io.finish(experiment, $name, stringify!($unit))?;
+                stats.finish(experiment, $name, stringify!($unit))?;
+              }
+              Err(error) => {
+                eprintln!("ERROR: {} failed in {}: {error}", $name, stringify!($unit));
+                let unit = Some($crate::registry::TestUnit::$unit);
+                failures.push($crate::registry::Failure { cut: String::from($name), unit, error });
+                match $env.on_failure {
+                  $crate::registry::OnFailure::SkipCut => break 'units,
+                  $crate::registry::OnFailure::SkipUnit => slate_benchmark::CUT::cleanup(&mut cut)?,
+                }
+              }
+            }
+          }
+        )*
+      }
+      if let Some(every) = $env.validate.filter(|_| failures.is_empty()) {
+        let mut validated = std::collections::HashSet::new();
+        $(
+          if filter.accepts_unit($crate::registry::TestUnit::$unit) {
+            register!(@validate experiment, $unit, &mut cut, &$env.$ds, every, validated);
+          }
+        )*
+      }
+      slate_benchmark::CUT::teardown(&mut cut)
+    })
+  };
+}
@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+use crate::Experiment;
+use crate::error::{BenchError, Result};
+use crate::stat;
+
+#[cfg(test)]
+mod test;
+
+/// Write the report into a temporary file in the same directory and replace `path` with it only after it's completely
+/// written and synced, so that a report inspected during a session or left by a crash is never truncated.
+pub fn write_atomically<F>(path: &Path, write: F) -> Result<()>
+where
+  F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+  let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+  let mut builder = tempfile::Builder::new();
+  builder.prefix(".").suffix(".tmp");
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    builder.permissions(std::fs::Permissions::from_mode(0o644));
+  }
+  let mut temp = builder.tempfile_in(dir)?;
+  {
+    let mut writer = BufWriter::new(temp.as_file_mut());
+    let format = csv_format();
+    if path.extension().is_some_and(|extension| extension == "csv") && format != CsvFormat::default() {
+      let mut buffer = Vec::new();
+      write(&mut buffer)?;
+      writer.write_all(format.convert(&String::from_utf8_lossy(&buffer)).as_bytes())?;
+    } else {
+      write(&mut writer)?;
+    }
+    writer.flush()?;
+  }
+  temp.as_file().sync_all()?;
+  temp.persist(path).map_err(|err| err.error)?;
+
+  // rename をディレクトリに永続化する
+  #[cfg(unix)]
+  File::open(dir)?.sync_all()?;
+  Ok(())
+}
+
+/// Delimiter of the fields of the CSV reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Delimiter {
+  /// カンマ
+  #[default]
+  Comma,
+  /// セミコロン (小数点にカンマを使うロケールの表計算ソフト向け)
+  Semicolon,
+  /// タブ (TSV)
+  Tab,
+}
+
+impl Delimiter {
+  fn as_char(self) -> char {
+    match self {
+      Delimiter::Comma => ',',
+      Delimiter::Semicolon => ';',
+      Delimiter::Tab => '\t',
+    }
+  }
+
+  fn name(self) -> &'static str {
+    match self {
+      Delimiter::Comma => "comma",
+      Delimiter::Semicolon => "semicolon",
+      Delimiter::Tab => "tab",
+    }
+  }
+}
+
+/// Format of the CSV reports, so that the spreadsheets in the locales with the decimal comma chart them as they are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CsvFormat {
+  pub delimiter: Delimiter,
+  /// Write the decimal separator of the numbers as a comma
+  pub decimal_comma: bool,
+}
+
+impl CsvFormat {
+  /// Session metadata of the format, so that the reports of the session are read back in it.
+  pub fn metadata(&self) -> Vec<(String, String)> {
+    vec![
+      (String::from("csv.delimiter"), String::from(self.delimiter.name())),
+      (String::from("csv.decimal-comma"), self.decimal_comma.to_string()),
+    ]
+  }
+
+  /// Format of the reports of the session recorded by [`metadata`](Self::metadata), or the default if not recorded.
+  pub fn of(session: &SessionMetadata) -> Result<Self> {
+    let mut format = Self::default();
+    if let Some(name) = session.entries.get("csv.delimiter") {
+      format.delimiter = Delimiter::from_str(name, true)
+        .map_err(|_| BenchError::Validation(format!("unknown CSV delimiter of {}: {name}", session.session)))?;
+    }
+    format.decimal_comma = session.entries.get("csv.decimal-comma").is_some_and(|value| value == "true");
+    Ok(format)
+  }
+
+  /// Convert the CSV text written with the comma delimiter and the decimal point into this format. The quoted fields
+  /// are kept as they are.
+  pub fn convert(&self, text: &str) -> String {
+    self.translate(text, ',', self.delimiter.as_char(), |field| {
+      if self.decimal_comma && field.parse::<f64>().is_ok() { Some(field.replace('.', ",")) } else { None }
+    })
+  }
+
+  /// Convert the CSV text in this format back into the comma delimiter and the decimal point, so that it's parsed in
+  /// the same way as the default format. The inverse of [`convert`](Self::convert).
+  pub fn normalize(&self, text: &str) -> String {
+    if *self == Self::default() {
+      return String::from(text);
+    }
+    self.translate(text, self.delimiter.as_char(), ',', |field| {
+      let field = field.replace(',', ".");
+      if self.decimal_comma && field.parse::<f64>().is_ok() { Some(field) } else { None }
+    })
+  }
+
+  /// Replace the delimiter `from` out of the double quotes with `to`, and the fields by `number` if it returns some.
+  fn translate<F>(&self, text: &str, from: char, to: char, number: F) -> String
+  where
+    F: Fn(&str) -> Option<String>,
+  {
+    let mut converted = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+      let (line, eol) = line.strip_suffix('\n').map_or((line, ""), |line| (line, "\n"));
+      for (k, field) in split_fields(line, from).into_iter().enumerate() {
+        if k > 0 {
+          converted.push(to);
+        }
+        match number(field) {
+          Some(field) => converted.push_str(&field),
+          None => converted.push_str(field),
+        }
+      }
+      converted.push_str(eol);
+    }
+    converted
+  }
+}
+
+/// Fields of a CSV line separated by the delimiter out of the double quotes.
+fn split_fields(line: &str, delimiter: char) -> Vec<&str> {
+  let (mut fields, mut start, mut quoted) = (Vec::new(), 0, false);
+  for (i, c) in line.char_indices() {
+    match c {
+      '"' => quoted = !quoted,
+      c if c == delimiter && !quoted => {
+        fields.push(&line[start..i]);
+        start = i + c.len_utf8();
+      }
+      _ => {}
+    }
+  }
+  fields.push(&line[start..]);
+  fields
+}
+
+static CSV_FORMAT: OnceLock<CsvFormat> = OnceLock::new();
+
+/// Set the format of the CSV reports written afterwards. Only the first call takes effect.
+pub fn set_csv_format(format: CsvFormat) -> Result<()> {
+  if format.decimal_comma && format.delimiter == Delimiter::Comma {
+    return Err(BenchError::Config(String::from("the decimal comma requires a delimiter other than the comma")));
+  }
+  let _ = CSV_FORMAT.set(format);
+  Ok(())
+}
+
+fn csv_format() -> CsvFormat {
+  CSV_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Convert the CSV text written with the comma delimiter and the decimal point into the format of the reports, for
+/// the reports written other than by [`write_atomically`].
+pub fn csv(text: &str) -> String {
+  let format = csv_format();
+  if format == CsvFormat::default() { String::from(text) } else { format.convert(text) }
+}
+
+/// Append the row to the CSV report, writing the header first if the report is new.
+pub fn append_csv(path: &Path, header: &str, row: &str) -> Result<()> {
+  let is_new = !path.exists();
+  let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+  if is_new {
+    file.write_all(csv(&format!("{header}\n")).as_bytes())?;
+  }
+  file.write_all(csv(&format!("{row}\n")).as_bytes())?;
+  Ok(())
+}
+
+/// Version of the slate crate this benchmark is built with, taken from `Cargo.lock` by the build script.
+pub const SLATE_VERSION: &str = env!("SLATE_VERSION");
+
+/// Git commit of the slate crate, if it's a git dependency.
+pub const SLATE_COMMIT: Option<&str> = option_env!("SLATE_COMMIT");
+
+/// Version of the slate crate with the abbreviated commit, if any, shown in the heading of each test unit.
+pub fn slate_label() -> String {
+  match SLATE_COMMIT {
+    Some(commit) => format!("slate {SLATE_VERSION}@{}", &commit[..commit.len().min(8)]),
+    None => format!("slate {SLATE_VERSION}"),
+  }
+}
+
+/// Session metadata of the slate crate this benchmark is built with, so that the sessions can be compared across the
+/// versions of slate.
+pub fn slate_metadata() -> Vec<(String, String)> {
+  let mut entries = vec![(String::from("slate.version"), String::from(SLATE_VERSION))];
+  entries.extend(SLATE_COMMIT.map(|commit| (String::from("slate.commit"), String::from(commit))));
+  entries
+}
+
+/// Measurements produced by an external tool, e.g. the Scala/JVM implementation of slate, to be placed in the report
+/// directory next to the reports of this benchmark so that they are plotted and compared together.
+///
+/// CSV has the same layout as the reports of this benchmark. The header gives the labels of the x-axis and y-axis, and
+/// each row has the x-value followed by one or more samples. Rows with the same x-value are merged, so that a tool
+/// that writes one sample per row can also be imported.
+///
+/// ```text
+/// SIZE,MILLISECONDS
+/// 1024,0.51,0.49,0.50
+/// ```
+///
+/// JSON has the equivalent structure.
+///
+/// ```text
+/// {"x": "SIZE", "y": "MILLISECONDS", "series": [{"x": 1024, "samples": [0.51, 0.49, 0.50]}]}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalReport {
+  pub x_label: String,
+  pub y_label: String,
+  /// x-values in the order of their first appearance with their samples
+  pub rows: Vec<(String, Vec<f64>)>,
+}
+
+impl ExternalReport {
+  /// Read the file as JSON if its extension is `.json`, otherwise as CSV in the `format`.
+  pub fn load(path: &Path, format: CsvFormat) -> Result<Self> {
+    let text = fs::read_to_string(path)?;
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+      Self::from_json(&text)
+    } else {
+      Self::from_csv(&format.normalize(&text))
+    }
+  }
+
+  /// Read the CSV with the x-values in the first column. Of the reports with the `SERIES` column, only the warm
+  /// accesses are read.
+  pub fn from_csv(text: &str) -> Result<Self> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let header = lines.next().ok_or_else(|| BenchError::Validation(String::from("empty CSV")))?;
+    let Some((x_label, y_label)) = header.split_once(',') else {
+      return Err(BenchError::Validation(format!("the CSV header must have the x and y labels: {header}")));
+    };
+    let series = match y_label.split_once(',') {
+      Some((series, y_label)) if series.trim() == stat::SERIES => Some(y_label),
+      _ => None,
+    };
+    let mut report = Self::new(x_label.trim(), series.unwrap_or(y_label).trim());
+    for line in lines {
+      let mut fields = line.split(',').map(str::trim);
+      let x = fields.next().unwrap_or_default();
+      if series.is_some() && fields.next() != Some(stat::WARM_ACCESS) {
+        continue;
+      }
+      let samples = fields.map(|y| parse_sample(x, y)).collect::<Result<Vec<_>>>()?;
+      report.append(x, samples)?;
+    }
+    Ok(report)
+  }
+
+  pub fn from_json(text: &str) -> Result<Self> {
+    let json = serde_json::from_str::<serde_json::Value>(text)
+      .map_err(|err| BenchError::Validation(format!("invalid JSON: {err}")))?;
+    let label =
+      |key: &str| json[key].as_str().ok_or_else(|| BenchError::Validation(format!("\"{key}\" must be a string")));
+    let mut report = Self::new(label("x")?, label("y")?);
+    let Some(series) = json["series"].as_array() else {
+      return Err(BenchError::Validation(String::from("\"series\" must be an array")));
+    };
+    for point in series {
+      let x = match &point["x"] {
+        serde_json::Value::String(x) => x.clone(),
+        serde_json::Value::Number(x) => x.to_string(),
+        x => return Err(BenchError::Validation(format!("\"x\" must be a number or a string: {x}"))),
+      };
+      let Some(samples) = point["samples"].as_array() else {
+        return Err(BenchError::Validation(format!("\"samples\" of {x} must be an array")));
+      };
+      let samples = samples
+        .iter()
+        .map(|y| y.as_f64().ok_or_else(|| BenchError::Validation(format!("the sample of {x} is not a number: {y}"))))
+        .collect::<Result<Vec<_>>>()?;
+      report.append(&x, samples)?;
+    }
+    Ok(report)
+  }
+
+  fn new(x_label: &str, y_label: &str) -> Self {
+    Self { x_label: String::from(x_label), y_label: String::from(y_label), rows: Vec::new() }
+  }
+
+  fn append(&mut self, x: &str, mut samples: Vec<f64>) -> Result<()> {
+    ensure!(!x.is_empty(), "empty x-value");
+    ensure!(!samples.is_empty(), "no sample for {x}");
+    match self.rows.iter_mut().find(|(x0, _)| x0 == x) {
+      Some((_, ys)) => ys.append(&mut samples),
+      None => self.rows.push((String::from(x), samples)),
+    }
+    Ok(())
+  }
+
+  /// Write in the same format as the reports of this benchmark.
+  pub fn save(&self, path: &Path) -> Result<()> {
+    write_atomically(path, |writer| {
+      writeln!(writer, "{},{}", self.x_label, self.y_label)?;
+      for (x, ys) in self.rows.iter() {
+        let ys = ys.iter().map(|y| format!("{y}")).collect::<Vec<_>>();
+        writeln!(writer, "{x},{}", ys.join(","))?;
+      }
+      Ok(())
+    })
+  }
+}
+
+/// Compare the reports of the same test units run on a disk and on tmpfs as the sessions `disk` and `tmpfs`, and write
+/// the report `io-ratio-{id}.csv` of `output` for each pair of their reports `{id}.csv`, read in the CSV format
+/// recorded in the metadata of each session. Each row has the means on both, their ratio and their difference, which is
+/// the cost of the I/O apart from the cost of the data structure. The files that aren't x-y reports and the x-values
+/// missing in either are skipped.
+///
+/// ```text
+/// SIZE,DISK,TMPFS,RATIO,IO
+/// 1024,0.52,0.13,4,0.39
+/// ```
+///
+/// ## Returns
+/// - the paths of the written reports
+pub fn write_io_ratios(disk: &Experiment, tmpfs: &Experiment, output: &Experiment) -> Result<Vec<PathBuf>> {
+  let mut paths = Vec::new();
+  let formats = (CsvFormat::of(&disk.metadata()?)?, CsvFormat::of(&tmpfs.metadata()?)?);
+  for id in disk.report_names("csv")?.iter() {
+    let load = |session: &Experiment, format| ExternalReport::load(&session.report_path(id), format).ok();
+    let (Some(on_disk), Some(on_tmpfs)) = (load(disk, formats.0), load(tmpfs, formats.1)) else {
+      continue;
+    };
+    let mean = |ys: &[f64]| ys.iter().sum::<f64>() / ys.len() as f64;
+    let rows = on_disk
+      .rows
+      .iter()
+      .filter_map(|(x, ys)| on_tmpfs.rows.iter().find(|(x0, _)| x0 == x).map(|(_, zs)| (x, mean(ys), mean(zs))))
+      .collect::<Vec<_>>();
+    if rows.is_empty() {
+      continue;
+    }
+    let path = output.report_path(&format!("io-ratio-{id}"));
+    write_atomically(&path, |writer| {
+      writeln!(writer, "{},DISK,TMPFS,RATIO,IO", on_disk.x_label)?;
+      for (x, y, z) in rows.iter() {
+        writeln!(writer, "{x},{y},{z},{},{}", y / z, y - z)?;
+      }
+      Ok(())
+    })?;
+    paths.push(path);
+  }
+  Ok(paths)
+}
+
+/// Regression of a report of a session from the same report of the baseline session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+  /// Name of the report, e.g. `get-slate-file.csv`
+  pub report: String,
+  /// x-value whose mean increased the most from the baseline
+  pub x: String,
+  /// Ratio of the mean at x to that of the baseline
+  pub ratio: f64,
+}
+
+/// Compare the reports of `session` with the same reports of `baseline`, read in the CSV format recorded in the
+/// metadata of each session, and find the reports whose mean at any x-value is larger than that of the baseline by more
+/// than `threshold` (0.1 for 10%), as the reports of this benchmark are the times. The files that aren't x-y reports
+/// and the x-values missing in either are skipped.
+///
+/// ## Returns
+/// - the regressions at the x-value of the largest increase of each report, sorted from the largest
+pub fn regressions(session: &Experiment, baseline: &Experiment, threshold: f64) -> Result<Vec<Regression>> {
+  let mut regressions = Vec::new();
+  let formats = (CsvFormat::of(&session.metadata()?)?, CsvFormat::of(&baseline.metadata()?)?);
+  for id in session.report_names("csv")?.iter() {
+    let load = |session: &Experiment, format| ExternalReport::load(&session.report_path(id), format).ok();
+    let (Some(current), Some(base)) = (load(session, formats.0), load(baseline, formats.1)) else {
+      continue;
+    };
+    let mean = |ys: &[f64]| ys.iter().sum::<f64>() / ys.len() as f64;
+    let worst = current
+      .rows
+      .iter()
+      .filter_map(|(x, ys)| base.rows.iter().find(|(x0, _)| x0 == x).map(|(_, zs)| (x, mean(ys) / mean(zs))))
+      .filter(|(_, ratio)| ratio.is_finite())
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some((x, ratio)) = worst.filter(|(_, ratio)| *ratio > 1.0 + threshold) {
+      regressions.push(Regression { report: id.clone(), x: x.clone(), ratio });
+    }
+  }
+  regressions.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+  Ok(regressions)
+}
+
+/// Metadata of a session read from its `{session}.session` marker file, which has `{key}={value}` lines such as the
+/// calibration results and the tags given by `--tag` as `tag.{key}={value}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionMetadata {
+  pub session: String,
+  pub entries: BTreeMap<String, String>,
+}
+
+impl SessionMetadata {
+  /// Read the metadata of all sessions in the report directory in either layout, sorted by the session ID.
+  pub fn load_all(dir_report: &Path) -> Result<Vec<Self>> {
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(dir_report)? {
+      let path = entry?.path();
+      let name = path.file_name().unwrap_or_default().to_string_lossy();
+      // セッションごとのディレクトリに出力したセッションは、その中にマーカーファイルを持つ
+      let marker = path.join(format!("{name}.session"));
+      if let Some(session) = name.strip_suffix(".session").filter(|_| path.is_file()) {
+        sessions.push(Self::parse(session, &fs::read_to_string(&path)?));
+      } else if marker.is_file() {
+        sessions.push(Self::parse(&name, &fs::read_to_string(&marker)?));
+      }
+    }
+    sessions.sort_by(|a, b| a.session.cmp(&b.session));
+    Ok(sessions)
+  }
+
+  pub fn parse(session: &str, text: &str) -> Self {
+    let entries = text.lines().filter_map(|line| line.split_once('=')).map(|(k, v)| (String::from(k), String::from(v)));
+    Self { session: String::from(session), entries: entries.collect() }
+  }
+
+  pub fn tag(&self, key: &str) -> Option<&str> {
+    self.entries.get(&format!("tag.{key}")).map(String::as_str)
+  }
+
+  pub fn tags(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.entries.iter().filter_map(|(k, v)| Some((k.strip_prefix("tag.")?, v.as_str())))
+  }
+
+  /// Whether the session has all the tags.
+  pub fn matches(&self, tags: &[(String, String)]) -> bool {
+    tags.iter().all(|(key, value)| self.tag(key) == Some(value.as_str()))
+  }
+}
+
+fn parse_sample(x: &str, y: &str) -> Result<f64> {
+  y.parse().map_err(|_| BenchError::Validation(format!("the sample of {x} is not a number: {y}")))
+}
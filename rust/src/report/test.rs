@@ -0,0 +1,120 @@
+use std::fs;
+
+use super::*;
+use crate::error::BenchError;
+
+#[test]
+fn replace_report_only_when_completed() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("report.csv");
+  write_atomically(&path, |w| Ok(writeln!(w, "X,Y\n1,2")?)).unwrap();
+  assert_eq!("X,Y\n1,2\n", fs::read_to_string(&path).unwrap());
+
+  // 書き込みに失敗した場合は以前のレポートが残り、一時ファイルも残らない
+  let result = write_atomically(&path, |w| {
+    writeln!(w, "X,Y")?;
+    Err(BenchError::Interrupted(String::from("test")))
+  });
+  assert!(result.is_err());
+  assert_eq!("X,Y\n1,2\n", fs::read_to_string(&path).unwrap());
+  assert_eq!(1, fs::read_dir(dir.path()).unwrap().count());
+}
+
+#[test]
+fn import_external_report() {
+  let csv = ExternalReport::from_csv("SIZE,MILLISECONDS\n1024,0.5,0.25\n2048,1.5\n1024,0.75\n").unwrap();
+  let json = r#"{"x": "SIZE", "y": "MILLISECONDS", "series": [
+    {"x": 1024, "samples": [0.5, 0.25, 0.75]}, {"x": "2048", "samples": [1.5]}
+  ]}"#;
+  assert_eq!(csv, ExternalReport::from_json(json).unwrap());
+
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("report.csv");
+  csv.save(&path).unwrap();
+  assert_eq!("SIZE,MILLISECONDS\n1024,0.5,0.25,0.75\n2048,1.5\n", fs::read_to_string(&path).unwrap());
+  assert_eq!(csv, ExternalReport::load(&path, CsvFormat::default()).unwrap());
+
+  let semicolon = CsvFormat { delimiter: Delimiter::Semicolon, decimal_comma: true };
+  fs::write(&path, "SIZE;MILLISECONDS\n1024;0,5;0,25;0,75\n2048;1,5\n").unwrap();
+  assert_eq!(csv, ExternalReport::load(&path, semicolon).unwrap());
+
+  // 最初のアクセスの系列を持つレポートは warm の系列のみを読む
+  let series = "SIZE,SERIES,MILLISECONDS\n1024,first,9.5\n1024,warm,0.5,0.25\n2048,warm,1.5\n1024,warm,0.75\n";
+  assert_eq!(csv, ExternalReport::from_csv(series).unwrap());
+
+  assert!(matches!(ExternalReport::from_csv("SIZE,MILLISECONDS\n1024,fast\n"), Err(BenchError::Validation(_))));
+  assert!(matches!(ExternalReport::from_csv("SIZE,MILLISECONDS\n1024\n"), Err(BenchError::Validation(_))));
+  assert!(matches!(ExternalReport::from_json(r#"{"x": "SIZE"}"#), Err(BenchError::Validation(_))));
+}
+
+#[test]
+fn io_ratios_of_disk_and_tmpfs() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("s-disk-get-slate-file.csv"), "SIZE,MILLISECONDS\n1,4,6\n2,8\n").unwrap();
+  fs::write(dir.path().join("s-tmpfs-get-slate-file.csv"), "SIZE,MILLISECONDS\n1,1\n3,2\n").unwrap();
+  fs::write(dir.path().join("s-disk-append-slate-file.csv"), "SIZE,MILLISECONDS\n1,4\n").unwrap();
+  fs::write(dir.path().join("s-disk-validation.csv"), "CUT,DATA SIZE,RESULT\nslate-file,1,ok\n").unwrap();
+  fs::write(dir.path().join("s-tmpfs-validation.csv"), "CUT,DATA SIZE,RESULT\nslate-file,1,ok\n").unwrap();
+
+  let experiment = Experiment::new("s", dir.path(), dir.path()).unwrap();
+  let (disk, tmpfs) =
+    (experiment.on_device("disk", dir.path()).unwrap(), experiment.on_device("tmpfs", dir.path()).unwrap());
+  let paths = write_io_ratios(&disk, &tmpfs, &experiment).unwrap();
+  assert_eq!(vec![dir.path().join("s-io-ratio-get-slate-file.csv")], paths);
+  assert_eq!("SIZE,DISK,TMPFS,RATIO,IO\n1,5,1,5,4\n", fs::read_to_string(&paths[0]).unwrap());
+}
+
+#[test]
+fn regressions_from_baseline() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("s-get-slate-file.csv"), "SIZE,MILLISECONDS\n1,4,6\n2,2\n").unwrap();
+  fs::write(dir.path().join("b-get-slate-file.csv"), "SIZE,MILLISECONDS\n1,4\n2,1\n").unwrap();
+  fs::write(dir.path().join("s-append-slate-file.csv"), "SIZE,MILLISECONDS\n1,1.05\n").unwrap();
+  fs::write(dir.path().join("b-append-slate-file.csv"), "SIZE,MILLISECONDS\n1,1\n").unwrap();
+  fs::write(dir.path().join("s-range-slate-file.csv"), "SIZE,MILLISECONDS\n1,3\n").unwrap();
+  fs::write(dir.path().join("b-range-slate-file.csv"), "SIZE,MILLISECONDS\n1,1\n").unwrap();
+  fs::write(dir.path().join("s-prove-slate-file.csv"), "SIZE,MILLISECONDS\n1,9\n").unwrap();
+
+  let session = Experiment::new("s", dir.path(), dir.path()).unwrap();
+  let baseline = Experiment::new("b", dir.path(), dir.path()).unwrap();
+  let regressions = regressions(&session, &baseline, 0.1).unwrap();
+  let found = regressions.iter().map(|r| (r.report.as_str(), r.x.as_str(), r.ratio)).collect::<Vec<_>>();
+  assert_eq!(vec![("range-slate-file.csv", "1", 3.0), ("get-slate-file.csv", "2", 2.0)], found);
+}
+
+#[test]
+fn session_tags() {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("s1.session"), "tag.host=nuc\ntag.fs=xfs\ncalibration.fsync_latency_ms=0.5\n").unwrap();
+  fs::write(dir.path().join("s2.session"), "tag.host=nuc\ntag.fs=ext4\n").unwrap();
+  fs::write(dir.path().join("s2-append-small-slate-file.csv"), "SIZE,MILLISECONDS\n").unwrap();
+  fs::create_dir(dir.path().join("s3")).unwrap();
+  fs::write(dir.path().join("s3").join("s3.session"), "tag.host=rpi\n").unwrap();
+
+  let sessions = SessionMetadata::load_all(dir.path()).unwrap();
+  assert_eq!(vec!["s1", "s2", "s3"], sessions.iter().map(|s| s.session.as_str()).collect::<Vec<_>>());
+  assert_eq!(vec![("fs", "xfs"), ("host", "nuc")], sessions[0].tags().collect::<Vec<_>>());
+  let tag = |key: &str, value: &str| (String::from(key), String::from(value));
+  assert!(sessions[..2].iter().all(|s| s.matches(&[tag("host", "nuc")])));
+  assert_eq!(Some("rpi"), sessions[2].tag("host"));
+  assert!(sessions[1].matches(&[tag("host", "nuc"), tag("fs", "ext4")]));
+  assert!(!sessions[0].matches(&[tag("fs", "ext4")]));
+}
+
+#[test]
+fn convert_csv_format() {
+  let text = "CUT,MEAN,ERROR\nslate-file,1.25,\"x, 0.5\"\n1024,-3.5e-2,\n";
+  let semicolon = CsvFormat { delimiter: Delimiter::Semicolon, decimal_comma: true };
+  assert_eq!("CUT;MEAN;ERROR\nslate-file;1,25;\"x, 0.5\"\n1024;-3,5e-2;\n", semicolon.convert(text));
+  let tab = CsvFormat { delimiter: Delimiter::Tab, decimal_comma: false };
+  assert_eq!("CUT\tMEAN\tERROR\nslate-file\t1.25\t\"x, 0.5\"\n1024\t-3.5e-2\t\n", tab.convert(text));
+  assert_eq!(text, CsvFormat::default().convert(text));
+
+  // 読み込み時は元の形式に戻す
+  assert_eq!(text, semicolon.normalize(&semicolon.convert(text)));
+  assert_eq!(text, tab.normalize(&tab.convert(text)));
+  let metadata = SessionMetadata { session: String::from("s"), entries: semicolon.metadata().into_iter().collect() };
+  assert_eq!(semicolon, CsvFormat::of(&metadata).unwrap());
+  let metadata = SessionMetadata { session: String::from("s"), entries: BTreeMap::new() };
+  assert_eq!(CsvFormat::default(), CsvFormat::of(&metadata).unwrap());
+}
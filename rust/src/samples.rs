@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::error::{BenchError, Result};
+use crate::report::write_atomically;
+use crate::splitmix64;
+
+#[cfg(test)]
+mod test;
+
+/// 生のサンプルの出力形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RawFormat {
+  /// 列指向の Parquet (parquet フィーチャーが有効な場合のみ)
+  Parquet,
+  /// `unit,backend,x,trial,value,timestamp` のヘッダーを持つ CSV
+  Csv,
+  /// 1 行に 1 サンプルの JSON オブジェクト
+  Ndjson,
+}
+
+impl RawFormat {
+  /// Parquet if the `parquet` feature is enabled, otherwise CSV, which is always available.
+  pub const DEFAULT: Self = if cfg!(feature = "parquet") { RawFormat::Parquet } else { RawFormat::Csv };
+}
+
+/// テキスト形式の生のサンプルを書き出しながら圧縮する方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+  None,
+  Gzip,
+  /// zstd フィーチャーが有効な場合のみ
+  Zstd,
+}
+
+/// Format and compression of the raw samples given by `--raw-samples`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawOutput {
+  pub format: RawFormat,
+  pub compression: Compression,
+}
+
+impl RawOutput {
+  /// Check if the format and the compression are available, so that a session doesn't fail at the first report.
+  pub fn check(&self) -> Result<()> {
+    match (self.format, self.compression) {
+      (RawFormat::Parquet, Compression::None) => sys::check(),
+      (RawFormat::Parquet, _) => {
+        Err(BenchError::Config(String::from("Parquet is compressed by itself, --raw-compression is for csv or ndjson")))
+      }
+      (_, Compression::Zstd) if !cfg!(feature = "zstd") => {
+        Err(BenchError::Config(String::from("--raw-compression zstd requires the `zstd` feature")))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Extension of the file, which is `raw.csv` for CSV so as not to be confused with the report.
+  pub fn extension(&self) -> &'static str {
+    match (self.format, self.compression) {
+      (RawFormat::Parquet, _) => "parquet",
+      (RawFormat::Csv, Compression::None) => "raw.csv",
+      (RawFormat::Csv, Compression::Gzip) => "raw.csv.gz",
+      (RawFormat::Csv, Compression::Zstd) => "raw.csv.zst",
+      (RawFormat::Ndjson, Compression::None) => "ndjson",
+      (RawFormat::Ndjson, Compression::Gzip) => "ndjson.gz",
+      (RawFormat::Ndjson, Compression::Zstd) => "ndjson.zst",
+    }
+  }
+}
+
+impl Default for RawOutput {
+  fn default() -> Self {
+    Self { format: RawFormat::Parquet, compression: Compression::None }
+  }
+}
+
+/// Raw samples of a report with the time they were taken, exported by `--raw-samples` for the analysis in
+/// pandas/polars. The samples at each x-value are numbered as the trials in the order they were added. With
+/// `--max-samples`, the kept samples are selected by the reservoir sampling as the reports, so the trials have gaps.
+///
+/// | column    | type                   |
+/// |-----------|------------------------|
+/// | unit      | string                 |
+/// | backend   | string                 |
+/// | x         | string                 |
+/// | trial     | uint32                 |
+/// | value     | float64                |
+/// | timestamp | timestamp[us] (UTC)    |
+///
+/// The timestamp of CSV and NDJSON is the UNIX time in microseconds.
+pub struct RawSamples {
+  output: RawOutput,
+  unit: String,
+  backend: String,
+  samples: BTreeMap<String, Kept>,
+  random: u64,
+}
+
+/// Samples kept at an x-value with the number of the samples added there.
+#[derive(Default)]
+struct Kept {
+  count: u64,
+  /// Trial number, value and the UNIX time in microseconds of each sample
+  samples: Vec<(u32, (f64, i64))>,
+}
+
+impl RawSamples {
+  /// Returns `None` unless `output` is specified, or an error if the output isn't available.
+  pub fn new(output: Option<RawOutput>, unit: &str, backend: &str) -> Result<Option<Self>> {
+    let Some(output) = output else {
+      return Ok(None);
+    };
+    output.check()?;
+    let (unit, backend) = (String::from(unit), String::from(backend));
+    Ok(Some(Self { output, unit, backend, samples: BTreeMap::new(), random: 0 }))
+  }
+
+  /// Add the samples at the x-value, keeping at most `max_samples` of them by the reservoir sampling if specified, so
+  /// that the memory and the output are bounded as the reports.
+  pub fn add<X: Display, I: IntoIterator<Item = f64>>(&mut self, x: &X, ys: I, max_samples: Option<usize>) {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|t| t.as_micros() as i64).unwrap_or_default();
+    let kept = self.samples.entry(x.to_string()).or_default();
+    for y in ys {
+      let sample = (kept.count as u32, (y, timestamp));
+      kept.count += 1;
+      match max_samples {
+        // Algorithm R: t 番目のサンプルは確率 k/t でリザーバーのいずれかと置き換える
+        Some(max_samples) if kept.samples.len() >= max_samples => {
+          self.random = splitmix64(self.random);
+          let j = (self.random % kept.count) as usize;
+          if j < max_samples {
+            kept.samples[j] = sample;
+          }
+        }
+        _ => kept.samples.push(sample),
+      }
+    }
+  }
+
+  /// Extension of the file to save the samples in.
+  pub fn extension(&self) -> &'static str {
+    self.output.extension()
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    match self.output.format {
+      RawFormat::Parquet => sys::save(self, path),
+      RawFormat::Csv => write_atomically(path, |writer| {
+        encode(self.output.compression, writer, |w| {
+          writeln!(w, "unit,backend,x,trial,value,timestamp")?;
+          for (x, trial, (value, timestamp)) in self.rows() {
+            writeln!(w, "{},{},{x},{trial},{value},{timestamp}", self.unit, self.backend)?;
+          }
+          Ok(())
+        })
+      }),
+      RawFormat::Ndjson => write_atomically(path, |writer| {
+        encode(self.output.compression, writer, |w| {
+          for (x, trial, (value, timestamp)) in self.rows() {
+            let row = json!({"unit": self.unit, "backend": self.backend, "x": x, "trial": trial, "value": value,
+              "timestamp": timestamp});
+            writeln!(w, "{row}")?;
+          }
+          Ok(())
+        })
+      }),
+    }
+  }
+
+  /// x-value, trial number, value and timestamp of each sample, in the order of the trials at each x-value.
+  fn rows(&self) -> impl Iterator<Item = (&String, u32, &(f64, i64))> {
+    self.samples.iter().flat_map(|(x, kept)| {
+      let mut samples = kept.samples.iter().collect::<Vec<_>>();
+      samples.sort_unstable_by_key(|(trial, _)| *trial);
+      samples.into_iter().map(move |(trial, y)| (x, *trial, y))
+    })
+  }
+}
+
+/// Write the text through the encoder, so that millions of samples aren't held as plain text.
+fn encode<F>(compression: Compression, writer: &mut dyn Write, write: F) -> Result<()>
+where
+  F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+  match compression {
+    Compression::None => write(writer),
+    Compression::Gzip => {
+      let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+      write(&mut encoder)?;
+      encoder.finish()?;
+      Ok(())
+    }
+    #[cfg(feature = "zstd")]
+    Compression::Zstd => {
+      let mut encoder = zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+      write(&mut encoder)?;
+      encoder.finish()?;
+      Ok(())
+    }
+    #[cfg(not(feature = "zstd"))]
+    Compression::Zstd => Err(BenchError::Config(String::from("--raw-compression zstd requires the `zstd` feature"))),
+  }
+}
+
+#[cfg(feature = "parquet")]
+mod sys {
+  use std::io::Write;
+  use std::path::Path;
+  use std::sync::Arc;
+
+  use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampMicrosecondArray, UInt32Array};
+  use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+  use arrow::record_batch::RecordBatch;
+  use parquet::arrow::ArrowWriter;
+  use parquet::basic::Compression;
+  use parquet::file::properties::WriterProperties;
+
+  use super::RawSamples;
+  use crate::error::{BenchError, Result};
+  use crate::report::write_atomically;
+
+  /// 数百万件のサンプルをまとめて配列にしないよう、この件数ごとにバッチとして書き込む
+  const BATCH_SIZE: usize = 64 * 1024;
+
+  pub fn check() -> Result<()> {
+    Ok(())
+  }
+
+  pub fn save(samples: &RawSamples, path: &Path) -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+      Field::new("unit", DataType::Utf8, false),
+      Field::new("backend", DataType::Utf8, false),
+      Field::new("x", DataType::Utf8, false),
+      Field::new("trial", DataType::UInt32, false),
+      Field::new("value", DataType::Float64, false),
+      Field::new("timestamp", DataType::Timestamp(TimeUnit::Microsecond, Some(Arc::from("UTC"))), false),
+    ]));
+    let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let error = |err: &dyn std::error::Error| BenchError::Io(std::io::Error::other(err.to_string()));
+    let mut writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(props)).map_err(|err| error(&err))?;
+
+    let rows = samples.rows().collect::<Vec<_>>();
+    for chunk in rows.chunks(BATCH_SIZE) {
+      let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(vec![samples.unit.as_str(); chunk.len()])),
+        Arc::new(StringArray::from(vec![samples.backend.as_str(); chunk.len()])),
+        Arc::new(StringArray::from_iter_values(chunk.iter().map(|(x, _, _)| x.as_str()))),
+        Arc::new(UInt32Array::from_iter_values(chunk.iter().map(|(_, trial, _)| *trial))),
+        Arc::new(Float64Array::from_iter_values(chunk.iter().map(|(_, _, (value, _))| *value))),
+        Arc::new(
+          TimestampMicrosecondArray::from_iter_values(chunk.iter().map(|(_, _, (_, t))| *t)).with_timezone("UTC"),
+        ),
+      ];
+      let batch = RecordBatch::try_new(schema.clone(), columns).map_err(|err| error(&err))?;
+      writer.write(&batch).map_err(|err| error(&err))?;
+    }
+    let bytes = writer.into_inner().map_err(|err| error(&err))?;
+    write_atomically(path, |w| Ok(w.write_all(&bytes)?))
+  }
+}
+
+#[cfg(not(feature = "parquet"))]
+mod sys {
+  use std::path::Path;
+
+  use super::RawSamples;
+  use crate::error::{BenchError, Result};
+
+  pub fn check() -> Result<()> {
+    Err(BenchError::Config(String::from("--raw-samples in Parquet requires the `parquet` feature")))
+  }
+
+  pub fn save(_samples: &RawSamples, _path: &Path) -> Result<()> {
+    check()
+  }
+}
@@ -0,0 +1,63 @@
+use std::fs::{self, File};
+use std::io::Read;
+
+use super::*;
+
+fn samples(format: RawFormat, compression: Compression) -> RawSamples {
+  let output = RawOutput { format, compression };
+  let mut samples = RawSamples::new(Some(output), "get", "vec").unwrap().unwrap();
+  samples.add(&1024, [0.5, 0.25], None);
+  samples
+}
+
+#[test]
+fn raw_samples_in_csv_compressed_by_gzip() {
+  let dir = tempfile::tempdir().unwrap();
+  let samples = samples(RawFormat::Csv, Compression::Gzip);
+  assert_eq!("raw.csv.gz", samples.extension());
+  let path = dir.path().join("get-vec.raw.csv.gz");
+  samples.save(&path).unwrap();
+
+  let mut text = String::new();
+  flate2::read::GzDecoder::new(File::open(&path).unwrap()).read_to_string(&mut text).unwrap();
+  let lines = text.lines().map(|line| line.rsplit_once(',').unwrap().0).collect::<Vec<_>>();
+  assert_eq!(vec!["unit,backend,x,trial,value", "get,vec,1024,0,0.5", "get,vec,1024,1,0.25"], lines);
+}
+
+#[test]
+fn raw_samples_in_ndjson() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("get-vec.ndjson");
+  samples(RawFormat::Ndjson, Compression::None).save(&path).unwrap();
+
+  let text = fs::read_to_string(&path).unwrap();
+  let rows = text.lines().map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()).collect::<Vec<_>>();
+  assert_eq!(2, rows.len());
+  assert_eq!(
+    ("1024", 1, 0.25),
+    (rows[1]["x"].as_str().unwrap(), rows[1]["trial"].as_u64().unwrap(), rows[1]["value"].as_f64().unwrap())
+  );
+}
+
+#[test]
+fn raw_samples_bounded_by_max_samples() {
+  let output = RawOutput { format: RawFormat::Csv, compression: Compression::None };
+  let mut samples = RawSamples::new(Some(output), "get", "vec").unwrap().unwrap();
+  for i in 0..100 {
+    samples.add(&1024, [i as f64], Some(10));
+  }
+  samples.add(&2048, [0.5, 0.25], Some(10));
+  let rows = samples.rows().map(|(x, trial, (value, _))| (x.clone(), trial, *value)).collect::<Vec<_>>();
+  assert_eq!(12, rows.len());
+  let trials = rows[..10].iter().map(|(_, trial, _)| *trial).collect::<Vec<_>>();
+  assert!(trials.windows(2).all(|w| w[0] < w[1]));
+  assert!(rows[..10].iter().all(|(x, trial, value)| x == "1024" && *trial as f64 == *value));
+  assert_eq!(vec![(String::from("2048"), 0, 0.5), (String::from("2048"), 1, 0.25)], rows[10..]);
+}
+
+#[test]
+fn parquet_is_not_compressed_again() {
+  let output = RawOutput { format: RawFormat::Parquet, compression: Compression::Gzip };
+  assert!(matches!(output.check(), Err(BenchError::Config(_))));
+  assert!(RawSamples::new(None, "get", "vec").unwrap().is_none());
+}
@@ -0,0 +1,145 @@
+//! Scenarios composed of the primitive steps in the TOML config, run by the `scenario` test unit so that a new
+//! workload doesn't require a new measurement on [`Case`](crate::Case).
+//!
+//! ```toml
+//! [scenarios.read-after-burst]
+//! description = "gets of the recent entries after a burst of appends"
+//! repeat = 3
+//! steps = [
+//!   { prepare = 100000 },
+//!   { append = 10000, rate = 2000.0 },
+//!   { get = 1000, distribution = "zipf", s = 1.2 },
+//!   { prove = 100 },
+//! ]
+//! ```
+use slate::Index;
+use toml::{Table, Value};
+
+use crate::error::{BenchError, Result};
+
+#[cfg(test)]
+mod test;
+
+/// Positions of the gets of a [`Step::Get`] in the current entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+  Uniform,
+  /// Biased to the recent entries by the Zipf distribution of the shape, as the `biased-get` test unit
+  Zipf(f64),
+  /// Always the latest entry
+  Latest,
+}
+
+/// Keys of the kinds of the steps, one of which each step has.
+const KINDS: [&str; 4] = ["prepare", "append", "get", "prove"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+  /// Build the database to n entries, out of the measurement. A larger database is rebuilt from empty.
+  Prepare(Index),
+  /// Append the entries one by one, at most `rate` appends per second if specified
+  Append {
+    count: u64,
+    rate: Option<f64>,
+  },
+  Get {
+    count: u64,
+    distribution: Distribution,
+  },
+  /// Get the auth paths of the entries at the uniformly random positions
+  Prove(u64),
+}
+
+impl Step {
+  /// Label of the step in the reports, prefixed with its 1-based index in the scenario.
+  pub fn label(&self, index: usize) -> String {
+    let step = match self {
+      Step::Prepare(_) => String::from("prepare"),
+      Step::Append { .. } => String::from("append"),
+      Step::Get { distribution: Distribution::Uniform, .. } => String::from("get-uniform"),
+      Step::Get { distribution: Distribution::Zipf(s), .. } => format!("get-zipf{s}"),
+      Step::Get { distribution: Distribution::Latest, .. } => String::from("get-latest"),
+      Step::Prove(_) => String::from("prove"),
+    };
+    // レポートで手順の順に並ぶよう、番号を 0 埋めする
+    format!("{:02}:{step}", index + 1)
+  }
+
+  fn parse(scenario: &str, table: &Table) -> Result<Self> {
+    let err = |msg: String| BenchError::Config(format!("{msg} in the scenario {scenario}"));
+    let count = |key: &str| match table.get(key) {
+      Some(Value::Integer(count)) if *count >= 0 => Ok(*count as u64),
+      Some(value) => Err(err(format!("{key} must be a non-negative integer: {value}"))),
+      None => Err(err(format!("no {key}"))),
+    };
+    let float = |key: &str| match table.get(key) {
+      Some(Value::Float(value)) if *value > 0.0 => Ok(Some(*value)),
+      Some(Value::Integer(value)) if *value > 0 => Ok(Some(*value as f64)),
+      Some(value) => Err(err(format!("{key} must be a positive number: {value}"))),
+      None => Ok(None),
+    };
+
+    let kinds = KINDS.into_iter().filter(|key| table.contains_key(*key));
+    let (step, allowed) = match kinds.collect::<Vec<_>>()[..] {
+      ["prepare"] => (Step::Prepare(count("prepare")?), &[][..]),
+      ["append"] => (Step::Append { count: count("append")?, rate: float("rate")? }, &["rate"][..]),
+      ["get"] => {
+        let (distribution, allowed) = match table.get("distribution").map(|value| value.as_str()) {
+          None | Some(Some("uniform")) => (Distribution::Uniform, &["distribution"][..]),
+          Some(Some("zipf")) => (Distribution::Zipf(float("s")?.unwrap_or(1.2)), &["distribution", "s"][..]),
+          Some(Some("latest")) => (Distribution::Latest, &["distribution"][..]),
+          Some(_) => return Err(err(String::from("distribution must be uniform, zipf or latest"))),
+        };
+        (Step::Get { count: count("get")?, distribution }, allowed)
+      }
+      ["prove"] => (Step::Prove(count("prove")?), &[][..]),
+      _ => return Err(err(String::from("each step must have one of prepare, append, get and prove"))),
+    };
+    if let Some(key) = table.keys().find(|key| !allowed.contains(&key.as_str()) && !KINDS.contains(&key.as_str())) {
+      return Err(err(format!("unknown key {key} of a step")));
+    }
+    Ok(step)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+  pub name: String,
+  pub description: Option<String>,
+  /// Number of the runs of the steps, each from an empty database
+  pub repeat: usize,
+  pub steps: Vec<Step>,
+}
+
+impl Scenario {
+  /// Parse the `[scenarios.NAME]` table of the config.
+  pub fn parse(name: &str, value: &Value) -> Result<Self> {
+    let err = |msg: &str| BenchError::Config(format!("{msg} in the scenario {name}"));
+    let Value::Table(table) = value else {
+      return Err(BenchError::Config(format!("[scenarios.{name}] must be a table")));
+    };
+    if let Some(key) = table.keys().find(|key| !["description", "repeat", "steps"].contains(&key.as_str())) {
+      return Err(err(&format!("unknown key {key}")));
+    }
+    let description = table.get("description").and_then(Value::as_str).map(String::from);
+    let repeat = match table.get("repeat") {
+      Some(Value::Integer(repeat)) if *repeat >= 1 => *repeat as usize,
+      Some(_) => return Err(err("repeat must be a positive integer")),
+      None => 1,
+    };
+    let Some(Value::Array(steps)) = table.get("steps") else {
+      return Err(err("steps must be an array of the steps"));
+    };
+    let steps = steps
+      .iter()
+      .map(|step| match step {
+        Value::Table(step) => Step::parse(name, step),
+        _ => Err(err("each step must be a table")),
+      })
+      .collect::<Result<Vec<_>>>()?;
+    if steps.is_empty() {
+      return Err(err("no steps"));
+    }
+    Ok(Self { name: String::from(name), description, repeat, steps })
+  }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+fn parse(text: &str) -> Result<Scenario> {
+  let table = text.parse::<Table>().unwrap();
+  Scenario::parse("test", &Value::Table(table))
+}
+
+#[test]
+fn parse_the_steps_of_a_scenario() {
+  let scenario = parse(
+    r#"
+    description = "gets after a burst"
+    repeat = 3
+    steps = [
+      { prepare = 100 },
+      { append = 10, rate = 2000 },
+      { get = 5, distribution = "zipf", s = 1.5 },
+      { get = 5, distribution = "latest" },
+      { get = 5 },
+      { prove = 2 },
+    ]
+    "#,
+  )
+  .unwrap();
+  assert_eq!(Some("gets after a burst"), scenario.description.as_deref());
+  assert_eq!(3, scenario.repeat);
+  assert_eq!(
+    vec![
+      Step::Prepare(100),
+      Step::Append { count: 10, rate: Some(2000.0) },
+      Step::Get { count: 5, distribution: Distribution::Zipf(1.5) },
+      Step::Get { count: 5, distribution: Distribution::Latest },
+      Step::Get { count: 5, distribution: Distribution::Uniform },
+      Step::Prove(2),
+    ],
+    scenario.steps
+  );
+  assert_eq!("03:get-zipf1.5", scenario.steps[2].label(2));
+}
+
+#[test]
+fn reject_invalid_steps() {
+  for steps in [
+    "[]",
+    "[{ prepare = 100, rate = 10 }]",
+    "[{ prepare = 100, append = 10 }]",
+    "[{ get = 5, distribution = \"normal\" }]",
+    "[{ get = 5, s = 1.5 }]",
+    "[{ get = 5, distribution = \"latest\", s = 1.5 }]",
+    "[{ append = -1 }]",
+    "[{ rate = 10 }]",
+    "[100]",
+  ] {
+    let err = parse(&format!("steps = {steps}")).unwrap_err();
+    assert!(matches!(err, BenchError::Config(_)), "{steps}: {err:?}");
+  }
+  assert!(parse("repeat = 0\nsteps = [{ prove = 1 }]").is_err());
+  assert!(parse("steps = [{ prove = 1 }]\nrepeats = 2").is_err());
+}
@@ -1,24 +1,100 @@
-use slate::{Index, Result};
-use slate_benchmark::unique_file;
+use slate::Index;
+use slate_benchmark::clock::Stopwatch;
+use slate_benchmark::ensure;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::platform;
+use slate_benchmark::{AppendCUT, CUT, GetCUT, HandleMode, Measurement, unique_file};
 use std::fs::{File, OpenOptions, remove_file};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
-
-use crate::{AppendCUT, CUT, GetCUT};
 
+/// Sequence of the fixed-size (u64) records in a file. The unindexed variant retrieves a record by scanning the file
+/// backward from the end, and the indexed variant seeks directly to the record's offset. The preallocated variant
+/// reserves the storage for all the records in advance, so that the appends don't include the extent allocation.
 pub struct SeqFileCUT {
   path: PathBuf,
   file: Option<File>,
   cache_level: usize,
+  indexed: bool,
+  /// Bytes reserved whenever the file is emptied
+  reserved: Option<u64>,
+  handle_mode: HandleMode,
 }
 
 impl SeqFileCUT {
   pub fn new(dir: &Path) -> Result<Self> {
+    Self::open(dir, false)
+  }
+
+  pub fn indexed(dir: &Path) -> Result<Self> {
+    Self::open(dir, true)
+  }
+
+  /// Unindexed variant with the storage reserved for n records.
+  pub fn preallocated(dir: &Path, n: Index) -> Result<Self> {
+    let mut cut = Self::open(dir, false)?;
+    cut.reserved = Some(n * 8);
+    cut.reserve()?;
+    Ok(cut)
+  }
+
+  fn open(dir: &Path, indexed: bool) -> Result<Self> {
     let path = unique_file(dir, "seqfile", ".db");
     let file = Some(OpenOptions::new().create_new(false).append(false).read(true).write(true).open(&path)?);
     let cache_level = 0;
-    Ok(Self { path, file, cache_level })
+    Ok(Self { path, file, cache_level, indexed, reserved: None, handle_mode: HandleMode::Persistent })
+  }
+
+  fn reserve(&self) -> Result<()> {
+    match self.reserved {
+      Some(len) => platform::reserve(self.file.as_ref().unwrap(), len),
+      None => Ok(()),
+    }
+  }
+
+  /// i 番目のレコードをファイルの末尾から逆順に走査して読み込む。キャッシュレベルは 1 回に読み込むレコード数 (2^level)
+  fn scan(&mut self, i: Index, values: impl Fn(u64) -> u64) -> Result<Measurement> {
+    let file = self.file.as_mut().unwrap();
+    let file_size = file.seek(SeekFrom::End(0))?;
+    ensure!(file_size % 8 == 0, "{file_size} is not a multiple of u64");
+    let mut buffer = vec![0u8; 8 * (1 << self.cache_level)];
+    let mut position = file_size;
+    let mut i_current = file_size / 8;
+    let start = Stopwatch::start();
+    while position > 0 {
+      let read_size = buffer.len().min(position as usize);
+      position -= read_size as u64;
+      file.seek(SeekFrom::Start(position))?;
+      file.read_exact(&mut buffer[..read_size])?;
+      for chunk in buffer[..read_size].rchunks_exact(8) {
+        let value = u64::from_le_bytes(chunk.try_into().unwrap());
+        if i_current == i {
+          let elapse = start.elapsed();
+          ensure!(value == values(i), "unexpected value {value} at {i}");
+          return Ok(Measurement::new(elapse));
+        }
+        i_current -= 1;
+      }
+    }
+    Err(BenchError::Validation(format!("{i} is out of range: n={}", file_size / 8)))
+  }
+
+  /// i 番目のレコードをその位置へのシーク 1 回で読み込む
+  fn seek(&mut self, i: Index, values: impl Fn(u64) -> u64) -> Result<Measurement> {
+    let file = self.file.as_mut().unwrap();
+    let file_size = file.metadata()?.len();
+    ensure!(file_size % 8 == 0, "{file_size} is not a multiple of u64");
+    if i == 0 || i > file_size / 8 {
+      return Err(BenchError::Validation(format!("{i} is out of range: n={}", file_size / 8)));
+    }
+    let mut buffer = [0u8; 8];
+    let start = Stopwatch::start();
+    file.seek(SeekFrom::Start((i - 1) * 8))?;
+    file.read_exact(&mut buffer)?;
+    let elapse = start.elapsed();
+    let value = u64::from_le_bytes(buffer);
+    ensure!(value == values(i), "unexpected value {value} at {i}");
+    Ok(Measurement::new(elapse))
   }
 }
 
@@ -35,7 +111,31 @@ impl Drop for SeqFileCUT {
 
 impl CUT for SeqFileCUT {
   fn implementation(&self) -> String {
-    String::from("seqfile-file")
+    String::from(match (self.indexed, self.reserved.is_some()) {
+      (true, _) => "seqfile-indexed-file",
+      (false, true) => "seqfile-file-preallocated",
+      (false, false) => "seqfile-file",
+    })
+  }
+
+  fn prepare<V: Fn(u64) -> u64, P: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
+    let file = self.file.as_mut().unwrap();
+    let file_size = file.metadata()?.len();
+    ensure!(file_size % 8 == 0, "{file_size} is not a multiple of u64");
+    let size = file_size / 8;
+    ensure!(size <= n, "seqfile {size} is larger than {n}");
+    for i in size + 1..=n {
+      file.write_all(&values(i).to_le_bytes())?;
+      (progress)(1)?;
+    }
+    Ok(())
+  }
+
+  fn cleanup(&mut self) -> Result<()> {
+    let file = self.file.as_mut().unwrap();
+    file.set_len(0)?;
+    // 切り詰めで解放された領域を確保し直す
+    self.reserve()
   }
 }
 
@@ -45,68 +145,36 @@ impl GetCUT for SeqFileCUT {
     Ok(())
   }
 
-  fn prepare<V: Fn(u64) -> u64, P: Fn(Index)>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
-    let file = self.file.as_mut().unwrap();
-    let file_size = file.metadata()?.len();
-    assert!(file_size % 8 == 0, "{file_size} is not a multiple of u64");
-    let size = file_size / 8;
-    assert!(size <= n);
-    for i in size + 1..=n {
-      file.write_all(&values(i).to_le_bytes())?;
-      (progress)(1);
-    }
+  fn set_handle_mode(&mut self, mode: HandleMode) -> Result<()> {
+    self.handle_mode = mode;
     Ok(())
   }
 
   #[inline(never)]
-  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Duration> {
-    let file = self.file.as_mut().unwrap();
-    let file_size = file.seek(SeekFrom::End(0))?;
-    assert!(file_size % 8 == 0);
-    let mut buffer = vec![0u8; 8 * (1 << self.cache_level)];
-    let mut position = file_size;
-    let mut i_current = file_size / 8;
-    let start = Instant::now();
-    while position > 0 {
-      let read_size = buffer.len().min(position as usize);
-      position -= read_size as u64;
-      file.seek(SeekFrom::Start(position))?;
-      file.read_exact(&mut buffer[..read_size])?;
-      for chunk in buffer[..read_size].rchunks_exact(8) {
-        let value = u64::from_le_bytes(chunk.try_into().unwrap());
-        if i_current == i {
-          let elapse = start.elapsed();
-          assert_eq!(values(i), value);
-          return Ok(elapse);
-        }
-        i_current -= 1;
-      }
+  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Measurement> {
+    if self.handle_mode == HandleMode::Reopen {
+      self.file = None;
+      self.file = Some(OpenOptions::new().read(true).write(true).open(&self.path)?);
     }
-    panic!()
+    if self.indexed { self.seek(i, values) } else { self.scan(i, values) }
   }
 }
 
 impl AppendCUT for SeqFileCUT {
   #[inline(never)]
-  fn append<V: Fn(u64) -> u64>(&mut self, n: Index, values: V) -> Result<(u64, Duration)> {
+  fn append<V: Fn(u64) -> u64>(&mut self, n: Index, values: V) -> Result<Measurement> {
     let file = self.file.as_mut().unwrap();
     let file_size = file.metadata()?.len();
     let begin = file_size / 8;
-    assert!(file_size % 8 == 0, "{file_size} is not a multiple of u64");
-    assert!(begin <= n, "begin={begin} is larger than n={n}");
+    ensure!(file_size % 8 == 0, "{file_size} is not a multiple of u64");
+    ensure!(begin <= n, "begin={begin} is larger than n={n}");
     file.seek(SeekFrom::End(0))?;
-    let start = Instant::now();
+    let start = Stopwatch::start();
     for i in (begin + 1)..=n {
       file.write_all(&values(i).to_le_bytes())?;
     }
     let elapse = start.elapsed();
     let size = file.metadata()?.len();
-    Ok((size, elapse))
-  }
-
-  fn clear(&mut self) -> Result<()> {
-    let file = self.file.as_mut().unwrap();
-    file.set_len(0)?;
-    Ok(())
+    Ok(Measurement::new(elapse).with_bytes(size).with_count(n - begin))
   }
 }
@@ -0,0 +1,806 @@
+//! CUTs of slate over the storages created by a [`StorageFactory`], usable from outside the benchmark binary to
+//! measure slate over any `Storage<Entry>` implementation with [`ClosureFactory`].
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "rocksdb")]
+use std::fs::remove_dir_all;
+use std::fs::{OpenOptions, remove_file};
+use std::hint::black_box;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, PoisonError, RwLock};
+
+#[cfg(feature = "rocksdb")]
+use slate::rocksdb::RocksDBStorage;
+use slate::{Entry, FileStorage, Index, Position, Prove, Slate, Storage};
+
+use crate::clock::{self, Stopwatch};
+use crate::counting::{CountingStorage, StorageCounter};
+use crate::error::{BenchError, Result};
+use crate::experiment::HANDSHAKE_TIMEOUT;
+#[cfg(feature = "rocksdb")]
+use crate::knobs::Knobs;
+use crate::payload;
+use crate::{
+  AppendCUT, AuthPathCUT, CUT, ConcurrentGetCUT, CountingCUT, GetCUT, HandleMode, Measurement, MemKVS,
+  MultiProcessGetCUT, ProveCUT, RangeCUT, RemoveOnDrop, RootHashCUT, file_size, platform, splitmix64, unique_file,
+};
+#[cfg(feature = "rocksdb")]
+use crate::{DualWriteCUT, WriteTarget};
+#[cfg(feature = "rocksdb")]
+use crate::{open_rocksdb_storage_with, rocksdb_options};
+
+pub trait StorageFactory<S: Storage<Entry>> {
+  fn name(&self) -> String;
+  fn new_storage(&self) -> slate::Result<S>;
+  fn storage_size(&self) -> slate::Result<u64>;
+  fn clear(&mut self) -> slate::Result<()>;
+  fn alternate(&self) -> slate::Result<Self>
+  where
+    Self: std::marker::Sized;
+}
+
+pub struct SlateCUT<S: Storage<Entry>, F: StorageFactory<S>> {
+  factory: Option<F>,
+  slate: Option<Slate<S>>,
+  auth_paths: AuthPathCache,
+  handle_mode: HandleMode,
+  _phantom: PhantomData<S>,
+}
+
+/// Memoization of the auth paths computed recently, evicted in FIFO order. An auth path depends on the latest
+/// generation, so the cache is cleared whenever an entry is appended.
+struct AuthPathCache {
+  capacity: usize,
+  paths: HashMap<Index, slate::AuthPath>,
+  order: VecDeque<Index>,
+}
+
+impl AuthPathCache {
+  fn new(capacity: usize) -> Self {
+    Self { capacity, paths: HashMap::with_capacity(capacity), order: VecDeque::with_capacity(capacity) }
+  }
+
+  fn get(&self, i: Index) -> Option<&slate::AuthPath> {
+    self.paths.get(&i)
+  }
+
+  fn insert(&mut self, i: Index, auth_path: slate::AuthPath) {
+    if self.capacity == 0 {
+      return;
+    }
+    while self.order.len() >= self.capacity {
+      let oldest = self.order.pop_front().unwrap();
+      self.paths.remove(&oldest);
+    }
+    self.order.push_back(i);
+    self.paths.insert(i, auth_path);
+  }
+
+  fn clear(&mut self) {
+    self.paths.clear();
+    self.order.clear();
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> SlateCUT<S, F> {
+  pub fn new(factory: F) -> Result<Self> {
+    let storage = factory.new_storage()?;
+    let slate = Some(Slate::with_cache_level(storage, 0)?);
+    let factory = Some(factory);
+    let handle_mode = HandleMode::Persistent;
+    Ok(Self { factory, slate, auth_paths: AuthPathCache::new(0), handle_mode, _phantom: PhantomData })
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> Drop for SlateCUT<S, F> {
+  fn drop(&mut self) {
+    drop(self.slate.take());
+    drop(self.factory.take());
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> CUT for SlateCUT<S, F> {
+  fn implementation(&self) -> String {
+    self.factory.as_ref().unwrap().name()
+  }
+
+  fn prepare<V: Fn(u64) -> u64, P: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
+    let slate = self.slate.as_mut().unwrap();
+    if slate.n() != n {
+      self.auth_paths.clear();
+      ensure!(slate.n() < n, "slate {} is larger than {n}", slate.n());
+      (progress)(slate.n())?;
+      while slate.n() < n {
+        let length = (n - slate.n()).min(1024);
+        for i in (slate.n() + 1)..=n.min(slate.n() + 1 + length) {
+          append_value(slate, i, values(i))?;
+        }
+        (progress)(length)?;
+      }
+    } else {
+      (progress)(slate.n())?;
+    }
+    Ok(())
+  }
+
+  fn cleanup(&mut self) -> Result<()> {
+    self.auth_paths.clear();
+    drop(self.slate.take());
+    self.factory.as_mut().unwrap().clear()?;
+    let storage = self.factory.as_ref().unwrap().new_storage()?;
+    self.slate = Some(Slate::with_cache_level(storage, 0)?);
+    Ok(())
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> AppendCUT for SlateCUT<S, F> {
+  #[inline(never)]
+  fn append<V: Fn(u64) -> u64>(&mut self, n: Index, values: V) -> Result<Measurement> {
+    let slate = self.slate.as_mut().unwrap();
+    ensure!(slate.n() <= n, "slate {} is larger than {n}", slate.n());
+    let begin = slate.n();
+    if begin != n {
+      self.auth_paths.clear();
+    }
+    let start = Stopwatch::start();
+    while slate.n() < n {
+      let i = slate.n() + 1;
+      append_value(slate, i, values(i))?;
+    }
+    let elapse = start.elapsed();
+    let size = self.factory.as_ref().unwrap().storage_size()?;
+    Ok(Measurement::new(elapse).with_bytes(size).with_count(n - begin))
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> GetCUT for SlateCUT<S, F> {
+  fn set_cache_level(&mut self, cache_level: usize) -> Result<()> {
+    if self.slate.as_ref().unwrap().cache().level() != cache_level {
+      drop(self.slate.take());
+      let storage = self.factory.as_ref().unwrap().new_storage()?;
+      self.slate = Some(Slate::with_cache_level(storage, cache_level)?);
+    }
+    Ok(())
+  }
+
+  fn set_handle_mode(&mut self, mode: HandleMode) -> Result<()> {
+    self.handle_mode = mode;
+    Ok(())
+  }
+
+  #[inline(never)]
+  fn get<V: Fn(u64) -> u64>(&mut self, i: Index, values: V) -> Result<Measurement> {
+    if self.handle_mode == HandleMode::Reopen {
+      let cache_level = self.slate.take().unwrap().cache().level();
+      let storage = self.factory.as_ref().unwrap().new_storage()?;
+      self.slate = Some(Slate::with_cache_level(storage, cache_level)?);
+    }
+    let slate = self.slate.as_mut().unwrap();
+    ensure!(slate.n() >= i, "n={} less than i={}", slate.n(), i);
+    let start = Stopwatch::start();
+    let value = slate.snapshot().query()?.get(i)?;
+    let elapsed = start.elapsed();
+    check_value(i, value, values(i))?;
+    Ok(Measurement::new(elapsed))
+  }
+
+  #[inline(never)]
+  fn get_batch<V: Fn(u64) -> u64>(&mut self, is: &[Index], values: V) -> Result<Measurement> {
+    let slate = self.slate.as_mut().unwrap();
+    ensure!(is.iter().all(|i| *i <= slate.n()), "n={} less than the positions {is:?}", slate.n());
+    let mut read = Vec::with_capacity(is.len());
+    let start = Stopwatch::start();
+    for i in is.iter() {
+      read.push(slate.snapshot().query()?.get(*i)?);
+    }
+    let elapsed = start.elapsed();
+    for (i, value) in is.iter().zip(read) {
+      check_value(*i, value, values(*i))?;
+    }
+    Ok(Measurement::new(elapsed).with_count(is.len() as u64))
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> AuthPathCUT for SlateCUT<S, F> {
+  fn set_auth_path_cache(&mut self, capacity: usize) -> Result<()> {
+    self.auth_paths = AuthPathCache::new(capacity);
+    Ok(())
+  }
+
+  #[inline(never)]
+  fn get_auth_path(&mut self, i: Index) -> Result<Measurement> {
+    let slate = self.slate.as_mut().unwrap();
+    ensure!(slate.n() >= i, "n={} less than i={}", slate.n(), i);
+    let start = Stopwatch::start();
+    if let Some(auth_path) = self.auth_paths.get(i) {
+      black_box(auth_path);
+      return Ok(Measurement::new(start.elapsed()));
+    }
+    let auth_path = slate.snapshot().query()?.get_auth_path(i)?;
+    let elapsed = start.elapsed();
+    let Some(auth_path) = auth_path else {
+      return Err(BenchError::Validation(format!("no auth path at i={i}")));
+    };
+    self.auth_paths.insert(i, auth_path);
+    Ok(Measurement::new(elapsed))
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> RootHashCUT for SlateCUT<S, F> {
+  #[inline(never)]
+  fn root_hash(&mut self) -> Result<Measurement> {
+    let slate = self.slate.as_mut().unwrap();
+    let n = slate.n();
+    // 最新のエントリの認証パスが木全体のルートに到達する
+    let start = Stopwatch::start();
+    let auth_path = slate.snapshot().query()?.get_auth_path(n)?;
+    let found = auth_path.as_ref().map(|auth_path| black_box(auth_path.root())).is_some();
+    let elapsed = start.elapsed();
+    ensure!(found, "no root hash at n={n}");
+    Ok(Measurement::new(elapsed))
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> RangeCUT for SlateCUT<S, F> {
+  #[inline(never)]
+  fn scan<V: Fn(u64) -> u64>(&mut self, i: Index, m: Index, values: V) -> Result<Measurement> {
+    let slate = self.slate.as_mut().unwrap();
+    ensure!(slate.n() >= i + m - 1, "n={} less than i+m-1={}", slate.n(), i + m - 1);
+    let start = Stopwatch::start();
+    let mut query = slate.snapshot().query()?;
+    let mut scanned = Vec::with_capacity(m as usize);
+    for k in i..i + m {
+      scanned.push(query.get(k)?);
+    }
+    let elapsed = start.elapsed();
+    for (k, value) in (i..).zip(scanned) {
+      check_value(k, value, values(k))?;
+    }
+    Ok(Measurement::new(elapsed).with_count(m))
+  }
+}
+
+impl<S, F> ConcurrentGetCUT for SlateCUT<S, F>
+where
+  S: Storage<Entry> + Sync + Send,
+  F: StorageFactory<S> + Sync + Send,
+{
+  #[inline(never)]
+  fn concurrent_get<V: Fn(u64) -> u64 + Sync>(
+    &mut self,
+    threads: usize,
+    is: &[Index],
+    values: V,
+  ) -> Result<Measurement> {
+    let slate = self.slate.as_ref().unwrap();
+    let values = &values;
+    let start = Stopwatch::start();
+    let results = std::thread::scope(|scope| {
+      let handles = is
+        .chunks(is.len().div_ceil(threads))
+        .map(|is| {
+          scope.spawn(move || -> Result<()> {
+            let mut query = slate.snapshot().query()?;
+            for i in is.iter() {
+              check_value(*i, query.get(*i)?, values(*i))?;
+            }
+            Ok(())
+          })
+        })
+        .collect::<Vec<_>>();
+      handles
+        .into_iter()
+        .map(|handle| {
+          handle.join().unwrap_or_else(|_| Err(BenchError::Interrupted(String::from("reader thread panicked"))))
+        })
+        .collect::<Result<Vec<()>>>()
+    });
+    let elapsed = start.elapsed();
+    results?;
+    Ok(Measurement::new(elapsed).with_count(is.len() as u64))
+  }
+}
+
+impl<S, F> ProveCUT for SlateCUT<S, F>
+where
+  S: Storage<Entry> + Sync + Send,
+  F: StorageFactory<S> + Sync + Send,
+{
+  #[inline(never)]
+  fn prove(&self, other: &Self) -> Result<(Option<u64>, Measurement)> {
+    let slate1 = self.slate.as_ref().unwrap();
+    let slate2 = other.slate.as_ref().unwrap();
+    let mut query1 = slate1.snapshot().query()?;
+    let mut query2 = slate2.snapshot().query()?;
+
+    let start = Stopwatch::start();
+    let (Some(mut auth_path1), Some(mut auth_path2)) =
+      (query1.get_auth_path(slate1.n())?, query2.get_auth_path(slate2.n())?)
+    else {
+      return Err(BenchError::Validation(format!("no auth path at n={}", slate1.n())));
+    };
+    let diff = loop {
+      match auth_path2.prove(&auth_path1)? {
+        Prove::Identical => break None,
+        Prove::Divergent(divergents) => {
+          let (min_i, min_j) = divergents.iter().min().unwrap();
+          if *min_j == 0 {
+            break Some(*min_i);
+          }
+          auth_path1 = query1.get_auth_path(*min_i)?.unwrap();
+          auth_path2 = query2.get_auth_path(*min_i)?.unwrap();
+        }
+      }
+    };
+    let elapse = start.elapsed();
+    Ok((diff, Measurement::new(elapse)))
+  }
+
+  fn alternate(&self) -> Result<Self> {
+    Self::new(self.factory.as_ref().unwrap().alternate()?)
+  }
+}
+
+/// Append the i-th value, or the payload of `--append-dataset` in its place. A value other than the splitmix64 one,
+/// such as the divergent entry of the prove test unit, is appended as it is.
+#[inline(always)]
+fn append_value<S: Storage<Entry>>(slate: &mut Slate<S>, i: Index, value: u64) -> Result<()> {
+  slate.append(value_bytes(i, &value.to_le_bytes()))?;
+  Ok(())
+}
+
+/// Bytes appended as the value at i by [`append_value`]: the payload of `--append-dataset` if the value is the one
+/// generated for i, otherwise the value itself.
+#[inline(always)]
+fn value_bytes(i: Index, value: &[u8; 8]) -> &[u8] {
+  match payload::payload(i) {
+    Some(payload) if u64::from_le_bytes(*value) == splitmix64(i) => payload,
+    _ => value,
+  }
+}
+
+/// Check the value read at i against the one appended by [`append_value`].
+fn check_value<B: AsRef<[u8]>>(i: Index, value: Option<B>, expected: u64) -> Result<()> {
+  let value = value.as_ref().map(AsRef::as_ref);
+  match payload::payload(i) {
+    Some(payload) if expected == splitmix64(i) => {
+      ensure!(value == Some(payload), "unexpected payload of {:?} bytes at {i}", value.map(<[u8]>::len));
+    }
+    _ => {
+      let value = value.map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+      ensure!(value == Some(expected), "unexpected value {value:?} at {i}");
+    }
+  }
+  Ok(())
+}
+
+// --- Closure ---
+
+type Closure<T> = Arc<dyn Fn() -> slate::Result<T> + Send + Sync>;
+
+/// [`StorageFactory`] assembled from closures, to benchmark slate over any `Storage<Entry>` implementation without
+/// writing a dedicated factory.
+pub struct ClosureFactory<S: Storage<Entry>> {
+  name: String,
+  new_storage: Closure<S>,
+  storage_size: Closure<u64>,
+  clear: Closure<()>,
+  alternate: Option<Closure<ClosureFactory<S>>>,
+}
+
+impl<S: Storage<Entry>> ClosureFactory<S> {
+  pub fn new<N, Z, C>(name: &str, new_storage: N, storage_size: Z, clear: C) -> Self
+  where
+    N: Fn() -> slate::Result<S> + Send + Sync + 'static,
+    Z: Fn() -> slate::Result<u64> + Send + Sync + 'static,
+    C: Fn() -> slate::Result<()> + Send + Sync + 'static,
+  {
+    Self {
+      name: String::from(name),
+      new_storage: Arc::new(new_storage),
+      storage_size: Arc::new(storage_size),
+      clear: Arc::new(clear),
+      alternate: None,
+    }
+  }
+
+  /// Set the closure to create an independent factory, which is required for the prove test unit.
+  pub fn with_alternate<A>(mut self, alternate: A) -> Self
+  where
+    A: Fn() -> slate::Result<ClosureFactory<S>> + Send + Sync + 'static,
+  {
+    self.alternate = Some(Arc::new(alternate));
+    self
+  }
+}
+
+impl<S: Storage<Entry>> StorageFactory<S> for ClosureFactory<S> {
+  fn name(&self) -> String {
+    self.name.clone()
+  }
+
+  fn new_storage(&self) -> slate::Result<S> {
+    (self.new_storage)()
+  }
+
+  fn storage_size(&self) -> slate::Result<u64> {
+    (self.storage_size)()
+  }
+
+  fn clear(&mut self) -> slate::Result<()> {
+    (self.clear)()
+  }
+
+  fn alternate(&self) -> slate::Result<Self> {
+    match &self.alternate {
+      Some(alternate) => alternate(),
+      None => {
+        let msg = format!("{} has no alternate factory", self.name);
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, msg).into())
+      }
+    }
+  }
+}
+
+// --- Counting ---
+
+/// Factory wrapping the storages of another factory in [`CountingStorage`] sharing a counter, for the `-counted` CUTs
+/// of `--count-storage`.
+pub struct CountingFactory<F> {
+  inner: F,
+  counter: StorageCounter,
+}
+
+impl<F> CountingFactory<F> {
+  pub fn new(inner: F) -> Self {
+    CountingFactory { inner, counter: StorageCounter::default() }
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> StorageFactory<CountingStorage<S>> for CountingFactory<F> {
+  fn name(&self) -> String {
+    format!("{}-counted", self.inner.name())
+  }
+
+  fn new_storage(&self) -> slate::Result<CountingStorage<S>> {
+    Ok(CountingStorage::with_counter(self.inner.new_storage()?, self.counter.clone()))
+  }
+
+  fn storage_size(&self) -> slate::Result<u64> {
+    self.inner.storage_size()
+  }
+
+  fn clear(&mut self) -> slate::Result<()> {
+    self.inner.clear()
+  }
+
+  fn alternate(&self) -> slate::Result<Self> {
+    Ok(Self::new(self.inner.alternate()?))
+  }
+}
+
+impl<S: Storage<Entry>, F: StorageFactory<S>> CountingCUT for SlateCUT<CountingStorage<S>, CountingFactory<F>> {
+  fn storage_counter(&self) -> StorageCounter {
+    self.factory.as_ref().unwrap().counter.clone()
+  }
+}
+
+// --- MemKVS ---
+
+/// Factory of slate over [`MemKVS`], assembled with [`ClosureFactory`].
+pub fn memkvs_factory(capacity: usize) -> ClosureFactory<MemKVS<Entry>> {
+  let cache = Arc::new(RwLock::new(HashMap::<Position, Entry>::with_capacity(capacity)));
+  let (storage_cache, clear_cache) = (cache.clone(), cache.clone());
+  ClosureFactory::new(
+    "slate-memkvs",
+    move || Ok(MemKVS::with_kvs(storage_cache.clone())),
+    || Ok(0u64),
+    move || {
+      clear_cache.write()?.clear();
+      Ok(())
+    },
+  )
+  .with_alternate(move || Ok(memkvs_factory(cache.read()?.capacity())))
+}
+
+/// Entry of slate holding the payload, for the serialization test unit. The entry is the 1024th one in an in-memory
+/// slate so that it has as many inner nodes as an entry in a typical database.
+pub fn entry_with_payload(payload: &[u8]) -> Result<Entry> {
+  const N: Index = 1024;
+  let kvs = Arc::new(RwLock::new(HashMap::<Position, Entry>::new()));
+  let mut slate = Slate::with_cache_level(MemKVS::with_kvs(kvs.clone()), 0)?;
+  for i in 1..N {
+    slate.append(&splitmix64(i).to_le_bytes())?;
+  }
+  slate.append(payload)?;
+  drop(slate);
+  let kvs = kvs.read().unwrap_or_else(PoisonError::into_inner);
+  let entry = kvs.iter().max_by_key(|(position, _)| **position).map(|(_, entry)| entry.clone());
+  entry.ok_or_else(|| BenchError::Validation(String::from("no entry written in the in-memory slate")))
+}
+
+// --- File --
+
+/// Number of the entries written into a probe file to estimate the file size per entry.
+const PROBE_ENTRIES: Index = 4096;
+
+pub struct FileFactory {
+  path: PathBuf,
+  /// Bytes reserved whenever the file is created
+  reserved: Option<u64>,
+}
+
+impl FileFactory {
+  pub fn new(dir: &Path) -> Self {
+    let path = unique_file(dir, "slate-file", ".db");
+    Self { path, reserved: None }
+  }
+
+  /// Variant that reserves the storage for n entries, so that the appends don't include the extent allocation. The
+  /// size is estimated from a probe file of the first [`PROBE_ENTRIES`] entries.
+  pub fn preallocated(dir: &Path, n: Index) -> Result<Self> {
+    let probe = RemoveOnDrop::new(unique_file(dir, "slate-probe", ".db"));
+    let mut slate = Slate::with_cache_level(FileStorage::from_file(probe.path(), false)?, 0)?;
+    for i in 1..=PROBE_ENTRIES.min(n) {
+      append_value(&mut slate, i, splitmix64(i))?;
+    }
+    drop(slate);
+    let bytes_per_entry = file_size(probe.path()) as f64 / PROBE_ENTRIES.min(n) as f64;
+    let mut factory = Self::new(dir);
+    factory.reserved = Some((bytes_per_entry * n as f64).ceil() as u64);
+    Ok(factory)
+  }
+}
+
+impl Drop for FileFactory {
+  fn drop(&mut self) {
+    if let Err(e) = self.clear() {
+      eprintln!("WARN: Failed to delete file {:?}: {}", self.path, e);
+    }
+  }
+}
+
+impl MultiProcessGetCUT for SlateCUT<FileStorage, FileFactory> {
+  fn reader_command(&self, n: Index, count: usize, seed: u64) -> Result<Command> {
+    let path = &self.factory.as_ref().unwrap().path;
+    let mut command = Command::new(std::env::current_exe()?);
+    // 親プロセスの引数はサブコマンドと併用できないため、クロックはサブコマンドのオプションとして渡す
+    command.arg("shared-reader").arg("--clock").arg(clock::clock().name());
+    if let Some(dataset) = payload::dataset() {
+      command.arg("--append-dataset").arg(dataset.path());
+      command.arg("--append-dataset-format").arg(dataset.format().name());
+      command.arg("--append-dataset-blake3").arg(dataset.fingerprint());
+    }
+    command.arg(path).args([n.to_string(), count.to_string(), seed.to_string()]);
+    Ok(command)
+  }
+}
+
+/// Reader process of [`MultiProcessGetCUT`] on the slate file written by another process, see
+/// [`MultiProcessGetCUT::reader_command`].
+pub fn read_shared(path: &Path, n: Index, count: usize, seed: u64) -> Result<()> {
+  let slate = Slate::with_cache_level(FileStorage::from_file(path, true)?, 0)?;
+  ensure!(slate.n() >= n, "slate {} is smaller than {n}", slate.n());
+  let mut out = std::io::stdout().lock();
+  writeln!(out, "ready")?;
+  out.flush()?;
+  // 親プロセスが開始を指示しないまま止まっても待ち続けないよう、標準入力は別スレッドで読み期限付きで待つ
+  let (tx, rx) = std::sync::mpsc::channel();
+  std::thread::spawn(move || tx.send(std::io::stdin().read_line(&mut String::new())));
+  let Ok(result) = rx.recv_timeout(HANDSHAKE_TIMEOUT) else {
+    let msg = format!("no start of the gets from the parent process in {}s", HANDSHAKE_TIMEOUT.as_secs());
+    return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, msg).into());
+  };
+  result?;
+
+  let mut state = seed;
+  for _ in 0..count {
+    state = splitmix64(state);
+    let i = state % n + 1;
+    let start = Stopwatch::start();
+    let value = slate.snapshot().query()?.get(i)?;
+    let elapsed = start.elapsed();
+    check_value(i, value, splitmix64(i))?;
+    writeln!(out, "{}", elapsed.as_nanos())?;
+  }
+  Ok(())
+}
+
+impl StorageFactory<FileStorage> for FileFactory {
+  fn name(&self) -> String {
+    String::from(if self.reserved.is_some() { "slate-file-preallocated" } else { "slate-file" })
+  }
+
+  fn new_storage(&self) -> slate::Result<FileStorage> {
+    if let Some(len) = self.reserved {
+      let file = OpenOptions::new().create(true).truncate(false).write(true).open(&self.path)?;
+      platform::reserve(&file, len).map_err(|err| std::io::Error::other(err.to_string()))?;
+    }
+    FileStorage::from_file(&self.path, false)
+  }
+
+  fn storage_size(&self) -> slate::Result<u64> {
+    Ok(file_size(&self.path))
+  }
+
+  fn clear(&mut self) -> slate::Result<()> {
+    if self.path.exists() {
+      remove_file(&self.path)?;
+    }
+    Ok(())
+  }
+
+  fn alternate(&self) -> slate::Result<Self> {
+    let mut factory = Self::new(&PathBuf::from(self.path.parent().unwrap()));
+    factory.reserved = self.reserved;
+    Ok(factory)
+  }
+}
+
+// --- RocksDB ---
+
+#[cfg(feature = "rocksdb")]
+pub struct RocksDBFactory {
+  lock_file: PathBuf,
+  knobs: Knobs,
+}
+
+#[cfg(feature = "rocksdb")]
+impl RocksDBFactory {
+  pub fn new(dir: &Path, knobs: &Knobs) -> Self {
+    let lock_file = unique_file(dir, "slate-rocksdb", ".lock");
+    assert!(lock_file.is_file());
+    Self { lock_file, knobs: knobs.clone() }
+  }
+
+  pub fn data_dir(&self) -> PathBuf {
+    let mut dir = self.lock_file.clone();
+    dir.set_extension("db");
+    dir
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl Drop for RocksDBFactory {
+  fn drop(&mut self) {
+    if let Err(e) = self.clear() {
+      eprintln!("WARN: Failed to delete directory {:?}: {}", self.data_dir(), e);
+    }
+    if self.lock_file.exists() {
+      if let Err(e) = remove_file(&self.lock_file) {
+        eprintln!("WARN: Failed to delete file {:?}: {}", self.lock_file, e);
+      }
+    }
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl StorageFactory<RocksDBStorage> for RocksDBFactory {
+  fn name(&self) -> String {
+    String::from("slate-rocksdb")
+  }
+
+  fn new_storage(&self) -> slate::Result<RocksDBStorage> {
+    open_rocksdb_storage_with(&self.data_dir(), &self.knobs)
+  }
+
+  fn storage_size(&self) -> slate::Result<u64> {
+    Ok(file_size(self.data_dir()))
+  }
+
+  fn clear(&mut self) -> slate::Result<()> {
+    let dir = self.data_dir();
+    if dir.exists() {
+      remove_dir_all(&dir)?;
+    }
+    Ok(())
+  }
+
+  fn alternate(&self) -> slate::Result<Self> {
+    Ok(Self::new(&PathBuf::from(self.lock_file.parent().unwrap()), &self.knobs))
+  }
+}
+
+// --- Dual write ---
+
+/// slate on a file written alongside RocksDB as the primary store, see [`DualWriteCUT`].
+#[cfg(feature = "rocksdb")]
+pub struct DualWriteRocksDBCUT {
+  lock_file: PathBuf,
+  knobs: Knobs,
+  primary: Option<rocksdb::DB>,
+  slate: SlateCUT<FileStorage, FileFactory>,
+}
+
+#[cfg(feature = "rocksdb")]
+impl DualWriteRocksDBCUT {
+  pub fn new(dir: &Path, knobs: &Knobs) -> Result<Self> {
+    let lock_file = unique_file(dir, "dual-write-rocksdb", ".lock");
+    let slate = SlateCUT::new(FileFactory::new(dir))?;
+    let mut cut = Self { lock_file, knobs: knobs.clone(), primary: None, slate };
+    cut.primary = Some(cut.open_primary()?);
+    Ok(cut)
+  }
+
+  fn data_dir(&self) -> PathBuf {
+    let mut dir = self.lock_file.clone();
+    dir.set_extension("db");
+    dir
+  }
+
+  /// Open the primary store with the same options as the RocksDB storage of slate.
+  fn open_primary(&self) -> Result<rocksdb::DB> {
+    let opts = rocksdb_options(&self.knobs);
+    Ok(rocksdb::DB::open(&opts, self.data_dir()).map_err(slate::error::Error::from)?)
+  }
+
+  fn clear_primary(&mut self) -> Result<()> {
+    drop(self.primary.take());
+    let dir = self.data_dir();
+    if dir.exists() {
+      remove_dir_all(&dir)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl Drop for DualWriteRocksDBCUT {
+  fn drop(&mut self) {
+    if let Err(e) = self.clear_primary() {
+      eprintln!("WARN: Failed to delete directory {:?}: {}", self.data_dir(), e);
+    }
+    if self.lock_file.exists() {
+      if let Err(e) = remove_file(&self.lock_file) {
+        eprintln!("WARN: Failed to delete file {:?}: {}", self.lock_file, e);
+      }
+    }
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl CUT for DualWriteRocksDBCUT {
+  fn implementation(&self) -> String {
+    String::from("dual-write-rocksdb")
+  }
+
+  fn prepare<V: Fn(u64) -> u64, P: Fn(Index) -> Result<()>>(&mut self, n: Index, values: V, progress: P) -> Result<()> {
+    let primary = self.primary.as_ref().unwrap();
+    for i in self.slate.slate.as_ref().unwrap().n() + 1..=n {
+      primary.put(i.to_be_bytes(), value_bytes(i, &values(i).to_le_bytes())).map_err(slate::error::Error::from)?;
+    }
+    self.slate.prepare(n, values, progress)
+  }
+
+  fn cleanup(&mut self) -> Result<()> {
+    self.clear_primary()?;
+    self.primary = Some(self.open_primary()?);
+    self.slate.cleanup()
+  }
+}
+
+#[cfg(feature = "rocksdb")]
+impl DualWriteCUT for DualWriteRocksDBCUT {
+  #[inline(never)]
+  fn write(&mut self, target: WriteTarget, i: Index, value: u64) -> Result<Measurement> {
+    let primary = self.primary.as_ref().unwrap();
+    let log = self.slate.slate.as_mut().unwrap();
+    if target != WriteTarget::Primary {
+      ensure!(log.n() + 1 == i, "slate {} is not followed by {i}", log.n());
+    }
+    // 主ストアと slate には同じバイト列 (--append-dataset のペイロードがあればそれ) を書き込む
+    let (key, value) = (i.to_be_bytes(), value.to_le_bytes());
+    let value = value_bytes(i, &value);
+    let start = Stopwatch::start();
+    if target != WriteTarget::Slate {
+      primary.put(key, value).map_err(slate::error::Error::from)?;
+    }
+    if target != WriteTarget::Primary {
+      log.append(value)?;
+    }
+    let elapsed = start.elapsed();
+    Ok(Measurement::new(elapsed))
+  }
+}
@@ -1,14 +1,146 @@
-use crate::IntoFloat;
+use crate::error::Result;
+use crate::interleave;
+use crate::report::write_atomically;
+use crate::samples::RawSamples;
+use crate::writer;
+use crate::{IntoFloat, splitmix64};
 use chrono::{DateTime, Local};
 use core::f64;
-use slate::Result;
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant, SystemTime};
 
+#[cfg(test)]
+mod test;
+
+/// Pettitt 検定を行う最小のサンプル数と、変化点とみなす有意水準
+const WARMUP_MIN_SAMPLES: usize = 10;
+const WARMUP_SIGNIFICANCE: f64 = 0.05;
+
+/// 平均の 95% 信頼区間の z 値
+const Z_95: f64 = 1.96;
+
+/// 分散の分解で走査ごとの効果と計測点ごとの平均を交互に推定する回数
+const BACKFITTING_ITERATIONS: usize = 20;
+
+/// Number of the samples by which the sample CV, observed as `cv`, is above `threshold` at 95% confidence, by the
+/// standard error cv·√((1 + 2cv²) / 2n) of the sample CV. More samples hardly bring the sample CV below the threshold
+/// after that, since it converges to the CV of the population rather than decreasing. 0 if `cv` is already below the
+/// threshold, `usize::MAX` if it can't be estimated.
+pub fn required_sample_size(cv: f64, threshold: f64) -> usize {
+  if !cv.is_finite() || threshold <= 0.0 {
+    return usize::MAX;
+  }
+  if cv < threshold {
+    return 0;
+  }
+  ((1.0 + 2.0 * cv * cv) / 2.0 * (Z_95 * cv / (cv - threshold)).powi(2)).ceil() as usize
+}
+
+/// Detect the end of the warm-up in the samples in the order they were taken, by the change point of the Pettitt test.
+/// Only a significant change point in the first half is regarded as the warm-up; a later one is rather a change of the
+/// environment such as the throttling.
+///
+/// ## Returns
+/// - the number of the samples before the steady state, 0 if no warm-up is detected
+pub fn warmup_length(ys: &[f64]) -> usize {
+  let n = ys.len();
+  if n < WARMUP_MIN_SAMPLES {
+    return 0;
+  }
+  // U_t = U_{t-1} + Σ_j sgn(y_t - y_j) を、y_t より小さいサンプルと大きいサンプルの数から求める
+  let mut sorted = ys.to_vec();
+  sorted.sort_unstable_by(f64::total_cmp);
+  let (mut u, mut k, mut t) = (0i64, 0i64, 0);
+  for (i, y) in ys[..n - 1].iter().enumerate() {
+    let less = sorted.partition_point(|z| z < y);
+    let greater = n - sorted.partition_point(|z| z <= y);
+    u += less as i64 - greater as i64;
+    if u.abs() > k {
+      (k, t) = (u.abs(), i + 1);
+    }
+  }
+  let p = 2.0 * (-6.0 * (k * k) as f64 / ((n * n * n + n * n) as f64)).exp();
+  if p < WARMUP_SIGNIFICANCE && t <= n / 2 { t } else { 0 }
+}
+
+/// File of the raw samples of the CSV report: in the sibling `raw/` directory for the report in `csv/` of the
+/// per-session layout, otherwise next to the CSV.
+fn raw_samples_path(csv: &Path, extension: &str) -> PathBuf {
+  let path = csv.with_extension(extension);
+  match csv.parent().filter(|dir| dir.file_name().is_some_and(|name| name == "csv")) {
+    Some(dir) => dir.with_file_name("raw").join(path.file_name().unwrap_or_default()),
+    None => path,
+  }
+}
+
+/// Variance components of the samples at an x-value over the passes of the gauge, by the additive model
+/// `y = μ(x) + β(pass) + ε` fitted to the mean of each pass at each x-value. `between` is the variance of the pass
+/// effects shared by all the x-values measured in the pass, i.e. the environmental drift, and `within` is the residual
+/// variance of the operation itself. More trials hardly converge the mean if `between` dominates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VarianceComponents {
+  pub passes: usize,
+  pub total: f64,
+  pub between: f64,
+  pub within: f64,
+}
+
+impl VarianceComponents {
+  /// Share of the drift between the passes in the variance.
+  pub fn between_share(&self) -> f64 {
+    self.between / (self.between + self.within)
+  }
+}
+
+/// Decompose the variance at each x-value measured in 2 or more passes, given the mean of the samples at each x-value
+/// in each pass. The pass effects are estimated by the backfitting, since the x-values converged are no longer
+/// measured in the later passes.
+pub fn decompose_variance<X: Clone + std::hash::Hash + Eq>(
+  passes: &[HashMap<X, f64>],
+) -> HashMap<X, VarianceComponents> {
+  let passes = passes.iter().filter(|pass| !pass.is_empty()).collect::<Vec<_>>();
+  let mean = |values: &mut dyn Iterator<Item = f64>| {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), y| (sum + y, n + 1));
+    sum / n as f64
+  };
+  let xs = passes.iter().flat_map(|pass| pass.keys()).cloned().collect::<HashSet<_>>();
+  let mut beta = vec![0.0; passes.len()];
+  let mut mu = HashMap::new();
+  for _ in 0..BACKFITTING_ITERATIONS {
+    for x in xs.iter() {
+      let m = mean(&mut passes.iter().zip(beta.iter()).filter_map(|(pass, b)| pass.get(x).map(|y| y - b)));
+      mu.insert(x.clone(), m);
+    }
+    for (pass, b) in passes.iter().zip(beta.iter_mut()) {
+      *b = mean(&mut pass.iter().map(|(x, y)| y - mu[x]));
+    }
+    let center = mean(&mut beta.iter().copied());
+    beta.iter_mut().for_each(|b| *b -= center);
+  }
+
+  let variance = |values: &[f64]| {
+    let m = mean(&mut values.iter().copied());
+    mean(&mut values.iter().map(|y| (y - m) * (y - m)))
+  };
+  let mut components = HashMap::new();
+  for x in xs {
+    let cells =
+      passes.iter().zip(beta.iter()).filter_map(|(pass, b)| pass.get(&x).map(|y| (*y, *b))).collect::<Vec<_>>();
+    if cells.len() < 2 {
+      continue;
+    }
+    let total = variance(&cells.iter().map(|(y, _)| *y).collect::<Vec<_>>());
+    let between = variance(&cells.iter().map(|(_, b)| *b).collect::<Vec<_>>());
+    let within = mean(&mut cells.iter().map(|(y, b)| (y - mu[&x] - b) * (y - mu[&x] - b)));
+    components.insert(x, VarianceComponents { passes: cells.len(), total, between, within });
+  }
+  components
+}
+
 #[derive(Debug, Clone)]
 pub struct Stat {
   unit: Unit,
@@ -112,37 +244,300 @@ impl Unit {
 
 pub struct XYReport<X: Display + Clone + std::hash::Hash + Eq + PartialEq + Ord, Y: IntoFloat + Display> {
   unit: Unit,
-  data_set: HashMap<X, Vec<Y>>,
+  data_set: HashMap<X, Series<Y>>,
+  output: Option<Output>,
+  raw: Option<RawSamples>,
+  /// Discard the samples of the warm-up detected at each x-value
+  discard_warmup: bool,
+  /// Maximum number of the samples kept at each x-value
+  max_samples: Option<usize>,
+  /// Sum and number of the samples at each x-value in each pass over the gauge, if the variance is decomposed
+  passes: Option<Vec<HashMap<X, (f64, usize)>>>,
+  /// Samples of the first accesses after the database is prepared at each x-value, if recorded apart from the others
+  first: Option<HashMap<X, Vec<Y>>>,
+}
+
+/// Label of the column that tells the series of the rows in a report with the first accesses, see
+/// [`XYReport::with_first_access`].
+pub const SERIES: &str = "SERIES";
+/// Series of the first accesses after the database is prepared, which are cold.
+pub const FIRST_ACCESS: &str = "first";
+/// Series of the other accesses.
+pub const WARM_ACCESS: &str = "warm";
+
+/// Samples at an x-value. Once their number exceeds the cap, `ys` becomes a reservoir of the steady samples sampled
+/// uniformly and the statistics over all of them are accumulated by `stream`.
+struct Series<Y> {
+  ys: Vec<Y>,
+  stream: Option<Stream>,
+}
+
+impl<Y: IntoFloat> Series<Y> {
+  fn push(&mut self, y: Y, max_samples: Option<usize>, discard_warmup: bool) {
+    let Some(max_samples) = max_samples else {
+      self.ys.push(y);
+      return;
+    };
+    match &mut self.stream {
+      // Algorithm R: t 番目のサンプルは確率 k/t でリザーバーのいずれかと置き換える
+      Some(stream) => {
+        stream.push(y.into_f64());
+        if self.ys.len() < max_samples {
+          self.ys.push(y);
+        } else {
+          let j = (stream.next_random() % stream.count as u64) as usize;
+          if j < max_samples {
+            self.ys[j] = y;
+          }
+        }
+      }
+      // 上限を超えた時点のサンプルでウォームアップを確定し、以降は逐次に統計を求める
+      None if self.ys.len() >= max_samples => {
+        let values = self.ys.iter().map(|y| y.into_f64()).collect::<Vec<_>>();
+        let warmup = if discard_warmup { warmup_length(&values) } else { 0 };
+        self.ys.drain(..warmup);
+        self.stream = Some(Stream::new(warmup, &values[warmup..]));
+        self.push(y, Some(max_samples), discard_warmup);
+      }
+      None => self.ys.push(y),
+    }
+  }
+}
+
+impl<Y> Default for Series<Y> {
+  fn default() -> Self {
+    Self { ys: Vec::new(), stream: None }
+  }
+}
+
+/// Count, mean, variance (by Welford's algorithm), minimum and maximum of the steady samples, with the warm-up fixed
+/// when the cap of the samples is exceeded.
+struct Stream {
+  warmup: usize,
+  count: usize,
+  mean: f64,
+  m2: f64,
+  min: f64,
+  max: f64,
+  random: u64,
+}
+
+impl Stream {
+  fn new(warmup: usize, steady: &[f64]) -> Self {
+    let (min, max) = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut stream = Self { warmup, count: 0, mean: 0.0, m2: 0.0, min, max, random: steady.len() as u64 };
+    for y in steady {
+      stream.push(*y);
+    }
+    stream
+  }
+
+  fn push(&mut self, y: f64) {
+    self.count += 1;
+    let delta = y - self.mean;
+    self.mean += delta / self.count as f64;
+    self.m2 += delta * (y - self.mean);
+    self.min = self.min.min(y);
+    self.max = self.max.max(y);
+  }
+
+  fn next_random(&mut self) -> u64 {
+    self.random = splitmix64(self.random);
+    self.random
+  }
+
+  /// Statistics over all the steady samples, except the median estimated from the reservoir.
+  fn stat<Y: IntoFloat>(&self, unit: Unit, reservoir: &[Y]) -> Stat {
+    let median = Stat::from_vec(unit, reservoir).median;
+    let std_dev = (self.m2 / self.count as f64).sqrt();
+    Stat { unit, count: self.count, mean: self.mean, median, std_dev, min: self.min, max: self.max }
+  }
+}
+
+/// Destination of the report, which is also written while unwinding by a panic so that the results so far are kept.
+struct Output {
+  path: PathBuf,
+  x_label: String,
+  y_labels: String,
 }
 
 impl<X: Display + Clone + std::hash::Hash + Eq + PartialEq + Ord, Y: IntoFloat + Display> XYReport<X, Y> {
   pub fn new(unit: Unit) -> Self {
-    XYReport { unit, data_set: HashMap::new() }
+    let (data_set, output, raw, passes) = (HashMap::new(), None, None, None);
+    XYReport { unit, data_set, output, raw, discard_warmup: false, max_samples: None, passes, first: None }
+  }
+
+  pub fn with_output(mut self, path: PathBuf, x_label: &str, y_labels: &str) -> Self {
+    self.output = Some(Output { path, x_label: String::from(x_label), y_labels: String::from(y_labels) });
+    self
+  }
+
+  /// Keep the raw samples to export them next to the CSV, if specified.
+  pub fn with_raw_samples(mut self, raw: Option<RawSamples>) -> Self {
+    self.raw = raw;
+    self
+  }
+
+  /// Discard the samples before the steady state detected by [`warmup_length`] at each x-value from the statistics and
+  /// the report, if enabled. The numbers of the discarded samples are saved as `{report}_warmup.csv`.
+  pub fn with_warmup_detection(mut self, enabled: bool) -> Self {
+    self.discard_warmup = enabled;
+    self
+  }
+
+  /// Keep at most `max_samples` samples at each x-value by the reservoir sampling, if specified, so that the memory
+  /// and the report are bounded for many trials. The statistics except the median are still over all the samples, and
+  /// the warm-up is detected in the samples until the cap is exceeded.
+  pub fn with_max_samples(mut self, max_samples: Option<usize>) -> Self {
+    self.max_samples = max_samples;
+    self
+  }
+
+  /// Decompose the variance at each x-value into the components between and within the passes over the gauge, which
+  /// are separated by [`end_pass`](Self::end_pass), if enabled. The components are saved as `{report}_variance.csv`.
+  pub fn with_variance_decomposition(mut self, enabled: bool) -> Self {
+    self.passes = enabled.then(|| vec![HashMap::new()]);
+    self
+  }
+
+  /// Record the samples given by [`add_first_access`](Self::add_first_access) as the series `first` apart from the
+  /// others, the series `warm`, if enabled. The CSV then has the column `SERIES` after the x-value.
+  ///
+  /// ```text
+  /// DISTANCE,SERIES,ACCESS TIME
+  /// 1024,first,0.91
+  /// 1024,warm,0.12,0.11
+  /// ```
+  pub fn with_first_access(mut self, enabled: bool) -> Self {
+    self.first = enabled.then(HashMap::new);
+    self
+  }
+
+  /// Add the sample of a first access after the database is prepared, which isn't included in the statistics.
+  pub fn add_first_access(&mut self, x: &X, y: Y) {
+    if let Some(first) = &mut self.first {
+      first.entry(x.clone()).or_default().push(y);
+    }
+  }
+
+  /// End the current pass over the gauge.
+  pub fn end_pass(&mut self) {
+    if let Some(passes) = &mut self.passes
+      && passes.last().is_some_and(|pass| !pass.is_empty())
+    {
+      passes.push(HashMap::new());
+    }
+  }
+
+  /// Variance components at each x-value, if the variance decomposition is enabled.
+  pub fn variance_components(&self) -> Option<HashMap<X, VarianceComponents>> {
+    let passes = self.passes.as_ref()?;
+    let means =
+      |pass: &HashMap<X, (f64, usize)>| pass.iter().map(|(x, (sum, n))| (x.clone(), sum / *n as f64)).collect();
+    Some(decompose_variance(&passes.iter().map(means).collect::<Vec<_>>()))
+  }
+
+  /// Save the report to the destination given by `with_output`.
+  pub fn save(&self) -> Result<&PathBuf> {
+    // 計測中に書き込みスレッドへ送った要約を、保存先の表示より前に出力する
+    writer::flush();
+    let output = self.output.as_ref().expect("no output of the report");
+    self.save_xy_to_csv(&output.path, &output.x_label, &output.y_labels)?;
+    if self.discard_warmup {
+      let stem = output.path.file_stem().unwrap_or_default().to_string_lossy();
+      let path = output.path.with_file_name(format!("{stem}_warmup.csv"));
+      let mut xs = self.data_set.keys().collect::<Vec<_>>();
+      xs.sort_unstable();
+      let mut dropped = 0;
+      write_atomically(&path, |writer| {
+        writeln!(writer, "{},SAMPLES,DISCARDED", output.x_label)?;
+        for x in xs {
+          let series = &self.data_set[x];
+          let count = series.stream.as_ref().map(|stream| stream.warmup + stream.count).unwrap_or(series.ys.len());
+          let n = self.warmup(x);
+          writeln!(writer, "{x},{count},{n}")?;
+          dropped += n;
+        }
+        Ok(())
+      })?;
+      println!("==> {dropped} samples of the warm-up have been discarded: {}", path.to_string_lossy());
+    }
+    if let Some(components) = self.variance_components() {
+      let stem = output.path.file_stem().unwrap_or_default().to_string_lossy();
+      let path = output.path.with_file_name(format!("{stem}_variance.csv"));
+      let mut xs = components.keys().collect::<Vec<_>>();
+      xs.sort_unstable();
+      write_atomically(&path, |writer| {
+        writeln!(writer, "{},PASSES,TOTAL,BETWEEN,WITHIN,BETWEEN SHARE", output.x_label)?;
+        for x in xs {
+          let c = &components[x];
+          writeln!(writer, "{x},{},{},{},{},{}", c.passes, c.total, c.between, c.within, c.between_share())?;
+        }
+        Ok(())
+      })?;
+      println!("==> The variance components have been saved in: {}", path.to_string_lossy());
+    }
+    if let Some(raw) = &self.raw {
+      let path = raw_samples_path(&output.path, raw.extension());
+      raw.save(&path)?;
+      println!("==> The raw samples have been saved in: {}", path.to_string_lossy());
+    }
+    Ok(&output.path)
   }
 
   pub fn add(&mut self, x: &X, y: Y) -> Stat {
     self.append(x, vec![y])
   }
 
-  pub fn append(&mut self, x: &X, mut ys: Vec<Y>) -> Stat {
-    self.data_set.entry(x.clone()).or_default().append(&mut ys);
-    self.calculate(x).unwrap()
+  pub fn append(&mut self, x: &X, ys: Vec<Y>) -> Stat {
+    let values = ys.iter().map(|y| y.into_f64()).collect::<Vec<_>>();
+    let series = self.data_set.entry(x.clone()).or_default();
+    for y in ys {
+      series.push(y, self.max_samples, self.discard_warmup);
+    }
+    if let Some(pass) = self.passes.as_mut().and_then(|passes| passes.last_mut()).filter(|_| !values.is_empty()) {
+      let (sum, n) = pass.entry(x.clone()).or_default();
+      *sum += values.iter().sum::<f64>();
+      *n += values.len();
+    }
+    let stat = self.calculate(x).unwrap();
+    let ys = values.into_iter();
+    if let Some(raw) = &mut self.raw {
+      raw.add(x, ys.clone(), self.max_samples);
+    }
+    if let Some(output) = &self.output {
+      crate::monitor::update(&output.path, x, &stat, ys);
+    }
+    stat
   }
 
   pub fn save_xy_to_csv(&self, path: &PathBuf, x_label: &str, y_labels: &str) -> Result<()> {
-    let file = File::create(path)?;
-    let mut writer = BufWriter::new(file);
-    writeln!(writer, "{x_label},{y_labels}")?;
-
-    let mut xs = self.data_set.keys().cloned().collect::<Vec<_>>();
-    xs.sort_unstable();
-    for x in xs.iter() {
-      let ys = self.data_set.get(x).unwrap().iter().map(|f| format!("{f}")).collect::<Vec<_>>();
-      writeln!(writer, "{},{}", x, ys.join(","))?;
-    }
+    let join = |ys: &[Y]| ys.iter().map(|f| format!("{f}")).collect::<Vec<_>>().join(",");
+    write_atomically(path, |writer| {
+      let Some(first) = &self.first else {
+        writeln!(writer, "{x_label},{y_labels}")?;
+        let mut xs = self.data_set.keys().cloned().collect::<Vec<_>>();
+        xs.sort_unstable();
+        for x in xs.iter() {
+          writeln!(writer, "{},{}", x, join(self.steady(x)))?;
+        }
+        return Ok(());
+      };
 
-    writer.flush()?;
-    Ok(())
+      writeln!(writer, "{x_label},{SERIES},{y_labels}")?;
+      let mut xs = self.data_set.keys().chain(first.keys()).cloned().collect::<Vec<_>>();
+      xs.sort_unstable();
+      xs.dedup();
+      for x in xs.iter() {
+        if let Some(ys) = first.get(x) {
+          writeln!(writer, "{x},{FIRST_ACCESS},{}", join(ys))?;
+        }
+        if self.data_set.contains_key(x) {
+          writeln!(writer, "{x},{WARM_ACCESS},{}", join(self.steady(x)))?;
+        }
+      }
+      Ok(())
+    })
   }
 
   pub fn max_cv(&self) -> f64 {
@@ -163,7 +558,7 @@ impl<X: Display + Clone + std::hash::Hash + Eq + PartialEq + Ord, Y: IntoFloat +
   }
 
   pub fn is_cv_sufficient(&self, x: X, cv: f64) -> bool {
-    match self.data_set.get(&x).map(|ys| Stat::from_vec(self.unit, ys)) {
+    match self.calculate(&x) {
       Some(stat) => {
         if stat.count <= 2 {
           false
@@ -175,8 +570,57 @@ impl<X: Display + Clone + std::hash::Hash + Eq + PartialEq + Ord, Y: IntoFloat +
     }
   }
 
+  /// Number of the trials by which the stop rule of [`is_cv_sufficient`](Self::is_cv_sufficient) is settled at x: the
+  /// trials taken if the CV is already below the threshold, `usize::MAX` if it's above the threshold at 95% confidence
+  /// by [`required_sample_size`] so that the rule is unreachable, or `None` until 3 samples are taken.
+  pub fn required_trials(&self, x: &X, threshold: f64) -> Option<usize> {
+    let stat = self.calculate(x).filter(|stat| stat.count > 2)?;
+    let n = required_sample_size(stat.cv(), threshold);
+    if stat.cv() >= threshold && stat.count >= n {
+      return Some(usize::MAX);
+    }
+    Some(n.max(stat.count).saturating_add(self.warmup(x)))
+  }
+
   pub fn calculate(&self, x: &X) -> Option<Stat> {
-    self.data_set.get(x).map(|ys| Stat::from_vec(self.unit, ys))
+    self.data_set.get(x).map(|series| match &series.stream {
+      Some(stream) => stream.stat(self.unit, &series.ys),
+      None => Stat::from_vec(self.unit, self.steady(x)),
+    })
+  }
+
+  /// Number of the samples of the warm-up at x, 0 unless the warm-up detection is enabled.
+  fn warmup(&self, x: &X) -> usize {
+    match self.data_set.get(x) {
+      Some(Series { stream: Some(stream), .. }) => stream.warmup,
+      Some(series) if self.discard_warmup => warmup_length(&series.ys.iter().map(|y| y.into_f64()).collect::<Vec<_>>()),
+      _ => 0,
+    }
+  }
+
+  /// Samples at x after the warm-up, or the reservoir of them once the cap is exceeded.
+  fn steady(&self, x: &X) -> &[Y] {
+    match self.data_set.get(x) {
+      Some(Series { ys, stream: Some(_) }) => ys,
+      Some(series) => &series.ys[self.warmup(x)..],
+      None => &[],
+    }
+  }
+}
+
+impl<X: Display + Clone + std::hash::Hash + Eq + PartialEq + Ord, Y: IntoFloat + Display> Drop for XYReport<X, Y> {
+  fn drop(&mut self) {
+    if std::thread::panicking()
+      && let Some(output) = &self.output
+    {
+      match self.save_xy_to_csv(&output.path, &output.x_label, &output.y_labels) {
+        Ok(()) => eprintln!("==> The partial results have been saved in: {}", output.path.to_string_lossy()),
+        Err(err) => eprintln!("WARN: fail to save the partial results in {}: {err}", output.path.to_string_lossy()),
+      }
+      if let Some(Err(err)) = self.raw.as_ref().map(|raw| raw.save(&raw_samples_path(&output.path, raw.extension()))) {
+        eprintln!("WARN: fail to save the partial raw samples of {}: {err}", output.path.to_string_lossy());
+      }
+    }
   }
 }
 
@@ -188,6 +632,12 @@ pub struct ExpirationTimer {
   max_trials: usize,
   current: usize,
   interval: usize,
+  /// Time of the last summary line, to annotate the next one with the throttling in between
+  last_summary: Cell<Instant>,
+  /// Estimated number of the trials required by the points not converged yet
+  required: Option<usize>,
+  /// Time waited for the turn of the other CUTs before the start, see [`interleave::waited`]
+  waited: Duration,
 }
 
 impl ExpirationTimer {
@@ -197,15 +647,30 @@ impl ExpirationTimer {
     let notice_interval = Duration::from_secs(minutes as u64 * 60);
     let current = 0;
     let interval = max_trials / div;
-    Self { start, dead_line, last_noticed, notice_interval, max_trials, current, interval }
+    let last_summary = Cell::new(start);
+    let required = None;
+    let waited = interleave::waited();
+    Self {
+      start,
+      dead_line,
+      last_noticed,
+      notice_interval,
+      max_trials,
+      current,
+      interval,
+      last_summary,
+      required,
+      waited,
+    }
   }
 
   pub fn expired(&self) -> bool {
-    self.start.elapsed() >= self.dead_line
+    self.elapsed() >= self.dead_line
   }
 
+  /// Time elapsed since the start, except for the time waited while the trials of the other CUTs run.
   pub fn elapsed(&self) -> Duration {
-    self.start.elapsed()
+    self.start.elapsed().saturating_sub(interleave::waited() - self.waited)
   }
 
   pub fn estimated_end_time(&self) -> Instant {
@@ -214,36 +679,17 @@ impl ExpirationTimer {
     } else {
       let avr_per_trial = self.elapsed() / self.current as u32;
       let total_estimate = avr_per_trial * self.max_trials as u32;
-      self.start + total_estimate
+      Instant::now() + total_estimate.saturating_sub(self.elapsed())
     }
   }
 
   pub fn eta(&self) -> String {
-    let system_time = SystemTime::now() + (self.estimated_end_time() - Instant::now());
-    let dt: DateTime<Local> = system_time.into();
-    let now: DateTime<Local> = SystemTime::now().into();
-    let diff = dt - now;
-    let fmt = if now.date_naive() != dt.date_naive() {
-      "%m-%d %H:%M"
-    } else if diff.num_hours() >= 1 {
-      "%H:%M"
-    } else {
-      "%H:%M:%S"
-    };
-    let eta = dt.format(fmt).to_string();
-
-    let secs = diff.num_seconds();
-    let h = secs / 3600;
-    let m = (secs % 3600) / 60;
-    let s = secs % 60;
-    let remaining = if h > 0 {
-      format!("{h}h{m:02}m")
-    } else if m > 0 {
-      format!("{m}m{s:02}s")
-    } else {
-      format!("{s}s")
-    };
-    format!("{eta} ({remaining})")
+    format_eta(self.estimated_end_time())
+  }
+
+  /// Show the estimated number of the trials required in the following summary lines.
+  pub fn set_required_trials(&mut self, required: Option<usize>) {
+    self.required = required;
   }
 
   pub fn carried_out(&mut self, amount: usize) -> bool {
@@ -261,55 +707,108 @@ impl ExpirationTimer {
     }
   }
 
-  fn heading(columns: &[Column]) {
-    println!("{}", columns.iter().map(|c| c.heading()).collect::<Vec<_>>().join(" "));
-    println!("{}", columns.iter().map(|c| c.line()).collect::<Vec<_>>().join(" "));
+  fn heading(columns: Vec<Column>) {
+    writer::submit(move || {
+      println!("{}", columns.iter().map(|c| c.heading()).collect::<Vec<_>>().join(" "));
+      println!("{}", columns.iter().map(|c| c.line()).collect::<Vec<_>>().join(" "));
+    });
   }
 
-  fn summary(columns: &[Column]) {
-    println!("{}", columns.iter().map(|c| c.fmt()).collect::<Vec<_>>().join(" "));
+  /// Format and print the summary line on the writer thread, so that it doesn't delay the next measurement.
+  fn summary(&self, columns: Vec<Column>) {
+    let (current, max_trials, end) = (self.current, self.max_trials, self.estimated_end_time());
+    let since = self.last_summary.replace(Instant::now());
+    writer::submit(move || {
+      crate::monitor::progress(current, max_trials, format_eta(end));
+      let line = columns.iter().map(|c| c.fmt()).collect::<Vec<_>>().join(" ");
+      match crate::cpu::throttling_since(since) {
+        Some(throttling) => println!("{line} ** THROTTLED: {throttling} **"),
+        None => println!("{line}"),
+      }
+    });
   }
 
   pub fn heading_ms() {
-    Self::heading(&[
+    Self::heading(vec![
       Column::DataSize(0),
       Column::MeanMS(0.0),
       Column::StdDevMS(0.0),
       Column::CV(0.0),
       Column::Trials(0),
-      Column::Eta(String::from("")),
+      Column::Required(None),
+      Column::Eta(Instant::now()),
     ]);
   }
   pub fn summary_ms(&self, data_size: u64, mean: f64, std_dev: f64) {
-    Self::summary(&[
+    self.summary(vec![
       Column::DataSize(data_size),
       Column::MeanMS(mean),
       Column::StdDevMS(std_dev),
       Column::CV(std_dev / mean * 100.0),
       Column::Trials(self.current),
-      Column::Eta(self.eta()),
+      Column::Required(self.required),
+      Column::Eta(self.estimated_end_time()),
     ]);
   }
   pub fn heading_max_cv() {
-    Self::heading(&[Column::DataSize(0), Column::CV(0.0), Column::Trials(0), Column::Eta(String::from(""))]);
+    Self::heading(vec![
+      Column::DataSize(0),
+      Column::CV(0.0),
+      Column::Trials(0),
+      Column::Required(None),
+      Column::Eta(Instant::now()),
+    ]);
   }
   pub fn summary_max_cv(&self, data_size: u64, max_cv: f64) {
-    Self::summary(&[
+    self.summary(vec![
       Column::DataSize(data_size),
       Column::CV(max_cv * 100.0),
       Column::Trials(self.current),
-      Column::Eta(self.eta()),
+      Column::Required(self.required),
+      Column::Eta(self.estimated_end_time()),
     ]);
   }
 }
 
+/// Estimated end time with the remaining time, e.g. `12:34:56 (5m02s)`.
+pub(crate) fn format_eta(end: Instant) -> String {
+  let system_time = SystemTime::now() + (end - Instant::now());
+  let dt: DateTime<Local> = system_time.into();
+  let now: DateTime<Local> = SystemTime::now().into();
+  let diff = dt - now;
+  let fmt = if now.date_naive() != dt.date_naive() {
+    "%m-%d %H:%M"
+  } else if diff.num_hours() >= 1 {
+    "%H:%M"
+  } else {
+    "%H:%M:%S"
+  };
+  let eta = dt.format(fmt).to_string();
+
+  let secs = diff.num_seconds();
+  let h = secs / 3600;
+  let m = (secs % 3600) / 60;
+  let s = secs % 60;
+  let remaining = if h > 0 {
+    format!("{h}h{m:02}m")
+  } else if m > 0 {
+    format!("{m}m{s:02}s")
+  } else {
+    format!("{s}s")
+  };
+  format!("{eta} ({remaining})")
+}
+
 enum Column {
   DataSize(u64),
   MeanMS(f64),
   StdDevMS(f64),
   CV(f64),
   Trials(usize),
-  Eta(String),
+  /// Estimated number of the trials required, shown as `-` if unknown
+  Required(Option<usize>),
+  /// Estimated end time
+  Eta(Instant),
 }
 
 impl Column {
@@ -320,6 +819,7 @@ impl Column {
       Self::StdDevMS(_) => "StdDev[ms]",
       Self::CV(_) => "CV[%]",
       Self::Trials(_) => "Trials",
+      Self::Required(_) => "Required",
       Self::Eta(_) => "ETA",
     }
   }
@@ -330,6 +830,7 @@ impl Column {
       Self::StdDevMS(_) => 12,
       Self::CV(_) => 6,
       Self::Trials(_) => 9,
+      Self::Required(_) => 9,
       Self::Eta(_) => 18,
     })
   }
@@ -341,6 +842,7 @@ impl Column {
       Self::StdDevMS(_) => "StdDev[ms]",
       Self::CV(_) => "CV[%]",
       Self::Trials(_) => "Trials",
+      Self::Required(_) => "Required",
       Self::Eta(_) => "ETA",
     };
     format!("{h:^s$}", s = self.len())
@@ -357,7 +859,9 @@ impl Column {
       Self::StdDevMS(sd) => format!("{sd:>w$.3}", w = self.len()),
       Self::CV(cv) => format!("{cv:>w$.1}", w = self.len()),
       Self::Trials(tr) => format!("{tr:>w$}", w = self.len()),
-      Self::Eta(eta) => format!("{eta:<w$}", w = self.len()),
+      Self::Required(Some(required)) if *required != usize::MAX => format!("{required:>w$}", w = self.len()),
+      Self::Required(_) => format!("{:>w$}", "-", w = self.len()),
+      Self::Eta(end) => format!("{:<w$}", format_eta(*end), w = self.len()),
     }
   }
 }
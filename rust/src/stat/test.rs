@@ -0,0 +1,99 @@
+use super::*;
+
+/// 8 回の遅い試行の後に定常状態となる計測値
+fn samples_with_warmup() -> Vec<f64> {
+  let warmup = (0..8).map(|i| 9.0 - i as f64 * 0.5);
+  warmup.chain((0..24).map(|i| 1.0 + (i % 3) as f64 * 0.1)).collect()
+}
+
+#[test]
+fn warmup_of_slow_first_trials() {
+  let ys = samples_with_warmup();
+  assert_eq!(8, warmup_length(&ys));
+
+  // 変化のない系列や後半の変化、少ないサンプルはウォームアップとみなさない
+  let steady = (0..24).map(|i| 1.0 + (i % 3) as f64 * 0.1).collect::<Vec<_>>();
+  assert_eq!(0, warmup_length(&steady));
+  let throttled = ys.iter().rev().copied().collect::<Vec<_>>();
+  assert_eq!(0, warmup_length(&throttled));
+  assert_eq!(0, warmup_length(&[9.0, 1.0, 1.0]));
+}
+
+#[test]
+fn discard_warmup_from_report() {
+  let ys = samples_with_warmup();
+  let mut report = XYReport::new(Unit::Milliseconds).with_warmup_detection(true);
+  report.append(&1u64, ys.clone());
+  assert_eq!(24, report.calculate(&1).unwrap().count);
+
+  let mut report = XYReport::new(Unit::Milliseconds);
+  report.append(&1u64, ys);
+  assert_eq!(32, report.calculate(&1).unwrap().count);
+}
+
+#[test]
+fn required_trials_for_the_cv_threshold() {
+  // (1 + 2 × 0.1²) / 2 × (1.96 × 0.1 / (0.1 - 0.05))² = 7.84
+  assert_eq!(8, required_sample_size(0.1, 0.05));
+  assert_eq!(0, required_sample_size(0.04, 0.05));
+  assert_eq!(usize::MAX, required_sample_size(f64::NAN, 0.05));
+  assert_eq!(usize::MAX, required_sample_size(0.1, 0.0));
+
+  let mut report = XYReport::new(Unit::Milliseconds);
+  report.append(&1u64, vec![1.0, 1.0]);
+  assert_eq!(None, report.required_trials(&1, 0.05));
+  report.add(&1, 1.0);
+  assert_eq!(Some(3), report.required_trials(&1, 0.05));
+
+  // the CV about 0.5 is far above the threshold after 6 trials
+  let mut report = XYReport::new(Unit::Milliseconds);
+  report.append(&1u64, vec![1.0, 3.0, 1.0, 3.0, 1.0, 3.0]);
+  assert_eq!(Some(usize::MAX), report.required_trials(&1, 0.05));
+}
+
+#[test]
+fn reservoir_of_samples_over_the_cap() {
+  let ys = (1..=1000).map(|i| i as f64).collect::<Vec<_>>();
+  let mut report = XYReport::new(Unit::Milliseconds).with_max_samples(Some(100));
+  for y in ys.iter() {
+    report.add(&1u64, *y);
+  }
+  assert_eq!(100, report.steady(&1).len());
+  let stat = report.calculate(&1).unwrap();
+  assert_eq!((1000, 500.5, 1.0, 1000.0), (stat.count, stat.mean, stat.min, stat.max));
+  assert!((stat.std_dev - Stat::from_vec(Unit::Milliseconds, &ys).std_dev).abs() < 1e-9);
+  assert!((250.0..750.0).contains(&stat.median));
+
+  // 上限を超えた時点で検出したウォームアップは統計から除外される
+  let mut report = XYReport::new(Unit::Milliseconds).with_warmup_detection(true).with_max_samples(Some(32));
+  report.append(&1u64, samples_with_warmup());
+  report.append(&1u64, vec![1.1; 68]);
+  assert_eq!(92, report.calculate(&1).unwrap().count);
+  assert_eq!(8, report.warmup(&1));
+}
+
+#[test]
+fn variance_between_and_within_passes() {
+  // すべての計測点が走査ごとに同じだけ変動する場合、分散はすべて走査間の成分となる
+  let drift = [0.0, 1.0, 0.0, 1.0];
+  let mut report = XYReport::new(Unit::Milliseconds).with_variance_decomposition(true);
+  for d in drift {
+    report.add(&1u64, 10.0 + d);
+    report.add(&2u64, 20.0 + d);
+    report.end_pass();
+  }
+  let components = report.variance_components().unwrap();
+  for x in [1, 2] {
+    let c = components[&x];
+    assert_eq!(4, c.passes);
+    assert!((c.total - 0.25).abs() < 1e-9 && (c.between - 0.25).abs() < 1e-9 && c.within.abs() < 1e-9);
+  }
+
+  // 走査に共通する変動のみが走査間の成分となる
+  let passes = [[(1u64, 10.0), (2, 20.0)], [(1, 11.0), (2, 20.0)], [(1, 10.0), (2, 21.0)], [(1, 11.0), (2, 21.0)]];
+  let passes = passes.iter().map(|pass| pass.iter().copied().collect::<HashMap<_, _>>()).collect::<Vec<_>>();
+  let c = decompose_variance(&passes)[&1];
+  assert!((c.total - 0.25).abs() < 1e-9 && (c.between - 0.125).abs() < 1e-9 && (c.within - 0.125).abs() < 1e-9);
+  assert!((c.between_share() - 0.5).abs() < 1e-9);
+  assert!(XYReport::<u64, f64>::new(Unit::Milliseconds).variance_components().is_none());
+}
@@ -0,0 +1,90 @@
+//! Parameter sweep of a test unit over the cartesian product of named knobs, such as the cache level × the value size.
+//!
+//! ```ignore
+//! let sweep = Sweep::new().knob("cache", 0..=3).knob("value", [8, 64]);
+//! experiment.sweep("cache-level", &sweep, |point| {
+//!   let level = point.parse::<usize>("cache")?;
+//!   experiment.case()?.measure_the_retrieval_time_relative_to_the_position(cut, &point.label(), level, ds)?;
+//!   Ok(())
+//! })?;
+//! ```
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::{BenchError, Result};
+
+#[cfg(test)]
+mod test;
+
+/// A named knob with the values to sweep.
+#[derive(Debug, Clone)]
+pub struct Knob {
+  pub name: String,
+  pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sweep {
+  knobs: Vec<Knob>,
+}
+
+impl Sweep {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a knob. The name should be short since it's a part of the report names.
+  pub fn knob<V: Display>(mut self, name: &str, values: impl IntoIterator<Item = V>) -> Self {
+    let values = values.into_iter().map(|v| v.to_string()).collect();
+    self.knobs.push(Knob { name: String::from(name), values });
+    self
+  }
+
+  pub fn knobs(&self) -> &[Knob] {
+    &self.knobs
+  }
+
+  /// All the combinations of the knob values, varying the last knob fastest.
+  pub fn points(&self) -> Vec<SweepPoint> {
+    let mut points = vec![SweepPoint::default()];
+    for knob in self.knobs.iter() {
+      points = points
+        .into_iter()
+        .flat_map(|point| {
+          knob.values.iter().map(move |value| {
+            let mut point = point.clone();
+            point.values.push((knob.name.clone(), value.clone()));
+            point
+          })
+        })
+        .collect();
+    }
+    points
+  }
+}
+
+/// A combination of the knob values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SweepPoint {
+  values: Vec<(String, String)>,
+}
+
+impl SweepPoint {
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self.values.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+  }
+
+  pub fn parse<T: FromStr>(&self, name: &str) -> Result<T> {
+    let value = self.get(name).ok_or_else(|| BenchError::Config(format!("no knob {name} in the sweep")))?;
+    value.parse().map_err(|_| BenchError::Config(format!("invalid value of the knob {name}: {value}")))
+  }
+
+  pub fn values(&self) -> &[(String, String)] {
+    &self.values
+  }
+
+  /// Identifier of the point used in the report names, e.g. `cache2-value64`.
+  pub fn label(&self) -> String {
+    self.values.iter().map(|(name, value)| format!("{name}{value}")).collect::<Vec<_>>().join("-")
+  }
+}
@@ -0,0 +1,16 @@
+use super::*;
+
+#[test]
+fn cartesian_product_of_knobs() {
+  let sweep = Sweep::new().knob("cache", 0..=1).knob("value", [8, 64]);
+  let labels = sweep.points().iter().map(|p| p.label()).collect::<Vec<_>>();
+  assert_eq!(vec!["cache0-value8", "cache0-value64", "cache1-value8", "cache1-value64"], labels);
+
+  let point = &sweep.points()[3];
+  assert_eq!(1, point.parse::<usize>("cache").unwrap());
+  assert_eq!(Some("64"), point.get("value"));
+  assert!(matches!(point.parse::<usize>("sync"), Err(BenchError::Config(_))));
+
+  // つまみのない掃引は 1 点のみ
+  assert_eq!(vec![SweepPoint::default()], Sweep::new().points());
+}
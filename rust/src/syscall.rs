@@ -0,0 +1,208 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::Experiment;
+use crate::clock;
+use crate::error::{BenchError, Result};
+use crate::report::{append_csv, write_atomically};
+
+const THREAD_IO: &str = "/proc/thread-self/io";
+const PROCESS_IO: &str = "/proc/self/io";
+
+/// I/O counters of the current thread or process from `/proc/{thread-self,self}/io`.
+#[derive(Debug, Clone, Copy, Default)]
+struct IoCounters {
+  /// Number of read syscalls (`read`, `pread`, ...)
+  syscr: u64,
+  /// Number of write syscalls (`write`, `pwrite`, ...)
+  syscw: u64,
+  /// Bytes passed to the read syscalls, including the page cache hits
+  rchar: u64,
+  /// Bytes passed to the write syscalls
+  wchar: u64,
+  /// Bytes actually fetched from the storage device
+  read_bytes: u64,
+  /// Bytes caused to be sent to the storage device
+  write_bytes: u64,
+}
+
+impl IoCounters {
+  fn read(path: &str) -> Result<Self> {
+    let io = fs::read_to_string(path)
+      .map_err(|err| BenchError::Config(format!("I/O statistics require {path} (Linux): {err}")))?;
+    let mut counters = IoCounters::default();
+    for line in io.lines() {
+      let Some((key, value)) = line.split_once(':') else { continue };
+      let value = value.trim().parse().unwrap_or(0);
+      match key {
+        "syscr" => counters.syscr = value,
+        "syscw" => counters.syscw = value,
+        "rchar" => counters.rchar = value,
+        "wchar" => counters.wchar = value,
+        "read_bytes" => counters.read_bytes = value,
+        "write_bytes" => counters.write_bytes = value,
+        _ => (),
+      }
+    }
+    Ok(counters)
+  }
+
+  fn delta(&self, before: &Self) -> Self {
+    IoCounters {
+      syscr: self.syscr.saturating_sub(before.syscr),
+      syscw: self.syscw.saturating_sub(before.syscw),
+      rchar: self.rchar.saturating_sub(before.rchar),
+      wchar: self.wchar.saturating_sub(before.wchar),
+      read_bytes: self.read_bytes.saturating_sub(before.read_bytes),
+      write_bytes: self.write_bytes.saturating_sub(before.write_bytes),
+    }
+  }
+
+  fn add(&mut self, other: &Self) {
+    self.syscr += other.syscr;
+    self.syscw += other.syscw;
+    self.rchar += other.rchar;
+    self.wchar += other.wchar;
+    self.read_bytes += other.read_bytes;
+    self.write_bytes += other.write_bytes;
+  }
+}
+
+/// Records the number of read/write syscalls of each operation by the position, if enabled by `--syscalls`.
+pub struct SyscallRecorder {
+  /// Counters consumed by reading the counters themselves, subtracted from each operation
+  overhead: Option<IoCounters>,
+  /// Counters and the number of the operations of each record
+  counts: BTreeMap<u64, Vec<(IoCounters, u64)>>,
+}
+
+/// Counters armed by [`SyscallRecorder::record`] on a thread with the counts of the timed sections so far.
+struct Armed {
+  overhead: IoCounters,
+  before: Option<IoCounters>,
+  counts: IoCounters,
+  sections: usize,
+  error: Option<BenchError>,
+}
+
+thread_local! {
+  static ARMED: RefCell<Option<Armed>> = const { RefCell::new(None) };
+}
+
+impl SyscallRecorder {
+  pub fn new(enabled: bool) -> Result<Self> {
+    let overhead = if enabled {
+      let before = IoCounters::read(THREAD_IO)?;
+      Some(IoCounters::read(THREAD_IO)?.delta(&before))
+    } else {
+      None
+    };
+    Ok(Self { overhead, counts: BTreeMap::new() })
+  }
+
+  /// Run `operations` operations and record at `x` the syscalls issued by this thread in the timed sections of the
+  /// [`Stopwatch`](crate::clock::Stopwatch)es within it, so that the work around the storage operations, such as the
+  /// verification and the storage size of a gauge interval, isn't counted.
+  pub fn record<T, F: FnOnce() -> Result<T>>(&mut self, x: u64, operations: u64, f: F) -> Result<T> {
+    let Some(overhead) = self.overhead else {
+      return f();
+    };
+    let armed = Armed { overhead, before: None, counts: IoCounters::default(), sections: 0, error: None };
+    ARMED.with(|cell| *cell.borrow_mut() = Some(armed));
+    let result = clock::armed(f);
+    let armed = ARMED.with(|cell| cell.borrow_mut().take()).unwrap();
+    if let Some(err) = armed.error {
+      return Err(err);
+    }
+    let result = result?;
+    if armed.sections > 0 && operations > 0 {
+      self.counts.entry(x).or_default().push((armed.counts, operations));
+    }
+    Ok(result)
+  }
+
+  /// Save the mean of the syscalls per operation at each position. Nothing is saved if disabled.
+  pub fn save_to_csv(&self, path: &Path, x_label: &str) -> Result<bool> {
+    if self.overhead.is_none() {
+      return Ok(false);
+    }
+    write_atomically(path, |writer| {
+      let labels = "READ SYSCALLS,WRITE SYSCALLS,READ BYTES,WRITE BYTES,DEVICE READ BYTES,DEVICE WRITE BYTES";
+      writeln!(writer, "{x_label},{labels}")?;
+      for (x, counts) in self.counts.iter() {
+        let operations = counts.iter().map(|(_, operations)| *operations).sum::<u64>() as f64;
+        let mean = |f: fn(&IoCounters) -> u64| counts.iter().map(|(c, _)| f(c)).sum::<u64>() as f64 / operations;
+        let (syscr, syscw) = (mean(|c| c.syscr), mean(|c| c.syscw));
+        let (rchar, wchar) = (mean(|c| c.rchar), mean(|c| c.wchar));
+        let (read_bytes, write_bytes) = (mean(|c| c.read_bytes), mean(|c| c.write_bytes));
+        writeln!(writer, "{x},{syscr:.2},{syscw:.2},{rchar:.1},{wchar:.1},{read_bytes:.1},{write_bytes:.1}")?;
+      }
+      Ok(())
+    })?;
+    Ok(true)
+  }
+}
+
+/// Read the counters armed on this thread at the start of a timed section.
+pub(crate) fn enter() {
+  ARMED.with(|cell| {
+    if let Some(armed) = cell.borrow_mut().as_mut()
+      && armed.before.is_none()
+      && armed.error.is_none()
+    {
+      match IoCounters::read(THREAD_IO) {
+        Ok(before) => armed.before = Some(before),
+        Err(err) => armed.error = Some(err),
+      }
+    }
+  })
+}
+
+/// Add up the syscalls of this thread since [`enter`] at the end of a timed section.
+pub(crate) fn leave() {
+  ARMED.with(|cell| {
+    if let Some(armed) = cell.borrow_mut().as_mut()
+      && let Some(before) = armed.before.take()
+    {
+      match IoCounters::read(THREAD_IO) {
+        Ok(after) => {
+          armed.counts.add(&after.delta(&before).delta(&armed.overhead));
+          armed.sections += 1;
+        }
+        Err(err) => armed.error = Some(err),
+      }
+    }
+  })
+}
+
+/// Physical I/O of the whole process during a test unit, reported per CUT to `{session}-io.csv`. It tells whether the
+/// cache level actually avoided the storage access.
+pub struct UnitIoStats {
+  start: Instant,
+  /// None if the counters aren't available on this platform
+  before: Option<IoCounters>,
+}
+
+impl UnitIoStats {
+  pub fn start() -> Self {
+    UnitIoStats { start: Instant::now(), before: IoCounters::read(PROCESS_IO).ok() }
+  }
+
+  pub fn finish(self, experiment: &Experiment, cut: &str, unit: &str) -> Result<()> {
+    let seconds = self.start.elapsed().as_secs_f64();
+    let Some(before) = self.before else {
+      return Ok(());
+    };
+    let io = IoCounters::read(PROCESS_IO)?.delta(&before);
+    println!("device I/O: {} bytes read, {} bytes written in {seconds:.1}[sec]", io.read_bytes, io.write_bytes);
+
+    let path = experiment.report_path("io.csv");
+    let header = "CUT,UNIT,SECONDS,READ SYSCALLS,WRITE SYSCALLS,DEVICE READ BYTES,DEVICE WRITE BYTES";
+    let row = format!("{cut},{unit},{seconds:.3},{},{},{},{}", io.syscr, io.syscw, io.read_bytes, io.write_bytes);
+    append_csv(&path, header, &row)
+  }
+}
@@ -0,0 +1,87 @@
+use std::io::{Read, Seek, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Value(u64);
+
+impl Serializable for Value {
+  fn write<W: Write>(&self, w: &mut W) -> Result<usize> {
+    w.write_u64::<LittleEndian>(self.0)?;
+    Ok(8)
+  }
+
+  fn read<R: Read + Seek>(r: &mut R, _position: Position) -> Result<Self> {
+    Ok(Value(r.read_u64::<LittleEndian>()?))
+  }
+}
+
+#[test]
+fn memkvs_with_sparse_positions() {
+  let mut kvs = MemKVS::<Value>::new();
+  assert_eq!((None, 1), kvs.first().unwrap());
+  assert_eq!((None, 1), kvs.last().unwrap());
+
+  assert_eq!(11, kvs.put(10, &Value(100)).unwrap());
+  assert_eq!(11, kvs.put(5, &Value(50)).unwrap());
+  assert_eq!(21, kvs.put(20, &Value(200)).unwrap());
+  assert_eq!(21, kvs.put(10, &Value(101)).unwrap());
+  assert_eq!((Some(Value(50)), 21), kvs.first().unwrap());
+  assert_eq!((Some(Value(200)), 21), kvs.last().unwrap());
+  assert_eq!(Value(101), kvs.reader().unwrap().read(10).unwrap());
+  assert!(kvs.contains(5).unwrap());
+  assert!(!kvs.contains(6).unwrap());
+  assert_eq!(Some(Value(200)), kvs.try_read(20).unwrap());
+  assert_eq!(None, kvs.try_read(21).unwrap());
+
+  // 共有されたマップを開き直しても同じ範囲となる
+  let mut reopened = MemKVS::with_kvs(kvs.kvs.clone());
+  assert_eq!((Some(Value(50)), 21), reopened.first().unwrap());
+  assert_eq!((Some(Value(200)), 21), reopened.last().unwrap());
+
+  // 他のインスタンスが書き込んだ範囲も反映される
+  assert_eq!(31, reopened.put(30, &Value(300)).unwrap());
+  assert_eq!((Some(Value(300)), 31), kvs.last().unwrap());
+  assert_eq!(32, kvs.put(31, &Value(310)).unwrap());
+}
+
+#[test]
+fn memkvs_read_missing_position() {
+  let mut kvs = MemKVS::<Value>::new();
+  kvs.put(1, &Value(1)).unwrap();
+  let mut reader = kvs.reader().unwrap();
+  assert_eq!(Value(1), reader.read(1).unwrap());
+  assert!(reader.read(2).is_err());
+}
+
+#[test]
+fn zipf_accuracy() {
+  let n = 10_000_000;
+  let default = ZipfSampler::new(0, 0.5, n).accuracy_report();
+  let accurate = ZipfAccuracy { min_samples: 100_000, convergence_threshold: 1.0 / 1_000_000.0 };
+  let mut sampler = ZipfSampler::with_accuracy(0, 0.5, n, accurate);
+  let report = sampler.accuracy_report();
+  assert!(report.cutoff > default.cutoff);
+  assert!(report.tail_mass < default.tail_mass);
+  assert!((0..1000).map(|_| sampler.next_u64()).all(|i| (1..=n).contains(&i)));
+
+  // 全体を保持できる場合はテールがない
+  assert_eq!(ZipfAccuracyReport { cutoff: 100, tail_mass: 0.0 }, ZipfSampler::new(0, 1.5, 100).accuracy_report());
+}
+
+#[test]
+fn transient_errors() {
+  use crate::error::BenchError;
+  use std::io::ErrorKind;
+  assert!(BenchError::Io(ErrorKind::StorageFull.into()).is_transient());
+  assert!(BenchError::Io(ErrorKind::WouldBlock.into()).is_transient());
+  assert!(!BenchError::Io(ErrorKind::NotFound.into()).is_transient());
+  assert!(!BenchError::Validation(String::from("lock")).is_transient());
+  // "block" は "lock" を含むが、別の単語として扱う
+  let corrupted = std::io::Error::new(ErrorKind::InvalidData, "corrupted block");
+  assert!(!BenchError::Storage(corrupted.into()).is_transient());
+  let busy = std::io::Error::new(ErrorKind::ResourceBusy, "lock held by another process");
+  assert!(BenchError::Storage(busy.into()).is_transient());
+}
@@ -0,0 +1,206 @@
+//! Recording and replay of the operations issued to the CUTs.
+//!
+//! A trace is a binary file of fixed-length records following the magic `SLTRACE1`. The timestamp is the time elapsed
+//! since the recording started.
+//!
+//! ```text
+//! operation: u8 (0=get, 1=append, 2=scan, 3=prove)
+//! position:  u64 little endian
+//! timestamp: u64 little endian, microseconds
+//! ```
+//!
+//! A trace captured by another tool can also be given as text with a `{operation},{position},{timestamp}` line for
+//! each operation, e.g. `get,1024,350`.
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use slate::Index;
+
+use crate::error::{BenchError, Result};
+
+#[cfg(test)]
+mod test;
+
+const MAGIC: &[u8; 8] = b"SLTRACE1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+  Get = 0,
+  Append = 1,
+  Scan = 2,
+  Prove = 3,
+}
+
+impl Operation {
+  const ALL: [Operation; 4] = [Operation::Get, Operation::Append, Operation::Scan, Operation::Prove];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Operation::Get => "get",
+      Operation::Append => "append",
+      Operation::Scan => "scan",
+      Operation::Prove => "prove",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+  pub operation: Operation,
+  pub position: Index,
+  /// Microseconds since the recording started
+  pub timestamp: u64,
+}
+
+struct Recorder {
+  start: Instant,
+  writer: Mutex<BufWriter<File>>,
+}
+
+static RECORDER: OnceLock<Recorder> = OnceLock::new();
+
+/// Number of the records buffered on a thread before they're passed to the writer thread.
+const BUFFER: usize = 4096;
+
+/// Records of a thread not yet passed to the writer thread, passed when the thread ends.
+struct Buffered(Vec<Record>);
+
+impl Drop for Buffered {
+  fn drop(&mut self) {
+    write_on_writer(std::mem::take(&mut self.0));
+  }
+}
+
+thread_local! {
+  static BUFFERED: RefCell<Buffered> = RefCell::new(Buffered(Vec::with_capacity(BUFFER)));
+}
+
+/// Start recording all the operations issued to the CUTs into `path` until [`finish`] is called.
+pub fn start(path: &Path) -> Result<()> {
+  let mut writer = BufWriter::new(File::create(path)?);
+  writer.write_all(MAGIC)?;
+  if RECORDER.set(Recorder { start: Instant::now(), writer: Mutex::new(writer) }).is_err() {
+    return Err(BenchError::Config(String::from("the trace is already being recorded")));
+  }
+  Ok(())
+}
+
+/// Record the operation, if the recording is started. It's called after the measurement and buffered on the current
+/// thread, so that the recording doesn't affect it, until [`submit`] passes the records to the [writer](crate::writer)
+/// thread or the buffer fills up.
+pub fn record(operation: Operation, position: Index) {
+  if let Some(recorder) = RECORDER.get() {
+    let timestamp = recorder.start.elapsed().as_micros() as u64;
+    BUFFERED.with(|buffered| {
+      let mut buffered = buffered.borrow_mut();
+      buffered.0.push(Record { operation, position, timestamp });
+      if buffered.0.len() >= BUFFER {
+        write_on_writer(std::mem::replace(&mut buffered.0, Vec::with_capacity(BUFFER)));
+      }
+    });
+  }
+}
+
+/// Pass the records buffered on the current thread to the writer thread. It's called out of the timed sections, e.g.
+/// between the trials.
+pub fn submit() {
+  if RECORDER.get().is_some() {
+    BUFFERED.with(|buffered| {
+      let mut buffered = buffered.borrow_mut();
+      if !buffered.0.is_empty() {
+        write_on_writer(std::mem::replace(&mut buffered.0, Vec::with_capacity(BUFFER)));
+      }
+    });
+  }
+}
+
+fn write_on_writer(records: Vec<Record>) {
+  if records.is_empty() {
+    return;
+  }
+  crate::writer::submit(move || {
+    let mut writer = RECORDER.get().unwrap().writer.lock().unwrap();
+    if let Err(err) = records.iter().try_for_each(|record| write(&mut *writer, record)) {
+      eprintln!("WARN: fail to record the trace: {err}");
+    }
+  });
+}
+
+/// Flush the recorded trace.
+pub fn finish() -> Result<()> {
+  submit();
+  crate::writer::flush();
+  if let Some(recorder) = RECORDER.get() {
+    let mut writer = recorder.writer.lock().unwrap();
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+  }
+  Ok(())
+}
+
+fn write<W: Write>(w: &mut W, record: &Record) -> Result<()> {
+  w.write_u8(record.operation as u8)?;
+  w.write_u64::<LittleEndian>(record.position)?;
+  w.write_u64::<LittleEndian>(record.timestamp)?;
+  Ok(())
+}
+
+/// Read the trace recorded by [`start`], or the text trace if it doesn't begin with the magic.
+pub fn load(path: &Path) -> Result<Vec<Record>> {
+  let mut r = BufReader::new(File::open(path)?);
+  if r.fill_buf()?.starts_with(MAGIC) {
+    r.consume(MAGIC.len());
+    read_binary(&mut r)
+  } else {
+    read_text(r)
+  }
+}
+
+fn read_binary<R: Read>(r: &mut R) -> Result<Vec<Record>> {
+  let mut records = Vec::new();
+  loop {
+    let operation = match r.read_u8() {
+      Ok(op) => op,
+      Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+      Err(err) => return Err(err.into()),
+    };
+    let Some(operation) = Operation::ALL.into_iter().find(|op| *op as u8 == operation) else {
+      return Err(BenchError::Validation(format!("unknown operation {operation} in the trace")));
+    };
+    let position = r.read_u64::<LittleEndian>()?;
+    let timestamp = r.read_u64::<LittleEndian>()?;
+    records.push(Record { operation, position, timestamp });
+  }
+  Ok(records)
+}
+
+fn read_text<R: BufRead>(r: R) -> Result<Vec<Record>> {
+  let mut records = Vec::new();
+  for (i, line) in r.lines().enumerate() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+    let record = match fields[..] {
+      [operation, position, timestamp] => {
+        let operation = Operation::ALL.into_iter().find(|op| op.name().eq_ignore_ascii_case(operation));
+        match (operation, position.parse(), timestamp.parse()) {
+          (Some(operation), Ok(position), Ok(timestamp)) => Some(Record { operation, position, timestamp }),
+          _ => None,
+        }
+      }
+      _ => None,
+    };
+    let Some(record) = record else {
+      return Err(BenchError::Validation(format!("invalid trace at line {}: {line}", i + 1)));
+    };
+    records.push(record);
+  }
+  Ok(records)
+}
@@ -0,0 +1,28 @@
+use std::fs;
+
+use super::*;
+
+#[test]
+fn read_binary_and_text_traces() {
+  let expected = vec![
+    Record { operation: Operation::Append, position: 100, timestamp: 0 },
+    Record { operation: Operation::Get, position: 42, timestamp: 15 },
+    Record { operation: Operation::Prove, position: 7, timestamp: 1_000_000 },
+  ];
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("trace.bin");
+  let mut w = BufWriter::new(File::create(&path).unwrap());
+  w.write_all(MAGIC).unwrap();
+  for record in expected.iter() {
+    write(&mut w, record).unwrap();
+  }
+  drop(w);
+  assert_eq!(expected, load(&path).unwrap());
+
+  let path = dir.path().join("trace.csv");
+  fs::write(&path, "append,100,0\nGET, 42, 15\n\nprove,7,1000000\n").unwrap();
+  assert_eq!(expected, load(&path).unwrap());
+
+  fs::write(&path, "get,1,0\nput,2,3\n").unwrap();
+  assert!(matches!(load(&path), Err(BenchError::Validation(_))));
+}
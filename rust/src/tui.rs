@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Cell, Gauge, Paragraph, Row, Table};
+
+use crate::error::Result;
+use crate::monitor::{self, State, Status};
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Terminal UI showing the test units × CUTs with their status, trials, CV and the recent samples, drawn from the
+/// [monitor](crate::monitor). While it's shown, stdout and stderr are redirected to the log file so that the column
+/// prints don't break the screen. The terminal and stdout are restored when dropped.
+pub struct Tui {
+  stop: Arc<AtomicBool>,
+  renderer: Option<JoinHandle<()>>,
+  stdout: i32,
+  stderr: i32,
+}
+
+impl Tui {
+  pub fn start(log: &Path) -> Result<Self> {
+    let log = File::create(log)?;
+    std::io::stdout().flush()?;
+    let (stdout, stderr) = unsafe { (libc::dup(1), libc::dup(2)) };
+    if stdout < 0 || stderr < 0 {
+      return Err(std::io::Error::last_os_error().into());
+    }
+    let mut terminal = Terminal::new(CrosstermBackend::new(unsafe { File::from_raw_fd(libc::dup(stdout)) }))?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.hide_cursor()?;
+    unsafe {
+      libc::dup2(log.as_raw_fd(), 1);
+      libc::dup2(log.as_raw_fd(), 2);
+    }
+
+    monitor::enable();
+    let stop = Arc::new(AtomicBool::new(false));
+    let renderer = {
+      let stop = stop.clone();
+      std::thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+          // 描画できない端末でもベンチマークは継続する
+          let _ = terminal.draw(|frame| {
+            monitor::with(|state| draw(frame, state));
+          });
+          std::thread::sleep(REFRESH_INTERVAL);
+        }
+        let _ = execute!(terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = terminal.show_cursor();
+      })
+    };
+    Ok(Self { stop, renderer: Some(renderer), stdout, stderr })
+  }
+}
+
+impl Drop for Tui {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+    if let Some(renderer) = self.renderer.take() {
+      let _ = renderer.join();
+    }
+    let _ = std::io::stdout().flush();
+    unsafe {
+      libc::dup2(self.stdout, 1);
+      libc::dup2(self.stderr, 2);
+      libc::close(self.stdout);
+      libc::close(self.stderr);
+    }
+  }
+}
+
+fn draw(frame: &mut ratatui::Frame, state: &State) {
+  let [summary, progress, table] =
+    Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Min(0)]).areas(frame.area());
+
+  let elapsed = state.started.elapsed().as_secs();
+  let line = format!("Elapsed: {}m{:02}s  ETA: {}", elapsed / 60, elapsed % 60, state.eta);
+  frame.render_widget(Paragraph::new(line), summary);
+  let ratio = if state.max_trials == 0 { 0.0 } else { (state.trials as f64 / state.max_trials as f64).min(1.0) };
+  let label = format!("{} / {} trials", state.trials, state.max_trials);
+  frame.render_widget(Gauge::default().ratio(ratio).label(label), progress);
+
+  // テストユニットを行、CUT を列とし、各セルに状態、試行数、CV と最近のサンプルを 2 行で示す
+  let rows = state.units.iter().map(|unit| {
+    let cells = state.cuts.iter().map(|cut| match state.cell(unit, cut) {
+      Some(cell) => {
+        let status = match cell.status {
+          Status::Running => "running",
+          Status::Done => "done",
+          Status::Failed => "failed",
+        };
+        let text = Text::from(vec![
+          Line::from(format!("{status} {} {:.2}%", cell.count, cell.cv * 100.0)),
+          Line::from(sparkline(cell.recent.iter().copied())),
+        ]);
+        let running = cell.status == Status::Running;
+        Cell::from(text).style(if running { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() })
+      }
+      None => Cell::from("-"),
+    });
+    Row::new(std::iter::once(Cell::from(unit.as_str())).chain(cells)).height(2)
+  });
+  let unit_width = state.units.iter().map(|unit| unit.len()).max().unwrap_or(0).max(4) as u16;
+  let widths = std::iter::once(Constraint::Length(unit_width)).chain(state.cuts.iter().map(|_| Constraint::Fill(1)));
+  let header = Row::new(std::iter::once("UNIT").chain(state.cuts.iter().map(String::as_str)))
+    .style(Style::default().add_modifier(Modifier::REVERSED));
+  frame.render_widget(Table::new(rows, widths).header(header).block(Block::bordered()), table);
+}
+
+fn sparkline<I: Iterator<Item = f64> + Clone>(ys: I) -> String {
+  let (min, max) = ys.clone().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| (min.min(y), max.max(y)));
+  ys.map(|y| {
+    let level = if max > min { (y - min) / (max - min) * (BARS.len() - 1) as f64 } else { 0.0 };
+    BARS[level.round() as usize]
+  })
+  .collect()
+}
@@ -0,0 +1,100 @@
+use std::fs::{self, File};
+use std::io::{Error, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use slate_benchmark::Experiment;
+use slate_benchmark::error::{BenchError, Result};
+use slate_benchmark::report::write_atomically;
+
+/// Remote location to upload the reports of the session to when it ends, so that they survive an ephemeral host.
+#[derive(Debug, Clone)]
+pub enum Destination {
+  /// `s3://bucket/prefix`, uploaded with the AWS CLI
+  S3(String),
+  /// `gs://bucket/prefix`, uploaded with gsutil
+  Gcs(String),
+  /// `file:///path` or a local path such as a mounted network storage
+  Local(PathBuf),
+}
+
+impl Destination {
+  pub fn parse(url: &str) -> Result<Self> {
+    let url = url.trim_end_matches('/');
+    if url.starts_with("s3://") {
+      Ok(Destination::S3(String::from(url)))
+    } else if url.starts_with("gs://") {
+      Ok(Destination::Gcs(String::from(url)))
+    } else if let Some(path) = url.strip_prefix("file://") {
+      Ok(Destination::Local(PathBuf::from(path)))
+    } else if !url.contains("://") && !url.is_empty() {
+      Ok(Destination::Local(PathBuf::from(url)))
+    } else {
+      Err(BenchError::Config(format!("unsupported upload destination: {url} (s3://, gs:// or file://)")))
+    }
+  }
+
+  /// Write the manifest of the reports of the sessions, i.e. the session and its sessions on the devices, into the
+  /// first one and upload them with the manifest. The files are named after their paths relative to the report
+  /// directory, e.g. `{session}/csv/{id}.csv` in the per-session layout.
+  ///
+  /// ```text
+  /// {session}-MANIFEST  "{file name} {bytes} {blake3 hash in hex}" for each report
+  /// ```
+  ///
+  /// ## Returns
+  /// - the number of the uploaded files
+  pub fn upload(&self, sessions: &[&Experiment]) -> Result<usize> {
+    let manifest = sessions[0].report_path("MANIFEST");
+    let mut files = Vec::new();
+    for session in sessions {
+      files.extend(session.report_files()?);
+    }
+    // 平坦な配置ではデバイスごとのセッションのレポートも {session}- で始まる
+    files.sort();
+    files.dedup();
+    files.retain(|path| *path != manifest);
+    let name = |path: &Path| {
+      let path = path.strip_prefix(sessions[0].dir_report()).unwrap_or(path);
+      path.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>().join("/")
+    };
+    write_atomically(&manifest, |writer| {
+      for path in files.iter() {
+        let mut hasher = blake3::Hasher::new();
+        let size = std::io::copy(&mut File::open(path)?, &mut hasher)?;
+        writeln!(writer, "{} {size} {}", name(path), hasher.finalize().to_hex())?;
+      }
+      Ok(())
+    })?;
+
+    // マニフェストは最後にアップロードし、存在すればすべてのレポートが揃っていることを示す
+    files.push(manifest);
+    for path in files.iter() {
+      self.put(path, &name(path))?;
+    }
+    Ok(files.len())
+  }
+
+  fn put(&self, path: &Path, name: &str) -> Result<()> {
+    let mut command = match self {
+      Destination::S3(url) => {
+        let mut command = Command::new("aws");
+        command.args(["s3", "cp", "--only-show-errors"]).arg(path).arg(format!("{url}/{name}"));
+        command
+      }
+      Destination::Gcs(url) => {
+        let mut command = Command::new("gsutil");
+        command.args(["-q", "cp"]).arg(path).arg(format!("{url}/{name}"));
+        command
+      }
+      Destination::Local(dir) => {
+        let target = dir.join(name);
+        fs::create_dir_all(target.parent().unwrap_or(dir))?;
+        fs::copy(path, target)?;
+        return Ok(());
+      }
+    };
+    let status = command.status()?;
+    if status.success() { Ok(()) } else { Err(Error::other(format!("{command:?} failed: {status}")).into()) }
+  }
+}
@@ -0,0 +1,266 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::ValueEnum;
+use slate::file::FileDevice;
+#[cfg(feature = "rocksdb")]
+use slate::rocksdb::RocksDBStorage;
+use slate::{BlockStorage, Entry, FileStorage, Index, Prove, Slate, Storage};
+use slate_benchmark::error::Result;
+use slate_benchmark::hashtree::binary::{BinaryHashTree, Node};
+use slate_benchmark::hashtree::hash::{Blake3, HashValue};
+use slate_benchmark::hashtree::implicit::ImplicitHashTree;
+use slate_benchmark::hashtree::leveled::LeveledHashTree;
+use slate_benchmark::hashtree::{HashTree, SlateHashTree};
+use slate_benchmark::knobs::Knobs;
+use slate_benchmark::{MemKVS, splitmix64};
+
+use crate::binarytree::{AlignedImplicitHashTree, FileHashTree};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+  /// BinaryHashTree のファイル
+  Hashtree,
+  /// BinaryHashTree の RocksDB ディレクトリ
+  #[cfg(feature = "rocksdb")]
+  HashtreeRocksdb,
+  /// Slate のファイル
+  Slate,
+}
+
+/// 格納済みの木を読み取り専用で開いて走査し、ハッシュ値を再計算して不一致のノード位置と所要時間を出力する。
+/// 不一致がなければ true を返す。
+pub fn verify(path: &Path, format: Format) -> Result<bool> {
+  println!("Verifying {format:?}: {path:?}");
+  let start = Instant::now();
+  let mismatches = match format {
+    Format::Hashtree => verify_hashtree(BinaryHashTree::<_, Blake3>::new(BlockStorage::from_file(path, true)?, 0)?)?,
+    #[cfg(feature = "rocksdb")]
+    Format::HashtreeRocksdb => verify_hashtree(BinaryHashTree::<_, Blake3>::from_rocksdb(path, 0, &Knobs::default())?)?,
+    Format::Slate => verify_slate(FileStorage::from_file(path, true)?)?,
+  };
+  let elapsed = start.elapsed();
+  println!("  {mismatches} mismatches in {:.3}[msec]", elapsed.as_nanos() as f64 / 1000.0 / 1000.0);
+  Ok(mismatches == 0)
+}
+
+fn verify_hashtree<S: Storage<Node>>(tree: BinaryHashTree<S, Blake3>) -> Result<usize> {
+  let mismatches = tree.verify()?;
+  for m in mismatches.iter() {
+    println!("  MISMATCH: node i={} @{}", m.index, m.position);
+  }
+  let counters = tree.counters();
+  println!("  {} nodes read", counters.reads);
+  Ok(mismatches.len())
+}
+
+/// Slate の各エントリの値からメモリ上に木を再構築し、両者の認証パスを比較する。
+fn verify_slate<S: Storage<Entry>>(storage: S) -> Result<usize> {
+  let slate = Slate::with_cache_level(storage, 0)?;
+  let n = slate.n();
+  let mut stored = slate.snapshot().query()?;
+  let mut rebuilt = Slate::with_cache_level(MemKVS::new(), 0)?;
+  for i in 1..=n {
+    rebuilt.append(&stored.get(i)?.unwrap())?;
+  }
+  let mut computed = rebuilt.snapshot().query()?;
+
+  // 複数の認証パスに同じノードが含まれるため重複を除いて報告する
+  let mut mismatches = BTreeSet::new();
+  for i in 1..=n {
+    let auth_path = stored.get_auth_path(i)?.unwrap();
+    if let Prove::Divergent(divergents) = auth_path.prove(&computed.get_auth_path(i)?.unwrap())? {
+      mismatches.extend(divergents);
+    }
+  }
+  for (i, j) in mismatches.iter() {
+    println!("  MISMATCH: node ({i}, {j})");
+  }
+  println!("  {n} entries read");
+  Ok(mismatches.len())
+}
+
+/// Root hash and the sampled values of a database, see [`verify_session`].
+struct Summary {
+  n: Index,
+  root: Option<HashValue>,
+  values: Vec<Option<Vec<u8>>>,
+}
+
+type Inspect = fn(&Path, &[Index]) -> Result<Summary>;
+
+/// 作業ディレクトリに残るデータベースのファイル名 (`{name}.db`) の name と、ルートハッシュが一致すべき木の構造、それを
+/// 開いて要約する関数。
+fn backends() -> Vec<(String, &'static str, Inspect)> {
+  #[cfg_attr(not(feature = "rocksdb"), allow(unused_mut))]
+  let mut backends: Vec<(String, &'static str, Inspect)> = vec![
+    (String::from("slate-file"), "slate", inspect_slate),
+    (
+      BinaryHashTree::<BlockStorage<FileDevice>, Blake3>::name(),
+      "hashtree",
+      inspect::<BinaryHashTree<BlockStorage<FileDevice>, Blake3>>,
+    ),
+    (ImplicitHashTree::<Blake3>::name(), "hashtree", inspect::<ImplicitHashTree<Blake3>>),
+    (AlignedImplicitHashTree::<false, Blake3>::name(), "hashtree", inspect::<AlignedImplicitHashTree<false, Blake3>>),
+    (AlignedImplicitHashTree::<true, Blake3>::name(), "hashtree", inspect::<AlignedImplicitHashTree<true, Blake3>>),
+    (LeveledHashTree::<Blake3>::name(), "hashtree", inspect::<LeveledHashTree<Blake3>>),
+  ];
+  #[cfg(feature = "rocksdb")]
+  backends.push((
+    BinaryHashTree::<RocksDBStorage, Blake3>::name(),
+    "hashtree",
+    inspect::<BinaryHashTree<RocksDBStorage, Blake3>>,
+  ));
+  #[cfg(feature = "rocksdb")]
+  backends.push((String::from("slate-rocksdb"), "slate", inspect_slate_rocksdb));
+  backends
+}
+
+fn inspect<T: FileHashTree>(path: &Path, positions: &[Index]) -> Result<Summary> {
+  summarize(T::open(path, 0, &Knobs::default())?, positions)
+}
+
+fn inspect_slate(path: &Path, positions: &[Index]) -> Result<Summary> {
+  summarize(SlateHashTree::new(Slate::with_cache_level(FileStorage::from_file(path, true)?, 0)?), positions)
+}
+
+#[cfg(feature = "rocksdb")]
+fn inspect_slate_rocksdb(path: &Path, positions: &[Index]) -> Result<Summary> {
+  let db =
+    rocksdb::DB::open_for_read_only(&rocksdb::Options::default(), path, false).map_err(slate::error::Error::from)?;
+  let storage = RocksDBStorage::new(std::sync::Arc::new(std::sync::RwLock::new(db)), &[], false);
+  summarize(SlateHashTree::new(Slate::with_cache_level(storage, 0)?), positions)
+}
+
+fn summarize<T: HashTree<Error = slate::error::Error>>(mut tree: T, positions: &[Index]) -> Result<Summary> {
+  let n = tree.size();
+  let root = tree.root_hash()?;
+  let values = positions.iter().map(|i| tree.get(*i)).collect::<slate::Result<Vec<_>>>()?;
+  Ok(Summary { n, root, values })
+}
+
+/// Index in [`backends`] of the backend whose database is stored at the path, if any. The `{name}_{i}.db` files are
+/// the alternates of the prove test unit, altered on purpose, or the databases of other CUTs on the same backend, and
+/// the empty files are the placeholders reserved by [`unique_file`](slate_benchmark::unique_file); neither is
+/// compared.
+fn backend_of(path: &Path, backends: &[(String, &'static str, Inspect)]) -> Option<usize> {
+  if path.is_file() && fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true) {
+    return None;
+  }
+  let name = path.file_name()?.to_string_lossy();
+  let name = name.strip_suffix(".db")?;
+  backends.iter().position(|(backend, _, _)| backend == name)
+}
+
+/// セッションの作業ディレクトリ (`slate_benchmark-{session}` と `--dir` ごとの `slate_benchmark-{session}-{LABEL}`) に
+/// 残るすべてのデータベースを開き直し、同じ構造で同じ件数の木のルートハッシュと、無作為に選んだ位置の値がすべての
+/// データベースで一致することを確認する。不一致がなければ true を返す。
+pub fn verify_session(dirs: &[PathBuf], session: &str, samples: usize) -> Result<bool> {
+  let backends = backends();
+  let prefix = format!("slate_benchmark-{session}");
+  let mut databases = Vec::new();
+  for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+    for entry in fs::read_dir(dir)? {
+      let work_dir = entry?.path();
+      let name = work_dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+      if !work_dir.is_dir() || !(name == prefix || name.starts_with(&format!("{prefix}-"))) {
+        continue;
+      }
+      for entry in fs::read_dir(&work_dir)? {
+        let path = entry?.path();
+        match backend_of(&path, &backends) {
+          Some(backend) => databases.push((path, backend)),
+          None => println!("  skipped: {path:?}"),
+        }
+      }
+    }
+  }
+  if databases.is_empty() {
+    println!("No databases of the session {session} are left in {dirs:?}");
+    return Ok(true);
+  }
+  databases.sort();
+
+  // 開けないデータベースは報告して比較から除き、残りの検証を続ける
+  let mut errors = 0;
+  let mut n = Index::MAX;
+  databases.retain(|(path, backend)| match (backends[*backend].2)(path, &[]) {
+    Ok(summary) => {
+      n = n.min(summary.n);
+      true
+    }
+    Err(err) => {
+      errors += 1;
+      println!("  ERROR: {path:?}: {err}");
+      false
+    }
+  });
+
+  // すべてのデータベースに存在する位置から標本を選ぶ
+  let mut positions = Vec::new();
+  if n > 0 {
+    positions = (0..samples as u64).map(|k| 1 + splitmix64(k) % n).chain([1, n]).collect::<Vec<_>>();
+    positions.sort_unstable();
+    positions.dedup();
+  }
+
+  let start = Instant::now();
+  let mut summaries = Vec::with_capacity(databases.len());
+  for (path, backend) in databases.iter() {
+    let (name, structure, inspect) = &backends[*backend];
+    let summary = match inspect(path, &positions) {
+      Ok(summary) => summary,
+      Err(err) => {
+        errors += 1;
+        println!("  ERROR: {path:?}: {err}");
+        continue;
+      }
+    };
+    let root = summary.root.as_ref().map(hex).unwrap_or_default();
+    println!("Verifying {name}: {path:?} ({} entries, root {root})", summary.n);
+    summaries.push((path, *structure, summary));
+  }
+
+  let mut mismatches = 0;
+  let mut roots = BTreeMap::<(&str, Index), BTreeMap<Option<HashValue>, Vec<&PathBuf>>>::new();
+  for (path, structure, summary) in summaries.iter() {
+    roots.entry((*structure, summary.n)).or_default().entry(summary.root).or_default().push(*path);
+  }
+  for ((structure, n), roots) in roots.iter().filter(|(_, roots)| roots.len() > 1) {
+    mismatches += 1;
+    println!("  MISMATCH: root hashes of {structure} with {n} entries");
+    for (root, paths) in roots.iter() {
+      println!("    {}: {paths:?}", root.as_ref().map(hex).unwrap_or_default());
+    }
+  }
+  for (k, i) in positions.iter().enumerate() {
+    let mut values = BTreeMap::<&Option<Vec<u8>>, Vec<&PathBuf>>::new();
+    for (path, _, summary) in summaries.iter() {
+      values.entry(&summary.values[k]).or_default().push(*path);
+    }
+    if values.len() > 1 {
+      mismatches += 1;
+      println!("  MISMATCH: values at {i}");
+      for (value, paths) in values.iter() {
+        println!("    {}: {paths:?}", value.as_deref().map(hex).unwrap_or_default());
+      }
+    }
+  }
+  let elapsed = start.elapsed();
+  println!(
+    "  {mismatches} mismatches and {errors} errors in {} databases at {} positions in {:.3}[msec]",
+    summaries.len(),
+    positions.len(),
+    elapsed.as_nanos() as f64 / 1000.0 / 1000.0
+  );
+  Ok(mismatches == 0 && errors == 0)
+}
+
+fn hex<B: AsRef<[u8]>>(bytes: B) -> String {
+  bytes.as_ref().iter().map(|b| format!("{b:02x}")).collect()
+}
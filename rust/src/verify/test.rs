@@ -0,0 +1,35 @@
+use super::*;
+
+/// Write a slate file with n entries whose value at `altered` differs from the others.
+fn write_slate(path: &Path, n: Index, altered: Option<Index>) {
+  let mut slate = Slate::with_cache_level(FileStorage::from_file(path, false).unwrap(), 0).unwrap();
+  for i in 1..=n {
+    let value = if altered == Some(i) { !splitmix64(i) } else { splitmix64(i) };
+    slate.append(&value.to_le_bytes()).unwrap();
+  }
+}
+
+#[test]
+fn verify_the_databases_left_in_the_session() {
+  let dir = tempfile::tempdir().unwrap();
+  let work_dir = dir.path().join("slate_benchmark-test");
+  let other_dir = dir.path().join("slate_benchmark-test-nvme");
+  fs::create_dir_all(&work_dir).unwrap();
+  fs::create_dir_all(&other_dir).unwrap();
+  write_slate(&work_dir.join("slate-file.db"), 32, None);
+  write_slate(&other_dir.join("slate-file.db"), 32, None);
+
+  // 証明のテストユニットが意図的に変更した代替のデータベースと、予約されただけの空のファイルは比較しない
+  write_slate(&work_dir.join("slate-file_1.db"), 32, Some(7));
+  fs::write(work_dir.join(format!("{}.db", LeveledHashTree::<Blake3>::name())), b"").unwrap();
+  assert!(verify_session(&[dir.path().to_path_buf()], "test", 8).unwrap());
+
+  // 開けないデータベースは検証を中断せずに失敗として報告する
+  fs::write(other_dir.join(format!("{}.db", LeveledHashTree::<Blake3>::name())), b"broken").unwrap();
+  assert!(!verify_session(&[dir.path().to_path_buf()], "test", 8).unwrap());
+  fs::remove_file(other_dir.join(format!("{}.db", LeveledHashTree::<Blake3>::name()))).unwrap();
+
+  fs::remove_file(work_dir.join("slate-file.db")).unwrap();
+  write_slate(&work_dir.join("slate-file.db"), 32, Some(7));
+  assert!(!verify_session(&[dir.path().to_path_buf()], "test", 8).unwrap());
+}
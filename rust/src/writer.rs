@@ -0,0 +1,77 @@
+//! Dedicated thread that writes the console summaries, the monitor updates and the trace, so that the formatting and
+//! the I/O for the reporting at the end of a point don't sit between two timed operations on the measurement thread.
+//! The jobs are run in the order submitted.
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+
+#[cfg(test)]
+mod test;
+
+enum Job {
+  Run(Box<dyn FnOnce() + Send>),
+  Flush(Sender<()>),
+}
+
+static WRITER: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+
+fn writer(init: impl FnOnce() + Send + 'static) -> &'static Mutex<Sender<Job>> {
+  WRITER.get_or_init(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    thread::Builder::new()
+      .name(String::from("report-writer"))
+      .spawn(move || {
+        init();
+        for job in receiver {
+          match job {
+            Job::Run(f) => f(),
+            Job::Flush(done) => {
+              let _ = done.send(());
+            }
+          }
+        }
+      })
+      .expect("fail to start the report writer thread");
+    Mutex::new(sender)
+  })
+}
+
+fn send(job: Job) {
+  let _ = writer(|| ()).lock().unwrap_or_else(PoisonError::into_inner).send(job);
+}
+
+/// Start the writer thread running `init` first, e.g. to move it off the core of the measurement thread. Otherwise
+/// it's started by the first job on the affinity of the thread submitting it. Nothing happens if already started.
+pub fn start<F: FnOnce() + Send + 'static>(init: F) {
+  writer(init);
+}
+
+/// Run the job on the writer thread after the jobs submitted before.
+pub fn submit<F: FnOnce() + Send + 'static>(job: F) {
+  send(Job::Run(Box::new(job)));
+}
+
+/// Print the line on the writer thread, in the order with the summaries.
+pub fn println<S: Into<String>>(line: S) {
+  let line = line.into();
+  submit(move || println!("{line}"));
+}
+
+/// Wait until the jobs submitted so far are done, e.g. before printing directly or exiting the process.
+pub fn flush() {
+  if WRITER.get().is_none() {
+    return;
+  }
+  let (done, wait) = mpsc::channel();
+  send(Job::Flush(done));
+  let _ = wait.recv();
+}
+
+/// Guard that [`flush`]es the jobs when dropped, so that the queued lines aren't lost on an early return.
+pub struct FlushOnDrop;
+
+impl Drop for FlushOnDrop {
+  fn drop(&mut self) {
+    flush();
+  }
+}
@@ -0,0 +1,14 @@
+use std::sync::{Arc, Mutex};
+
+use super::*;
+
+#[test]
+fn jobs_run_in_order_until_flush() {
+  let done = Arc::new(Mutex::new(Vec::new()));
+  for i in 0..100 {
+    let done = done.clone();
+    submit(move || done.lock().unwrap().push(i));
+  }
+  flush();
+  assert_eq!((0..100).collect::<Vec<_>>(), *done.lock().unwrap());
+}
@@ -34,9 +34,30 @@ from pathlib import Path
 FONT = "Neue Haas Grotesk Display Pro"
 
 
+def csv_format(filepath):
+    """CSV と同じセッションのマーカーファイル ({session}.session) に記録された区切り文字と小数点のカンマを読み込む"""
+    path = Path(filepath).resolve()
+    # session レイアウトは {session}/csv/*.csv、flat レイアウトは {session}-*.csv (長いセッション名を優先)
+    markers = [path.parent.parent / f"{path.parent.parent.name}.session"]
+    flat = [m for m in path.parent.glob("*.session") if path.name.startswith(m.stem + "-")]
+    markers += sorted(flat, key=lambda m: -len(m.stem))
+    for marker in markers:
+        if marker.is_file():
+            with open(marker, "r") as f:
+                entries = dict(line.rstrip("\n").split("=", 1) for line in f if "=" in line)
+            delimiter = {"semicolon": ";", "tab": "\t"}.get(entries.get("csv.delimiter"), ",")
+            return delimiter, entries.get("csv.decimal-comma") == "true"
+    return ",", False
+
+
+def parse_number(text, decimal_comma):
+    return float(text.replace(",", ".") if decimal_comma else text)
+
+
 def read_csv_data(filepath):
     """CSVファイルを読み込んで測定データに変換"""
     print(f"読み込み中: {filepath}")
+    delimiter, decimal_comma = csv_format(filepath)
 
     # CSVファイルを読み込み
     with open(filepath, "r") as f:
@@ -45,22 +66,30 @@ def read_csv_data(filepath):
     # ヘッダーをスキップして、データ行を処理
     data_points = []
 
+    # SERIES 列を持つレポートは最初のアクセス (first) を除いて warm の系列のみを使う
+    header = lines[0].strip().split(delimiter) if lines else []
+    has_series = len(header) >= 3 and header[1].strip() == "SERIES"
+
     for line in lines[1:]:  # 最初の行（ヘッダー）をスキップ
         line = line.strip()
         if not line:
             continue
 
-        # カンマで分割
-        parts = line.split(",")
+        # 区切り文字で分割
+        parts = line.split(delimiter)
+        if has_series:
+            if len(parts) < 2 or parts[1].strip() != "warm":
+                continue
+            parts = [parts[0]] + parts[2:]
 
         if len(parts) >= 2:
-            x_value = float(parts[0])  # N値
+            x_value = parse_number(parts[0], decimal_comma)  # N値
 
             # 2列目以降の測定値を取得
             for i in range(1, len(parts)):
                 if parts[i].strip():  # 空でない場合
                     try:
-                        y_value = float(parts[i])
+                        y_value = parse_number(parts[i], decimal_comma)
                         data_points.append({"X": x_value, "Y": y_value})
                     except ValueError:
                         continue